@@ -1,4 +1,5 @@
 use alloc::string::String;
+use alloc::vec::Vec;
 use axerrno::AxError::PermissionDenied;
 use axerrno::{ax_err, AxError, AxResult};
 use axfs::api::current_uid;
@@ -7,10 +8,13 @@ use axfs::fops::{Directory, File, FileAttr, OpenOptions};
 pub use axfs::fops::DirEntry as AxDirEntry;
 pub use axfs::fops::FileAttr as AxFileAttr;
 pub use axfs::fops::FilePerm as AxFilePerm;
+pub use axfs::fops::FileSystemInfo as AxFileSystemInfo;
 pub use axfs::fops::FileType as AxFileType;
 pub use axfs::fops::OpenOptions as AxOpenOptions;
 pub use axio::SeekFrom as AxSeekFrom;
 
+#[cfg(feature = "user")]
+use axfs::audit::{self, AuditAction};
 #[cfg(feature = "myfs")]
 pub use axfs::fops::{Disk as AxDisk, MyFileSystemIf};
 #[cfg(feature = "user")]
@@ -64,9 +68,6 @@ pub fn ax_file_attr(file: &AxFileHandle) -> AxResult<AxFileAttr> {
 
 pub fn ax_file_change_attr(file: &AxFileHandle, perm: u16, uid: u32, gid: u32) -> AxResult {
     let a = file.0.get_attr()?;
-    if current_uid().is_ok_and(|uid| (uid != 0 && uid != a.user_id())) {
-        return Err(PermissionDenied);
-    }
     let new_attr = FileAttr::new(
         a.perm_from_u16(perm),
         uid,
@@ -82,8 +83,8 @@ pub fn ax_read_dir(dir: &mut AxDirHandle, dirents: &mut [AxDirEntry]) -> AxResul
     dir.0.read_dir(dirents)
 }
 
-pub fn ax_create_dir(path: &str) -> AxResult {
-    axfs::api::create_dir(path)
+pub fn ax_create_dir(path: &str, mode: u32) -> AxResult {
+    axfs::api::DirBuilder::new().mode(mode).create(path)
 }
 
 pub fn ax_remove_dir(path: &str) -> AxResult {
@@ -98,6 +99,14 @@ pub fn ax_rename(old: &str, new: &str) -> AxResult {
     axfs::api::rename(old, new)
 }
 
+pub fn ax_statfs(path: &str) -> AxResult<AxFileSystemInfo> {
+    axfs::api::statfs(path)
+}
+
+pub fn ax_mount_points() -> AxResult<Vec<String>> {
+    Ok(axfs::api::mount_points())
+}
+
 pub fn ax_current_dir() -> AxResult<String> {
     axfs::api::current_dir()
 }
@@ -106,17 +115,49 @@ pub fn ax_set_current_dir(path: &str) -> AxResult {
     axfs::api::set_current_dir(path)
 }
 
+pub fn ax_umask(mask: u16) -> u16 {
+    axfs::api::set_umask(mask)
+}
+
 pub fn ax_getuid() -> AxResult<u32> {
     current_uid()
 }
 
+/// Whether the current task holds the capability `cap`.
+#[cfg(feature = "user")]
+fn current_has_cap(cap: axtask::PrivCap) -> bool {
+    axtask::current().credentials().has_cap(cap)
+}
+
+/// Switches to the user with its primary and supplementary groups.
+///
+/// This changes the group ids, so it requires an authentication or
+/// [`PrivCap::SETGID`](axtask::PrivCap::SETGID).
+#[cfg(feature = "user")]
+fn switch_user(uid: u32) -> AxResult {
+    let gid = axuser::api::user_gid(uid).unwrap_or(uid);
+    axfs::api::set_current_gid(gid)?;
+    axuser::api::init_groups(uid);
+    axfs::api::set_current_uid(uid)
+}
+
 #[allow(unreachable_code)]
 pub fn ax_setuid(uid: u32) -> AxResult {
-    if current_uid().is_ok_and(|uid| (uid == 0)) {
+    #[cfg(not(feature = "user"))]
+    {
+        // without users, everything runs as the superuser
         return axfs::api::set_current_uid(uid);
     }
     #[cfg(feature = "user")]
     {
+        use axtask::PrivCap;
+
+        if current_has_cap(PrivCap::SETUID) {
+            if current_has_cap(PrivCap::SETGID) {
+                return switch_user(uid);
+            }
+            return axfs::api::set_current_uid(uid);
+        }
         axhal::console::putchar(b'P');
         axhal::console::putchar(b'a');
         axhal::console::putchar(b's');
@@ -128,25 +169,49 @@ pub fn ax_setuid(uid: u32) -> AxResult {
         axhal::console::putchar(b':');
         axhal::console::putchar(b' ');
         let password = get_password();
+        let target = user_name(uid);
         if !axuser::api::verify(uid, password) {
+            audit::record(AuditAction::Su, &target, false);
             return Err(AxError::AuthenticationFailure);
         }
-        return axfs::api::set_current_uid(uid);
+        audit::record(AuditAction::Su, &target, true);
+        switch_user(uid)
     }
-    Err(PermissionDenied)
 }
 
 #[allow(unreachable_code)]
-pub fn sudo() -> AxResult {
-    let uid = current_uid()?;
-    if uid == 0 {
+pub fn sudo(command: &str) -> AxResult {
+    #[cfg(not(feature = "user"))]
+    {
+        // without users, everything runs as the superuser
+        let _ = command;
         return Ok(());
     }
-    #[cfg(not(feature = "user"))]
-    { return Err(PermissionDenied); }
     #[cfg(feature = "user")]
     {
+        use axuser::api::SudoPermission;
+
+        // a task that may become the superuser by itself needs no sudoers entry
+        if current_has_cap(axtask::PrivCap::SETUID) {
+            return ax_setuid(0);
+        }
+        let uid = current_uid()?;
         let name = user_name(uid);
+        match axuser::api::sudo_permission(&name, command, "root") {
+            SudoPermission::Denied => {
+                audit::record(AuditAction::Sudo, command, false);
+                return Err(PermissionDenied);
+            }
+            SudoPermission::NoPassword => {
+                audit::record(AuditAction::Sudo, command, true);
+                return switch_user(0);
+            }
+            SudoPermission::Password if axuser::api::sudo_auth_cached(uid) => {
+                audit::record(AuditAction::Sudo, command, true);
+                return switch_user(0);
+            }
+            SudoPermission::Password => {}
+        }
         axhal::console::putchar(b'[');
         axhal::console::putchar(b's');
         axhal::console::putchar(b'u');
@@ -176,15 +241,13 @@ pub fn sudo() -> AxResult {
         axhal::console::putchar(b':');
         axhal::console::putchar(b' ');
         let password = get_password();
-        if axuser::api::is_sudoer(name) {
-            if !axuser::api::verify(uid, password) {
-                return Err(AxError::AuthenticationFailure);
-            } else {
-                axfs::api::set_current_uid(0)
-            }
-        } else {
-            Err(PermissionDenied)
+        if !axuser::api::verify(uid, password) {
+            audit::record(AuditAction::Sudo, command, false);
+            return Err(AxError::AuthenticationFailure);
         }
+        axuser::api::sudo_record_auth(uid);
+        audit::record(AuditAction::Sudo, command, true);
+        switch_user(0)
     }
 }
 
@@ -244,55 +307,25 @@ pub fn ax_setpassword() -> AxResult {
         if !axuser::api::verify(current_uid().unwrap_or(0), password) {
             return Err(AxError::AuthenticationFailure);
         }
-        axhal::console::putchar(b'N');
-        axhal::console::putchar(b'e');
-        axhal::console::putchar(b'w');
-        axhal::console::putchar(b' ');
-        axhal::console::putchar(b'p');
-        axhal::console::putchar(b'a');
-        axhal::console::putchar(b's');
-        axhal::console::putchar(b's');
-        axhal::console::putchar(b'w');
-        axhal::console::putchar(b'o');
-        axhal::console::putchar(b'r');
-        axhal::console::putchar(b'd');
-        axhal::console::putchar(b':');
-        axhal::console::putchar(b' ');
-        let password1 = get_password();
-        axhal::console::putchar(b'R');
-        axhal::console::putchar(b'e');
-        axhal::console::putchar(b't');
-        axhal::console::putchar(b'y');
-        axhal::console::putchar(b'p');
-        axhal::console::putchar(b'e');
-        axhal::console::putchar(b' ');
-        axhal::console::putchar(b'n');
-        axhal::console::putchar(b'e');
-        axhal::console::putchar(b'w');
-        axhal::console::putchar(b' ');
-        axhal::console::putchar(b'p');
-        axhal::console::putchar(b'a');
-        axhal::console::putchar(b's');
-        axhal::console::putchar(b's');
-        axhal::console::putchar(b'w');
-        axhal::console::putchar(b'o');
-        axhal::console::putchar(b'r');
-        axhal::console::putchar(b'd');
-        axhal::console::putchar(b':');
-        axhal::console::putchar(b' ');
-        let password2 = get_password();
-        if password1 == password2 {
-            return axuser::api::set_password(password1);
-        }
-        let pnm = "Sorry, passwords do not match.\n";
-        for c in pnm.as_bytes() {
-            axhal::console::putchar(*c);
-        }
-        return Err(AxError::AuthenticationFailure);
+        return axuser::api::set_password(read_new_password()?);
     }
     Err(PermissionDenied)
 }
 
+/// Asks for a new password twice, and returns it if both inputs match.
+#[cfg(feature = "user")]
+pub(crate) fn read_new_password() -> AxResult<String> {
+    axhal::console::write_bytes(b"New password: ");
+    let password1 = get_password();
+    axhal::console::write_bytes(b"Retype new password: ");
+    let password2 = get_password();
+    if password1 == password2 {
+        return Ok(password1);
+    }
+    axhal::console::write_bytes(b"Sorry, passwords do not match.\n");
+    Err(AxError::AuthenticationFailure)
+}
+
 #[allow(unused)]
 fn get_password() -> String {
     let mut password = String::new();
@@ -6,6 +6,11 @@ cfg_fs! {
     pub use fs::*;
 }
 
+cfg_user! {
+    mod user;
+    pub use user::*;
+}
+
 cfg_net! {
     mod net;
     pub use net::*;
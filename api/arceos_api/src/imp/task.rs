@@ -67,10 +67,45 @@ cfg_task! {
         }
     }
 
-    pub fn ax_wait_for_exit(task: AxTaskHandle) -> Option<i32> {
+    /// Scheduling policies of tasks.
+    pub type AxSchedPolicy = axtask::SchedPolicy;
+
+    pub fn ax_spawn_with_policy<F>(
+        f: F,
+        name: alloc::string::String,
+        stack_size: usize,
+        policy: AxSchedPolicy,
+        prio: isize,
+    ) -> crate::AxResult<AxTaskHandle>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        if policy.is_realtime() && !axtask::capable(axtask::PrivCap::SYS_NICE) {
+            return axerrno::ax_err!(PermissionDenied);
+        }
+        match axtask::spawn_with_policy(f, name, stack_size, policy, prio) {
+            Some(inner) => Ok(AxTaskHandle {
+                id: inner.id().as_u64(),
+                inner,
+            }),
+            None => axerrno::ax_err!(
+                InvalidInput,
+                "ax_spawn_with_policy: unsupported policy or priority"
+            ),
+        }
+    }
+
+    /// The error of joining a task.
+    pub type AxJoinError = axtask::JoinError;
+
+    pub fn ax_wait_for_exit(task: AxTaskHandle) -> Result<i32, AxJoinError> {
         task.inner.join()
     }
 
+    pub fn ax_detach_task(task: AxTaskHandle) {
+        task.inner.detach();
+    }
+
     pub fn ax_set_current_priority(prio: isize) -> crate::AxResult {
         if axtask::set_priority(prio) {
             Ok(())
@@ -82,6 +117,23 @@ cfg_task! {
         }
     }
 
+    #[cfg(feature = "sched_edf")]
+    pub fn ax_set_current_deadline(
+        params: Option<(Duration, Duration, Duration)>,
+    ) -> crate::AxResult {
+        if params.is_some() && !axtask::capable(axtask::PrivCap::SYS_NICE) {
+            return axerrno::ax_err!(PermissionDenied);
+        }
+        if axtask::set_deadline(axtask::current().as_task_ref(), params) {
+            Ok(())
+        } else {
+            axerrno::ax_err!(
+                InvalidInput,
+                "ax_set_current_deadline: invalid parameters or not enough bandwidth"
+            )
+        }
+    }
+
     pub fn ax_wait_queue_wait(
         wq: &AxWaitQueueHandle,
         until_condition: impl Fn() -> bool,
@@ -99,6 +151,41 @@ cfg_task! {
         false
     }
 
+    /// User and group identities of a task.
+    pub type AxCredentials = axtask::Credentials;
+    /// Privileges of a task.
+    pub type AxPrivCap = axtask::PrivCap;
+
+    fn update_credentials(f: impl FnOnce(&mut AxCredentials) -> bool) -> crate::AxResult {
+        let curr = axtask::current();
+        let mut cred = curr.credentials();
+        if !f(&mut cred) {
+            return axerrno::ax_err!(PermissionDenied);
+        }
+        curr.set_credentials(cred);
+        Ok(())
+    }
+
+    pub fn ax_current_credentials() -> AxCredentials {
+        axtask::current().credentials()
+    }
+
+    pub fn ax_cred_setuid(uid: u32) -> crate::AxResult {
+        update_credentials(|cred| cred.setuid(uid))
+    }
+
+    pub fn ax_cred_setgid(gid: u32) -> crate::AxResult {
+        update_credentials(|cred| cred.setgid(gid))
+    }
+
+    pub fn ax_cred_setgroups(groups: &[u32]) -> crate::AxResult {
+        update_credentials(|cred| cred.setgroups(groups))
+    }
+
+    pub fn ax_cred_set_caps(permitted: AxPrivCap, effective: AxPrivCap) -> crate::AxResult {
+        update_credentials(|cred| cred.set_caps(permitted, effective))
+    }
+
     pub fn ax_wait_queue_wake(wq: &AxWaitQueueHandle, count: u32) {
         if count == u32::MAX {
             wq.0.notify_all(true);
@@ -0,0 +1,60 @@
+use alloc::vec::Vec;
+use axerrno::{ax_err, AxResult};
+use axtask::PrivCap;
+
+pub use axfs::audit::AuditEvent as AxAuditEvent;
+pub use axuser::api::UserChanges as AxUserChanges;
+pub use axuser::api::UserInfo as AxUserInfo;
+pub use axuser::session::LastEntry as AxLastEntry;
+pub use axuser::session::RecordKind as AxRecordKind;
+pub use axuser::session::Session as AxSession;
+pub use axuser::session::SessionEnd as AxSessionEnd;
+pub use axuser::session::SessionRecord as AxSessionRecord;
+
+pub fn ax_add_user(name: &str) -> AxResult<u32> {
+    Ok(axuser::api::add_user(name)?)
+}
+
+pub fn ax_delete_user(name: &str) -> AxResult {
+    Ok(axuser::api::delete_user(name)?)
+}
+
+pub fn ax_modify_user(name: &str, changes: &AxUserChanges) -> AxResult {
+    Ok(axuser::api::modify_user(name, changes)?)
+}
+
+pub fn ax_lock_user(name: &str, lock: bool) -> AxResult {
+    Ok(axuser::api::lock_user(name, lock)?)
+}
+
+pub fn ax_set_user_password(name: &str) -> AxResult {
+    // check before asking for the password
+    if !axtask::current().credentials().is_privileged() {
+        return ax_err!(PermissionDenied);
+    }
+    let password = super::fs::read_new_password()?;
+    Ok(axuser::api::set_user_password(name, &password)?)
+}
+
+pub fn ax_list_users() -> AxResult<Vec<AxUserInfo>> {
+    Ok(axuser::api::list_users()?)
+}
+
+pub fn ax_audit_events(count: usize) -> AxResult<Vec<AxAuditEvent>> {
+    if !axtask::capable(PrivCap::AUDIT_READ) {
+        return ax_err!(PermissionDenied);
+    }
+    Ok(axfs::audit::recent(count))
+}
+
+pub fn ax_current_session() -> Option<AxSession> {
+    axuser::session::current()
+}
+
+pub fn ax_who() -> AxResult<Vec<AxSessionRecord>> {
+    axuser::session::who()
+}
+
+pub fn ax_last(count: usize) -> AxResult<Vec<AxLastEntry>> {
+    axuser::session::last(count)
+}
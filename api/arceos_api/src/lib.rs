@@ -87,8 +87,16 @@ pub mod task {
         @cfg "multitask";
         pub type AxTaskHandle;
         pub type AxWaitQueueHandle;
+        pub type AxCredentials;
+        pub type AxPrivCap;
+        pub type AxSchedPolicy;
+        pub type AxJoinError;
     }
 
+    /// Task-local storage.
+    #[cfg(feature = "multitask")]
+    pub use axtask::{task_local, LocalKey};
+
     define_api! {
         /// Current task is going to sleep, it will be woken up at the given deadline.
         ///
@@ -116,9 +124,20 @@ pub mod task {
             name: alloc::string::String,
             stack_size: usize
         ) -> AxTaskHandle;
+        /// Spawns a new task with the given scheduling policy and static
+        /// priority. Real-time policies need the `SYS_NICE` capability.
+        pub fn ax_spawn_with_policy(
+            f: impl FnOnce() + Send + 'static,
+            name: alloc::string::String,
+            stack_size: usize,
+            policy: AxSchedPolicy,
+            prio: isize,
+        ) -> crate::AxResult<AxTaskHandle>;
         /// Waits for the given task to exit, and returns its exit code (the
-        /// argument of [`ax_exit`]).
-        pub fn ax_wait_for_exit(task: AxTaskHandle) -> Option<i32>;
+        /// argument of [`ax_exit`]), or the error if it panicked.
+        pub fn ax_wait_for_exit(task: AxTaskHandle) -> Result<i32, AxJoinError>;
+        /// Detaches the given task, which will not be waited for.
+        pub fn ax_detach_task(task: AxTaskHandle);
         /// Sets the priority of the current task.
         pub fn ax_set_current_priority(prio: isize) -> crate::AxResult;
 
@@ -135,9 +154,43 @@ pub mod task {
         /// The maximum number of tasks to wake up is specified by `count`. If
         /// `count` is `u32::MAX`, it will wake up all tasks in the wait queue.
         pub fn ax_wait_queue_wake(wq: &AxWaitQueueHandle, count: u32);
+
+        /// Returns the user and group identities of the current task.
+        pub fn ax_current_credentials() -> AxCredentials;
+        /// Changes the user IDs of the current task like `setuid(2)`.
+        pub fn ax_cred_setuid(uid: u32) -> crate::AxResult;
+        /// Changes the group IDs of the current task like `setgid(2)`.
+        pub fn ax_cred_setgid(gid: u32) -> crate::AxResult;
+        /// Replaces the supplementary groups of the current task.
+        pub fn ax_cred_setgroups(groups: &[u32]) -> crate::AxResult;
+        /// Replaces the capabilities of the current task like `capset(2)`.
+        pub fn ax_cred_set_caps(permitted: AxPrivCap, effective: AxPrivCap) -> crate::AxResult;
+    }
+
+    define_api! {
+        @cfg "sched_edf";
+
+        /// Reserves `runtime` of CPU time before `deadline` in every `period`
+        /// (in this order) for the current task, or gives up the reservation
+        /// if `params` is `None`. Reserving needs the `SYS_NICE` capability.
+        pub fn ax_set_current_deadline(
+            params: Option<(core::time::Duration, core::time::Duration, core::time::Duration)>
+        ) -> crate::AxResult;
     }
 }
 
+/// Synchronization primitives blocking the current task in wait queues.
+pub mod sync {
+    #[cfg(feature = "multitask")]
+    pub use axsync::{
+        mpsc, oneshot, Barrier, BarrierWaitResult, Condvar, Mutex, MutexGuard, Once, RwLock,
+        RwLockReadGuard, RwLockWriteGuard, Semaphore, SemaphoreGuard,
+    };
+
+    #[cfg(all(feature = "multitask", feature = "irq"))]
+    pub use axsync::WaitTimeoutResult;
+}
+
 /// Filesystem manipulation operations.
 pub mod fs {
     use crate::AxResult;
@@ -152,6 +205,7 @@ pub mod fs {
         pub type AxFilePerm;
         pub type AxDirEntry;
         pub type AxSeekFrom;
+        pub type AxFileSystemInfo;
         #[cfg(feature = "myfs")]
         pub type AxDisk;
         #[cfg(feature = "myfs")]
@@ -206,8 +260,9 @@ pub mod fs {
         /// After the read, the cursor of the directory will be advanced by the
         /// number of entries read.
         pub fn ax_read_dir(dir: &mut AxDirHandle, dirents: &mut [AxDirEntry]) -> AxResult<usize>;
-        /// Creates a new, empty directory at the provided path.
-        pub fn ax_create_dir(path: &str) -> AxResult;
+        /// Creates a new, empty directory at the provided path, with the
+        /// permission bits `mode` minus the umask of the current task.
+        pub fn ax_create_dir(path: &str, mode: u32) -> AxResult;
         /// Removes an empty directory.
         ///
         /// If the directory is not empty, it will return an error.
@@ -219,20 +274,77 @@ pub mod fs {
         /// It will delete the original file if `old` already exists.
         pub fn ax_rename(old: &str, new: &str) -> AxResult;
 
+        /// Returns the attributes of the filesystem that contains the given path.
+        pub fn ax_statfs(path: &str) -> AxResult<AxFileSystemInfo>;
+        /// Returns the paths of all mount points, starting with the root `/`.
+        pub fn ax_mount_points() -> AxResult<alloc::vec::Vec<alloc::string::String>>;
+
         /// Returns the current working directory.
         pub fn ax_current_dir() -> AxResult<alloc::string::String>;
         /// Changes the current working directory to the specified path.
         pub fn ax_set_current_dir(path: &str) -> AxResult;
+        /// Sets the file mode creation mask of the current task, and returns
+        /// the previous mask.
+        pub fn ax_umask(mask: u16) -> u16;
 
         /// Returns the current user id.
         pub fn ax_getuid() -> AxResult<u32>;
         /// Changes the current user id to the specified id.
+        ///
+        /// This requires the `SETUID` capability, and the groups change too
+        /// with the `SETGID` capability. Otherwise the password of the user is
+        /// asked.
         pub fn ax_setuid(uid: u32) -> AxResult;
-        /// Changes the current user id to 0 if the current user is a sudoer.
-        pub fn sudo() -> AxResult;
+        /// Changes the current user id to 0 if `/etc/sudoers` allows the
+        /// current user to run `command` as root, or the current task has the
+        /// `SETUID` capability.
+        ///
+        /// The password is asked unless the entry is `NOPASSWD` or the user
+        /// has authenticated recently.
+        pub fn sudo(command: &str) -> AxResult;
         /// Changes the current user password.
         pub fn ax_setpassword() -> AxResult;
     }
+
+    define_api_type! {
+        @cfg "user";
+        pub type AxUserInfo;
+        pub type AxUserChanges;
+        pub type AxAuditEvent;
+        pub type AxSession;
+        pub type AxSessionRecord;
+        pub type AxRecordKind;
+        pub type AxLastEntry;
+        pub type AxSessionEnd;
+    }
+
+    define_api! {
+        @cfg "user";
+
+        /// Creates a user with a group of the same name and a home directory,
+        /// and returns its user id.
+        pub fn ax_add_user(name: &str) -> AxResult<u32>;
+        /// Deletes a user and its group of the same name.
+        pub fn ax_delete_user(name: &str) -> AxResult;
+        /// Changes the comment, home directory or login shell of a user.
+        pub fn ax_modify_user(name: &str, changes: &AxUserChanges) -> AxResult;
+        /// Locks or unlocks the password of a user.
+        pub fn ax_lock_user(name: &str, lock: bool) -> AxResult;
+        /// Asks for a new password of a user on the console, and sets it.
+        pub fn ax_set_user_password(name: &str) -> AxResult;
+        /// Returns all users, ordered by user id.
+        pub fn ax_list_users() -> AxResult<alloc::vec::Vec<AxUserInfo>>;
+        /// Returns up to `count` of the most recent security audit events,
+        /// oldest first.
+        pub fn ax_audit_events(count: usize) -> AxResult<alloc::vec::Vec<AxAuditEvent>>;
+        /// Returns the login session of the console, if a user is logged in.
+        pub fn ax_current_session() -> Option<AxSession>;
+        /// Returns the active login sessions.
+        pub fn ax_who() -> AxResult<alloc::vec::Vec<AxSessionRecord>>;
+        /// Returns up to `count` of the most recent login sessions, newest
+        /// first.
+        pub fn ax_last(count: usize) -> AxResult<alloc::vec::Vec<AxLastEntry>>;
+    }
 }
 
 /// Networking primitives for TCP/UDP communication.
@@ -66,6 +66,10 @@ macro_rules! cfg_fs {
     ($($item:item)*) => { _cfg_common!{ "fs" $($item)* } }
 }
 
+macro_rules! cfg_user {
+    ($($item:item)*) => { _cfg_common!{ "user" $($item)* } }
+}
+
 macro_rules! cfg_net {
     ($($item:item)*) => { _cfg_common!{ "net" $($item)* } }
 }
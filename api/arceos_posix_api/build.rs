@@ -1,17 +1,26 @@
 fn main() {
     use std::io::Write;
 
-    fn gen_pthread_mutex(out_file: &str) -> std::io::Result<()> {
+    fn gen_pthread_types(out_file: &str) -> std::io::Result<()> {
         // TODO: generate size and initial content automatically.
-        let (mutex_size, mutex_init) = if cfg!(feature = "multitask") {
-            if cfg!(feature = "smp") {
-                (6, "{0, 8, 0, 0, 0, 0}") // core::mem::transmute::<_, [usize; 6]>(axsync::Mutex::new(()))
+        let ((mutex_size, mutex_init), (cond_size, cond_init), (rwlock_size, rwlock_init)) =
+            if cfg!(feature = "multitask") {
+                if cfg!(feature = "smp") {
+                    (
+                        (8, "{0, 0, 0, 0, 8, 0, 0, 0}"), // core::mem::transmute::<_, [usize; 8]>(axsync::Mutex::new(()))
+                        (6, "{0, 0, 8, 0, 0, 0}"), // core::mem::transmute::<_, [usize; 6]>(axsync::Condvar::new())
+                        (7, "{0, 0, 8, 0, 0, 0, 0}"), // core::mem::transmute::<_, [usize; 7]>(axsync::RwLock::new(()))
+                    )
+                } else {
+                    (
+                        (6, "{0, 8, 0, 0, 0, 0}"), // core::mem::transmute::<_, [usize; 6]>(axsync::Mutex::new(()))
+                        (5, "{0, 8, 0, 0, 0}"), // core::mem::transmute::<_, [usize; 5]>(axsync::Condvar::new())
+                        (6, "{0, 8, 0, 0, 0, 0}"), // core::mem::transmute::<_, [usize; 6]>(axsync::RwLock::new(()))
+                    )
+                }
             } else {
-                (5, "{8, 0, 0, 0, 0}") // core::mem::transmute::<_, [usize; 5]>(axsync::Mutex::new(()))
-            }
-        } else {
-            (1, "{0}")
-        };
+                ((1, "{0}"), (1, "{0}"), (1, "{0}"))
+            };
 
         let mut output = Vec::new();
         writeln!(
@@ -26,6 +35,18 @@ typedef struct {{
 }} pthread_mutex_t;
 
 #define PTHREAD_MUTEX_INITIALIZER {{ .__l = {mutex_init}}}
+
+typedef struct {{
+    long __l[{cond_size}];
+}} pthread_cond_t;
+
+#define PTHREAD_COND_INITIALIZER {{ .__l = {cond_init}}}
+
+typedef struct {{
+    long __l[{rwlock_size}];
+}} pthread_rwlock_t;
+
+#define PTHREAD_RWLOCK_INITIALIZER {{ .__l = {rwlock_init}}}
 "#
         )?;
         std::fs::write(out_file, output)?;
@@ -37,10 +58,13 @@ typedef struct {{
 
         let allow_types = [
             "stat",
+            "statfs",
             "size_t",
             "ssize_t",
             "off_t",
             "mode_t",
+            "uid_t",
+            "gid_t",
             "sock.*",
             "fd_set",
             "timeval",
@@ -48,7 +72,15 @@ typedef struct {{
             "pthread_attr_t",
             "pthread_mutex_t",
             "pthread_mutexattr_t",
+            "pthread_cond_t",
+            "pthread_condattr_t",
+            "pthread_rwlock_t",
+            "pthread_rwlockattr_t",
+            "sem_t",
+            "cpu_set_t",
+            "sched_param",
             "epoll_event",
+            "inotify_event",
             "iovec",
             "clockid_t",
             "rlimit",
@@ -64,7 +96,12 @@ typedef struct {{
             "_SC_.*",
             "EPOLL_CTL_.*",
             "EPOLL.*",
+            "IN_.*",
+            "PROT_.*",
+            "MAP_.*",
+            "MS_.*",
             "RLIMIT_.*",
+            "SCHED_.*",
             "EAI_.*",
             "MAXADDRS",
         ];
@@ -74,7 +111,7 @@ typedef struct {{
 
         impl bindgen::callbacks::ParseCallbacks for MyCallbacks {
             fn include_file(&self, fname: &str) {
-                if !fname.contains("ax_pthread_mutex.h") {
+                if !fname.contains("ax_pthread_types.h") {
                     println!("cargo:rerun-if-changed={}", fname);
                 }
             }
@@ -101,6 +138,6 @@ typedef struct {{
             .expect("Couldn't write bindings!");
     }
 
-    gen_pthread_mutex("../../ulib/axlibc/include/ax_pthread_mutex.h").unwrap();
+    gen_pthread_types("../../ulib/axlibc/include/ax_pthread_types.h").unwrap();
     gen_c_to_rust_bindings("ctypes.h", "src/ctypes_gen.rs");
 }
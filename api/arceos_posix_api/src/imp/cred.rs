@@ -0,0 +1,91 @@
+use crate::ctypes;
+use axerrno::{LinuxError, LinuxResult};
+use axtask::Credentials;
+use core::ffi::c_int;
+
+fn update_credentials(f: impl FnOnce(&mut Credentials) -> bool) -> LinuxResult<c_int> {
+    let curr = axtask::current();
+    let mut cred = curr.credentials();
+    if !f(&mut cred) {
+        return Err(LinuxError::EPERM);
+    }
+    curr.set_credentials(cred);
+    Ok(0)
+}
+
+/// Get the real user ID of the current task.
+pub fn sys_getuid() -> ctypes::uid_t {
+    axtask::current().credentials().ruid as _
+}
+
+/// Get the effective user ID of the current task.
+pub fn sys_geteuid() -> ctypes::uid_t {
+    axtask::current().credentials().euid as _
+}
+
+/// Get the real group ID of the current task.
+pub fn sys_getgid() -> ctypes::gid_t {
+    axtask::current().credentials().rgid as _
+}
+
+/// Get the effective group ID of the current task.
+pub fn sys_getegid() -> ctypes::gid_t {
+    axtask::current().credentials().egid as _
+}
+
+/// Set the user IDs of the current task.
+///
+/// Return `EPERM` if the task is not privileged and `uid` is neither its real
+/// nor its saved user ID.
+pub fn sys_setuid(uid: ctypes::uid_t) -> c_int {
+    debug!("sys_setuid <= {}", uid);
+    syscall_body!(sys_setuid, update_credentials(|cred| cred.setuid(uid as _)))
+}
+
+/// Set the group IDs of the current task.
+///
+/// Return `EPERM` if the task is not privileged and `gid` is neither its real
+/// nor its saved group ID.
+pub fn sys_setgid(gid: ctypes::gid_t) -> c_int {
+    debug!("sys_setgid <= {}", gid);
+    syscall_body!(sys_setgid, update_credentials(|cred| cred.setgid(gid as _)))
+}
+
+/// Get the supplementary group IDs of the current task.
+///
+/// If `size` is 0, only return the number of groups. Otherwise return
+/// `EINVAL` if `list` cannot hold all of them.
+pub unsafe fn sys_getgroups(size: c_int, list: *mut ctypes::gid_t) -> c_int {
+    debug!("sys_getgroups <= {} {:#x}", size, list as usize);
+    syscall_body!(sys_getgroups, {
+        let groups = axtask::current().credentials().groups;
+        if size == 0 {
+            return Ok(groups.len() as c_int);
+        }
+        if size < 0 || (size as usize) < groups.len() {
+            return Err(LinuxError::EINVAL);
+        }
+        if list.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        for (i, &gid) in groups.iter().enumerate() {
+            unsafe { *list.add(i) = gid as _ };
+        }
+        Ok(groups.len() as c_int)
+    })
+}
+
+/// Set the supplementary group IDs of the current task.
+///
+/// Return `EPERM` if the task is not privileged.
+pub unsafe fn sys_setgroups(size: usize, list: *const ctypes::gid_t) -> c_int {
+    debug!("sys_setgroups <= {} {:#x}", size, list as usize);
+    syscall_body!(sys_setgroups, {
+        if size > 0 && list.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let groups: alloc::vec::Vec<u32> =
+            (0..size).map(|i| unsafe { *list.add(i) } as u32).collect();
+        update_credentials(|cred| cred.setgroups(&groups))
+    })
+}
@@ -1,4 +1,6 @@
 use alloc::sync::Arc;
+#[cfg(feature = "mmap")]
+use core::borrow::Borrow;
 use core::ffi::{c_char, c_int};
 
 use axerrno::{LinuxError, LinuxResult};
@@ -34,11 +36,35 @@ impl File {
 
 impl FileLike for File {
     fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
-        Ok(self.inner.lock().read(buf)?)
+        let mut file = self.inner.lock();
+        // go through the pages of the shared mappings of the file, and keep
+        // the locks of the filesystem away from `buf`, which may map a file
+        #[cfg(feature = "mmap")]
+        {
+            let pos = file.seek(SeekFrom::Current(0))?;
+            let n = axmm::read_file(&MappedFile(&*file), pos, buf)?;
+            file.seek(SeekFrom::Start(pos + n as u64))?;
+            Ok(n)
+        }
+        #[cfg(not(feature = "mmap"))]
+        Ok(file.read(buf)?)
     }
 
     fn write(&self, buf: &[u8]) -> LinuxResult<usize> {
-        Ok(self.inner.lock().write(buf)?)
+        let mut file = self.inner.lock();
+        #[cfg(feature = "mmap")]
+        {
+            let pos = if file.is_append() {
+                file.get_attr()?.size()
+            } else {
+                file.seek(SeekFrom::Current(0))?
+            };
+            let n = axmm::write_file(&MappedFile(&*file), pos, buf)?;
+            file.seek(SeekFrom::Start(pos + n as u64))?;
+            Ok(n)
+        }
+        #[cfg(not(feature = "mmap"))]
+        Ok(file.write(buf)?)
     }
 
     fn stat(&self) -> LinuxResult<ctypes::stat> {
@@ -75,8 +101,55 @@ impl FileLike for File {
     }
 }
 
+/// A file mapped into memory, or accessed through the pages of its shared
+/// mappings.
+///
+/// A mapping has a handle of its own, so that page faults never wait for the
+/// lock of the [`File`], which may be held by the task accessing the mapping.
+#[cfg(feature = "mmap")]
+struct MappedFile<F>(F);
+
+#[cfg(feature = "mmap")]
+impl File {
+    /// Returns the backend to map the file with, or `EACCES` if the file is
+    /// not opened for reading.
+    pub(crate) fn mmap_backend(&self) -> LinuxResult<Arc<dyn axmm::MmapBackend>> {
+        let file = self.inner.lock();
+        if !file.is_readable() {
+            return Err(LinuxError::EACCES);
+        }
+        Ok(Arc::new(MappedFile(file.duplicate()?)))
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl<F> axmm::MmapBackend for MappedFile<F>
+where
+    F: Borrow<axfs::fops::File> + Send + Sync,
+{
+    fn file_id(&self) -> (usize, u64) {
+        self.0.borrow().file_id()
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> axerrno::AxResult<usize> {
+        self.0.borrow().read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> axerrno::AxResult<usize> {
+        self.0.borrow().write_at(offset, buf)
+    }
+
+    fn size(&self) -> axerrno::AxResult<u64> {
+        Ok(self.0.borrow().get_attr()?.size())
+    }
+
+    fn writable(&self) -> bool {
+        self.0.borrow().is_writable()
+    }
+}
+
 /// Convert open flags to [`OpenOptions`].
-fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
+fn flags_to_options(flags: c_int, mode: ctypes::mode_t) -> OpenOptions {
     let flags = flags as u32;
     let mut options = OpenOptions::new();
     match flags & 0b11 {
@@ -99,6 +172,7 @@ fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
     if flags & ctypes::O_EXEC != 0 {
         options.create_new(true);
     }
+    options.mode(mode);
     options
 }
 
@@ -110,8 +184,9 @@ pub fn sys_open(filename: *const c_char, flags: c_int, mode: ctypes::mode_t) ->
     let filename = char_ptr_to_str(filename);
     debug!("sys_open <= {:?} {:#o} {:#o}", filename, flags, mode);
     syscall_body!(sys_open, {
+        let filename = filename?;
         let options = flags_to_options(flags, mode);
-        let file = axfs::fops::File::open(filename?, &options)?;
+        let file = axfs::fops::File::open(filename, &options)?;
         File::new(file).add_to_fd_table()
     })
 }
@@ -145,7 +220,8 @@ pub unsafe fn sys_stat(path: *const c_char, buf: *mut ctypes::stat) -> c_int {
         }
         let mut options = OpenOptions::new();
         options.read(true);
-        let file = axfs::fops::File::open(path?, &options)?;
+        let path = path?;
+        let file = axfs::fops::File::open(path, &options)?;
         let st = File::new(file).stat()?;
         unsafe { *buf = st };
         Ok(0)
@@ -167,6 +243,53 @@ pub unsafe fn sys_fstat(fd: c_int, buf: *mut ctypes::stat) -> c_int {
     })
 }
 
+/// Convert filesystem attributes to a C `struct statfs`.
+fn fs_info_to_statfs(info: axfs::api::FileSystemInfo) -> ctypes::statfs {
+    ctypes::statfs {
+        f_bsize: info.block_size() as _,
+        f_blocks: info.blocks() as _,
+        f_bfree: info.blocks_free() as _,
+        f_bavail: info.blocks_free() as _,
+        f_files: info.files() as _,
+        f_ffree: info.files_free() as _,
+        f_namelen: info.name_max() as _,
+        f_frsize: info.block_size() as _,
+        ..Default::default()
+    }
+}
+
+/// Get the attributes of the filesystem containing `path` and write into `buf`.
+///
+/// Return 0 if success.
+pub unsafe fn sys_statfs(path: *const c_char, buf: *mut ctypes::statfs) -> c_int {
+    let path = char_ptr_to_str(path);
+    debug!("sys_statfs <= {:?} {:#x}", path, buf as usize);
+    syscall_body!(sys_statfs, {
+        if buf.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let info = axfs::api::statfs(path?)?;
+        unsafe { *buf = fs_info_to_statfs(info) };
+        Ok(0)
+    })
+}
+
+/// Get the attributes of the filesystem containing the file `fd` and write
+/// into `buf`.
+///
+/// Return 0 if success.
+pub unsafe fn sys_fstatfs(fd: c_int, buf: *mut ctypes::statfs) -> c_int {
+    debug!("sys_fstatfs <= {} {:#x}", fd, buf as usize);
+    syscall_body!(sys_fstatfs, {
+        if buf.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        let info = File::from_fd(fd)?.inner.lock().statfs()?;
+        unsafe { *buf = fs_info_to_statfs(info) };
+        Ok(0)
+    })
+}
+
 /// Get the metadata of the symbolic link and write into `buf`.
 ///
 /// Return 0 if success.
@@ -202,6 +325,29 @@ pub fn sys_getcwd(buf: *mut c_char, size: usize) -> *mut c_char {
     })
 }
 
+/// Create a directory `pathname` with the permission bits `mode`, before the
+/// umask is applied.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_mkdir(pathname: *const c_char, mode: ctypes::mode_t) -> c_int {
+    let pathname = char_ptr_to_str(pathname);
+    debug!("sys_mkdir <= {:?} {:#o}", pathname, mode);
+    syscall_body!(sys_mkdir, {
+        axfs::api::DirBuilder::new()
+            .mode(mode as _)
+            .create(pathname?)?;
+        Ok(0)
+    })
+}
+
+/// Set the file mode creation mask of the current task to `mask & 0o777`.
+///
+/// Return the previous mask.
+pub fn sys_umask(mask: ctypes::mode_t) -> ctypes::mode_t {
+    debug!("sys_umask <= {:#o}", mask);
+    axfs::api::set_umask(mask as u16) as _
+}
+
 /// Rename `old` to `new`
 /// If new exists, it is first removed.
 ///
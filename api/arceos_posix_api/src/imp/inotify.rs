@@ -0,0 +1,161 @@
+//! `inotify` implementation on top of [`axfs::notify`].
+
+use alloc::sync::Arc;
+use core::ffi::{c_char, c_int};
+use core::mem::size_of;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use axerrno::{LinuxError, LinuxResult};
+use axfs::notify::{Event, Inotify, WatchMask};
+use axio::PollState;
+
+use super::fd_ops::{add_file_like, get_file_like, FileLike};
+use crate::{ctypes, utils::char_ptr_to_str};
+
+pub struct InotifyInstance {
+    inner: Inotify,
+    nonblocking: AtomicBool,
+}
+
+impl InotifyInstance {
+    fn new(nonblocking: bool) -> Self {
+        Self {
+            inner: Inotify::new(),
+            nonblocking: AtomicBool::new(nonblocking),
+        }
+    }
+
+    fn from_fd(fd: c_int) -> LinuxResult<Arc<Self>> {
+        get_file_like(fd)?
+            .into_any()
+            .downcast::<Self>()
+            .map_err(|_| LinuxError::EINVAL)
+    }
+}
+
+/// Size of `struct inotify_event` followed by the null-terminated name,
+/// padded to the alignment of the header.
+fn event_size(event: &Event) -> (usize, usize) {
+    const ALIGN: usize = core::mem::align_of::<ctypes::inotify_event>();
+    let name_len = if event.name.is_empty() {
+        0
+    } else {
+        (event.name.len() + 1).next_multiple_of(ALIGN)
+    };
+    (size_of::<ctypes::inotify_event>() + name_len, name_len)
+}
+
+impl FileLike for InotifyInstance {
+    fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
+        let mut read_len = 0;
+        loop {
+            while let Some((size, name_len)) = self.inner.peek_event(event_size) {
+                if read_len + size > buf.len() {
+                    if read_len == 0 {
+                        return Err(LinuxError::EINVAL);
+                    }
+                    return Ok(read_len);
+                }
+                let event = self.inner.read_event().unwrap();
+                let header = ctypes::inotify_event {
+                    wd: event.wd,
+                    mask: event.mask.bits(),
+                    cookie: event.cookie,
+                    len: name_len as u32,
+                    ..Default::default()
+                };
+                let dst = &mut buf[read_len..read_len + size];
+                let header_size = size_of::<ctypes::inotify_event>();
+                dst[..header_size].copy_from_slice(unsafe {
+                    core::slice::from_raw_parts(&header as *const _ as *const u8, header_size)
+                });
+                dst[header_size..].fill(0);
+                dst[header_size..header_size + event.name.len()]
+                    .copy_from_slice(event.name.as_bytes());
+                read_len += size;
+            }
+            if read_len > 0 {
+                return Ok(read_len);
+            }
+            // without multitask, no other task could ever queue an event
+            if self.nonblocking.load(Ordering::Relaxed) || cfg!(not(feature = "multitask")) {
+                return Err(LinuxError::EAGAIN);
+            }
+            #[cfg(feature = "multitask")]
+            self.inner.wait_event();
+        }
+    }
+
+    fn write(&self, _buf: &[u8]) -> LinuxResult<usize> {
+        Err(LinuxError::EINVAL)
+    }
+
+    fn stat(&self) -> LinuxResult<ctypes::stat> {
+        let st_mode = 0o600u32; // rw-------
+        Ok(ctypes::stat {
+            st_ino: 1,
+            st_nlink: 1,
+            st_mode,
+            ..Default::default()
+        })
+    }
+
+    fn into_any(self: Arc<Self>) -> Arc<dyn core::any::Any + Send + Sync> {
+        self
+    }
+
+    fn poll(&self) -> LinuxResult<PollState> {
+        Ok(PollState {
+            readable: self.inner.has_events(),
+            writable: false,
+        })
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> LinuxResult {
+        self.nonblocking.store(nonblocking, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Creates a new inotify instance.
+///
+/// It returns a file descriptor referring to the new inotify instance, whose
+/// events can be read as `struct inotify_event` records.
+pub fn sys_inotify_init1(flags: c_int) -> c_int {
+    debug!("sys_inotify_init1 <= {:#x}", flags);
+    syscall_body!(sys_inotify_init1, {
+        let flags = flags as u32;
+        if flags & !(ctypes::IN_NONBLOCK | ctypes::IN_CLOEXEC) != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let instance = InotifyInstance::new(flags & ctypes::IN_NONBLOCK != 0);
+        add_file_like(Arc::new(instance))
+    })
+}
+
+/// Adds a watch on `pathname` to the inotify instance `fd`, or modifies the
+/// existing one.
+///
+/// Return the watch descriptor.
+pub fn sys_inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int {
+    let pathname = char_ptr_to_str(pathname);
+    debug!("sys_inotify_add_watch <= {} {:?} {:#x}", fd, pathname, mask);
+    syscall_body!(sys_inotify_add_watch, {
+        let instance = InotifyInstance::from_fd(fd)?;
+        let wd = instance
+            .inner
+            .add_watch(pathname?, WatchMask::from_bits_truncate(mask))?;
+        Ok(wd)
+    })
+}
+
+/// Removes the watch `wd` from the inotify instance `fd`.
+///
+/// Return 0 if success.
+pub fn sys_inotify_rm_watch(fd: c_int, wd: c_int) -> c_int {
+    debug!("sys_inotify_rm_watch <= {} {}", fd, wd);
+    syscall_body!(sys_inotify_rm_watch, {
+        InotifyInstance::from_fd(fd)?.inner.rm_watch(wd)?;
+        Ok(0)
+    })
+}
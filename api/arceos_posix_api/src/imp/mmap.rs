@@ -0,0 +1,126 @@
+//! Memory mapping functions on top of [`axmm`].
+
+use alloc::sync::Arc;
+use core::ffi::{c_int, c_void};
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::paging::MappingFlags;
+use axmm::MmapBackend;
+
+use crate::ctypes;
+
+/// Convert `PROT_*` flags to [`MappingFlags`].
+fn prot_to_flags(prot: c_int) -> LinuxResult<MappingFlags> {
+    let prot = prot as u32;
+    if prot & !(ctypes::PROT_READ | ctypes::PROT_WRITE | ctypes::PROT_EXEC) != 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let mut flags = MappingFlags::empty();
+    if prot & ctypes::PROT_READ != 0 {
+        flags |= MappingFlags::READ;
+    }
+    if prot & ctypes::PROT_WRITE != 0 {
+        flags |= MappingFlags::WRITE;
+    }
+    if prot & ctypes::PROT_EXEC != 0 {
+        flags |= MappingFlags::EXECUTE;
+    }
+    Ok(flags)
+}
+
+#[cfg(feature = "fs")]
+fn file_backend(fd: c_int) -> LinuxResult<Arc<dyn MmapBackend>> {
+    super::fd_ops::get_file_like(fd)?
+        .into_any()
+        .downcast::<super::fs::File>()
+        .map_err(|_| LinuxError::ENODEV)?
+        .mmap_backend()
+}
+
+#[cfg(not(feature = "fs"))]
+fn file_backend(_fd: c_int) -> LinuxResult<Arc<dyn MmapBackend>> {
+    Err(LinuxError::ENODEV)
+}
+
+/// Map `len` bytes of the file `fd` starting at `off`, or anonymous memory
+/// if `MAP_ANONYMOUS` is set in `flags`.
+///
+/// Pages are allocated and read from the file on the first access. Return the
+/// start address of the mapping.
+pub fn sys_mmap(
+    addr: *mut c_void,
+    len: ctypes::size_t,
+    prot: c_int,
+    flags: c_int,
+    fd: c_int,
+    off: ctypes::off_t,
+) -> *mut c_void {
+    debug!(
+        "sys_mmap <= {:#x} {:#x} {:#x} {:#x} {} {:#x}",
+        addr as usize, len, prot, flags, fd, off
+    );
+    syscall_body!(sys_mmap, {
+        let flags = flags as u32;
+        let shared = match flags & ctypes::MAP_TYPE {
+            ctypes::MAP_SHARED | ctypes::MAP_SHARED_VALIDATE => true,
+            ctypes::MAP_PRIVATE => false,
+            _ => return Err(LinuxError::EINVAL),
+        };
+        let backend = if flags & ctypes::MAP_ANONYMOUS != 0 {
+            None
+        } else if off < 0 {
+            return Err(LinuxError::EINVAL);
+        } else {
+            Some((file_backend(fd)?, off as u64))
+        };
+        let start = axmm::mmap(
+            (addr as usize).into(),
+            len as usize,
+            prot_to_flags(prot)?,
+            shared,
+            flags & ctypes::MAP_FIXED != 0,
+            backend,
+        )?;
+        Ok(start.as_usize() as *mut c_void)
+    })
+}
+
+/// Remove the mappings in the range `[addr, addr + len)`.
+///
+/// Changes of shared file mappings are written back to the file.
+pub fn sys_munmap(addr: *mut c_void, len: ctypes::size_t) -> c_int {
+    debug!("sys_munmap <= {:#x} {:#x}", addr as usize, len);
+    syscall_body!(sys_munmap, {
+        axmm::munmap((addr as usize).into(), len as usize)?;
+        Ok(0)
+    })
+}
+
+/// Write back the changes of shared file mappings in the range
+/// `[addr, addr + len)`.
+pub fn sys_msync(addr: *mut c_void, len: ctypes::size_t, flags: c_int) -> c_int {
+    debug!("sys_msync <= {:#x} {:#x} {:#x}", addr as usize, len, flags);
+    syscall_body!(sys_msync, {
+        let flags = flags as u32;
+        if flags & !(ctypes::MS_ASYNC | ctypes::MS_SYNC | ctypes::MS_INVALIDATE) != 0
+            || flags & ctypes::MS_ASYNC != 0 && flags & ctypes::MS_SYNC != 0
+        {
+            return Err(LinuxError::EINVAL);
+        }
+        axmm::msync((addr as usize).into(), len as usize)?;
+        Ok(0)
+    })
+}
+
+/// Change the access protection of the mappings in the range
+/// `[addr, addr + len)` to `prot`.
+pub fn sys_mprotect(addr: *mut c_void, len: ctypes::size_t, prot: c_int) -> c_int {
+    debug!(
+        "sys_mprotect <= {:#x} {:#x} {:#x}",
+        addr as usize, len, prot
+    );
+    syscall_body!(sys_mprotect, {
+        axmm::mprotect((addr as usize).into(), len as usize, prot_to_flags(prot)?)?;
+        Ok(0)
+    })
+}
@@ -6,15 +6,23 @@ pub mod sys;
 pub mod task;
 pub mod time;
 
+#[cfg(feature = "multitask")]
+pub mod cred;
 #[cfg(feature = "fd")]
 pub mod fd_ops;
 #[cfg(feature = "fs")]
 pub mod fs;
+#[cfg(feature = "fs")]
+pub mod inotify;
 #[cfg(any(feature = "select", feature = "epoll"))]
 pub mod io_mpx;
+#[cfg(feature = "mmap")]
+pub mod mmap;
 #[cfg(feature = "net")]
 pub mod net;
 #[cfg(feature = "pipe")]
 pub mod pipe;
 #[cfg(feature = "multitask")]
 pub mod pthread;
+#[cfg(feature = "multitask")]
+pub mod semaphore;
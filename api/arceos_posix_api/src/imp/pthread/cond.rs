@@ -0,0 +1,141 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axerrno::LinuxResult;
+use axsync::Condvar;
+
+use core::ffi::c_int;
+use core::mem::{size_of, ManuallyDrop};
+
+use super::mutex::PthreadMutex;
+
+static_assertions::const_assert_eq!(
+    size_of::<PthreadCond>(),
+    size_of::<ctypes::pthread_cond_t>()
+);
+
+#[repr(C)]
+pub struct PthreadCond(Condvar);
+
+impl PthreadCond {
+    const fn new() -> Self {
+        Self(Condvar::new())
+    }
+
+    fn wait(&self, mutex: &PthreadMutex) -> LinuxResult {
+        // the mutex is locked by the caller, whose guard has been forgotten
+        let guard = unsafe { mutex.0.make_guard_unchecked() };
+        let _guard = ManuallyDrop::new(self.0.wait(guard));
+        Ok(())
+    }
+
+    fn timed_wait(&self, mutex: &PthreadMutex, abstime: *const ctypes::timespec) -> LinuxResult {
+        let timeout = crate::imp::time::abstime_to_timeout(abstime)?;
+        #[cfg(feature = "irq")]
+        {
+            let guard = unsafe { mutex.0.make_guard_unchecked() };
+            let (guard, res) = self.0.wait_timeout(guard, timeout);
+            let _guard = ManuallyDrop::new(guard);
+            if res.timed_out() {
+                return Err(axerrno::LinuxError::ETIMEDOUT);
+            }
+            Ok(())
+        }
+        #[cfg(not(feature = "irq"))]
+        {
+            warn!("pthread_cond_timedwait: the timeout {timeout:?} is ignored without the `irq` feature");
+            self.wait(mutex)
+        }
+    }
+}
+
+/// Initialize a condition variable.
+pub fn sys_pthread_cond_init(
+    cond: *mut ctypes::pthread_cond_t,
+    _attr: *const ctypes::pthread_condattr_t,
+) -> c_int {
+    debug!("sys_pthread_cond_init <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_init, {
+        check_null_mut_ptr(cond)?;
+        unsafe {
+            cond.cast::<PthreadCond>().write(PthreadCond::new());
+        }
+        Ok(0)
+    })
+}
+
+/// Destroy a condition variable.
+pub fn sys_pthread_cond_destroy(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_destroy <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_destroy, {
+        check_null_mut_ptr(cond)?;
+        unsafe {
+            cond.cast::<PthreadCond>().drop_in_place();
+        }
+        Ok(0)
+    })
+}
+
+/// Release the locked mutex, and block on the condition variable until it is
+/// signaled. The mutex is locked again before returning.
+pub fn sys_pthread_cond_wait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+) -> c_int {
+    debug!(
+        "sys_pthread_cond_wait <= {:#x} {:#x}",
+        cond as usize, mutex as usize
+    );
+    syscall_body!(sys_pthread_cond_wait, {
+        check_null_mut_ptr(cond)?;
+        check_null_mut_ptr(mutex)?;
+        unsafe {
+            (*cond.cast::<PthreadCond>()).wait(&*mutex.cast::<PthreadMutex>())?;
+        }
+        Ok(0)
+    })
+}
+
+/// Like `sys_pthread_cond_wait`, but fails with `ETIMEDOUT` if the condition
+/// variable is not signaled before the absolute time `abstime`.
+pub fn sys_pthread_cond_timedwait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    debug!(
+        "sys_pthread_cond_timedwait <= {:#x} {:#x}",
+        cond as usize, mutex as usize
+    );
+    syscall_body!(sys_pthread_cond_timedwait, {
+        check_null_mut_ptr(cond)?;
+        check_null_mut_ptr(mutex)?;
+        unsafe {
+            (*cond.cast::<PthreadCond>()).timed_wait(&*mutex.cast::<PthreadMutex>(), abstime)?;
+        }
+        Ok(0)
+    })
+}
+
+/// Wake up one task blocked on the condition variable.
+pub fn sys_pthread_cond_signal(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_signal <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_signal, {
+        check_null_mut_ptr(cond)?;
+        unsafe {
+            (*cond.cast::<PthreadCond>()).0.notify_one();
+        }
+        Ok(0)
+    })
+}
+
+/// Wake up all tasks blocked on the condition variable.
+pub fn sys_pthread_cond_broadcast(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_broadcast <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_broadcast, {
+        check_null_mut_ptr(cond)?;
+        unsafe {
+            (*cond.cast::<PthreadCond>()).0.notify_all();
+        }
+        Ok(0)
+    })
+}
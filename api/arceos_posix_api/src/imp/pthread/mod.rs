@@ -3,12 +3,14 @@ use core::cell::UnsafeCell;
 use core::ffi::{c_int, c_void};
 
 use axerrno::{LinuxError, LinuxResult};
-use axtask::AxTaskRef;
+use axtask::{AxTaskRef, PrivCap, SchedPolicy};
 use spin::RwLock;
 
 use crate::ctypes;
 
+pub mod cond;
 pub mod mutex;
+pub mod rwlock;
 
 lazy_static::lazy_static! {
     static ref TID_TO_PTHREAD: RwLock<BTreeMap<u64, ForceSendSync<ctypes::pthread_t>>> = {
@@ -94,13 +96,88 @@ impl Pthread {
         }
 
         let thread = unsafe { Box::from_raw(ptr as *mut Pthread) };
-        thread.inner.join();
+        if let Err(e) = thread.inner.join() {
+            warn!("sys_pthread_join: {:?}", e);
+        }
         let tid = thread.inner.id().as_u64();
         let retval = unsafe { *thread.retval.result.get() };
         TID_TO_PTHREAD.write().remove(&tid);
         drop(thread);
         Ok(retval)
     }
+
+    fn task(ptr: ctypes::pthread_t) -> &'static AxTaskRef {
+        unsafe { &(*(ptr as *const Pthread)).inner }
+    }
+
+    /// Returns the task of the thread whose ID is `pid`, or the current task
+    /// if `pid` is zero.
+    fn find_task(pid: c_int) -> LinuxResult<AxTaskRef> {
+        if pid == 0 {
+            return Ok(axtask::current().as_task_ref().clone());
+        }
+        let threads = TID_TO_PTHREAD.read();
+        let ptr = threads.get(&(pid as u64)).ok_or(LinuxError::ESRCH)?;
+        Ok(Self::task(ptr.0).clone())
+    }
+}
+
+/// Whether the effective user of the current thread is the real or effective
+/// user of `task`, which lets it change the scheduling of `task` without the
+/// `SYS_NICE` capability.
+fn is_same_user(task: &AxTaskRef) -> bool {
+    let euid = axtask::current().credentials().euid;
+    let target = task.credentials();
+    euid == target.ruid || euid == target.euid
+}
+
+fn set_sched_policy(task: &AxTaskRef, policy: c_int, prio: c_int) -> LinuxResult<c_int> {
+    let policy = SchedPolicy::try_from(policy).map_err(|_| LinuxError::EINVAL)?;
+    let prio = prio as isize;
+    if !policy.priority_range().contains(&prio) {
+        return Err(LinuxError::EINVAL);
+    }
+    if !axtask::capable(PrivCap::SYS_NICE) && (policy.is_realtime() || !is_same_user(task)) {
+        return Err(LinuxError::EPERM);
+    }
+    if !axtask::set_sched_policy(task, policy, prio) {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(0)
+}
+
+fn set_affinity(
+    task: &AxTaskRef,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> LinuxResult<c_int> {
+    if cpuset.is_null() || cpusetsize < core::mem::size_of::<usize>() {
+        return Err(LinuxError::EINVAL);
+    }
+    if !axtask::capable(PrivCap::SYS_NICE) && !is_same_user(task) {
+        return Err(LinuxError::EPERM);
+    }
+    // only the first word is used, which is enough for all CPUs we support
+    let mask = unsafe { (*cpuset).__bits[0] } as axtask::CpuMask;
+    if !axtask::set_affinity(task, mask) {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(0)
+}
+
+fn get_affinity(
+    task: &AxTaskRef,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> LinuxResult<c_int> {
+    if cpuset.is_null() || cpusetsize < core::mem::size_of::<usize>() {
+        return Err(LinuxError::EINVAL);
+    }
+    unsafe {
+        core::ptr::write_bytes(cpuset as *mut u8, 0, cpusetsize);
+        (*cpuset).__bits[0] = task.cpumask() as _;
+    }
+    Ok(0)
 }
 
 /// Returns the `pthread` struct of current thread.
@@ -147,6 +224,136 @@ pub unsafe fn sys_pthread_join(thread: ctypes::pthread_t, retval: *mut *mut c_vo
     })
 }
 
+/// Sets the CPU affinity mask of the given thread.
+///
+/// Fails with `EINVAL` if none of the CPUs in `cpuset` is online, and with
+/// `EPERM` if the thread belongs to another user and the caller does not have
+/// the `SYS_NICE` capability.
+pub unsafe fn sys_pthread_setaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> c_int {
+    debug!("sys_pthread_setaffinity_np <= {:#x}", thread as usize);
+    syscall_body!(sys_pthread_setaffinity_np, {
+        set_affinity(Pthread::task(thread), cpusetsize, cpuset)
+    })
+}
+
+/// Gets the CPU affinity mask of the given thread.
+pub unsafe fn sys_pthread_getaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> c_int {
+    debug!("sys_pthread_getaffinity_np <= {:#x}", thread as usize);
+    syscall_body!(sys_pthread_getaffinity_np, {
+        get_affinity(Pthread::task(thread), cpusetsize, cpuset)
+    })
+}
+
+/// Sets the CPU affinity mask of the thread whose ID is `pid`, or of the
+/// calling thread if `pid` is zero.
+///
+/// Like `sched_setscheduler`, changing a thread of another user needs the
+/// `SYS_NICE` capability.
+pub unsafe fn sys_sched_setaffinity(
+    pid: c_int,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> c_int {
+    debug!("sys_sched_setaffinity <= {}", pid);
+    syscall_body!(sys_sched_setaffinity, {
+        set_affinity(&Pthread::find_task(pid)?, cpusetsize, cpuset)
+    })
+}
+
+/// Gets the CPU affinity mask of the thread whose ID is `pid`, or of the
+/// calling thread if `pid` is zero.
+pub unsafe fn sys_sched_getaffinity(
+    pid: c_int,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> c_int {
+    debug!("sys_sched_getaffinity <= {}", pid);
+    syscall_body!(sys_sched_getaffinity, {
+        get_affinity(&Pthread::find_task(pid)?, cpusetsize, cpuset)
+    })
+}
+
+/// Sets the scheduling policy and the static priority of the thread whose ID
+/// is `pid`, or of the calling thread if `pid` is zero.
+///
+/// Real-time policies need the `SYS_NICE` capability, and are only supported
+/// by the real-time scheduler.
+pub unsafe fn sys_sched_setscheduler(
+    pid: c_int,
+    policy: c_int,
+    param: *const ctypes::sched_param,
+) -> c_int {
+    debug!("sys_sched_setscheduler <= {} {}", pid, policy);
+    syscall_body!(sys_sched_setscheduler, {
+        if param.is_null() {
+            return Err(LinuxError::EINVAL);
+        }
+        set_sched_policy(&Pthread::find_task(pid)?, policy, (*param).sched_priority)
+    })
+}
+
+/// Returns the scheduling policy of the thread whose ID is `pid`, or of the
+/// calling thread if `pid` is zero.
+pub fn sys_sched_getscheduler(pid: c_int) -> c_int {
+    debug!("sys_sched_getscheduler <= {}", pid);
+    syscall_body!(sys_sched_getscheduler, {
+        let (policy, _) = axtask::sched_policy(&Pthread::find_task(pid)?);
+        Ok(policy as c_int)
+    })
+}
+
+/// Sets the static priority of the thread whose ID is `pid`, or of the calling
+/// thread if `pid` is zero, keeping its scheduling policy.
+pub unsafe fn sys_sched_setparam(pid: c_int, param: *const ctypes::sched_param) -> c_int {
+    debug!("sys_sched_setparam <= {}", pid);
+    syscall_body!(sys_sched_setparam, {
+        if param.is_null() {
+            return Err(LinuxError::EINVAL);
+        }
+        let task = Pthread::find_task(pid)?;
+        let (policy, _) = axtask::sched_policy(&task);
+        set_sched_policy(&task, policy as c_int, (*param).sched_priority)
+    })
+}
+
+/// Gets the static priority of the thread whose ID is `pid`, or of the calling
+/// thread if `pid` is zero.
+pub unsafe fn sys_sched_getparam(pid: c_int, param: *mut ctypes::sched_param) -> c_int {
+    debug!("sys_sched_getparam <= {}", pid);
+    syscall_body!(sys_sched_getparam, {
+        if param.is_null() {
+            return Err(LinuxError::EINVAL);
+        }
+        let (_, prio) = axtask::sched_policy(&Pthread::find_task(pid)?);
+        (*param).sched_priority = prio as c_int;
+        Ok(0)
+    })
+}
+
+/// Returns the highest static priority of the scheduling policy.
+pub fn sys_sched_get_priority_max(policy: c_int) -> c_int {
+    syscall_body!(sys_sched_get_priority_max, {
+        let policy = SchedPolicy::try_from(policy).map_err(|_| LinuxError::EINVAL)?;
+        Ok(*policy.priority_range().end())
+    })
+}
+
+/// Returns the lowest static priority of the scheduling policy.
+pub fn sys_sched_get_priority_min(policy: c_int) -> c_int {
+    syscall_body!(sys_sched_get_priority_min, {
+        let policy = SchedPolicy::try_from(policy).map_err(|_| LinuxError::EINVAL)?;
+        Ok(*policy.priority_range().start())
+    })
+}
+
 #[derive(Clone, Copy)]
 struct ForceSendSync<T>(T);
 
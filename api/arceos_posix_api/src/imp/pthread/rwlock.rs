@@ -0,0 +1,202 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axerrno::{LinuxError, LinuxResult};
+use axsync::RwLock;
+
+use core::ffi::c_int;
+use core::mem::{forget, size_of};
+
+static_assertions::const_assert_eq!(
+    size_of::<PthreadRwLock>(),
+    size_of::<ctypes::pthread_rwlock_t>()
+);
+
+#[repr(C)]
+pub struct PthreadRwLock(RwLock<()>);
+
+impl PthreadRwLock {
+    const fn new() -> Self {
+        Self(RwLock::new(()))
+    }
+
+    fn read(&self) -> LinuxResult {
+        forget(self.0.read());
+        Ok(())
+    }
+
+    fn try_read(&self) -> LinuxResult {
+        forget(self.0.try_read().ok_or(LinuxError::EBUSY)?);
+        Ok(())
+    }
+
+    fn timed_read(&self, abstime: *const ctypes::timespec) -> LinuxResult {
+        let timeout = crate::imp::time::abstime_to_timeout(abstime)?;
+        #[cfg(feature = "irq")]
+        {
+            forget(self.0.read_timeout(timeout).ok_or(LinuxError::ETIMEDOUT)?);
+            Ok(())
+        }
+        #[cfg(not(feature = "irq"))]
+        {
+            warn!("pthread_rwlock_timedrdlock: the timeout {timeout:?} is ignored without the `irq` feature");
+            self.read()
+        }
+    }
+
+    fn write(&self) -> LinuxResult {
+        forget(self.0.write());
+        Ok(())
+    }
+
+    fn try_write(&self) -> LinuxResult {
+        forget(self.0.try_write().ok_or(LinuxError::EBUSY)?);
+        Ok(())
+    }
+
+    fn timed_write(&self, abstime: *const ctypes::timespec) -> LinuxResult {
+        let timeout = crate::imp::time::abstime_to_timeout(abstime)?;
+        #[cfg(feature = "irq")]
+        {
+            forget(self.0.write_timeout(timeout).ok_or(LinuxError::ETIMEDOUT)?);
+            Ok(())
+        }
+        #[cfg(not(feature = "irq"))]
+        {
+            warn!("pthread_rwlock_timedwrlock: the timeout {timeout:?} is ignored without the `irq` feature");
+            self.write()
+        }
+    }
+
+    fn unlock(&self) -> LinuxResult {
+        // the lock is held by the caller, whose guard has been forgotten
+        unsafe {
+            if self.0.is_write_locked() {
+                self.0.force_write_unlock();
+            } else if self.0.reader_count() > 0 {
+                self.0.force_read_unlock();
+            } else {
+                return Err(LinuxError::EPERM);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Initialize a readers-writer lock.
+pub fn sys_pthread_rwlock_init(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    _attr: *const ctypes::pthread_rwlockattr_t,
+) -> c_int {
+    debug!("sys_pthread_rwlock_init <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_init, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            rwlock.cast::<PthreadRwLock>().write(PthreadRwLock::new());
+        }
+        Ok(0)
+    })
+}
+
+/// Destroy a readers-writer lock.
+pub fn sys_pthread_rwlock_destroy(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_destroy <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_destroy, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            rwlock.cast::<PthreadRwLock>().drop_in_place();
+        }
+        Ok(0)
+    })
+}
+
+/// Lock the readers-writer lock for reading.
+pub fn sys_pthread_rwlock_rdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_rdlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_rdlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).read()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Try to lock the readers-writer lock for reading without blocking.
+pub fn sys_pthread_rwlock_tryrdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_tryrdlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_tryrdlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).try_read()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Lock the readers-writer lock for writing.
+pub fn sys_pthread_rwlock_wrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_wrlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_wrlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).write()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Try to lock the readers-writer lock for writing without blocking.
+pub fn sys_pthread_rwlock_trywrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_trywrlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_trywrlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).try_write()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Unlock the readers-writer lock held by the current thread.
+pub fn sys_pthread_rwlock_unlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_unlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_unlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).unlock()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Lock the readers-writer lock for reading, or fails with `ETIMEDOUT` if it
+/// can not be acquired before the absolute time `abstime`.
+pub fn sys_pthread_rwlock_timedrdlock(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    debug!("sys_pthread_rwlock_timedrdlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_timedrdlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).timed_read(abstime)?;
+        }
+        Ok(0)
+    })
+}
+
+/// Lock the readers-writer lock for writing, or fails with `ETIMEDOUT` if it
+/// can not be acquired before the absolute time `abstime`.
+pub fn sys_pthread_rwlock_timedwrlock(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    debug!("sys_pthread_rwlock_timedwrlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_timedwrlock, {
+        check_null_mut_ptr(rwlock)?;
+        unsafe {
+            (*rwlock.cast::<PthreadRwLock>()).timed_write(abstime)?;
+        }
+        Ok(0)
+    })
+}
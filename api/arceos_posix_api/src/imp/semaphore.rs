@@ -0,0 +1,139 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axerrno::{LinuxError, LinuxResult};
+use axsync::Semaphore;
+
+use core::ffi::{c_int, c_uint};
+use core::mem::{align_of, size_of};
+
+static_assertions::const_assert!(size_of::<PosixSemaphore>() <= size_of::<ctypes::sem_t>());
+static_assertions::const_assert!(align_of::<PosixSemaphore>() <= align_of::<ctypes::sem_t>());
+
+/// The maximum value of a semaphore, `SEM_VALUE_MAX` in C.
+const SEM_VALUE_MAX: usize = c_int::MAX as usize;
+
+#[repr(C)]
+pub struct PosixSemaphore(Semaphore);
+
+impl PosixSemaphore {
+    const fn new(value: usize) -> Self {
+        Self(Semaphore::new(value))
+    }
+
+    fn timed_wait(&self, abstime: *const ctypes::timespec) -> LinuxResult {
+        let timeout = crate::imp::time::abstime_to_timeout(abstime)?;
+        #[cfg(feature = "irq")]
+        if !self.0.acquire_timeout(timeout) {
+            return Err(LinuxError::ETIMEDOUT);
+        }
+        #[cfg(not(feature = "irq"))]
+        {
+            warn!("sem_timedwait: the timeout {timeout:?} is ignored without the `irq` feature");
+            self.0.acquire();
+        }
+        Ok(())
+    }
+
+    fn post(&self) -> LinuxResult {
+        if self.0.available() >= SEM_VALUE_MAX {
+            return Err(LinuxError::EOVERFLOW);
+        }
+        self.0.release();
+        Ok(())
+    }
+}
+
+/// Initialize an unnamed semaphore with the given value.
+///
+/// The semaphore is always shared between all threads, so `pshared` is
+/// ignored.
+pub fn sys_sem_init(sem: *mut ctypes::sem_t, _pshared: c_int, value: c_uint) -> c_int {
+    debug!("sys_sem_init <= {:#x} {}", sem as usize, value);
+    syscall_body!(sys_sem_init, {
+        check_null_mut_ptr(sem)?;
+        if value as usize > SEM_VALUE_MAX {
+            return Err(LinuxError::EINVAL);
+        }
+        unsafe {
+            sem.cast::<PosixSemaphore>()
+                .write(PosixSemaphore::new(value as usize));
+        }
+        Ok(0)
+    })
+}
+
+/// Destroy an unnamed semaphore.
+pub fn sys_sem_destroy(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("sys_sem_destroy <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_destroy, {
+        check_null_mut_ptr(sem)?;
+        unsafe {
+            sem.cast::<PosixSemaphore>().drop_in_place();
+        }
+        Ok(0)
+    })
+}
+
+/// Decrement the semaphore, blocking until its value is greater than zero.
+pub fn sys_sem_wait(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("sys_sem_wait <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_wait, {
+        check_null_mut_ptr(sem)?;
+        unsafe {
+            (*sem.cast::<PosixSemaphore>()).0.acquire();
+        }
+        Ok(0)
+    })
+}
+
+/// Decrement the semaphore if its value is greater than zero, or fails with
+/// `EAGAIN` otherwise.
+pub fn sys_sem_trywait(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("sys_sem_trywait <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_trywait, {
+        check_null_mut_ptr(sem)?;
+        if unsafe { (*sem.cast::<PosixSemaphore>()).0.try_acquire() } {
+            Ok(0)
+        } else {
+            Err(LinuxError::EAGAIN)
+        }
+    })
+}
+
+/// Like `sys_sem_wait`, but fails with `ETIMEDOUT` if the semaphore can not be
+/// decremented before the absolute time `abstime`.
+pub fn sys_sem_timedwait(sem: *mut ctypes::sem_t, abstime: *const ctypes::timespec) -> c_int {
+    debug!("sys_sem_timedwait <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_timedwait, {
+        check_null_mut_ptr(sem)?;
+        unsafe {
+            (*sem.cast::<PosixSemaphore>()).timed_wait(abstime)?;
+        }
+        Ok(0)
+    })
+}
+
+/// Increment the semaphore, and wake up a thread blocked on it.
+pub fn sys_sem_post(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("sys_sem_post <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_post, {
+        check_null_mut_ptr(sem)?;
+        unsafe {
+            (*sem.cast::<PosixSemaphore>()).post()?;
+        }
+        Ok(0)
+    })
+}
+
+/// Get the current value of the semaphore.
+pub fn sys_sem_getvalue(sem: *mut ctypes::sem_t, sval: *mut c_int) -> c_int {
+    debug!("sys_sem_getvalue <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_getvalue, {
+        check_null_mut_ptr(sem)?;
+        check_null_mut_ptr(sval)?;
+        unsafe {
+            *sval = (*sem.cast::<PosixSemaphore>()).0.available() as c_int;
+        }
+        Ok(0)
+    })
+}
@@ -82,3 +82,22 @@ pub unsafe fn sys_nanosleep(req: *const ctypes::timespec, rem: *mut ctypes::time
         Ok(0)
     })
 }
+
+/// Converts the absolute time `abstime` of timed waiting functions to the
+/// duration from now, which is zero if it has passed.
+#[cfg(feature = "multitask")]
+pub(crate) fn abstime_to_timeout(
+    abstime: *const ctypes::timespec,
+) -> axerrno::LinuxResult<Duration> {
+    if abstime.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    let abstime = unsafe { *abstime };
+    if !(0..1_000_000_000).contains(&abstime.tv_nsec) {
+        return Err(LinuxError::EINVAL);
+    }
+    if abstime.tv_sec < 0 {
+        return Ok(Duration::ZERO);
+    }
+    Ok(Duration::from(abstime).saturating_sub(axhal::time::current_time()))
+}
@@ -38,14 +38,26 @@ pub use imp::sys::sys_sysconf;
 pub use imp::task::{sys_exit, sys_getpid, sys_sched_yield};
 pub use imp::time::{sys_clock_gettime, sys_nanosleep};
 
+#[cfg(feature = "multitask")]
+pub use imp::cred::{
+    sys_getegid, sys_geteuid, sys_getgid, sys_getgroups, sys_getuid, sys_setgid, sys_setgroups,
+    sys_setuid,
+};
 #[cfg(feature = "fd")]
 pub use imp::fd_ops::{sys_close, sys_dup, sys_dup2, sys_fcntl};
 #[cfg(feature = "fs")]
-pub use imp::fs::{sys_fstat, sys_getcwd, sys_lseek, sys_lstat, sys_open, sys_rename, sys_stat};
+pub use imp::fs::{
+    sys_fstat, sys_fstatfs, sys_getcwd, sys_lseek, sys_lstat, sys_mkdir, sys_open, sys_rename,
+    sys_stat, sys_statfs, sys_umask,
+};
+#[cfg(feature = "fs")]
+pub use imp::inotify::{sys_inotify_add_watch, sys_inotify_init1, sys_inotify_rm_watch};
 #[cfg(feature = "select")]
 pub use imp::io_mpx::sys_select;
 #[cfg(feature = "epoll")]
 pub use imp::io_mpx::{sys_epoll_create, sys_epoll_ctl, sys_epoll_wait};
+#[cfg(feature = "mmap")]
+pub use imp::mmap::{sys_mmap, sys_mprotect, sys_msync, sys_munmap};
 #[cfg(feature = "net")]
 pub use imp::net::{
     sys_accept, sys_bind, sys_connect, sys_freeaddrinfo, sys_getaddrinfo, sys_getpeername,
@@ -55,8 +67,30 @@ pub use imp::net::{
 #[cfg(feature = "pipe")]
 pub use imp::pipe::sys_pipe;
 #[cfg(feature = "multitask")]
+pub use imp::pthread::cond::{
+    sys_pthread_cond_broadcast, sys_pthread_cond_destroy, sys_pthread_cond_init,
+    sys_pthread_cond_signal, sys_pthread_cond_timedwait, sys_pthread_cond_wait,
+};
+#[cfg(feature = "multitask")]
 pub use imp::pthread::mutex::{
-    sys_pthread_mutex_init, sys_pthread_mutex_lock, sys_pthread_mutex_unlock,
+    sys_pthread_mutex_init, sys_pthread_mutex_lock, sys_pthread_mutex_trylock,
+    sys_pthread_mutex_unlock,
 };
 #[cfg(feature = "multitask")]
-pub use imp::pthread::{sys_pthread_create, sys_pthread_exit, sys_pthread_join, sys_pthread_self};
+pub use imp::pthread::rwlock::{
+    sys_pthread_rwlock_destroy, sys_pthread_rwlock_init, sys_pthread_rwlock_rdlock,
+    sys_pthread_rwlock_timedrdlock, sys_pthread_rwlock_timedwrlock, sys_pthread_rwlock_tryrdlock,
+    sys_pthread_rwlock_trywrlock, sys_pthread_rwlock_unlock, sys_pthread_rwlock_wrlock,
+};
+#[cfg(feature = "multitask")]
+pub use imp::pthread::{
+    sys_pthread_create, sys_pthread_exit, sys_pthread_getaffinity_np, sys_pthread_join,
+    sys_pthread_self, sys_pthread_setaffinity_np, sys_sched_get_priority_max,
+    sys_sched_get_priority_min, sys_sched_getaffinity, sys_sched_getparam, sys_sched_getscheduler,
+    sys_sched_setaffinity, sys_sched_setparam, sys_sched_setscheduler,
+};
+#[cfg(feature = "multitask")]
+pub use imp::semaphore::{
+    sys_sem_destroy, sys_sem_getvalue, sys_sem_init, sys_sem_post, sys_sem_timedwait,
+    sys_sem_trywait, sys_sem_wait,
+};
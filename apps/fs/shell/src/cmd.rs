@@ -1,7 +1,10 @@
 use alloc::string::ToString;
-use std::env::set_password;
+use std::env::{
+    add_user, audit_events, delete_user, last, lock_user, set_password, set_user_password, who,
+    SessionEnd,
+};
 use std::fs::{self, File, FileType};
-use std::io::{self, prelude::*, Error};
+use std::io::{self, prelude::*};
 use std::{string::String, vec::Vec};
 
 #[cfg(all(not(feature = "axstd"), unix))]
@@ -114,6 +117,7 @@ const CMD_TABLE: &[(&str, CmdHandler)] = &[
     ("uname", do_uname),
     ("chmod", do_chmod),
     ("chown", do_chown),
+    ("umask", do_umask),
     ("whoami", do_whoami),
     ("su", do_su),
     ("sudo", do_sudo),
@@ -121,6 +125,12 @@ const CMD_TABLE: &[(&str, CmdHandler)] = &[
     ("adduser", adduser),
     ("deluser", deluser),
     ("passwd", passwd),
+    ("df", do_df),
+    ("audit", do_audit),
+    ("who", do_who),
+    ("last", do_last),
+    ("ps", do_ps),
+    ("top", do_top),
 ];
 
 fn file_type_to_char(ty: FileType) -> char {
@@ -154,9 +164,20 @@ const fn file_perm_to_rwx(mode: u32) -> [u8; 9] {
         };
     }
 
+    macro_rules! special {
+        ($bit:literal, $idx:literal, $set:literal, $unset:literal) => {
+            if mode & (1 << $bit) != 0 {
+                perm[$idx] = if perm[$idx] == b'x' { $set } else { $unset }
+            }
+        };
+    }
+
     set!(2, b'r'); set!(1, b'w'); set!(0, b'x');
     set!(5, b'r'); set!(4, b'w'); set!(3, b'x');
     set!(8, b'r'); set!(7, b'w'); set!(6, b'x');
+    special!(11, 2, b's', b'S'); // set-user-ID
+    special!(10, 5, b's', b'S'); // set-group-ID
+    special!(9, 8, b't', b'T'); // sticky
     perm
 }
 
@@ -339,8 +360,10 @@ fn do_rm(args: &str) {
 }
 
 fn do_cd(mut args: &str) {
+    let home;
     if args.is_empty() {
-        args = "/";
+        home = std::env::var("HOME").unwrap_or_else(|| "/".into());
+        args = &home;
     }
     if !args.contains(char::is_whitespace) {
         if let Err(e) = std::env::set_current_dir(args) {
@@ -411,7 +434,6 @@ fn do_chmod(args: &str) {
     if recursive {
         print_err!("chmod", "can't recursive chmod yet");
     }
-    let m = mf[0].as_bytes();
     let fname = mf[1];
 
     fn chmod_one(fname: &str, perm: u16) -> io::Result<()> {
@@ -420,7 +442,13 @@ fn do_chmod(args: &str) {
         file.change_metadata(perm, md.uid(), md.gid())
     }
 
-    let perm = (m[0] - b'0') as u16 * 64 + (m[1] - b'0') as u16 * 8 + (m[2] - b'0') as u16;
+    let perm = match u16::from_str_radix(mf[0], 8) {
+        Ok(perm) if perm <= 0o7777 => perm,
+        _ => {
+            print_err!("chmod", mf[0], "invalid mode");
+            return;
+        }
+    };
     if let Err(e) = chmod_one(fname, perm) {
         print_err!("chmod", fname, e);
     }
@@ -470,6 +498,62 @@ fn do_chown(args: &str) {
     }
 }
 
+fn do_df(args: &str) {
+    fn df_one(path: &str) -> io::Result<()> {
+        let info = fs::statfs(path)?;
+        let to_kb = |blocks: u64| blocks * info.block_size() / 1024;
+        let usage = match info.blocks() {
+            0 => "-".to_string(),
+            total => alloc::format!("{}%", (info.blocks_used() * 100 + total - 1) / total),
+        };
+        println!(
+            "{:<12} {:>10} {:>10} {:>10} {:>5} {:>8} {:>8}",
+            path,
+            to_kb(info.blocks()),
+            to_kb(info.blocks_used()),
+            to_kb(info.blocks_free()),
+            usage,
+            info.files(),
+            info.files_free(),
+        );
+        Ok(())
+    }
+
+    let paths = if args.is_empty() {
+        match fs::mount_points() {
+            Ok(paths) => paths,
+            Err(e) => {
+                print_err!("df", e);
+                return;
+            }
+        }
+    } else {
+        args.split_whitespace().map(String::from).collect()
+    };
+    println!(
+        "{:<12} {:>10} {:>10} {:>10} {:>5} {:>8} {:>8}",
+        "Mounted on", "1K-blocks", "Used", "Available", "Use%", "Inodes", "IFree"
+    );
+    for path in paths {
+        if let Err(e) = df_one(&path) {
+            print_err!("df", path, e);
+        }
+    }
+}
+
+fn do_umask(args: &str) {
+    let args = args.trim();
+    if args.is_empty() {
+        let mask = std::env::umask(0);
+        std::env::umask(mask);
+        println!("{:04o}", mask);
+    } else if let Ok(mask) = u16::from_str_radix(args, 8) {
+        std::env::umask(mask);
+    } else {
+        print_err!("umask", args, "invalid mode");
+    }
+}
+
 fn do_whoami(_args: &str) {
     let i = std::env::current_uid().unwrap();
     println!("{}", user_name(i));
@@ -499,7 +583,7 @@ fn do_if_test_exist(args: &str) {
 
 fn do_sudo(args: &str) {
     let i = std::env::current_uid().unwrap();
-    if let Err(e) = std::env::sudo() {
+    if let Err(e) = std::env::sudo(args) {
         print_err!("sudo", args, e);
         return;
     }
@@ -510,130 +594,263 @@ fn do_sudo(args: &str) {
 }
 
 fn adduser(args: &str) {
-    fn add_file(username: &str) -> io::Result<()> {
-        let mut content: String = String::new();
-        let mut file = File::open("/etc/passwd")?;
-        file.read_to_string(&mut content)?;
-        content = content.trim().to_string();
-        let last_record: Vec<&str> = content
-            .split('\n')
-            .last()
-            .unwrap_or_default()
-            .split(":")
-            .collect();
-        if last_record.len() != 7 {
-            return Err(Error::InvalidData);
-        }
-        let mut file = File::create("/etc/passwd")?;
-        file.write_all(content.as_bytes())?;
-        let text_list = [
-            "\n",
-            username,
-            ":x:",
-            &(last_record[2].parse::<u32>().unwrap_or(0) + 1).to_string(),
-            ":",
-            &(last_record[3].parse::<u32>().unwrap_or(0) + 1).to_string(),
-            ":,,,:/home/",
-            username,
-            ":/bin/sh\n",
-        ];
-        for text in text_list {
-            file.write_all(text.as_bytes())?;
-        }
-        content = String::new();
-        let mut file = File::open("/etc/group")?;
-        file.read_to_string(&mut content)?;
-        content = content.trim().to_string();
-        let last_record: Vec<&str> = content
-            .split('\n')
-            .last()
-            .unwrap_or_default()
-            .split(":")
-            .collect();
-        if last_record.len() <= 2 {
-            return Err(Error::InvalidData);
-        }
-        let mut file = File::create("/etc/group")?;
-        file.write_all(content.as_bytes())?;
-        let text_list = [
-            "\n",
-            username,
-            ":x:",
-            &(last_record[2].parse::<u32>().unwrap_or(0) + 1).to_string(),
-            ":\n",
-        ];
-        for text in text_list {
-            file.write_all(text.as_bytes())?;
+    if args.is_empty() {
+        print_err!("adduser", "missing operand");
+    } else if args.contains(char::is_whitespace) {
+        print_err!("adduser", "too many arguments");
+    } else if let Err(e) = add_user(args) {
+        print_err!("adduser", args, e);
+    }
+}
+
+fn deluser(args: &str) {
+    if args.is_empty() {
+        print_err!("deluser", "missing operand");
+    } else if args.contains(char::is_whitespace) {
+        print_err!("deluser", "too many arguments");
+    } else if let Err(e) = delete_user(args) {
+        print_err!("deluser", args, e);
+    }
+}
+
+fn passwd(args: &str) {
+    let res = match args.split_whitespace().collect::<Vec<_>>()[..] {
+        [] => set_password(),
+        ["-l", name] => lock_user(name, true),
+        ["-u", name] => lock_user(name, false),
+        [name] => set_user_password(name),
+        _ => {
+            print_err!("passwd", "usage: passwd [[-l|-u] USER]");
+            return;
         }
-        let home_path = "/home/".to_string() + username;
-        do_mkdir(home_path.clone().as_str());
-        do_chmod(("777 ".to_string() + home_path.clone().as_str()).as_str());
-        do_chown(
-            (username.to_string() + ":" + username + " " + home_path.clone().as_str()).as_str(),
-        );
-        do_chmod(("700 ".to_string() + home_path.clone().as_str()).as_str());
-        Ok(())
+    };
+    if let Err(e) = res {
+        print_err!("passwd", e);
     }
+}
 
-    if !args.contains(char::is_whitespace) {
-        let mut uid: u32 = u32::MAX;
-        for i in 0..3 {
-            if args == user_name(i) {
-                uid = i;
+fn do_audit(args: &str) {
+    const DEFAULT_COUNT: usize = 20;
+    let count = match args.trim() {
+        "" => DEFAULT_COUNT,
+        n => match n.parse() {
+            Ok(n) => n,
+            Err(_) => {
+                print_err!("audit", "usage: audit [COUNT]");
+                return;
+            }
+        },
+    };
+    match audit_events(count) {
+        Ok(events) => {
+            for event in events {
+                println!("{}", event);
             }
         }
-        if uid != u32::MAX {
-            print_err!("adduser", "existing user");
-            return;
-        }
-        if let Err(e) = add_file(args) {
-            print_err!("adduser", e);
+        Err(e) => print_err!("audit", e),
+    }
+}
+
+/// Formats a time since boot as `+HH:MM:SS`.
+fn fmt_uptime(t: std::time::Duration) -> String {
+    let secs = t.as_secs();
+    alloc::format!("+{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn do_who(_args: &str) {
+    match who() {
+        Ok(sessions) => {
+            for s in sessions {
+                println!("{:<8} {:<8} {}", s.user, s.tty, fmt_uptime(s.time));
+            }
         }
-    } else {
-        print_err!("deluser", "too many arguments");
+        Err(e) => print_err!("who", e),
     }
 }
 
-fn deluser(args: &str) {
-    fn del_file(username: &str) -> io::Result<()> {
-        let mut content: String = String::new();
-        let mut file = File::open("/etc/passwd")?;
-        file.read_to_string(&mut content)?;
-        content = content.trim().to_string();
-        let lines: Vec<&str> = content.split('\n').collect();
-        let mut new_content = String::new();
-        let mut del = false;
-        for line in lines {
-            if line.is_empty() {
-                continue;
+fn do_last(args: &str) {
+    const DEFAULT_COUNT: usize = 20;
+    let count = match args.trim() {
+        "" => DEFAULT_COUNT,
+        n => match n.parse() {
+            Ok(n) => n,
+            Err(_) => {
+                print_err!("last", "usage: last [COUNT]");
+                return;
             }
-            let v: Vec<&str> = line.split(':').collect();
-            if v[0] == username.to_string() {
-                del = true;
-                continue;
+        },
+    };
+    match last(count) {
+        Ok(entries) => {
+            for e in entries {
+                let end = match e.end {
+                    SessionEnd::LoggedIn => "still logged in".to_string(),
+                    SessionEnd::Logout(t) => alloc::format!("- {}", fmt_uptime(t)),
+                    SessionEnd::Reboot => "- down".to_string(),
+                };
+                println!(
+                    "{:<8} {:<8} {} {}",
+                    e.user,
+                    e.tty,
+                    fmt_uptime(e.login_time),
+                    end
+                );
             }
-            new_content += (line.to_string() + "\n").as_str();
         }
-        let mut file = File::create("/etc/passwd")?;
-        file.write_all(new_content.as_bytes())?;
-        if !del {
-            println!("deluser: user not exist");
-        }
-        Ok(())
+        Err(e) => print_err!("last", e),
     }
+}
 
-    if !args.contains(char::is_whitespace) {
-        if let Err(e) = del_file(args) {
-            print_err!("deluser", e);
+/// A task listed in `/proc/tasks`.
+struct TaskEntry {
+    id: u64,
+    cpu: String,
+    state: String,
+    policy: String,
+    prio: String,
+    nice: String,
+    runtime_us: u64,
+    switches: u64,
+    stack_used: usize,
+    stack_size: usize,
+    name: String,
+}
+
+fn read_tasks() -> io::Result<Vec<TaskEntry>> {
+    fn parse(line: &str) -> Option<TaskEntry> {
+        let mut fields = line.splitn(11, ' ');
+        let mut next = || fields.next();
+        Some(TaskEntry {
+            id: next()?.parse().ok()?,
+            cpu: next()?.to_string(),
+            state: next()?.to_string(),
+            policy: next()?.to_string(),
+            prio: next()?.to_string(),
+            nice: next()?.to_string(),
+            runtime_us: next()?.parse().ok()?,
+            switches: next()?.parse().ok()?,
+            stack_used: next()?.parse().ok()?,
+            stack_size: next()?.parse().ok()?,
+            name: next().unwrap_or_default().to_string(),
+        })
+    }
+
+    let mut content = String::new();
+    File::open("/proc/tasks")?.read_to_string(&mut content)?;
+    Ok(content.lines().skip(1).filter_map(parse).collect())
+}
+
+/// Formats a CPU time in microseconds as `MM:SS.cc`.
+fn fmt_cpu_time(us: u64) -> String {
+    let cs = us / 10_000;
+    alloc::format!("{:02}:{:02}.{:02}", cs / 6000, cs / 100 % 60, cs % 100)
+}
+
+fn do_ps(_args: &str) {
+    let tasks = match read_tasks() {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            print_err!("ps", "/proc/tasks", e);
+            return;
         }
-    } else {
-        print_err!("deluser", "too many arguments");
+    };
+    println!(
+        "{:>5} {:>3} {:<8} {:<6} {:>4} {:>4} {:>11} {:>8} {:>15} NAME",
+        "ID", "CPU", "STATE", "POLICY", "PRIO", "NICE", "TIME", "SWITCHES", "STACK"
+    );
+    for t in tasks {
+        println!(
+            "{:>5} {:>3} {:<8} {:<6} {:>4} {:>4} {:>11} {:>8} {:>15} {}",
+            t.id,
+            t.cpu,
+            t.state,
+            t.policy,
+            t.prio,
+            t.nice,
+            fmt_cpu_time(t.runtime_us),
+            t.switches,
+            if t.stack_used == 0 {
+                alloc::format!("-/{}", t.stack_size)
+            } else {
+                alloc::format!("{}/{}", t.stack_used, t.stack_size)
+            },
+            t.name,
+        );
     }
 }
 
-fn passwd(_args: &str) {
-    if let Err(e) = set_password() {
-        print_err!("passwd", e);
+fn do_top(args: &str) {
+    const DEFAULT_ROUNDS: usize = 1;
+    const INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+    let rounds = match args.trim() {
+        "" => DEFAULT_ROUNDS,
+        n => match n.parse() {
+            Ok(n) => n,
+            Err(_) => {
+                print_err!("top", "usage: top [ROUNDS]");
+                return;
+            }
+        },
+    };
+
+    let mut prev = match read_tasks() {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            print_err!("top", "/proc/tasks", e);
+            return;
+        }
+    };
+    let mut last_sample = std::time::Instant::now();
+    for round in 0..rounds {
+        std::thread::sleep(INTERVAL);
+        let tasks = match read_tasks() {
+            Ok(tasks) => tasks,
+            Err(e) => {
+                print_err!("top", "/proc/tasks", e);
+                return;
+            }
+        };
+        let elapsed_us = last_sample.elapsed().as_micros().max(1) as u64;
+        last_sample = std::time::Instant::now();
+
+        // per mille of one CPU used by each task since the previous sample
+        let mut usage: Vec<(u64, &TaskEntry)> = tasks
+            .iter()
+            .map(|t| {
+                let before = prev
+                    .iter()
+                    .find(|p| p.id == t.id)
+                    .map_or(0, |p| p.runtime_us);
+                let delta = t.runtime_us.saturating_sub(before);
+                (delta * 1000 / elapsed_us, t)
+            })
+            .collect();
+        usage.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.id.cmp(&b.1.id)));
+
+        if round > 0 {
+            println!();
+        }
+        println!("Tasks: {} total", tasks.len());
+        println!(
+            "{:>5} {:>3} {:<8} {:<6} {:>4} {:>4} {:>6} {:>11} NAME",
+            "ID", "CPU", "STATE", "POLICY", "PRIO", "NICE", "%CPU", "TIME"
+        );
+        for (permille, t) in usage {
+            println!(
+                "{:>5} {:>3} {:<8} {:<6} {:>4} {:>4} {:>4}.{} {:>11} {}",
+                t.id,
+                t.cpu,
+                t.state,
+                t.policy,
+                t.prio,
+                t.nice,
+                permille / 10,
+                permille % 10,
+                fmt_cpu_time(t.runtime_us),
+                t.name,
+            );
+        }
+        prev = tasks;
     }
 }
 
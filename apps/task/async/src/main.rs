@@ -0,0 +1,91 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate axstd as std;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use axasync::time::{sleep, Duration};
+
+const NUM_WORKERS: usize = 3;
+const NUM_TASKS: usize = 50;
+const NUM_YIELDS: usize = 10;
+
+/// Tasks wake up after their deadlines, and return their outputs to the
+/// joiner.
+async fn test_sleep() {
+    let start = Instant::now();
+    let handles: Vec<_> = (0..NUM_TASKS)
+        .map(|i| {
+            axasync::spawn(async move {
+                let duration = Duration::from_millis(10 * (NUM_TASKS - i) as u64);
+                sleep(duration).await;
+                assert!(start.elapsed() >= duration);
+                i * i
+            })
+        })
+        .collect();
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(handle.await, i * i);
+    }
+    println!("sleep test OK!");
+}
+
+/// Tasks yielding to each other all make progress.
+async fn test_yield() {
+    let polls = Arc::new(AtomicUsize::new(0));
+    let handles: Vec<_> = (0..NUM_TASKS)
+        .map(|_| {
+            let polls = polls.clone();
+            axasync::spawn(async move {
+                for _ in 0..NUM_YIELDS {
+                    polls.fetch_add(1, Ordering::Relaxed);
+                    axasync::yield_now().await;
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.await;
+    }
+    assert_eq!(polls.load(Ordering::Relaxed), NUM_TASKS * NUM_YIELDS);
+    println!("yield test OK!");
+}
+
+/// A spawned task keeps running after its handle is dropped.
+async fn test_detach() {
+    let done = Arc::new(AtomicUsize::new(0));
+    let done2 = done.clone();
+    drop(axasync::spawn(async move {
+        sleep(Duration::from_millis(20)).await;
+        done2.store(1, Ordering::Release);
+    }));
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(done.load(Ordering::Acquire), 1);
+    println!("detach test OK!");
+}
+
+#[no_mangle]
+fn main() {
+    println!("Hello, async main task!");
+    axasync::spawn_workers(NUM_WORKERS);
+    axasync::block_on(async {
+        test_sleep().await;
+        test_yield().await;
+        test_detach().await;
+    });
+
+    // all threads are idle while the only task sleeps
+    let now = Instant::now();
+    axasync::block_on(sleep(Duration::from_millis(500)));
+    let elapsed = now.elapsed();
+    println!("block_on sleep for {:?}", elapsed);
+    assert!(elapsed >= Duration::from_millis(500));
+
+    println!("Async tests run OK!");
+}
@@ -0,0 +1,34 @@
+#![cfg_attr(feature = "axstd", no_std)]
+#![cfg_attr(feature = "axstd", no_main)]
+
+#[macro_use]
+#[cfg(feature = "axstd")]
+extern crate axstd as std;
+
+use std::thread;
+
+const STACK_SIZE: usize = 0x4000;
+
+/// Recurses with a large frame on each level, until the stack overflows.
+#[inline(never)]
+fn recurse(depth: usize) -> usize {
+    let frame = core::hint::black_box([depth as u8; 0x400]);
+    if core::hint::black_box(depth) == usize::MAX {
+        return 0;
+    }
+    core::hint::black_box(recurse(depth + 1)) + frame[0] as usize
+}
+
+#[cfg_attr(feature = "axstd", no_mangle)]
+fn main() {
+    println!("Running stack overflow tests...");
+    let task = thread::Builder::new()
+        .name("overflow".into())
+        .stack_size(STACK_SIZE)
+        .spawn(|| recurse(0))
+        .unwrap();
+    // the guard page below the stack turns the overflow into a page fault,
+    // which terminates the task with its name.
+    assert!(task.join().is_err());
+    println!("Stack overflow tests run OK!");
+}
@@ -37,6 +37,20 @@ impl DirNode {
     pub fn add(&self, name: &'static str, node: VfsNodeRef) {
         self.children.write().insert(name, node);
     }
+
+    /// Returns the number of nodes in this directory, recursively, including
+    /// the directory itself.
+    pub fn node_count(&self) -> u64 {
+        let mut count = 1;
+        for node in self.children.read().values() {
+            let is_dir = node.get_attr().is_ok_and(|attr| attr.is_dir());
+            match is_dir.then(|| node.as_any().downcast_ref::<DirNode>()) {
+                Some(Some(dir)) => count += dir.node_count(),
+                _ => count += 1,
+            }
+        }
+        count
+    }
 }
 
 impl VfsNodeOps for DirNode {
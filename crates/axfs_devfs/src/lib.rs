@@ -18,7 +18,7 @@ pub use self::null::NullDev;
 pub use self::zero::ZeroDev;
 
 use alloc::sync::Arc;
-use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
+use axfs_vfs::{FileSystemInfo, VfsNodeRef, VfsOps, VfsResult};
 use spin::once::Once;
 
 /// A device filesystem that implements [`axfs_vfs::VfsOps`].
@@ -59,6 +59,11 @@ impl VfsOps for DeviceFileSystem {
         Ok(())
     }
 
+    /// Device nodes occupy no storage, so only the number of nodes is reported.
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        Ok(FileSystemInfo::new(512, 0, 0, self.root.node_count(), 0))
+    }
+
     fn root_dir(&self) -> VfsNodeRef {
         self.root.clone()
     }
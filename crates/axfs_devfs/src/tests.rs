@@ -112,4 +112,8 @@ fn test_devfs() {
 
     test_devfs_ops(&devfs).unwrap();
     test_get_parent(&devfs).unwrap();
+
+    let info = devfs.statfs().unwrap();
+    assert_eq!(info.files(), 7);
+    assert_eq!(info.blocks(), 0);
 }
@@ -1,4 +1,4 @@
-use alloc::collections::BTreeMap;
+use alloc::collections::{btree_map::Entry, BTreeMap};
 use alloc::sync::{Arc, Weak};
 use alloc::{string::String, vec::Vec};
 
@@ -56,7 +56,7 @@ impl DirNode {
             return Err(VfsError::AlreadyExists);
         }
         let node: VfsNodeRef = match ty {
-            VfsNodeType::File => Arc::new(FileNode::new()),
+            VfsNodeType::File | VfsNodeType::SymLink => Arc::new(FileNode::new(ty)),
             VfsNodeType::Dir => Self::new(Some(self.this.clone())),
             _ => return Err(VfsError::Unsupported),
         };
@@ -64,6 +64,36 @@ impl DirNode {
         Ok(())
     }
 
+    /// Adds an existing node (e.g., a file with generated contents) with the
+    /// given name to this directory.
+    pub fn add_node(&self, name: &str, node: VfsNodeRef) -> VfsResult {
+        match self.children.write().entry(name.into()) {
+            Entry::Occupied(_) => Err(VfsError::AlreadyExists),
+            Entry::Vacant(entry) => {
+                entry.insert(node);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the number of nodes and the total size of file contents (in
+    /// bytes) in this directory, recursively, including the directory itself.
+    pub fn usage(&self) -> (u64, u64) {
+        let mut nodes = 1;
+        let mut bytes = 0;
+        for node in self.children.read().values() {
+            if let Some(dir) = node.as_any().downcast_ref::<DirNode>() {
+                let (n, b) = dir.usage();
+                nodes += n;
+                bytes += b;
+            } else if let Some(file) = node.as_any().downcast_ref::<FileNode>() {
+                nodes += 1;
+                bytes += file.size() as u64;
+            }
+        }
+        (nodes, bytes)
+    }
+
     /// Removes a node by the given name in this directory.
     pub fn remove_node(&self, name: &str) -> VfsResult {
         let mut children = self.children.write();
@@ -76,6 +106,60 @@ impl DirNode {
         children.remove(name);
         Ok(())
     }
+
+    /// Returns the parent directory of `path` and the last name in it.
+    fn lookup_parent<'a>(&self, path: &'a str) -> VfsResult<(VfsNodeRef, &'a str)> {
+        let path = path.trim_end_matches('/');
+        let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(VfsError::InvalidInput);
+        }
+        let parent = self.this.upgrade().unwrap().lookup(parent)?;
+        if parent.as_any().downcast_ref::<DirNode>().is_none() {
+            return Err(VfsError::NotADirectory);
+        }
+        Ok((parent, name))
+    }
+
+    /// Moves the node `src_name` in this directory to `dst_name` in `dst`,
+    /// replacing the node there if any.
+    fn move_node(&self, src_name: &str, dst: &VfsNodeRef, dst_name: &str) -> VfsResult {
+        let dst_dir = dst.as_any().downcast_ref::<DirNode>().unwrap();
+        let node = self
+            .children
+            .read()
+            .get(src_name)
+            .cloned()
+            .ok_or(VfsError::NotFound)?;
+        let moved_dir = node.as_any().downcast_ref::<DirNode>();
+        if moved_dir.is_some() {
+            // a directory cannot be moved into itself
+            let mut ancestor = Some(dst.clone());
+            while let Some(dir) = ancestor {
+                if Arc::ptr_eq(&dir, &node) {
+                    return Err(VfsError::InvalidInput);
+                }
+                ancestor = dir.parent();
+            }
+        }
+        if let Some(old) = dst_dir.children.read().get(dst_name) {
+            match (old.as_any().downcast_ref::<DirNode>(), moved_dir.is_some()) {
+                (Some(old_dir), true) if !old_dir.children.read().is_empty() => {
+                    return Err(VfsError::DirectoryNotEmpty)
+                }
+                (Some(_), false) => return Err(VfsError::IsADirectory),
+                (None, true) => return Err(VfsError::NotADirectory),
+                _ => {}
+            }
+        }
+
+        self.children.write().remove(src_name);
+        if let Some(dir) = moved_dir {
+            dir.set_parent(Some(dst));
+        }
+        dst_dir.children.write().insert(dst_name.into(), node);
+        Ok(())
+    }
 }
 
 impl VfsNodeOps for DirNode {
@@ -179,6 +263,14 @@ impl VfsNodeOps for DirNode {
         }
     }
 
+    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        log::debug!("rename at ramfs: {} -> {}", src_path, dst_path);
+        let (src_parent, src_name) = self.lookup_parent(src_path)?;
+        let (dst_parent, dst_name) = self.lookup_parent(dst_path)?;
+        let src_dir = src_parent.as_any().downcast_ref::<DirNode>().unwrap();
+        src_dir.move_node(src_name, &dst_parent, dst_name)
+    }
+
     axfs_vfs::impl_vfs_dir_default! {}
 }
 
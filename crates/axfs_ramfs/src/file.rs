@@ -6,26 +6,36 @@ use spin::RwLock;
 
 /// The file node in the RAM filesystem.
 ///
-/// It implements [`axfs_vfs::VfsNodeOps`].
+/// It implements [`axfs_vfs::VfsNodeOps`]. A symbolic link is also a file
+/// node, whose content is the link target.
 pub struct FileNode {
     attr: RwLock<VfsNodeAttr>,
     content: RwLock<Vec<u8>>,
 }
 
 impl FileNode {
-    pub(super) const fn new() -> Self {
+    pub(super) const fn new(ty: VfsNodeType) -> Self {
+        let perm = match ty {
+            VfsNodeType::SymLink => VfsNodePerm::from_bits_truncate(0o777),
+            _ => VfsNodePerm::default_file(),
+        };
         Self {
             attr: RwLock::new(VfsNodeAttr::new(
-                VfsNodePerm::default_file(),
+                perm,
                 0,
                 0,
-                VfsNodeType::File,
+                ty,
                 0,
                 0,
             )),
             content: RwLock::new(Vec::new()),
         }
     }
+
+    /// Returns the size of the file content, in bytes.
+    pub fn size(&self) -> usize {
+        self.content.read().len()
+    }
 }
 
 impl VfsNodeOps for FileNode {
@@ -16,9 +16,12 @@ pub use self::dir::DirNode;
 pub use self::file::FileNode;
 
 use alloc::sync::Arc;
-use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
+use axfs_vfs::{FileSystemInfo, VfsNodeRef, VfsOps, VfsResult};
 use spin::once::Once;
 
+/// Block size reported by [`RamFileSystem::statfs`](VfsOps::statfs).
+const BLOCK_SIZE: u64 = 512;
+
 /// A RAM filesystem that implements [`axfs_vfs::VfsOps`].
 pub struct RamFileSystem {
     parent: Once<VfsNodeRef>,
@@ -50,6 +53,14 @@ impl VfsOps for RamFileSystem {
         Ok(())
     }
 
+    /// The RAM filesystem has no fixed capacity, so only the space and nodes
+    /// in use are reported, with no free blocks or inodes.
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        let (nodes, bytes) = self.root.usage();
+        let blocks = bytes.div_ceil(BLOCK_SIZE);
+        Ok(FileSystemInfo::new(BLOCK_SIZE, blocks, 0, nodes, 0))
+    }
+
     fn root_dir(&self) -> VfsNodeRef {
         self.root.clone()
     }
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
+use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
 
 use crate::*;
 
@@ -134,3 +134,184 @@ fn test_ramfs() {
     assert_eq!(root.remove("./foo"), Ok(()));
     assert!(ramfs.root_dir_node().get_entries().is_empty());
 }
+
+#[test]
+fn test_ramfs_symlink() {
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("link", VfsNodeType::SymLink).unwrap();
+
+    let link = root.clone().lookup("link").unwrap();
+    let attr = link.get_attr().unwrap();
+    assert_eq!(attr.file_type(), VfsNodeType::SymLink);
+    assert_eq!(attr.perm().mode(), 0o777);
+    assert_eq!(link.write_at(0, b"/etc/passwd").unwrap(), 11);
+    let mut buf = [0; 16];
+    assert_eq!(link.read_at(0, &mut buf).unwrap(), 11);
+    assert_eq!(&buf[..11], b"/etc/passwd");
+    assert_eq!(
+        root.create("fifo", VfsNodeType::Fifo).err(),
+        Some(VfsError::Unsupported)
+    );
+}
+
+#[test]
+fn test_ramfs_statfs() {
+    let ramfs = RamFileSystem::new();
+    let info = ramfs.statfs().unwrap();
+    assert_eq!(info.files(), 1);
+    assert_eq!(info.blocks(), 0);
+
+    let root = ramfs.root_dir();
+    root.create("foo", VfsNodeType::Dir).unwrap();
+    root.create("foo/f1", VfsNodeType::File).unwrap();
+    let f1 = root.lookup("foo/f1").unwrap();
+    assert_eq!(f1.write_at(0, &[1; 1000]).unwrap(), 1000);
+
+    let info = ramfs.statfs().unwrap();
+    assert_eq!(info.block_size(), 512);
+    assert_eq!(info.files(), 3);
+    assert_eq!(info.files_free(), 0);
+    assert_eq!(info.blocks(), 2);
+    assert_eq!(info.blocks_free(), 0);
+    assert_eq!(info.blocks_used(), 2);
+}
+
+#[test]
+fn test_ramfs_special_bits() {
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("tmp", VfsNodeType::Dir).unwrap();
+    root.create("tmp/prog", VfsNodeType::File).unwrap();
+
+    let tmp = root.clone().lookup("tmp").unwrap();
+    let attr = tmp.get_attr().unwrap();
+    tmp.set_attr(VfsNodeAttr::new(
+        VfsNodePerm::from_bits_truncate(0o1777),
+        0,
+        0,
+        attr.file_type(),
+        attr.size(),
+        attr.blocks(),
+    ))
+    .unwrap();
+    let perm = tmp.get_attr().unwrap().perm();
+    assert_eq!(perm.mode(), 0o1777);
+    assert_eq!(&perm.rwx_buf(), b"rwxrwxrwt");
+
+    let prog = root.lookup("tmp/prog").unwrap();
+    let attr = prog.get_attr().unwrap();
+    prog.set_attr(VfsNodeAttr::new(
+        VfsNodePerm::from_bits_truncate(0o6644),
+        1000,
+        100,
+        attr.file_type(),
+        attr.size(),
+        attr.blocks(),
+    ))
+    .unwrap();
+    let perm = prog.get_attr().unwrap().perm();
+    assert_eq!(perm.mode(), 0o6644);
+    assert_eq!(&perm.rwx_buf(), b"rwSr-Sr--");
+}
+
+#[test]
+fn test_ramfs_add_node() {
+    /// A file whose contents are generated when read.
+    struct Generated;
+
+    impl VfsNodeOps for Generated {
+        fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+            Ok(VfsNodeAttr::new_file(0, 0))
+        }
+
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+            let data = &b"generated"[(offset as usize).min(9)..];
+            let len = data.len().min(buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            Ok(len)
+        }
+
+        axfs_vfs::impl_vfs_non_dir_default! {}
+    }
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("proc", VfsNodeType::Dir).unwrap();
+    let proc_dir = root.clone().lookup("proc").unwrap();
+    let proc_dir = proc_dir.as_any().downcast_ref::<DirNode>().unwrap();
+    proc_dir.add_node("gen", Arc::new(Generated)).unwrap();
+    assert_eq!(
+        proc_dir.add_node("gen", Arc::new(Generated)).err(),
+        Some(VfsError::AlreadyExists)
+    );
+
+    let node = root.lookup("proc/gen").unwrap();
+    let mut buf = [0; 16];
+    assert_eq!(node.read_at(3, &mut buf).unwrap(), 6);
+    assert_eq!(&buf[..6], b"erated");
+    assert_eq!(proc_dir.get_entries(), ["gen"]);
+}
+
+#[test]
+fn test_ramfs_rename() -> VfsResult {
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("etc", VfsNodeType::Dir)?;
+    root.create("etc/passwd", VfsNodeType::File)?;
+    root.create("etc/passwd+", VfsNodeType::File)?;
+    root.create("a", VfsNodeType::Dir)?;
+    root.create("a/b", VfsNodeType::Dir)?;
+    root.create("a/b/f", VfsNodeType::File)?;
+
+    // replace an existing file
+    let new = root.clone().lookup("etc/passwd+")?;
+    new.write_at(0, b"root")?;
+    root.rename("/etc/passwd+", "/etc/passwd")?;
+    let node = root.clone().lookup("etc/passwd")?;
+    assert!(Arc::ptr_eq(&node, &new));
+    assert_eq!(
+        root.clone().lookup("etc/passwd+").err(),
+        Some(VfsError::NotFound)
+    );
+
+    // move a directory to another one
+    root.rename("a/b", "etc/b/")?;
+    let b = root.clone().lookup("etc/b")?;
+    assert!(Arc::ptr_eq(
+        &b.parent().unwrap(),
+        &root.clone().lookup("etc")?
+    ));
+    assert!(root.clone().lookup("etc/b/f").is_ok());
+    assert_eq!(root.clone().lookup("a/b").err(), Some(VfsError::NotFound));
+
+    assert_eq!(
+        root.rename("etc", "etc/b/c").err(),
+        Some(VfsError::InvalidInput)
+    );
+    assert_eq!(
+        root.rename("etc/b", "etc/passwd").err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.rename("etc/passwd", "etc/b").err(),
+        Some(VfsError::IsADirectory)
+    );
+    assert_eq!(
+        root.rename("a", "etc/b").err(),
+        Some(VfsError::DirectoryNotEmpty)
+    );
+    assert_eq!(
+        root.rename("etc/x", "etc/y").err(),
+        Some(VfsError::NotFound)
+    );
+    assert_eq!(
+        root.rename("etc/passwd", "etc/passwd/x").err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.rename("etc/passwd", "..").err(),
+        Some(VfsError::InvalidInput)
+    );
+    Ok(())
+}
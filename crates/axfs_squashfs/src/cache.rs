@@ -0,0 +1,71 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use axfs_vfs::VfsResult;
+use spin::Mutex;
+
+struct CacheInner<T> {
+    tick: u64,
+    entries: BTreeMap<u64, (Arc<T>, u64)>,
+}
+
+/// A least-recently-used cache of decompressed blocks, keyed by their
+/// on-disk position.
+pub struct BlockCache<T> {
+    capacity: usize,
+    inner: Mutex<CacheInner<T>>,
+}
+
+impl<T> BlockCache<T> {
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(CacheInner {
+                tick: 0,
+                entries: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// Returns the block at `key`, calling `load` to read it on a miss.
+    ///
+    /// The lock is not held while loading, so two tasks missing the same
+    /// block may both read it; only one copy is kept.
+    pub fn get_or_load(&self, key: u64, load: impl FnOnce() -> VfsResult<T>) -> VfsResult<Arc<T>> {
+        if let Some(block) = self.get(key) {
+            return Ok(block);
+        }
+        let block = Arc::new(load()?);
+        let mut inner = self.inner.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+        if inner.entries.len() >= self.capacity && !inner.entries.contains_key(&key) {
+            let lru = inner
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(k, _)| *k);
+            if let Some(lru) = lru {
+                inner.entries.remove(&lru);
+            }
+        }
+        inner.entries.insert(key, (block.clone(), tick));
+        Ok(block)
+    }
+
+    fn get(&self, key: u64) -> Option<Arc<T>> {
+        let mut inner = self.inner.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.entries.get_mut(&key).map(|(block, used)| {
+            *used = tick;
+            block.clone()
+        })
+    }
+
+    /// Returns the number of cached blocks.
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.inner.lock().entries.len()
+    }
+}
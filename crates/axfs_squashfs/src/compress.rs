@@ -0,0 +1,75 @@
+use axfs_vfs::{VfsError, VfsResult};
+
+/// Compression algorithm of an image, as recorded in the superblock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compressor {
+    #[cfg(feature = "lz4")]
+    Lz4,
+    #[cfg(feature = "zstd")]
+    Zstd,
+    /// An algorithm that is not supported or not enabled.
+    ///
+    /// Images using it can still be read if all of their blocks are stored
+    /// uncompressed.
+    Unsupported(u16),
+}
+
+impl Compressor {
+    pub fn from_id(id: u16) -> Self {
+        match id {
+            #[cfg(feature = "lz4")]
+            5 => Self::Lz4,
+            #[cfg(feature = "zstd")]
+            6 => Self::Zstd,
+            _ => Self::Unsupported(id),
+        }
+    }
+
+    /// Decompresses `src` into `dst`, returning the decompressed length.
+    pub fn decompress(self, src: &[u8], dst: &mut [u8]) -> VfsResult<usize> {
+        match self {
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => lz4_flex::block::decompress_into(src, dst).map_err(|e| {
+                log::warn!("squashfs: lz4 decompression failed: {:?}", e);
+                VfsError::InvalidData
+            }),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => zstd_decompress(src, dst),
+            Self::Unsupported(id) => {
+                log::warn!(
+                    "squashfs: cannot decompress {} bytes, unsupported compression {}",
+                    src.len(),
+                    id
+                );
+                let _ = dst;
+                Err(VfsError::Unsupported)
+            }
+        }
+    }
+}
+
+/// Decompresses the zstd frame in `src` into `dst`, which must be large enough
+/// to hold all of it.
+#[cfg(feature = "zstd")]
+fn zstd_decompress(src: &[u8], dst: &mut [u8]) -> VfsResult<usize> {
+    use ruzstd::io::Read;
+
+    fn zstd_err(e: impl core::fmt::Debug) -> VfsError {
+        log::warn!("squashfs: zstd decompression failed: {:?}", e);
+        VfsError::InvalidData
+    }
+
+    let mut decoder = ruzstd::StreamingDecoder::new(src).map_err(zstd_err)?;
+    let mut len = 0;
+    while len < dst.len() {
+        match decoder.read(&mut dst[len..]).map_err(zstd_err)? {
+            0 => return Ok(len),
+            n => len += n,
+        }
+    }
+    // the block must end here
+    match decoder.read(&mut [0]).map_err(zstd_err)? {
+        0 => Ok(len),
+        _ => Err(zstd_err("block too large")),
+    }
+}
@@ -0,0 +1,139 @@
+use alloc::sync::{Arc, Weak};
+use alloc::{string::String, vec::Vec};
+
+use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
+use axfs_vfs::{VfsError, VfsResult};
+use spin::{Once, RwLock};
+
+use crate::file::FileNode;
+use crate::inode::{DirEntry, Inode};
+use crate::reader::FsInner;
+
+/// The directory node in the SquashFS filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`]. Entries are read from the image
+/// on first use, and child nodes are created on each lookup.
+pub struct DirNode {
+    fs: Arc<FsInner>,
+    inode: Inode,
+    this: Weak<DirNode>,
+    parent: RwLock<Option<VfsNodeRef>>,
+    entries: Once<Vec<DirEntry>>,
+}
+
+impl DirNode {
+    pub(super) fn new(fs: Arc<FsInner>, inode: Inode, parent: Option<VfsNodeRef>) -> Arc<Self> {
+        Arc::new_cyclic(|this| Self {
+            fs,
+            inode,
+            this: this.clone(),
+            parent: RwLock::new(parent),
+            entries: Once::new(),
+        })
+    }
+
+    pub(super) fn set_parent(&self, parent: Option<&VfsNodeRef>) {
+        *self.parent.write() = parent.cloned();
+    }
+
+    fn entries(&self) -> VfsResult<&[DirEntry]> {
+        self.entries
+            .try_call_once(|| self.fs.read_dir_entries(&self.inode.kind))
+            .map(|entries| entries.as_slice())
+    }
+
+    /// Returns a string list of all entries in this directory.
+    pub fn get_entries(&self) -> VfsResult<Vec<String>> {
+        Ok(self.entries()?.iter().map(|e| e.name.clone()).collect())
+    }
+
+    fn child(&self, name: &str) -> VfsResult<VfsNodeRef> {
+        let entries = self.entries()?;
+        let idx = entries
+            .binary_search_by(|e| e.name.as_str().cmp(name))
+            .map_err(|_| VfsError::NotFound)?;
+        let inode = self.fs.read_inode(entries[idx].inode)?;
+        if inode.ty.is_dir() {
+            let this = self.this.upgrade().map(|this| this as VfsNodeRef);
+            Ok(Self::new(self.fs.clone(), inode, this))
+        } else {
+            Ok(Arc::new(FileNode::new(self.fs.clone(), inode)))
+        }
+    }
+}
+
+impl VfsNodeOps for DirNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(self.inode.attr())
+    }
+
+    fn set_attr(&self, _attr: VfsNodeAttr) -> VfsResult {
+        Err(VfsError::PermissionDenied)
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        self.parent.read().clone()
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        let (name, rest) = split_path(path);
+        let node = match name {
+            "" | "." => Ok(self.clone() as VfsNodeRef),
+            ".." => self.parent().ok_or(VfsError::NotFound),
+            _ => self.child(name),
+        }?;
+
+        if let Some(rest) = rest {
+            node.lookup(rest)
+        } else {
+            Ok(node)
+        }
+    }
+
+    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        let mut entries = self.entries()?.iter().skip(start_idx.max(2) - 2);
+        for (i, ent) in dirents.iter_mut().enumerate() {
+            match i + start_idx {
+                0 => *ent = VfsDirEntry::new(".", VfsNodeType::Dir),
+                1 => *ent = VfsDirEntry::new("..", VfsNodeType::Dir),
+                _ => {
+                    if let Some(entry) = entries.next() {
+                        *ent = VfsDirEntry::new(&entry.name, entry.ty);
+                    } else {
+                        return Ok(i);
+                    }
+                }
+            }
+        }
+        Ok(dirents.len())
+    }
+
+    /// Succeeds only if the node already exists, so that mount points can be
+    /// "created" on a read-only image.
+    fn create(&self, path: &str, _ty: VfsNodeType) -> VfsResult {
+        log::debug!("create at squashfs: {}", path);
+        let this = self.this.upgrade().ok_or(VfsError::NotFound)?;
+        match this.lookup(path) {
+            Ok(_) => Ok(()),
+            Err(VfsError::NotFound) => Err(VfsError::PermissionDenied),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn remove(&self, _path: &str) -> VfsResult {
+        Err(VfsError::PermissionDenied)
+    }
+
+    fn rename(&self, _src_path: &str, _dst_path: &str) -> VfsResult {
+        Err(VfsError::PermissionDenied)
+    }
+
+    axfs_vfs::impl_vfs_dir_default! {}
+}
+
+fn split_path(path: &str) -> (&str, Option<&str>) {
+    let trimmed_path = path.trim_start_matches('/');
+    trimmed_path.find('/').map_or((trimmed_path, None), |n| {
+        (&trimmed_path[..n], Some(&trimmed_path[n + 1..]))
+    })
+}
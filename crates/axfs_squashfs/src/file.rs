@@ -0,0 +1,126 @@
+use alloc::{sync::Arc, vec::Vec};
+use axfs_vfs::{impl_vfs_non_dir_default, VfsError, VfsNodeAttr, VfsNodeOps, VfsResult};
+
+use crate::inode::{is_sparse, Inode, InodeKind};
+use crate::layout::{DATA_UNCOMPRESSED, NO_FRAGMENT};
+use crate::reader::FsInner;
+
+/// The non-directory node in the SquashFS filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`]. Reading a regular file returns its
+/// contents, and reading a symbolic link returns its target.
+pub struct FileNode {
+    fs: Arc<FsInner>,
+    inode: Inode,
+    /// Position in the image of each full data block.
+    block_positions: Vec<u64>,
+}
+
+impl FileNode {
+    pub(super) fn new(fs: Arc<FsInner>, inode: Inode) -> Self {
+        let block_positions = match &inode.kind {
+            InodeKind::File {
+                blocks_start,
+                block_sizes,
+                ..
+            } => block_sizes
+                .iter()
+                .scan(*blocks_start, |pos, &size| {
+                    let this = *pos;
+                    *pos += (size & !DATA_UNCOMPRESSED) as u64;
+                    Some(this)
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        Self {
+            fs,
+            inode,
+            block_positions,
+        }
+    }
+
+    /// Copies the part of block `idx` starting at `offset` into `buf`.
+    fn read_block(&self, idx: usize, offset: usize, buf: &mut [u8]) -> VfsResult<usize> {
+        let (block_sizes, fragment, fragment_offset) = match &self.inode.kind {
+            InodeKind::File {
+                block_sizes,
+                fragment,
+                fragment_offset,
+                ..
+            } => (block_sizes, *fragment, *fragment_offset as usize),
+            _ => return Err(VfsError::Unsupported),
+        };
+        let block_size = self.fs.superblock().block_size as usize;
+        let len = buf.len().min(block_size - offset);
+        if let Some(&size) = block_sizes.get(idx) {
+            if is_sparse(size) {
+                buf[..len].fill(0);
+                return Ok(len);
+            }
+            let data = self.fs.read_data_block(self.block_positions[idx], size)?;
+            let src = data
+                .get(offset..offset + len)
+                .ok_or(VfsError::InvalidData)?;
+            buf[..len].copy_from_slice(src);
+        } else {
+            if fragment == NO_FRAGMENT {
+                return Err(VfsError::InvalidData);
+            }
+            let (pos, size) = self.fs.fragment(fragment)?;
+            let data = self.fs.read_data_block(pos, size)?;
+            let start = fragment_offset + offset;
+            let src = data.get(start..start + len).ok_or(VfsError::InvalidData)?;
+            buf[..len].copy_from_slice(src);
+        }
+        Ok(len)
+    }
+}
+
+impl VfsNodeOps for FileNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(self.inode.attr())
+    }
+
+    fn set_attr(&self, _attr: VfsNodeAttr) -> VfsResult {
+        Err(VfsError::PermissionDenied)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Err(VfsError::PermissionDenied)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let size = self.inode.size;
+        if offset >= size {
+            return Ok(0);
+        }
+        let end = (size - offset).min(buf.len() as u64) as usize;
+        let buf = &mut buf[..end];
+        if let InodeKind::Symlink { target } = &self.inode.kind {
+            let start = offset as usize;
+            buf.copy_from_slice(&target[start..start + end]);
+            return Ok(end);
+        }
+
+        let block_size = self.fs.superblock().block_size as u64;
+        let mut read = 0;
+        while read < buf.len() {
+            let pos = offset + read as u64;
+            let idx = (pos / block_size) as usize;
+            let block_offset = (pos % block_size) as usize;
+            read += self.read_block(idx, block_offset, &mut buf[read..])?;
+        }
+        Ok(read)
+    }
+
+    fn write_at(&self, _offset: u64, _buf: &[u8]) -> VfsResult<usize> {
+        Err(VfsError::PermissionDenied)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        Ok(())
+    }
+
+    impl_vfs_non_dir_default! {}
+}
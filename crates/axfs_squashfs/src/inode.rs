@@ -0,0 +1,221 @@
+use alloc::{string::String, vec, vec::Vec};
+
+use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodePerm, VfsNodeType, VfsResult};
+
+use crate::layout::{DATA_UNCOMPRESSED, NO_FRAGMENT};
+use crate::reader::FsInner;
+
+/// Size of the header shared by all inode types, in bytes.
+const INODE_HEADER_SIZE: usize = 16;
+
+/// Type-specific part of an inode.
+pub enum InodeKind {
+    Dir {
+        /// Position of the listing's first metadata block, relative to the
+        /// directory table.
+        block: u32,
+        /// Offset of the listing in the decompressed metadata block.
+        offset: u16,
+        /// Size of the listing, in bytes.
+        size: u32,
+    },
+    File {
+        /// Position of the first data block in the image.
+        blocks_start: u64,
+        /// On-disk size fields of the full data blocks.
+        block_sizes: Vec<u32>,
+        /// Index of the fragment holding the tail, or [`NO_FRAGMENT`].
+        fragment: u32,
+        /// Offset of the tail in the decompressed fragment block.
+        fragment_offset: u32,
+    },
+    Symlink {
+        target: Vec<u8>,
+    },
+    Other,
+}
+
+/// A decoded inode.
+pub struct Inode {
+    pub ty: VfsNodeType,
+    pub perm: VfsNodePerm,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    pub kind: InodeKind,
+}
+
+impl Inode {
+    pub fn attr(&self) -> VfsNodeAttr {
+        VfsNodeAttr::new(
+            self.perm,
+            self.uid,
+            self.gid,
+            self.ty,
+            self.size,
+            self.size.div_ceil(512),
+        )
+    }
+}
+
+/// Maps the basic inode type used in directory entries to a node type.
+pub fn node_type(ty: u16) -> VfsResult<VfsNodeType> {
+    Ok(match ty {
+        1 => VfsNodeType::Dir,
+        2 => VfsNodeType::File,
+        3 => VfsNodeType::SymLink,
+        4 => VfsNodeType::BlockDevice,
+        5 => VfsNodeType::CharDevice,
+        6 => VfsNodeType::Fifo,
+        7 => VfsNodeType::Socket,
+        _ => return Err(VfsError::InvalidData),
+    })
+}
+
+/// A directory entry decoded from a directory listing.
+pub struct DirEntry {
+    pub name: String,
+    pub ty: VfsNodeType,
+    /// Reference to the inode: the position of its metadata block relative to
+    /// the inode table in the upper 48 bits, and its offset in the
+    /// decompressed block in the lower 16 bits.
+    pub inode: u64,
+}
+
+impl FsInner {
+    /// Reads the inode referenced by `inode_ref`.
+    pub fn read_inode(&self, inode_ref: u64) -> VfsResult<Inode> {
+        let pos = self.superblock().inode_table + (inode_ref >> 16);
+        let mut r = self.metadata(pos, (inode_ref & 0xffff) as usize);
+        let mut header = [0; INODE_HEADER_SIZE];
+        r.read(&mut header)?;
+        let raw_ty = u16::from_le_bytes([header[0], header[1]]);
+        let mode = u16::from_le_bytes([header[2], header[3]]);
+        let uid = self.id(u16::from_le_bytes([header[4], header[5]]))?;
+        let gid = self.id(u16::from_le_bytes([header[6], header[7]]))?;
+        // Extended types are numbered 7 above their basic counterparts.
+        let extended = raw_ty > 7;
+        let ty = node_type(if extended { raw_ty - 7 } else { raw_ty })?;
+
+        let (size, kind) = match ty {
+            VfsNodeType::Dir => {
+                let (block, size, offset) = if extended {
+                    let _link_count = r.read_u32()?;
+                    let size = r.read_u32()?;
+                    let block = r.read_u32()?;
+                    let _parent = r.read_u32()?;
+                    let _index_count = r.read_u16()?;
+                    (block, size, r.read_u16()?)
+                } else {
+                    let block = r.read_u32()?;
+                    let _link_count = r.read_u32()?;
+                    let size = r.read_u16()? as u32;
+                    (block, size, r.read_u16()?)
+                };
+                let kind = InodeKind::Dir {
+                    block,
+                    offset,
+                    size,
+                };
+                (size as u64, kind)
+            }
+            VfsNodeType::File => {
+                let (blocks_start, size, fragment, fragment_offset) = if extended {
+                    let blocks_start = r.read_u64()?;
+                    let size = r.read_u64()?;
+                    let _sparse = r.read_u64()?;
+                    let _link_count = r.read_u32()?;
+                    let fragment = r.read_u32()?;
+                    let fragment_offset = r.read_u32()?;
+                    let _xattr = r.read_u32()?;
+                    (blocks_start, size, fragment, fragment_offset)
+                } else {
+                    let blocks_start = r.read_u32()? as u64;
+                    let fragment = r.read_u32()?;
+                    let fragment_offset = r.read_u32()?;
+                    let size = r.read_u32()? as u64;
+                    (blocks_start, size, fragment, fragment_offset)
+                };
+                let block_size = self.superblock().block_size as u64;
+                let count = if fragment == NO_FRAGMENT {
+                    size.div_ceil(block_size)
+                } else {
+                    size / block_size
+                };
+                let mut block_sizes = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    block_sizes.push(r.read_u32()?);
+                }
+                let kind = InodeKind::File {
+                    blocks_start,
+                    block_sizes,
+                    fragment,
+                    fragment_offset,
+                };
+                (size, kind)
+            }
+            VfsNodeType::SymLink => {
+                let _link_count = r.read_u32()?;
+                let len = r.read_u32()?;
+                let mut target = vec![0; len as usize];
+                r.read(&mut target)?;
+                (len as u64, InodeKind::Symlink { target })
+            }
+            _ => (0, InodeKind::Other),
+        };
+        Ok(Inode {
+            ty,
+            perm: VfsNodePerm::from_bits_truncate(mode & 0o7777),
+            uid,
+            gid,
+            size,
+            kind,
+        })
+    }
+
+    /// Reads the entries of the directory listing described by `kind`.
+    pub fn read_dir_entries(&self, kind: &InodeKind) -> VfsResult<Vec<DirEntry>> {
+        let (block, offset, size) = match *kind {
+            InodeKind::Dir {
+                block,
+                offset,
+                size,
+            } => (block, offset, size),
+            _ => return Err(VfsError::NotADirectory),
+        };
+        // The recorded size includes 3 bytes for the implicit `.` and `..`.
+        let mut remaining = (size as usize).saturating_sub(3);
+        let pos = self.superblock().dir_table + block as u64;
+        let mut r = self.metadata(pos, offset as usize);
+        let mut entries = Vec::new();
+        while remaining >= 12 {
+            let count = r.read_u32()? as usize + 1;
+            let start = r.read_u32()? as u64;
+            let _inode_number = r.read_u32()?;
+            remaining -= 12;
+            for _ in 0..count {
+                let offset = r.read_u16()? as u64;
+                let _inode_offset = r.read_u16()?;
+                let ty = node_type(r.read_u16()?)?;
+                let name_len = r.read_u16()? as usize + 1;
+                let mut name = vec![0; name_len];
+                r.read(&mut name)?;
+                remaining = remaining
+                    .checked_sub(8 + name_len)
+                    .ok_or(VfsError::InvalidData)?;
+                let name = String::from_utf8(name).map_err(|_| VfsError::InvalidData)?;
+                entries.push(DirEntry {
+                    name,
+                    ty,
+                    inode: start << 16 | offset,
+                });
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Returns whether a data block size field denotes a sparse (all-zero) block.
+pub const fn is_sparse(size: u32) -> bool {
+    size & !DATA_UNCOMPRESSED == 0
+}
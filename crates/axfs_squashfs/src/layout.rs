@@ -0,0 +1,96 @@
+//! On-disk layout of SquashFS 4.0 images.
+//!
+//! All integers are stored in little-endian.
+
+use axfs_vfs::{VfsError, VfsResult};
+
+/// The magic number `hsqs` at the start of the superblock.
+pub const MAGIC: u32 = 0x7371_7368;
+/// Size of the superblock, in bytes.
+pub const SUPERBLOCK_SIZE: usize = 96;
+/// Size of an uncompressed metadata block, in bytes.
+pub const METADATA_BLOCK_SIZE: usize = 8192;
+/// Set in a metadata block header if the block is stored uncompressed.
+pub const METADATA_UNCOMPRESSED: u16 = 1 << 15;
+/// Set in a data block or fragment size if the block is stored uncompressed.
+pub const DATA_UNCOMPRESSED: u32 = 1 << 24;
+/// Marks a file without a fragment.
+pub const NO_FRAGMENT: u32 = 0xffff_ffff;
+/// Size of a fragment table entry, in bytes.
+pub const FRAGMENT_ENTRY_SIZE: usize = 16;
+
+/// Little-endian field accessors over a byte slice.
+pub struct Le<'a>(pub &'a [u8]);
+
+impl Le<'_> {
+    pub fn u16(&self, off: usize) -> u16 {
+        u16::from_le_bytes([self.0[off], self.0[off + 1]])
+    }
+
+    pub fn u32(&self, off: usize) -> u32 {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(&self.0[off..off + 4]);
+        u32::from_le_bytes(buf)
+    }
+
+    pub fn u64(&self, off: usize) -> u64 {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(&self.0[off..off + 8]);
+        u64::from_le_bytes(buf)
+    }
+}
+
+/// The superblock at the start of the image.
+#[derive(Debug, Clone, Copy)]
+pub struct SuperBlock {
+    pub inode_count: u32,
+    pub block_size: u32,
+    pub fragment_count: u32,
+    pub compression: u16,
+    pub id_count: u16,
+    pub root_inode: u64,
+    pub bytes_used: u64,
+    pub id_table: u64,
+    pub inode_table: u64,
+    pub dir_table: u64,
+    pub fragment_table: u64,
+}
+
+impl SuperBlock {
+    /// Parses and validates the superblock.
+    pub fn parse(buf: &[u8; SUPERBLOCK_SIZE]) -> VfsResult<Self> {
+        let le = Le(buf);
+        if le.u32(0) != MAGIC {
+            log::warn!("squashfs: bad magic {:#x}", le.u32(0));
+            return Err(VfsError::InvalidData);
+        }
+        let (major, minor) = (le.u16(28), le.u16(30));
+        if (major, minor) != (4, 0) {
+            log::warn!("squashfs: unsupported version {}.{}", major, minor);
+            return Err(VfsError::InvalidData);
+        }
+        let sb = Self {
+            inode_count: le.u32(4),
+            block_size: le.u32(12),
+            fragment_count: le.u32(16),
+            compression: le.u16(20),
+            id_count: le.u16(26),
+            root_inode: le.u64(32),
+            bytes_used: le.u64(40),
+            id_table: le.u64(48),
+            inode_table: le.u64(64),
+            dir_table: le.u64(72),
+            fragment_table: le.u64(80),
+        };
+        let block_log = le.u16(22);
+        if !sb.block_size.is_power_of_two()
+            || sb.block_size < 4096
+            || sb.block_size > 1 << 20
+            || 1u32.checked_shl(block_log as u32) != Some(sb.block_size)
+        {
+            log::warn!("squashfs: invalid block size {}", sb.block_size);
+            return Err(VfsError::InvalidData);
+        }
+        Ok(sb)
+    }
+}
@@ -0,0 +1,97 @@
+//! Read-only [SquashFS] image filesystem used by [ArceOS](https://github.com/rcore-os/arceos).
+//!
+//! The implementation is based on [`axfs_vfs`]. It reads SquashFS 4.0 images
+//! from any [`ImageReader`], such as a block device or an in-memory buffer.
+//! Metadata and data blocks compressed by LZ4 (feature `lz4`) or zstd (feature
+//! `zstd`) are decompressed on demand, and recently used blocks are kept in a
+//! decompressed-block cache. Uncompressed blocks are always supported.
+//!
+//! All modifying operations fail with [`PermissionDenied`](axfs_vfs::VfsError::PermissionDenied).
+//!
+//! [SquashFS]: https://dr-emann.github.io/squashfs/
+
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod cache;
+mod compress;
+mod dir;
+mod file;
+mod inode;
+mod layout;
+mod reader;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::dir::DirNode;
+pub use self::file::FileNode;
+pub use self::reader::ImageReader;
+
+use alloc::sync::Arc;
+use axfs_vfs::{FileSystemInfo, VfsNodeRef, VfsOps, VfsResult};
+use spin::once::Once;
+
+use self::reader::FsInner;
+
+/// Maximum length of a file name in SquashFS.
+const NAME_MAX: u64 = 256;
+
+/// A read-only SquashFS filesystem that implements [`axfs_vfs::VfsOps`].
+pub struct SquashFileSystem {
+    inner: Arc<FsInner>,
+    parent: Once<VfsNodeRef>,
+    root: Arc<DirNode>,
+}
+
+impl SquashFileSystem {
+    /// Opens the SquashFS image provided by `image`.
+    ///
+    /// Returns [`InvalidData`](axfs_vfs::VfsError::InvalidData) if the image
+    /// does not contain a valid SquashFS 4.0 superblock.
+    pub fn new<R: ImageReader + 'static>(image: R) -> VfsResult<Self> {
+        let inner = Arc::new(FsInner::new(alloc::boxed::Box::new(image))?);
+        let root_inode = inner.read_inode(inner.superblock().root_inode)?;
+        if !root_inode.ty.is_dir() {
+            return Err(axfs_vfs::VfsError::InvalidData);
+        }
+        let root = DirNode::new(inner.clone(), root_inode, None);
+        Ok(Self {
+            inner,
+            parent: Once::new(),
+            root,
+        })
+    }
+
+    /// Returns the root directory node in [`Arc<DirNode>`](DirNode).
+    pub fn root_dir_node(&self) -> Arc<DirNode> {
+        self.root.clone()
+    }
+}
+
+impl VfsOps for SquashFileSystem {
+    fn mount(&self, _path: &str, mount_point: VfsNodeRef) -> VfsResult {
+        if let Some(parent) = mount_point.parent() {
+            self.root.set_parent(Some(self.parent.call_once(|| parent)));
+        } else {
+            self.root.set_parent(None);
+        }
+        Ok(())
+    }
+
+    /// The image cannot be modified, so there are no free blocks or inodes.
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        let sb = self.inner.superblock();
+        let block_size = sb.block_size as u64;
+        let blocks = sb.bytes_used.div_ceil(block_size);
+        Ok(
+            FileSystemInfo::new(block_size, blocks, 0, sb.inode_count as u64, 0)
+                .with_name_max(NAME_MAX),
+        )
+    }
+
+    fn root_dir(&self) -> VfsNodeRef {
+        self.root.clone()
+    }
+}
@@ -0,0 +1,228 @@
+use alloc::{boxed::Box, sync::Arc, vec, vec::Vec};
+
+use axfs_vfs::{VfsError, VfsResult};
+
+use crate::cache::BlockCache;
+use crate::compress::Compressor;
+use crate::layout::*;
+
+/// Number of decompressed metadata blocks kept in the cache.
+const METADATA_CACHE_CAPACITY: usize = 64;
+/// Number of decompressed data blocks and fragments kept in the cache.
+const DATA_CACHE_CAPACITY: usize = 32;
+
+/// Random-access reader of a SquashFS image.
+pub trait ImageReader: Send + Sync {
+    /// Reads exactly `buf.len()` bytes starting at byte `offset` of the image.
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult;
+}
+
+fn read_slice(image: &[u8], offset: u64, buf: &mut [u8]) -> VfsResult {
+    let start = usize::try_from(offset).map_err(|_| VfsError::UnexpectedEof)?;
+    let end = start
+        .checked_add(buf.len())
+        .ok_or(VfsError::UnexpectedEof)?;
+    buf.copy_from_slice(image.get(start..end).ok_or(VfsError::UnexpectedEof)?);
+    Ok(())
+}
+
+impl ImageReader for Vec<u8> {
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult {
+        read_slice(self, offset, buf)
+    }
+}
+
+impl ImageReader for &'static [u8] {
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult {
+        read_slice(self, offset, buf)
+    }
+}
+
+/// A decompressed metadata block.
+pub struct MetadataBlock {
+    data: Vec<u8>,
+    /// Position of the following metadata block in the image.
+    next: u64,
+}
+
+/// State shared by all nodes of a mounted image.
+pub struct FsInner {
+    image: Box<dyn ImageReader>,
+    sb: SuperBlock,
+    compressor: Compressor,
+    ids: Vec<u32>,
+    fragment_blocks: Vec<u64>,
+    metadata_cache: BlockCache<MetadataBlock>,
+    data_cache: BlockCache<Vec<u8>>,
+}
+
+impl FsInner {
+    pub fn new(image: Box<dyn ImageReader>) -> VfsResult<Self> {
+        let mut buf = [0; SUPERBLOCK_SIZE];
+        image.read_exact_at(0, &mut buf)?;
+        let sb = SuperBlock::parse(&buf)?;
+        let mut fs = Self {
+            image,
+            sb,
+            compressor: Compressor::from_id(sb.compression),
+            ids: Vec::new(),
+            fragment_blocks: Vec::new(),
+            metadata_cache: BlockCache::new(METADATA_CACHE_CAPACITY),
+            data_cache: BlockCache::new(DATA_CACHE_CAPACITY),
+        };
+        fs.ids = fs.read_id_table()?;
+        let fragment_blocks =
+            (sb.fragment_count as usize).div_ceil(METADATA_BLOCK_SIZE / FRAGMENT_ENTRY_SIZE);
+        fs.fragment_blocks = fs.read_u64s(sb.fragment_table, fragment_blocks)?;
+        Ok(fs)
+    }
+
+    pub fn superblock(&self) -> &SuperBlock {
+        &self.sb
+    }
+
+    /// Maps an index into the id table to a user or group id.
+    pub fn id(&self, index: u16) -> VfsResult<u32> {
+        self.ids
+            .get(index as usize)
+            .copied()
+            .ok_or(VfsError::InvalidData)
+    }
+
+    fn read_u64s(&self, offset: u64, count: usize) -> VfsResult<Vec<u64>> {
+        let mut buf = vec![0; count * 8];
+        if count > 0 {
+            self.image.read_exact_at(offset, &mut buf)?;
+        }
+        Ok((0..count).map(|i| Le(&buf).u64(i * 8)).collect())
+    }
+
+    /// Reads the id table, which is stored in consecutive metadata blocks
+    /// located through the list of block pointers in the superblock.
+    fn read_id_table(&self) -> VfsResult<Vec<u32>> {
+        let count = self.sb.id_count as usize;
+        let blocks = (count * 4).div_ceil(METADATA_BLOCK_SIZE);
+        let mut ids = Vec::with_capacity(count);
+        if let Some(&first) = self.read_u64s(self.sb.id_table, blocks)?.first() {
+            let mut reader = self.metadata(first, 0);
+            for _ in 0..count {
+                ids.push(reader.read_u32()?);
+            }
+        }
+        Ok(ids)
+    }
+
+    fn read_metadata_block(&self, pos: u64) -> VfsResult<Arc<MetadataBlock>> {
+        self.metadata_cache.get_or_load(pos, || {
+            let mut header = [0; 2];
+            self.image.read_exact_at(pos, &mut header)?;
+            let header = u16::from_le_bytes(header);
+            let size = (header & !METADATA_UNCOMPRESSED) as usize;
+            if size == 0 || size > METADATA_BLOCK_SIZE {
+                return Err(VfsError::InvalidData);
+            }
+            let mut raw = vec![0; size];
+            self.image.read_exact_at(pos + 2, &mut raw)?;
+            let data = if header & METADATA_UNCOMPRESSED != 0 {
+                raw
+            } else {
+                let mut data = vec![0; METADATA_BLOCK_SIZE];
+                let len = self.compressor.decompress(&raw, &mut data)?;
+                data.truncate(len);
+                data
+            };
+            Ok(MetadataBlock {
+                data,
+                next: pos + 2 + size as u64,
+            })
+        })
+    }
+
+    /// Returns a reader of the metadata starting at byte `offset` of the
+    /// decompressed metadata block at position `pos`.
+    pub fn metadata(&self, pos: u64, offset: usize) -> MetadataReader<'_> {
+        MetadataReader {
+            fs: self,
+            pos,
+            offset,
+        }
+    }
+
+    /// Reads the data block or fragment block at position `pos`, whose
+    /// on-disk size field is `size`.
+    pub fn read_data_block(&self, pos: u64, size: u32) -> VfsResult<Arc<Vec<u8>>> {
+        self.data_cache.get_or_load(pos, || {
+            let disk_size = (size & !DATA_UNCOMPRESSED) as usize;
+            if disk_size > self.sb.block_size as usize {
+                return Err(VfsError::InvalidData);
+            }
+            let mut raw = vec![0; disk_size];
+            self.image.read_exact_at(pos, &mut raw)?;
+            if size & DATA_UNCOMPRESSED != 0 {
+                return Ok(raw);
+            }
+            let mut data = vec![0; self.sb.block_size as usize];
+            let len = self.compressor.decompress(&raw, &mut data)?;
+            data.truncate(len);
+            Ok(data)
+        })
+    }
+
+    /// Returns the position and on-disk size field of fragment block `index`.
+    pub fn fragment(&self, index: u32) -> VfsResult<(u64, u32)> {
+        let per_block = METADATA_BLOCK_SIZE / FRAGMENT_ENTRY_SIZE;
+        let index = index as usize;
+        let pos = *self
+            .fragment_blocks
+            .get(index / per_block)
+            .ok_or(VfsError::InvalidData)?;
+        let mut reader = self.metadata(pos, index % per_block * FRAGMENT_ENTRY_SIZE);
+        let start = reader.read_u64()?;
+        let size = reader.read_u32()?;
+        Ok((start, size))
+    }
+}
+
+/// Sequential reader of data stored across metadata blocks.
+pub struct MetadataReader<'a> {
+    fs: &'a FsInner,
+    pos: u64,
+    offset: usize,
+}
+
+impl MetadataReader<'_> {
+    pub fn read(&mut self, buf: &mut [u8]) -> VfsResult {
+        let mut read = 0;
+        while read < buf.len() {
+            let block = self.fs.read_metadata_block(self.pos)?;
+            if self.offset >= block.data.len() {
+                self.offset -= block.data.len();
+                self.pos = block.next;
+                continue;
+            }
+            let n = (buf.len() - read).min(block.data.len() - self.offset);
+            buf[read..read + n].copy_from_slice(&block.data[self.offset..self.offset + n]);
+            read += n;
+            self.offset += n;
+        }
+        Ok(())
+    }
+
+    pub fn read_u16(&mut self) -> VfsResult<u16> {
+        let mut buf = [0; 2];
+        self.read(&mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    pub fn read_u32(&mut self) -> VfsResult<u32> {
+        let mut buf = [0; 4];
+        self.read(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    pub fn read_u64(&mut self) -> VfsResult<u64> {
+        let mut buf = [0; 8];
+        self.read(&mut buf)?;
+        Ok(u64::from_le_bytes(buf))
+    }
+}
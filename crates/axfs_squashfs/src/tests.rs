@@ -0,0 +1,400 @@
+use axfs_vfs::{VfsDirEntry, VfsError, VfsNodeType, VfsOps, VfsResult};
+
+use crate::cache::BlockCache;
+use crate::*;
+
+const BLOCK_SIZE: usize = 4096;
+const A_TXT: &[u8] = b"hello, squashfs\n";
+const BIG_SIZE: usize = BLOCK_SIZE * 2 + 100;
+const LINK_TARGET: &[u8] = b"../a.txt";
+
+fn big_byte(i: usize) -> u8 {
+    if (BLOCK_SIZE..BLOCK_SIZE * 2).contains(&i) {
+        0 // sparse block
+    } else {
+        (i % 251) as u8
+    }
+}
+
+fn put16(buf: &mut Vec<u8>, v: u16) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put32(buf: &mut Vec<u8>, v: u32) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put64(buf: &mut Vec<u8>, v: u64) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn inode_header(buf: &mut Vec<u8>, ty: u16, mode: u16, uid_idx: u16, number: u32) {
+    put16(buf, ty);
+    put16(buf, mode);
+    put16(buf, uid_idx);
+    put16(buf, 0);
+    put32(buf, 0);
+    put32(buf, number);
+}
+
+/// Compresses a block, or returns `None` to store it uncompressed.
+type Compress = fn(&[u8]) -> Option<Vec<u8>>;
+
+/// Appends a metadata block holding `data`.
+fn metadata_block(image: &mut Vec<u8>, data: &[u8], compress: Compress) {
+    match compress(data) {
+        Some(block) => {
+            put16(image, block.len() as u16);
+            image.extend_from_slice(&block);
+        }
+        None => {
+            put16(image, data.len() as u16 | 0x8000);
+            image.extend_from_slice(data);
+        }
+    }
+}
+
+/// Appends a data block or fragment block holding `data`, and returns its
+/// on-disk size field.
+fn data_block(image: &mut Vec<u8>, data: &[u8], compress: Compress) -> u32 {
+    match compress(data) {
+        Some(block) => {
+            image.extend_from_slice(&block);
+            block.len() as u32
+        }
+        None => {
+            image.extend_from_slice(data);
+            data.len() as u32 | 1 << 24
+        }
+    }
+}
+
+/// Appends a directory listing with a single header.
+fn dir_listing(buf: &mut Vec<u8>, entries: &[(&str, u16, u16, u32)]) {
+    let base = entries[0].3;
+    put32(buf, entries.len() as u32 - 1);
+    put32(buf, 0);
+    put32(buf, base);
+    for &(name, ty, offset, number) in entries {
+        put16(buf, offset);
+        put16(buf, (number - base) as u16);
+        put16(buf, ty);
+        put16(buf, name.len() as u16 - 1);
+        buf.extend_from_slice(name.as_bytes());
+    }
+}
+
+/// Builds an image with the following tree, whose blocks are compressed by
+/// `compress` for the algorithm `compression`:
+///
+/// ```text
+/// /
+/// ├── a.txt         (owned by uid 1000, stored in the fragment)
+/// ├── big.bin       (one data block, one sparse block, tail in the fragment)
+/// └── sub/
+///     └── link -> ../a.txt
+/// ```
+fn build_image_with(compression: u16, compress: Compress) -> Vec<u8> {
+    let mut image = vec![0; 96];
+
+    // Data block of `big.bin`.
+    let big_start = image.len() as u64;
+    let big_block: Vec<u8> = (0..BLOCK_SIZE).map(big_byte).collect();
+    let big_size = data_block(&mut image, &big_block, compress);
+
+    // Fragment block: `a.txt` followed by the tail of `big.bin`.
+    let frag_start = image.len() as u64;
+    let mut frag = A_TXT.to_vec();
+    frag.extend((BLOCK_SIZE * 2..BIG_SIZE).map(big_byte));
+    let frag_size = data_block(&mut image, &frag, compress);
+
+    // Inodes, all in one metadata block.
+    let mut inodes = Vec::new();
+    let a_off = inodes.len() as u16;
+    inode_header(&mut inodes, 2, 0o640, 1, 2);
+    put32(&mut inodes, 0);
+    put32(&mut inodes, 0); // fragment index
+    put32(&mut inodes, 0); // offset in fragment
+    put32(&mut inodes, A_TXT.len() as u32);
+
+    let big_off = inodes.len() as u16;
+    inode_header(&mut inodes, 9, 0o644, 0, 3);
+    put64(&mut inodes, big_start);
+    put64(&mut inodes, BIG_SIZE as u64);
+    put64(&mut inodes, BLOCK_SIZE as u64); // sparse bytes
+    put32(&mut inodes, 1);
+    put32(&mut inodes, 0); // fragment index
+    put32(&mut inodes, A_TXT.len() as u32);
+    put32(&mut inodes, u32::MAX);
+    put32(&mut inodes, big_size);
+    put32(&mut inodes, 0);
+
+    let link_off = inodes.len() as u16;
+    inode_header(&mut inodes, 3, 0o777, 0, 5);
+    put32(&mut inodes, 1);
+    put32(&mut inodes, LINK_TARGET.len() as u32);
+    inodes.extend_from_slice(LINK_TARGET);
+
+    let mut dirs = Vec::new();
+    dir_listing(&mut dirs, &[("link", 3, link_off, 5)]);
+    let sub_listing = dirs.len();
+
+    let sub_off = inodes.len() as u16;
+    inode_header(&mut inodes, 1, 0o755, 0, 4);
+    put32(&mut inodes, 0);
+    put32(&mut inodes, 2);
+    put16(&mut inodes, sub_listing as u16 + 3);
+    put16(&mut inodes, 0);
+    put32(&mut inodes, 1);
+
+    let root_listing_off = dirs.len() as u16;
+    dir_listing(
+        &mut dirs,
+        &[
+            ("a.txt", 2, a_off, 2),
+            ("big.bin", 2, big_off, 3),
+            ("sub", 1, sub_off, 4),
+        ],
+    );
+    let root_listing = dirs.len() - root_listing_off as usize;
+
+    let root_off = inodes.len() as u16;
+    inode_header(&mut inodes, 1, 0o755, 0, 1);
+    put32(&mut inodes, 0);
+    put32(&mut inodes, 3);
+    put16(&mut inodes, root_listing as u16 + 3);
+    put16(&mut inodes, root_listing_off);
+    put32(&mut inodes, 6);
+
+    let inode_table = image.len() as u64;
+    metadata_block(&mut image, &inodes, compress);
+    let dir_table = image.len() as u64;
+    metadata_block(&mut image, &dirs, compress);
+
+    let mut frags = Vec::new();
+    put64(&mut frags, frag_start);
+    put32(&mut frags, frag_size);
+    put32(&mut frags, 0);
+    let frag_block = image.len() as u64;
+    metadata_block(&mut image, &frags, compress);
+    let frag_table = image.len() as u64;
+    put64(&mut image, frag_block);
+
+    let mut ids = Vec::new();
+    put32(&mut ids, 0);
+    put32(&mut ids, 1000);
+    let id_block = image.len() as u64;
+    metadata_block(&mut image, &ids, compress);
+    let id_table = image.len() as u64;
+    put64(&mut image, id_block);
+
+    let mut sb = Vec::new();
+    put32(&mut sb, 0x7371_7368);
+    put32(&mut sb, 5); // inode count
+    put32(&mut sb, 0); // mtime
+    put32(&mut sb, BLOCK_SIZE as u32);
+    put32(&mut sb, 1); // fragment count
+    put16(&mut sb, compression);
+    put16(&mut sb, 12);
+    put16(&mut sb, 0x1 | 0x2 | 0x8 | 0x200);
+    put16(&mut sb, 2); // id count
+    put16(&mut sb, 4);
+    put16(&mut sb, 0);
+    put64(&mut sb, root_off as u64);
+    put64(&mut sb, image.len() as u64);
+    put64(&mut sb, id_table);
+    put64(&mut sb, u64::MAX); // xattr table
+    put64(&mut sb, inode_table);
+    put64(&mut sb, dir_table);
+    put64(&mut sb, frag_table);
+    put64(&mut sb, u64::MAX); // export table
+    image[..96].copy_from_slice(&sb);
+    image
+}
+
+/// Builds the image with all blocks uncompressed. The compression is gzip,
+/// which is never used.
+fn build_image() -> Vec<u8> {
+    build_image_with(1, |_| None)
+}
+
+fn test_squashfs_ops(fs: &SquashFileSystem) -> VfsResult {
+    let mut buf = [0xff; 64];
+
+    let root = fs.root_dir();
+    assert!(root.get_attr()?.is_dir());
+    assert_eq!(root.get_attr()?.perm().mode(), 0o755);
+    assert_eq!(root.clone().lookup("nope").err(), Some(VfsError::NotFound));
+    assert_eq!(
+        root.clone().lookup("a.txt/").err(),
+        Some(VfsError::NotADirectory)
+    );
+
+    let a = root.clone().lookup("///a.txt")?;
+    let attr = a.get_attr()?;
+    assert_eq!(attr.file_type(), VfsNodeType::File);
+    assert_eq!(attr.size(), A_TXT.len() as u64);
+    assert_eq!(attr.perm().mode(), 0o640);
+    assert_eq!(attr.user_id(), 1000);
+    assert_eq!(a.read_at(0, &mut buf)?, A_TXT.len());
+    assert_eq!(&buf[..A_TXT.len()], A_TXT);
+    assert_eq!(a.read_at(7, &mut buf[..4])?, 4);
+    assert_eq!(&buf[..4], b"squa");
+    assert_eq!(a.read_at(100, &mut buf)?, 0);
+
+    let big = root.clone().lookup("big.bin")?;
+    assert_eq!(big.get_attr()?.size(), BIG_SIZE as u64);
+    let mut content = vec![0xff; BIG_SIZE + 10];
+    assert_eq!(big.read_at(0, &mut content)?, BIG_SIZE);
+    assert!((0..BIG_SIZE).all(|i| content[i] == big_byte(i)));
+    // Read across the data block, the sparse block and the fragment.
+    for start in [BLOCK_SIZE - 5, BLOCK_SIZE * 2 - 5, BIG_SIZE - 5] {
+        let n = big.read_at(start as u64, &mut buf)?;
+        assert_eq!(n, 64.min(BIG_SIZE - start));
+        assert!((0..n).all(|i| buf[i] == big_byte(start + i)));
+    }
+
+    let link = root.clone().lookup("sub/link")?;
+    assert_eq!(link.get_attr()?.file_type(), VfsNodeType::SymLink);
+    assert_eq!(link.read_at(0, &mut buf)?, LINK_TARGET.len());
+    assert_eq!(&buf[..LINK_TARGET.len()], LINK_TARGET);
+
+    let a2 = root.clone().lookup("./sub/../a.txt")?;
+    assert_eq!(a2.get_attr()?.size(), A_TXT.len() as u64);
+    let sub = root.clone().lookup("sub")?;
+    assert!(sub.get_attr()?.is_dir());
+    assert_eq!(sub.read_at(0, &mut buf).err(), Some(VfsError::IsADirectory));
+
+    let mut dirents: Vec<_> = (0..8).map(|_| VfsDirEntry::default()).collect();
+    let n = root.read_dir(0, &mut dirents)?;
+    let names: Vec<_> = dirents[..n]
+        .iter()
+        .map(|e| core::str::from_utf8(e.name_as_bytes()).unwrap())
+        .collect();
+    assert_eq!(names, [".", "..", "a.txt", "big.bin", "sub"]);
+    assert_eq!(dirents[4].entry_type(), VfsNodeType::Dir);
+    assert_eq!(root.read_dir(3, &mut dirents)?, 2);
+
+    Ok(())
+}
+
+fn test_read_only(fs: &SquashFileSystem) -> VfsResult {
+    let root = fs.root_dir();
+    let a = root.clone().lookup("a.txt")?;
+    assert_eq!(a.write_at(0, b"x").err(), Some(VfsError::PermissionDenied));
+    assert_eq!(a.truncate(0).err(), Some(VfsError::PermissionDenied));
+    assert_eq!(
+        a.set_attr(a.get_attr()?).err(),
+        Some(VfsError::PermissionDenied)
+    );
+
+    // Creating an existing node succeeds, so mount points can be used.
+    root.create("sub", VfsNodeType::Dir)?;
+    assert_eq!(
+        root.create("new", VfsNodeType::File).err(),
+        Some(VfsError::PermissionDenied)
+    );
+    assert_eq!(root.remove("a.txt").err(), Some(VfsError::PermissionDenied));
+    assert_eq!(
+        root.rename("a.txt", "b.txt").err(),
+        Some(VfsError::PermissionDenied)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_squashfs() {
+    let fs = SquashFileSystem::new(build_image()).unwrap();
+    test_squashfs_ops(&fs).unwrap();
+    test_read_only(&fs).unwrap();
+
+    let info = fs.statfs().unwrap();
+    assert_eq!(info.block_size(), BLOCK_SIZE as u64);
+    assert_eq!(info.blocks_free(), 0);
+    assert_eq!(info.files(), 5);
+    assert_eq!(info.name_max(), 256);
+}
+
+#[cfg(feature = "lz4")]
+#[test]
+fn test_lz4() {
+    let compress: Compress = |data| Some(lz4_flex::block::compress(data));
+    let fs = SquashFileSystem::new(build_image_with(5, compress)).unwrap();
+    test_squashfs_ops(&fs).unwrap();
+
+    let block: Vec<u8> = (0..BLOCK_SIZE).map(big_byte).collect();
+    let lz4 = crate::compress::Compressor::from_id(5);
+    assert_eq!(
+        lz4.decompress(&compress(&block).unwrap(), &mut [0; 100]),
+        Err(VfsError::InvalidData)
+    );
+}
+
+/// The data block of `big.bin` compressed by `zstd -19 --no-check`: the first
+/// 251 bytes as literals, and a sequence repeating them.
+#[cfg(feature = "zstd")]
+fn zstd_big_block(data: &[u8]) -> Option<Vec<u8>> {
+    if data.len() != BLOCK_SIZE {
+        return None;
+    }
+    let mut block = vec![
+        0x28, 0xb5, 0x2f, 0xfd, 0x60, 0x00, 0x0f, 0x2d, 0x08, 0x00, 0xb4, 0x0f,
+    ];
+    block.extend(0..251);
+    block.extend([0x01, 0x00, 0x7b, 0x81, 0xfb, 0x7d, 0x23, 0x05]);
+    Some(block)
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd() {
+    // Only the data block of `big.bin` is compressed.
+    let fs = SquashFileSystem::new(build_image_with(6, zstd_big_block)).unwrap();
+    test_squashfs_ops(&fs).unwrap();
+
+    let block: Vec<u8> = (0..BLOCK_SIZE).map(big_byte).collect();
+    let frame = zstd_big_block(&block).unwrap();
+    let zstd = crate::compress::Compressor::from_id(6);
+    let mut data = vec![0; BLOCK_SIZE];
+    assert_eq!(zstd.decompress(&frame, &mut data), Ok(BLOCK_SIZE));
+    assert_eq!(data, block);
+    assert_eq!(
+        zstd.decompress(&frame, &mut [0; 100]),
+        Err(VfsError::InvalidData)
+    );
+    assert_eq!(
+        zstd.decompress(&frame[..100], &mut data),
+        Err(VfsError::InvalidData)
+    );
+}
+
+#[test]
+fn test_invalid_image() {
+    let mut image = build_image();
+    image[0] = 0;
+    assert_eq!(
+        SquashFileSystem::new(image).err(),
+        Some(VfsError::InvalidData)
+    );
+    assert_eq!(
+        SquashFileSystem::new(vec![0; 10]).err(),
+        Some(VfsError::UnexpectedEof)
+    );
+}
+
+#[test]
+fn test_block_cache() {
+    let cache = BlockCache::new(2);
+    assert_eq!(*cache.get_or_load(1, || Ok(10)).unwrap(), 10);
+    assert_eq!(*cache.get_or_load(2, || Ok(20)).unwrap(), 20);
+    // A hit does not call the loader and marks the block as recently used.
+    assert_eq!(*cache.get_or_load(1, || unreachable!()).unwrap(), 10);
+    assert_eq!(*cache.get_or_load(3, || Ok(30)).unwrap(), 30);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(*cache.get_or_load(1, || unreachable!()).unwrap(), 10);
+    assert_eq!(*cache.get_or_load(2, || Ok(21)).unwrap(), 21);
+    assert_eq!(
+        cache.get_or_load(4, || Err(VfsError::Io)).err(),
+        Some(VfsError::Io)
+    );
+}
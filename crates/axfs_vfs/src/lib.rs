@@ -105,6 +105,15 @@ pub trait VfsNodeOps: Send + Sync {
         ax_err!(Unsupported)
     }
 
+    /// Get the number that identifies the file of the node in its filesystem,
+    /// like an inode number. All the nodes of a file must return the same.
+    ///
+    /// The default is the address of the node, for filesystems that keep a
+    /// single node for each file.
+    fn ino(&self) -> u64 {
+        self as *const Self as *const () as usize as u64
+    }
+
     // file operations:
 
     /// Read data from the file at the given offset.
@@ -1,8 +1,19 @@
 /// Filesystem attributes.
-///
-/// Currently not used.
-#[non_exhaustive]
-pub struct FileSystemInfo;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSystemInfo {
+    /// Size of a block, in bytes.
+    block_size: u64,
+    /// Total number of blocks.
+    blocks: u64,
+    /// Number of free blocks.
+    blocks_free: u64,
+    /// Total number of inodes (file nodes).
+    files: u64,
+    /// Number of free inodes.
+    files_free: u64,
+    /// Maximum length of a file name.
+    name_max: u64,
+}
 
 /// Node (file/directory) attributes.
 #[allow(dead_code)]
@@ -46,6 +57,14 @@ bitflags::bitflags! {
         const OTHER_WRITE = 0o2;
         /// Others have execute permission.
         const OTHER_EXEC = 0o1;
+
+        /// Sticky: only the owners may remove or rename the entries of the
+        /// directory.
+        const STICKY = 0o1000;
+        /// Set-group-ID: nodes created in the directory belong to its group.
+        const SET_GID = 0o2000;
+        /// Set-user-ID.
+        const SET_UID = 0o4000;
     }
 }
 
@@ -100,7 +119,10 @@ impl VfsNodePerm {
 
     /// Returns a 9-bytes string representation of the permission.
     ///
-    /// For example, `0o755` is represented as `rwxr-xr-x`.
+    /// For example, `0o755` is represented as `rwxr-xr-x`. The set-user-ID,
+    /// set-group-ID and sticky bits are shown as `s`, `s` and `t` in place of
+    /// the execute bits, or `S`, `S` and `T` if those are not set, so `0o1777`
+    /// is represented as `rwxrwxrwt`.
     pub const fn rwx_buf(&self) -> [u8; 9] {
         let mut perm = [b'-'; 9];
         if self.contains(Self::OWNER_READ) {
@@ -130,6 +152,15 @@ impl VfsNodePerm {
         if self.contains(Self::OTHER_EXEC) {
             perm[8] = b'x';
         }
+        if self.contains(Self::SET_UID) {
+            perm[2] = if perm[2] == b'x' { b's' } else { b'S' };
+        }
+        if self.contains(Self::SET_GID) {
+            perm[5] = if perm[5] == b'x' { b's' } else { b'S' };
+        }
+        if self.contains(Self::STICKY) {
+            perm[8] = if perm[8] == b'x' { b't' } else { b'T' };
+        }
         perm
     }
 
@@ -231,6 +262,68 @@ impl VfsNodeType {
     }
 }
 
+impl FileSystemInfo {
+    /// Creates a new `FileSystemInfo` with the given block size, number of
+    /// total/free blocks and number of total/free inodes.
+    pub const fn new(
+        block_size: u64,
+        blocks: u64,
+        blocks_free: u64,
+        files: u64,
+        files_free: u64,
+    ) -> Self {
+        Self {
+            block_size,
+            blocks,
+            blocks_free,
+            files,
+            files_free,
+            name_max: 255,
+        }
+    }
+
+    /// Sets the maximum length of a file name.
+    pub const fn with_name_max(mut self, name_max: u64) -> Self {
+        self.name_max = name_max;
+        self
+    }
+
+    /// Returns the size of a block, in bytes.
+    pub const fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    /// Returns the total number of blocks.
+    pub const fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    /// Returns the number of free blocks.
+    pub const fn blocks_free(&self) -> u64 {
+        self.blocks_free
+    }
+
+    /// Returns the number of used blocks.
+    pub const fn blocks_used(&self) -> u64 {
+        self.blocks.saturating_sub(self.blocks_free)
+    }
+
+    /// Returns the total number of inodes.
+    pub const fn files(&self) -> u64 {
+        self.files
+    }
+
+    /// Returns the number of free inodes.
+    pub const fn files_free(&self) -> u64 {
+        self.files_free
+    }
+
+    /// Returns the maximum length of a file name.
+    pub const fn name_max(&self) -> u64 {
+        self.name_max
+    }
+}
+
 impl VfsNodeAttr {
     /// Creates a new `VfsNodeAttr` with the given permission mode, type, size
     /// and number of blocks.
@@ -294,7 +387,7 @@ impl VfsNodeAttr {
 
     /// Returns the permission of the num.
     pub const fn perm_from_u16(&self, num: u16) -> VfsNodePerm {
-        VfsNodePerm::from_bits_retain(num & 0o777)
+        VfsNodePerm::from_bits_truncate(num)
     }
 
     /// Returns the user id of the node.
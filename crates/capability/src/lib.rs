@@ -41,6 +41,37 @@ bitflags::bitflags! {
     }
 }
 
+bitflags::bitflags! {
+    /// Privileges of a task beyond the access rights of single objects, like
+    /// the POSIX `capabilities(7)`.
+    ///
+    /// The superuser holds all of them, and a task can drop the ones it does
+    /// not need.
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PrivCap: u64 {
+        /// Change the owner and group of any file (`CAP_CHOWN`).
+        const CHOWN = 1 << 0;
+        /// Bypass the read, write and execute permission checks of files
+        /// (`CAP_DAC_OVERRIDE`).
+        const DAC_OVERRIDE = 1 << 1;
+        /// Change the mode of files the task does not own (`CAP_FOWNER`).
+        const FOWNER = 1 << 3;
+        /// Change the group IDs and supplementary groups (`CAP_SETGID`).
+        const SETGID = 1 << 6;
+        /// Change the user IDs (`CAP_SETUID`).
+        const SETUID = 1 << 7;
+        /// Bind sockets to ports below 1024 (`CAP_NET_BIND_SERVICE`).
+        const NET_BIND_SERVICE = 1 << 10;
+        /// Shut down or reboot the system (`CAP_SYS_BOOT`).
+        const SYS_BOOT = 1 << 22;
+        /// Use real-time scheduling policies, and change the scheduling of
+        /// tasks of other users (`CAP_SYS_NICE`).
+        const SYS_NICE = 1 << 23;
+        /// Read the audit log (`CAP_AUDIT_READ`).
+        const AUDIT_READ = 1 << 37;
+    }
+}
+
 /// Error type for capability violation.
 #[derive(Debug, Default, Eq, PartialEq)]
 #[non_exhaustive]
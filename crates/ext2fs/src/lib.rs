@@ -18,7 +18,7 @@ pub use block_dev::BlockDevice;
 pub use config::{BLOCKS_PER_GRP, BLOCK_SIZE};
 pub use efs::Ext2FileSystem;
 use layout::{BlockGroupDesc, DiskInode, SuperBlock};
-pub use layout::{EXT2_S_IFDIR, EXT2_S_IFREG};
+pub use layout::{EXT2_S_IFDIR, EXT2_S_IFREG, IMODE};
 pub use timer::{TimeProvider, ZeroTimeProvider};
 pub use vfs::Inode;
 use vfs::InodeCache;
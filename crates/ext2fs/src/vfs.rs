@@ -96,8 +96,24 @@ impl Inode {
     }
 
     pub fn create(&self, name: &str, file_type: u16) -> Option<Self> {
+        self.create_with_owner(name, file_type, DEFAULT_IMODE.bits(), 0, 0)
+    }
+
+    /// Creates a node with the permission bits `mode`, owned by `uid` and `gid`.
+    ///
+    /// In a directory with the set-group-ID bit, the node belongs to the group
+    /// of the directory instead, and a new directory inherits the bit.
+    pub fn create_with_owner(
+        &self,
+        name: &str,
+        file_type: u16,
+        mode: u16,
+        uid: usize,
+        gid: usize,
+    ) -> Option<Self> {
         let mut lk = self.access()?.lock();
-        lk.create(name, file_type).map(|inner| Self::new(inner))
+        lk.create(name, file_type, IMODE::from_bits_truncate(mode), uid, gid)
+            .map(|inner| Self::new(inner))
     }
 
     pub fn ls(&self) -> Option<Vec<String>> {
@@ -285,13 +301,27 @@ impl InodeCache {
         }
     }
 
-    pub fn create(&mut self, name: &str, mut file_type: u16) -> Option<Arc<SpinMutex<InodeCache>>> {
+    pub fn create(
+        &mut self,
+        name: &str,
+        mut file_type: u16,
+        mut mode: IMODE,
+        uid: usize,
+        mut gid: usize,
+    ) -> Option<Arc<SpinMutex<InodeCache>>> {
         assert!(self.file_type() == EXT2_FT_DIR);
         if self.get_inode_id(name).is_some() {
             error!("Try to create a file already exists");
             return None;
         }
         file_type &= 0xF000;
+        let parent = self.disk_inode();
+        if parent.acl().contains(IMODE::EXT2_S_ISGID) {
+            gid = parent.i_gid as usize;
+            if file_type == EXT2_S_IFDIR {
+                mode |= IMODE::EXT2_S_ISGID;
+            }
+        }
         let new_inode_id = self.fs.alloc_inode().unwrap();
         let (new_inode_block_id, new_inode_block_offset) = self.fs.get_disk_inode_pos(new_inode_id);
         let inode_block = self
@@ -302,7 +332,7 @@ impl InodeCache {
         inode_block
             .lock()
             .modify(new_inode_block_offset, |disk_inode: &mut DiskInode| {
-                *disk_inode = DiskInode::new(DEFAULT_IMODE, file_type, 0, 0);
+                *disk_inode = DiskInode::new(mode, file_type, uid, gid);
                 let cur_time = self.fs.timer.get_current_time();
                 disk_inode.i_atime = cur_time;
                 disk_inode.i_ctime = cur_time;
@@ -362,7 +392,7 @@ impl InodeCache {
     pub fn symlink(&mut self, name: &str, path_name: &str) -> bool {
         assert!(self.file_type() == EXT2_FT_DIR);
         debug!("symlink {} to {}", name, path_name);
-        if let Some(inode) = self.create(name, EXT2_S_IFLNK) {
+        if let Some(inode) = self.create(name, EXT2_S_IFLNK, DEFAULT_IMODE, 0, 0) {
             inode.lock().append(path_name.as_bytes());
             true
         } else {
@@ -537,9 +567,11 @@ impl InodeCache {
             disk_inode.i_mtime = self.fs.timer.get_current_time();
         })
     }
+    /// Replaces the permission bits, including the set-user-ID, set-group-ID
+    /// and sticky bits, with `access`.
     pub fn chmod(&self, access: IMODE) {
         self.modify_disk_inode(|disk_inode| {
-            disk_inode.i_mode = (disk_inode.i_mode & 0o7000) | access.bits();
+            disk_inode.i_mode = disk_inode.file_type() | access.bits();
             let cur_time = self.fs.timer.get_current_time();
             disk_inode.i_ctime = cur_time;
             disk_inode.i_atime = cur_time;
@@ -83,7 +83,9 @@ pub struct NoOp;
 
 cfg_if::cfg_if! {
     // For user-mode std apps, we use the alias of [`NoOp`] for all guards,
-    // since we can not disable IRQs or preemption in user-mode.
+    // since we can not disable IRQs or preemption in user-mode. Only with the
+    // `preempt` feature, the preemption of a kernel running in user-mode (e.g.,
+    // in unit tests) is still disabled/enabled by [`KernelGuardIf`].
     if #[cfg(any(target_os = "none", doc))] {
         /// A guard that disables/enables local IRQs around the critical section.
         pub struct IrqSave(usize);
@@ -99,6 +101,16 @@ cfg_if::cfg_if! {
         /// first, followed by local IRQs. When leaving the critical section, it
         /// re-enables local IRQs first, followed by kernel preemption.
         pub struct NoPreemptIrqSave(usize);
+    } else if #[cfg(feature = "preempt")] {
+        /// Alias of [`NoOp`].
+        pub type IrqSave = NoOp;
+
+        /// A guard that disables/enables kernel preemption around the critical
+        /// section.
+        pub struct NoPreempt;
+
+        /// Alias of [`NoPreempt`].
+        pub type NoPreemptIrqSave = NoPreempt;
     } else {
         /// Alias of [`NoOp`].
         pub type IrqSave = NoOp;
@@ -147,20 +159,6 @@ mod imp {
         }
     }
 
-    impl BaseGuard for NoPreempt {
-        type State = ();
-        fn acquire() -> Self::State {
-            // disable preempt
-            #[cfg(feature = "preempt")]
-            crate_interface::call_interface!(KernelGuardIf::disable_preempt);
-        }
-        fn release(_state: Self::State) {
-            // enable preempt
-            #[cfg(feature = "preempt")]
-            crate_interface::call_interface!(KernelGuardIf::enable_preempt);
-        }
-    }
-
     impl BaseGuard for NoPreemptIrqSave {
         type State = usize;
         fn acquire() -> Self::State {
@@ -198,40 +196,59 @@ mod imp {
         }
     }
 
-    impl NoPreempt {
-        /// Creates a new [`NoPreempt`] guard.
+    impl NoPreemptIrqSave {
+        /// Creates a new [`NoPreemptIrqSave`] guard.
         pub fn new() -> Self {
-            Self::acquire();
-            Self
+            Self(Self::acquire())
         }
     }
 
-    impl Drop for NoPreempt {
+    impl Drop for NoPreemptIrqSave {
         fn drop(&mut self) {
-            Self::release(())
+            Self::release(self.0)
         }
     }
 
-    impl Default for NoPreempt {
+    impl Default for NoPreemptIrqSave {
         fn default() -> Self {
             Self::new()
         }
     }
+}
 
-    impl NoPreemptIrqSave {
-        /// Creates a new [`NoPreemptIrqSave`] guard.
+#[cfg(any(target_os = "none", doc, feature = "preempt"))]
+mod no_preempt {
+    use super::*;
+
+    impl BaseGuard for NoPreempt {
+        type State = ();
+        fn acquire() -> Self::State {
+            // disable preempt
+            #[cfg(feature = "preempt")]
+            crate_interface::call_interface!(KernelGuardIf::disable_preempt);
+        }
+        fn release(_state: Self::State) {
+            // enable preempt
+            #[cfg(feature = "preempt")]
+            crate_interface::call_interface!(KernelGuardIf::enable_preempt);
+        }
+    }
+
+    impl NoPreempt {
+        /// Creates a new [`NoPreempt`] guard.
         pub fn new() -> Self {
-            Self(Self::acquire())
+            Self::acquire();
+            Self
         }
     }
 
-    impl Drop for NoPreemptIrqSave {
+    impl Drop for NoPreempt {
         fn drop(&mut self) {
-            Self::release(self.0)
+            Self::release(())
         }
     }
 
-    impl Default for NoPreemptIrqSave {
+    impl Default for NoPreempt {
         fn default() -> Self {
             Self::new()
         }
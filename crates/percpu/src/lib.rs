@@ -8,7 +8,9 @@
 //!
 //! When accessing the per-CPU data on the current CPU, it first use the thread
 //! pointer register to obtain the corresponding per-CPU data area, and then add
-//! an offset to access the corresponding field.
+//! an offset to access the corresponding field. The data on other CPUs can be
+//! accessed with `remote_ptr()`, which adds the same offset to the base address
+//! of their areas.
 //!
 //! # Notes
 //!
@@ -46,6 +48,8 @@
 #![feature(doc_cfg)]
 
 extern crate percpu_macros;
+// The code generated by `def_percpu` refers to this crate as `percpu`.
+extern crate self as percpu;
 
 #[cfg_attr(feature = "sp-naive", path = "naive.rs")]
 mod imp;
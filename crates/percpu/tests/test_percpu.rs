@@ -68,6 +68,15 @@ fn test_percpu() {
         assert_eq!(base + STRUCT.offset(), STRUCT.current_ptr() as usize);
     }
 
+    unsafe {
+        assert_eq!(STRUCT.remote_ptr(0), STRUCT.current_ptr());
+        #[cfg(not(feature = "sp-naive"))]
+        assert_eq!(
+            STRUCT.remote_ptr(1) as usize,
+            percpu_area_base(1) + STRUCT.offset()
+        );
+    }
+
     BOOL.write_current(true);
     U8.write_current(123);
     U16.write_current(0xabcd);
@@ -99,4 +108,8 @@ fn test_percpu() {
         assert_eq!(s.foo, 0x2333);
         assert_eq!(s.bar, 100);
     });
+
+    let remote = unsafe { STRUCT.remote_ref_raw(0) };
+    assert_eq!(remote.foo, 0x2333);
+    assert_eq!(remote.bar, 100);
 }
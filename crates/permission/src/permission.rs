@@ -1,35 +1,94 @@
 use axerrno::AxResult;
-use axfs_vfs::VfsNodePerm;
-use axsync::Mutex;
+use axfs_vfs::VfsNodeAttr;
 use capability::Cap;
 
-static UID: Mutex<u32> = Mutex::new(0);
+pub use axtask::{Credentials, PrivCap};
 
+/// Returns the credentials of the current task.
+pub fn current_credentials() -> Credentials {
+    axtask::current().credentials()
+}
+
+/// Replaces the credentials of the current task.
+pub fn set_current_credentials(cred: Credentials) {
+    axtask::current().set_credentials(cred)
+}
+
+/// Returns the effective user id of the current task.
 pub fn current_uid() -> AxResult<u32> {
-    Ok(*UID.lock())
+    Ok(current_credentials().euid)
 }
 
+/// Returns the effective group id of the current task.
 pub fn current_gid() -> AxResult<u32> {
-    current_uid()
+    Ok(current_credentials().egid)
+}
+
+/// Returns the file mode creation mask of the current task.
+pub fn current_umask() -> u16 {
+    axtask::current().umask()
+}
+
+/// Sets the file mode creation mask of the current task, and returns the
+/// previous mask.
+pub fn set_current_umask(mask: u16) -> u16 {
+    axtask::current().set_umask(mask)
 }
 
+/// Whether the current task holds the capability `cap`.
+pub fn current_has_cap(cap: PrivCap) -> bool {
+    current_credentials().has_cap(cap)
+}
+
+/// Sets the real, effective and saved user ids of the current task to `uid`,
+/// without any permission check.
+///
+/// Becoming the superuser grants all capabilities, and leaving it drops them.
 pub fn set_current_uid(uid: u32) -> AxResult {
-    *UID.lock() = uid;
+    let old = current_credentials();
+    let mut cred = old.clone();
+    cred.ruid = uid;
+    cred.euid = uid;
+    cred.suid = uid;
+    if uid == 0 {
+        cred.cap_permitted = PrivCap::all();
+        cred.cap_effective = PrivCap::all();
+    } else {
+        cred.update_caps(&old);
+    }
+    set_current_credentials(cred);
+    Ok(())
+}
+
+/// Sets the real, effective and saved group ids of the current task to `gid`,
+/// without any permission check.
+pub fn set_current_gid(gid: u32) -> AxResult {
+    let mut cred = current_credentials();
+    cred.rgid = gid;
+    cred.egid = gid;
+    cred.sgid = gid;
+    set_current_credentials(cred);
     Ok(())
 }
 
-pub fn fops_cap(perm: VfsNodePerm, uid: u32, gid: u32) -> Cap {
-    let current_uid = current_uid().unwrap();
-    let current_gid = current_gid().unwrap();
-    let fp = if current_uid == 0 {
-        (true, true, true)
-    } else if current_uid == uid && current_gid == gid {
+/// Returns the access the current task has to the node with attributes `attr`.
+///
+/// [`PrivCap::DAC_OVERRIDE`] grants reading and writing, but executing only if
+/// the node is a directory or executable by anyone.
+pub fn fops_cap(attr: &VfsNodeAttr) -> Cap {
+    let (perm, uid, gid) = (attr.perm(), attr.user_id(), attr.group_id());
+    let cred = current_credentials();
+    let fp = if cred.has_cap(PrivCap::DAC_OVERRIDE) {
+        let executable =
+            perm.owner_executable() || perm.group_executable() || perm.other_executable();
+        (true, true, attr.is_dir() || executable)
+    } else if cred.euid == uid {
         (
             perm.owner_readable(),
             perm.owner_writable(),
             perm.owner_executable(),
         )
-    } else if current_gid == gid {
+    } else if cred.in_group(gid) {
         (
             perm.group_readable(),
             perm.group_writable(),
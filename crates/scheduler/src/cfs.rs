@@ -2,7 +2,7 @@ use alloc::{collections::BTreeMap, sync::Arc};
 use core::ops::Deref;
 use core::sync::atomic::{AtomicIsize, Ordering};
 
-use crate::BaseScheduler;
+use crate::{BaseScheduler, SchedPolicy};
 
 /// task for CFS
 pub struct CFSTask<T> {
@@ -49,11 +49,11 @@ impl<T> CFSTask<T> {
         }
     }
 
-    fn get_id(&self) -> isize {
+    pub(crate) fn get_id(&self) -> isize {
         self.id.load(Ordering::Acquire)
     }
 
-    fn get_vruntime(&self) -> isize {
+    pub(crate) fn get_vruntime(&self) -> isize {
         if self.nice.load(Ordering::Acquire) == 0 {
             self.init_vruntime.load(Ordering::Acquire) + self.delta.load(Ordering::Acquire)
         } else {
@@ -62,13 +62,13 @@ impl<T> CFSTask<T> {
         }
     }
 
-    fn set_vruntime(&self, v: isize) {
+    pub(crate) fn set_vruntime(&self, v: isize) {
         self.init_vruntime.store(v, Ordering::Release);
     }
 
     // Simple Implementation: no change in vruntime.
     // Only modifying priority of current process is supported currently.
-    fn set_priority(&self, nice: isize) {
+    pub(crate) fn set_priority(&self, nice: isize) {
         let current_init_vruntime = self.get_vruntime();
         self.init_vruntime
             .store(current_init_vruntime, Ordering::Release);
@@ -76,14 +76,28 @@ impl<T> CFSTask<T> {
         self.nice.store(nice, Ordering::Release);
     }
 
-    fn set_id(&self, id: isize) {
+    pub(crate) fn reset_vruntime(&self, v: isize) {
+        self.init_vruntime.store(v, Ordering::Release);
+        self.delta.store(0, Ordering::Release);
+    }
+
+    pub(crate) fn set_id(&self, id: isize) {
         self.id.store(id, Ordering::Release);
     }
 
-    fn task_tick(&self) {
+    pub(crate) fn task_tick(&self) {
         self.delta.fetch_add(1, Ordering::Release);
     }
 
+    /// Returns the number of ticks (at least 1) after which the virtual
+    /// runtime exceeds `vruntime`.
+    pub(crate) fn ticks_to_exceed(&self, vruntime: isize) -> usize {
+        // the least delta with `delta * 1024 / weight > vruntime - init_vruntime`
+        let excess = vruntime - self.init_vruntime.load(Ordering::Acquire) + 1;
+        let delta = (excess * self.get_weight() + 1023) / 1024;
+        (delta - self.delta.load(Ordering::Acquire)).max(1) as usize
+    }
+
     /// Returns a reference to the inner task struct.
     pub const fn inner(&self) -> &T {
         &self.inner
@@ -173,12 +187,38 @@ impl<T> BaseScheduler for CFScheduler<T> {
             .insert((prev.clone().get_vruntime(), taskid), prev);
     }
 
+    fn steal_task(&mut self, filter: &dyn Fn(&Self::SchedItem) -> bool) -> Option<Self::SchedItem> {
+        let key = *self
+            .ready_queue
+            .iter()
+            .rev()
+            .find(|(_, t)| filter(t))
+            .map(|(key, _)| key)?;
+        let task = self.ready_queue.remove(&key);
+        if let Some(((min_vruntime, _), _)) = self.ready_queue.first_key_value() {
+            self.min_vruntime = Some(AtomicIsize::new(*min_vruntime));
+        } else {
+            self.min_vruntime = None;
+        }
+        task
+    }
+
     fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
         current.task_tick();
         self.min_vruntime.is_none()
             || current.get_vruntime() > self.min_vruntime.as_mut().unwrap().load(Ordering::Acquire)
     }
 
+    fn ticks_to_resched(&self, current: &Self::SchedItem) -> Option<usize> {
+        // no other task to switch to
+        if self.ready_queue.is_empty() {
+            return None;
+        }
+        Some(self.min_vruntime.as_ref().map_or(1, |min_vruntime| {
+            current.ticks_to_exceed(min_vruntime.load(Ordering::Acquire))
+        }))
+    }
+
     fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool {
         if (-20..=19).contains(&prio) {
             task.set_priority(prio);
@@ -187,4 +227,12 @@ impl<T> BaseScheduler for CFScheduler<T> {
             false
         }
     }
+
+    fn set_policy(&mut self, _task: &Self::SchedItem, policy: SchedPolicy, prio: isize) -> bool {
+        policy == SchedPolicy::Normal && prio == 0
+    }
+
+    fn should_preempt(&self, _task: &Self::SchedItem, _current: &Self::SchedItem) -> bool {
+        false
+    }
 }
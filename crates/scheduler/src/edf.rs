@@ -0,0 +1,424 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicBool, AtomicIsize, AtomicU64, Ordering};
+
+use crate::{BaseScheduler, SchedPolicy};
+
+/// Fixed-point scale of the bandwidth (`runtime / period`) of tasks.
+const BW_SCALE: u64 = 1 << 20;
+
+/// Reservation parameters of a deadline task in the [`EdfScheduler`], all in
+/// timer ticks.
+///
+/// In each `period`, the task is guaranteed to run for `runtime` before
+/// `deadline` (relative to the start of the period) has passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdfParams {
+    /// Execution budget in each period.
+    pub runtime: u64,
+    /// Relative deadline in each period.
+    pub deadline: u64,
+    /// Length of the period.
+    pub period: u64,
+}
+
+impl EdfParams {
+    /// Whether `0 < runtime <= deadline <= period`.
+    pub const fn is_valid(&self) -> bool {
+        self.runtime > 0 && self.runtime <= self.deadline && self.deadline <= self.period
+    }
+
+    /// The share of CPU time reserved, scaled by [`BW_SCALE`].
+    const fn bandwidth(&self) -> u64 {
+        self.runtime * BW_SCALE / self.period
+    }
+}
+
+/// A task wrapper for the [`EdfScheduler`].
+///
+/// It holds the reservation parameters and the current budget and deadline of
+/// a deadline task, or a time slice counter for a best-effort task.
+pub struct EdfTask<T, const MAX_TIME_SLICE: usize> {
+    inner: T,
+    runtime: AtomicU64,
+    rel_deadline: AtomicU64,
+    period: AtomicU64,
+    /// Start of the current period.
+    release: AtomicU64,
+    abs_deadline: AtomicU64,
+    budget: AtomicIsize,
+    throttled: AtomicBool,
+    seq: AtomicU64,
+    time_slice: AtomicIsize,
+}
+
+impl<T, const S: usize> EdfTask<T, S> {
+    /// Creates a new best-effort [`EdfTask`] from the inner task struct.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            runtime: AtomicU64::new(0),
+            rel_deadline: AtomicU64::new(0),
+            period: AtomicU64::new(0),
+            release: AtomicU64::new(0),
+            abs_deadline: AtomicU64::new(0),
+            budget: AtomicIsize::new(0),
+            throttled: AtomicBool::new(false),
+            seq: AtomicU64::new(0),
+            time_slice: AtomicIsize::new(S as isize),
+        }
+    }
+
+    /// Returns the reservation parameters, or [`None`] for a best-effort task.
+    pub fn params(&self) -> Option<EdfParams> {
+        match self.runtime.load(Ordering::Acquire) {
+            0 => None,
+            runtime => Some(EdfParams {
+                runtime,
+                deadline: self.rel_deadline.load(Ordering::Acquire),
+                period: self.period.load(Ordering::Acquire),
+            }),
+        }
+    }
+
+    /// Returns the absolute deadline of the current period in timer ticks, or
+    /// [`None`] for a best-effort task.
+    pub fn deadline(&self) -> Option<u64> {
+        self.is_deadline()
+            .then(|| self.abs_deadline.load(Ordering::Acquire))
+    }
+
+    fn is_deadline(&self) -> bool {
+        self.runtime.load(Ordering::Acquire) != 0
+    }
+
+    fn abs_deadline(&self) -> u64 {
+        self.abs_deadline.load(Ordering::Acquire)
+    }
+
+    fn budget(&self) -> isize {
+        self.budget.load(Ordering::Acquire)
+    }
+
+    fn seq(&self) -> u64 {
+        self.seq.load(Ordering::Acquire)
+    }
+
+    /// Starts a new period at `now` with a full budget.
+    fn replenish(&self, now: u64) {
+        self.release.store(now, Ordering::Release);
+        self.abs_deadline.store(
+            now + self.rel_deadline.load(Ordering::Acquire),
+            Ordering::Release,
+        );
+        self.budget.store(
+            self.runtime.load(Ordering::Acquire) as isize,
+            Ordering::Release,
+        );
+    }
+
+    /// Start of the next period.
+    fn next_release(&self) -> u64 {
+        self.release.load(Ordering::Acquire) + self.period.load(Ordering::Acquire)
+    }
+
+    fn time_slice(&self) -> isize {
+        self.time_slice.load(Ordering::Acquire)
+    }
+
+    fn reset_time_slice(&self) {
+        self.time_slice.store(S as isize, Ordering::Release);
+    }
+
+    /// Returns a reference to the inner task struct.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T, const S: usize> Deref for EdfTask<T, S> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// An [Earliest Deadline First] (EDF) scheduler with CPU reservations, like
+/// the `SCHED_DEADLINE` policy in Linux.
+///
+/// Deadline tasks are given [`EdfParams`] with [`set_params`], which fails if
+/// the total bandwidth (`runtime / period`) of the deadline tasks would exceed
+/// the CPU. The ready deadline task with the earliest absolute deadline always
+/// runs first. A task that has used up its budget, or yields the CPU, is
+/// throttled until its next period starts. Throttled tasks stay in the
+/// scheduler, and are replenished by [`task_tick`] and [`idle_ticks`].
+///
+/// Best-effort tasks, which are not given the parameters, run in round-robin
+/// order only when no deadline task is ready. Deadline tasks are never
+/// stolen by other schedulers, as their bandwidth is reserved on this one.
+///
+/// The time unit is the timer tick, so the scheduler must see every tick. An
+/// idle CPU may skip the ticks until [`next_release`], and catch up with them
+/// by [`idle_ticks`] later.
+///
+/// [Earliest Deadline First]: https://en.wikipedia.org/wiki/Earliest_deadline_first_scheduling
+/// [`set_params`]: EdfScheduler::set_params
+/// [`task_tick`]: BaseScheduler::task_tick
+/// [`idle_ticks`]: EdfScheduler::idle_ticks
+/// [`next_release`]: EdfScheduler::next_release
+pub struct EdfScheduler<T, const MAX_TIME_SLICE: usize> {
+    ready_queue: BTreeMap<(u64, u64), Arc<EdfTask<T, MAX_TIME_SLICE>>>, // (deadline, seq)
+    throttled: BTreeMap<(u64, u64), Arc<EdfTask<T, MAX_TIME_SLICE>>>,   // (release, seq)
+    best_effort: VecDeque<Arc<EdfTask<T, MAX_TIME_SLICE>>>,
+    total_bw: u64,
+    clock: u64,
+    seq_pool: u64,
+}
+
+impl<T, const S: usize> EdfScheduler<T, S> {
+    /// Creates a new empty [`EdfScheduler`].
+    pub const fn new() -> Self {
+        Self {
+            ready_queue: BTreeMap::new(),
+            throttled: BTreeMap::new(),
+            best_effort: VecDeque::new(),
+            total_bw: 0,
+            clock: 0,
+            seq_pool: 0,
+        }
+    }
+    /// get the name of scheduler
+    pub fn scheduler_name() -> &'static str {
+        "Earliest Deadline First"
+    }
+
+    /// Returns the current time in timer ticks.
+    pub fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    /// Makes `task` a deadline task with the given parameters, or a
+    /// best-effort task if `params` is [`None`]. It takes effect immediately,
+    /// starting a new period.
+    ///
+    /// Returns `false` if the parameters are invalid, or the total bandwidth
+    /// of the deadline tasks would exceed the CPU. A deadline task must be
+    /// turned into a best-effort one to release its bandwidth before it is
+    /// dropped.
+    pub fn set_params(&mut self, task: &Arc<EdfTask<T, S>>, params: Option<EdfParams>) -> bool {
+        let old_bw = task.params().map_or(0, |p| p.bandwidth());
+        let new_bw = match params {
+            Some(p) if !p.is_valid() => return false,
+            Some(p) => p.bandwidth(),
+            None => 0,
+        };
+        if self.total_bw - old_bw + new_bw > BW_SCALE {
+            return false;
+        }
+        self.total_bw = self.total_bw - old_bw + new_bw;
+
+        let queued = self.remove_task(task);
+        let p = params.unwrap_or(EdfParams {
+            runtime: 0,
+            deadline: 0,
+            period: 0,
+        });
+        task.runtime.store(p.runtime, Ordering::Release);
+        task.rel_deadline.store(p.deadline, Ordering::Release);
+        task.period.store(p.period, Ordering::Release);
+        task.throttled.store(false, Ordering::Release);
+        task.replenish(self.clock);
+        task.reset_time_slice();
+        if let Some(task) = queued {
+            self.enqueue(task);
+        }
+        true
+    }
+
+    /// Advances the clock at a timer tick when no task is running. Returns
+    /// `true` if a throttled task is ready to run again.
+    pub fn idle_tick(&mut self) -> bool {
+        self.idle_ticks(1)
+    }
+
+    /// Advances the clock by `ticks` timer ticks when no task is running.
+    /// Returns `true` if a throttled task is ready to run again.
+    pub fn idle_ticks(&mut self, ticks: u64) -> bool {
+        self.clock += ticks;
+        self.replenish() > 0
+    }
+
+    /// Returns the time (in timer ticks) when the next throttled task is ready
+    /// to run again, or [`None`] if no task is throttled.
+    pub fn next_release(&self) -> Option<u64> {
+        self.throttled
+            .first_key_value()
+            .map(|((release, _), _)| *release)
+    }
+
+    fn next_seq(&mut self) -> u64 {
+        self.seq_pool += 1;
+        self.seq_pool
+    }
+
+    fn enqueue(&mut self, task: Arc<EdfTask<T, S>>) {
+        if task.is_deadline() {
+            let seq = self.next_seq();
+            task.seq.store(seq, Ordering::Release);
+            self.ready_queue.insert((task.abs_deadline(), seq), task);
+        } else {
+            self.best_effort.push_back(task);
+        }
+    }
+
+    /// Keeps `task` away until its next period starts.
+    fn throttle(&mut self, task: Arc<EdfTask<T, S>>) {
+        let seq = self.next_seq();
+        task.seq.store(seq, Ordering::Release);
+        task.throttled.store(true, Ordering::Release);
+        self.throttled.insert((task.next_release(), seq), task);
+        self.replenish();
+    }
+
+    /// Moves the throttled tasks whose next period has started into the ready
+    /// queue. Returns the number of them.
+    fn replenish(&mut self) -> usize {
+        let mut n = 0;
+        while let Some(entry) = self.throttled.first_entry() {
+            if entry.key().0 > self.clock {
+                break;
+            }
+            let task = entry.remove();
+            task.throttled.store(false, Ordering::Release);
+            task.replenish(self.clock);
+            self.enqueue(task);
+            n += 1;
+        }
+        n
+    }
+}
+
+impl<T, const S: usize> BaseScheduler for EdfScheduler<T, S> {
+    type SchedItem = Arc<EdfTask<T, S>>;
+
+    fn init(&mut self) {}
+
+    fn add_task(&mut self, task: Self::SchedItem) {
+        if task.is_deadline() {
+            // Keep the deadline if the remaining budget can be used up before
+            // it at the reserved bandwidth, otherwise start a new period (the
+            // wake-up rule of the Constant Bandwidth Server).
+            let params = task.params().unwrap();
+            let deadline = task.abs_deadline();
+            let budget = task.budget().max(0) as u64;
+            if deadline <= self.clock
+                || budget * params.period > (deadline - self.clock) * params.runtime
+            {
+                task.replenish(self.clock);
+            }
+        }
+        self.enqueue(task);
+    }
+
+    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+        if !task.is_deadline() {
+            return self
+                .best_effort
+                .iter()
+                .position(|t| Arc::ptr_eq(t, task))
+                .and_then(|idx| self.best_effort.remove(idx));
+        }
+        let (queue, key) = if task.throttled.load(Ordering::Acquire) {
+            (&mut self.throttled, (task.next_release(), task.seq()))
+        } else {
+            (&mut self.ready_queue, (task.abs_deadline(), task.seq()))
+        };
+        if !queue.get(&key).is_some_and(|t| Arc::ptr_eq(t, task)) {
+            return None;
+        }
+        task.throttled.store(false, Ordering::Release);
+        queue.remove(&key)
+    }
+
+    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+        if let Some((_, task)) = self.ready_queue.pop_first() {
+            return Some(task);
+        }
+        self.best_effort.pop_front()
+    }
+
+    /// Puts the previous task back. A deadline task that has used up its
+    /// budget, or yields the CPU (`preempt` is `false`), is throttled until
+    /// its next period.
+    fn put_prev_task(&mut self, prev: Self::SchedItem, preempt: bool) {
+        if prev.is_deadline() {
+            if prev.budget() <= 0 || !preempt {
+                self.throttle(prev);
+            } else {
+                self.enqueue(prev);
+            }
+        } else if prev.time_slice() > 0 && preempt {
+            self.best_effort.push_front(prev);
+        } else {
+            prev.reset_time_slice();
+            self.best_effort.push_back(prev);
+        }
+    }
+
+    fn steal_task(&mut self, filter: &dyn Fn(&Self::SchedItem) -> bool) -> Option<Self::SchedItem> {
+        self.best_effort
+            .iter()
+            .rposition(filter)
+            .and_then(|idx| self.best_effort.remove(idx))
+    }
+
+    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
+        self.clock += 1;
+        self.replenish();
+        if current.is_deadline() {
+            let old_budget = current.budget.fetch_sub(1, Ordering::Release);
+            old_budget <= 1
+                || self
+                    .ready_queue
+                    .first_key_value()
+                    .is_some_and(|((deadline, _), _)| *deadline < current.abs_deadline())
+        } else {
+            let old_slice = current.time_slice.fetch_sub(1, Ordering::Release);
+            old_slice <= 1 || !self.ready_queue.is_empty()
+        }
+    }
+
+    fn ticks_to_resched(&self, current: &Self::SchedItem) -> Option<usize> {
+        let ticks = if current.is_deadline() {
+            let earlier_ready = self
+                .ready_queue
+                .first_key_value()
+                .is_some_and(|((deadline, _), _)| *deadline < current.abs_deadline());
+            if earlier_ready {
+                1
+            } else {
+                current.budget()
+            }
+        } else if !self.ready_queue.is_empty() {
+            1
+        } else {
+            current.time_slice()
+        };
+        Some(ticks.max(1) as usize)
+    }
+
+    fn set_priority(&mut self, _task: &Self::SchedItem, _prio: isize) -> bool {
+        false
+    }
+
+    fn set_policy(&mut self, _task: &Self::SchedItem, policy: SchedPolicy, prio: isize) -> bool {
+        policy == SchedPolicy::Normal && prio == 0
+    }
+
+    fn should_preempt(&self, task: &Self::SchedItem, current: &Self::SchedItem) -> bool {
+        task.is_deadline()
+            && (!current.is_deadline() || task.abs_deadline() < current.abs_deadline())
+    }
+}
@@ -1,9 +1,10 @@
 use alloc::sync::Arc;
+use core::mem::ManuallyDrop;
 use core::ops::Deref;
 
 use linked_list::{Adapter, Links, List};
 
-use crate::BaseScheduler;
+use crate::{BaseScheduler, SchedPolicy};
 
 /// A task wrapper for the [`FifoScheduler`].
 ///
@@ -92,11 +93,38 @@ impl<T> BaseScheduler for FifoScheduler<T> {
         self.ready_queue.push_back(prev);
     }
 
+    fn steal_task(&mut self, filter: &dyn Fn(&Self::SchedItem) -> bool) -> Option<Self::SchedItem> {
+        let mut found = None;
+        let mut cursor = self.ready_queue.cursor_front();
+        while let Some(t) = cursor.current() {
+            // SAFETY: all tasks in the list are inserted by `Arc::into_raw()`, and
+            // `ManuallyDrop` keeps the reference count unchanged.
+            let task = ManuallyDrop::new(unsafe { Arc::from_raw(t as *const FifoTask<T>) });
+            if filter(&task) {
+                found = Some(task);
+            }
+            cursor.move_next();
+        }
+        found.and_then(|task| unsafe { self.ready_queue.remove(&task) })
+    }
+
     fn task_tick(&mut self, _current: &Self::SchedItem) -> bool {
         false // no reschedule
     }
 
+    fn ticks_to_resched(&self, _current: &Self::SchedItem) -> Option<usize> {
+        None
+    }
+
     fn set_priority(&mut self, _task: &Self::SchedItem, _prio: isize) -> bool {
         false
     }
+
+    fn set_policy(&mut self, _task: &Self::SchedItem, policy: SchedPolicy, prio: isize) -> bool {
+        policy == SchedPolicy::Normal && prio == 0
+    }
+
+    fn should_preempt(&self, _task: &Self::SchedItem, _current: &Self::SchedItem) -> bool {
+        false
+    }
 }
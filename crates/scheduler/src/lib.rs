@@ -5,13 +5,19 @@
 //! - [`FifoScheduler`]: FIFO (First-In-First-Out) scheduler (cooperative).
 //! - [`RRScheduler`]: Round-robin scheduler (preemptive).
 //! - [`CFScheduler`]: Completely Fair Scheduler (preemptive).
+//! - [`RTScheduler`]: Real-time scheduler with static priorities, which runs
+//!   normal tasks with CFS when no real-time task is ready (preemptive).
+//! - [`EdfScheduler`]: Earliest Deadline First scheduler with CPU reservations
+//!   (preemptive).
 
 #![cfg_attr(not(test), no_std)]
 #![feature(const_mut_refs)]
 
 mod cfs;
+mod edf;
 mod fifo;
 mod round_robin;
+mod rt;
 
 #[cfg(test)]
 mod tests;
@@ -19,8 +25,59 @@ mod tests;
 extern crate alloc;
 
 pub use cfs::{CFSTask, CFScheduler};
+pub use edf::{EdfParams, EdfScheduler, EdfTask};
 pub use fifo::{FifoScheduler, FifoTask};
 pub use round_robin::{RRScheduler, RRTask};
+pub use rt::{RTScheduler, RTTask};
+
+use core::ops::RangeInclusive;
+
+/// Scheduling policies (classes) of tasks, with the same values as the
+/// `SCHED_*` constants in Linux.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchedPolicy {
+    /// The default time-sharing policy (`SCHED_OTHER`).
+    Normal = 0,
+    /// Real-time first-in first-out policy (`SCHED_FIFO`). A task runs until
+    /// it blocks, yields, or is preempted by a higher priority task.
+    Fifo = 1,
+    /// Real-time round-robin policy (`SCHED_RR`). Like [`SchedPolicy::Fifo`],
+    /// but tasks of the same priority take turns on time slice expiry.
+    RR = 2,
+}
+
+impl SchedPolicy {
+    /// Returns the range of the static priorities for this policy.
+    ///
+    /// Real-time policies have priorities from 1 (lowest) to 99 (highest),
+    /// which always preempt [`SchedPolicy::Normal`] tasks, whose static
+    /// priority is always 0.
+    pub const fn priority_range(self) -> RangeInclusive<isize> {
+        match self {
+            Self::Normal => 0..=0,
+            Self::Fifo | Self::RR => 1..=99,
+        }
+    }
+
+    /// Returns `true` if it is a real-time policy.
+    pub const fn is_realtime(self) -> bool {
+        !matches!(self, Self::Normal)
+    }
+}
+
+impl TryFrom<i32> for SchedPolicy {
+    type Error = ();
+
+    fn try_from(policy: i32) -> Result<Self, ()> {
+        match policy {
+            0 => Ok(Self::Normal),
+            1 => Ok(Self::Fifo),
+            2 => Ok(Self::RR),
+            _ => Err(()),
+        }
+    }
+}
 
 /// The base scheduler trait that all schedulers should implement.
 ///
@@ -58,12 +115,38 @@ pub trait BaseScheduler {
     /// ready queue.
     fn put_prev_task(&mut self, prev: Self::SchedItem, preempt: bool);
 
+    /// Removes and returns a task that `filter` accepts, so that it can be
+    /// migrated to another scheduler. Returns [`None`] if no task is accepted.
+    ///
+    /// Tasks that are least likely to be picked next are preferred.
+    fn steal_task(&mut self, filter: &dyn Fn(&Self::SchedItem) -> bool) -> Option<Self::SchedItem>;
+
     /// Advances the scheduler state at each timer tick. Returns `true` if
     /// re-scheduling is required.
     ///
     /// `current` is the current running task.
     fn task_tick(&mut self, current: &Self::SchedItem) -> bool;
 
+    /// Returns the number of ticks after which [`task_tick`] requires
+    /// re-scheduling, if `current` keeps running and no task is added, or
+    /// [`None`] if it never does. The ticks in between can be skipped.
+    ///
+    /// The default implementation returns `Some(1)`.
+    ///
+    /// [`task_tick`]: BaseScheduler::task_tick
+    fn ticks_to_resched(&self, _current: &Self::SchedItem) -> Option<usize> {
+        Some(1)
+    }
+
     /// set priority for a task
     fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool;
+
+    /// Sets the scheduling policy and the static priority of a task, which may
+    /// be in the ready queue or not. Returns `false` if the scheduler does not
+    /// support the policy or the priority is out of range.
+    fn set_policy(&mut self, task: &Self::SchedItem, policy: SchedPolicy, prio: isize) -> bool;
+
+    /// Returns `true` if `task`, which has just been added to the scheduler,
+    /// should preempt the `current` running task right away.
+    fn should_preempt(&self, task: &Self::SchedItem, current: &Self::SchedItem) -> bool;
 }
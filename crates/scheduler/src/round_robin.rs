@@ -2,7 +2,7 @@ use alloc::{collections::VecDeque, sync::Arc};
 use core::ops::Deref;
 use core::sync::atomic::{AtomicIsize, Ordering};
 
-use crate::BaseScheduler;
+use crate::{BaseScheduler, SchedPolicy};
 
 /// A task wrapper for the [`RRScheduler`].
 ///
@@ -102,12 +102,31 @@ impl<T, const S: usize> BaseScheduler for RRScheduler<T, S> {
         }
     }
 
+    fn steal_task(&mut self, filter: &dyn Fn(&Self::SchedItem) -> bool) -> Option<Self::SchedItem> {
+        self.ready_queue
+            .iter()
+            .rposition(filter)
+            .and_then(|idx| self.ready_queue.remove(idx))
+    }
+
     fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
         let old_slice = current.time_slice.fetch_sub(1, Ordering::Release);
         old_slice <= 1
     }
 
+    fn ticks_to_resched(&self, current: &Self::SchedItem) -> Option<usize> {
+        Some(current.time_slice().max(1) as usize)
+    }
+
     fn set_priority(&mut self, _task: &Self::SchedItem, _prio: isize) -> bool {
         false
     }
+
+    fn set_policy(&mut self, _task: &Self::SchedItem, policy: SchedPolicy, prio: isize) -> bool {
+        policy == SchedPolicy::Normal && prio == 0
+    }
+
+    fn should_preempt(&self, _task: &Self::SchedItem, _current: &Self::SchedItem) -> bool {
+        false
+    }
 }
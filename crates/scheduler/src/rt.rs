@@ -0,0 +1,301 @@
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicIsize, AtomicU8, Ordering};
+
+use crate::{BaseScheduler, CFSTask, SchedPolicy};
+
+/// A task wrapper for the [`RTScheduler`].
+///
+/// It holds the scheduling policy and the static priority of the task, a time
+/// slice counter for [`SchedPolicy::RR`] tasks, and the CFS states for
+/// [`SchedPolicy::Normal`] tasks.
+pub struct RTTask<T, const MAX_TIME_SLICE: usize> {
+    inner: T,
+    fair: CFSTask<()>,
+    policy: AtomicU8,
+    rt_priority: AtomicIsize,
+    time_slice: AtomicIsize,
+}
+
+impl<T, const S: usize> RTTask<T, S> {
+    /// Creates a new [`RTTask`] from the inner task struct, with the
+    /// [`SchedPolicy::Normal`] policy.
+    pub const fn new(inner: T) -> Self {
+        Self {
+            inner,
+            fair: CFSTask::new(()),
+            policy: AtomicU8::new(SchedPolicy::Normal as u8),
+            rt_priority: AtomicIsize::new(0),
+            time_slice: AtomicIsize::new(S as isize),
+        }
+    }
+
+    /// Returns the scheduling policy of the task.
+    pub fn policy(&self) -> SchedPolicy {
+        SchedPolicy::try_from(self.policy.load(Ordering::Acquire) as i32)
+            .unwrap_or(SchedPolicy::Normal)
+    }
+
+    /// Returns the static priority of the task, which is always 0 for
+    /// [`SchedPolicy::Normal`] tasks.
+    pub fn rt_priority(&self) -> isize {
+        self.rt_priority.load(Ordering::Acquire)
+    }
+
+    fn time_slice(&self) -> isize {
+        self.time_slice.load(Ordering::Acquire)
+    }
+
+    fn reset_time_slice(&self) {
+        self.time_slice.store(S as isize, Ordering::Release);
+    }
+
+    /// Returns a reference to the inner task struct.
+    pub const fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T, const S: usize> Deref for RTTask<T, S> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+/// A real-time scheduler with static priorities, like the `SCHED_FIFO` and
+/// `SCHED_RR` policies in Linux.
+///
+/// Real-time tasks have priorities from 1 to 99, and the ready task with the
+/// highest priority always runs first. Tasks of the same priority are kept in
+/// a FIFO queue, which is rotated when a [`SchedPolicy::RR`] task has used up
+/// its time slice. [`SchedPolicy::Normal`] tasks are scheduled like in the
+/// [`CFScheduler`], and run only when no real-time task is ready.
+///
+/// New tasks are [`SchedPolicy::Normal`] ones, and [`set_policy`] moves them
+/// between the classes.
+///
+/// [`CFScheduler`]: crate::CFScheduler
+/// [`set_policy`]: BaseScheduler::set_policy
+pub struct RTScheduler<T, const MAX_TIME_SLICE: usize> {
+    rt_queues: BTreeMap<isize, VecDeque<Arc<RTTask<T, MAX_TIME_SLICE>>>>, // priority -> tasks
+    fair_queue: BTreeMap<(isize, isize), Arc<RTTask<T, MAX_TIME_SLICE>>>, // (vruntime, taskid)
+    min_vruntime: isize,
+    id_pool: isize,
+}
+
+impl<T, const S: usize> RTScheduler<T, S> {
+    /// Creates a new empty [`RTScheduler`].
+    pub const fn new() -> Self {
+        Self {
+            rt_queues: BTreeMap::new(),
+            fair_queue: BTreeMap::new(),
+            min_vruntime: 0,
+            id_pool: 0,
+        }
+    }
+    /// get the name of scheduler
+    pub fn scheduler_name() -> &'static str {
+        "Real-time"
+    }
+
+    /// Returns the priority of the highest priority ready real-time task.
+    fn highest_rt_priority(&self) -> Option<isize> {
+        self.rt_queues.keys().next_back().copied()
+    }
+
+    fn enqueue_rt(&mut self, task: Arc<RTTask<T, S>>, front: bool) {
+        let queue = self.rt_queues.entry(task.rt_priority()).or_default();
+        if front {
+            queue.push_front(task);
+        } else {
+            queue.push_back(task);
+        }
+    }
+
+    fn enqueue_fair(&mut self, task: Arc<RTTask<T, S>>) {
+        let taskid = self.id_pool;
+        self.id_pool += 1;
+        task.fair.set_id(taskid);
+        self.fair_queue
+            .insert((task.fair.get_vruntime(), taskid), task);
+        self.update_min_vruntime();
+    }
+
+    fn update_min_vruntime(&mut self) {
+        if let Some(((min_vruntime, _), _)) = self.fair_queue.first_key_value() {
+            self.min_vruntime = *min_vruntime;
+        }
+    }
+}
+
+impl<T, const S: usize> BaseScheduler for RTScheduler<T, S> {
+    type SchedItem = Arc<RTTask<T, S>>;
+
+    fn init(&mut self) {}
+
+    fn add_task(&mut self, task: Self::SchedItem) {
+        if task.policy().is_realtime() {
+            self.enqueue_rt(task, false);
+        } else {
+            task.fair.reset_vruntime(self.min_vruntime);
+            self.enqueue_fair(task);
+        }
+    }
+
+    fn remove_task(&mut self, task: &Self::SchedItem) -> Option<Self::SchedItem> {
+        if task.policy().is_realtime() {
+            let prio = task.rt_priority();
+            let queue = self.rt_queues.get_mut(&prio)?;
+            let task = queue
+                .iter()
+                .position(|t| Arc::ptr_eq(t, task))
+                .and_then(|idx| queue.remove(idx));
+            if queue.is_empty() {
+                self.rt_queues.remove(&prio);
+            }
+            task
+        } else {
+            let key = (task.fair.get_vruntime(), task.fair.get_id());
+            if !self
+                .fair_queue
+                .get(&key)
+                .is_some_and(|t| Arc::ptr_eq(t, task))
+            {
+                return None;
+            }
+            let task = self.fair_queue.remove(&key);
+            self.update_min_vruntime();
+            task
+        }
+    }
+
+    fn pick_next_task(&mut self) -> Option<Self::SchedItem> {
+        if let Some(mut entry) = self.rt_queues.last_entry() {
+            let task = entry.get_mut().pop_front();
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+            return task;
+        }
+        let (_, task) = self.fair_queue.pop_first()?;
+        self.update_min_vruntime();
+        Some(task)
+    }
+
+    fn put_prev_task(&mut self, prev: Self::SchedItem, preempt: bool) {
+        match prev.policy() {
+            SchedPolicy::Fifo => self.enqueue_rt(prev, preempt),
+            SchedPolicy::RR => {
+                if prev.time_slice() > 0 && preempt {
+                    self.enqueue_rt(prev, true);
+                } else {
+                    prev.reset_time_slice();
+                    self.enqueue_rt(prev, false);
+                }
+            }
+            SchedPolicy::Normal => self.enqueue_fair(prev),
+        }
+    }
+
+    fn steal_task(&mut self, filter: &dyn Fn(&Self::SchedItem) -> bool) -> Option<Self::SchedItem> {
+        if let Some(key) = self
+            .fair_queue
+            .iter()
+            .rev()
+            .find(|(_, t)| filter(t))
+            .map(|(key, _)| *key)
+        {
+            let task = self.fair_queue.remove(&key);
+            self.update_min_vruntime();
+            return task;
+        }
+        let (&prio, queue) = self
+            .rt_queues
+            .iter_mut()
+            .find(|(_, queue)| queue.iter().any(filter))?;
+        let task = queue
+            .iter()
+            .rposition(filter)
+            .and_then(|idx| queue.remove(idx));
+        if queue.is_empty() {
+            self.rt_queues.remove(&prio);
+        }
+        task
+    }
+
+    fn task_tick(&mut self, current: &Self::SchedItem) -> bool {
+        let higher_ready = self
+            .highest_rt_priority()
+            .is_some_and(|prio| prio > current.rt_priority());
+        match current.policy() {
+            SchedPolicy::Fifo => higher_ready,
+            SchedPolicy::RR => {
+                let old_slice = current.time_slice.fetch_sub(1, Ordering::Release);
+                old_slice <= 1 || higher_ready
+            }
+            SchedPolicy::Normal => {
+                current.fair.task_tick();
+                higher_ready
+                    || self
+                        .fair_queue
+                        .first_key_value()
+                        .is_some_and(|((min_vruntime, _), _)| {
+                            current.fair.get_vruntime() > *min_vruntime
+                        })
+            }
+        }
+    }
+
+    fn ticks_to_resched(&self, current: &Self::SchedItem) -> Option<usize> {
+        if self
+            .highest_rt_priority()
+            .is_some_and(|prio| prio > current.rt_priority())
+        {
+            return Some(1);
+        }
+        match current.policy() {
+            SchedPolicy::Fifo => None,
+            SchedPolicy::RR => Some(current.time_slice().max(1) as usize),
+            SchedPolicy::Normal => self
+                .fair_queue
+                .first_key_value()
+                .map(|((min_vruntime, _), _)| current.fair.ticks_to_exceed(*min_vruntime)),
+        }
+    }
+
+    /// Sets the nice value of a task, which takes effect when it is a
+    /// [`SchedPolicy::Normal`] task.
+    fn set_priority(&mut self, task: &Self::SchedItem, prio: isize) -> bool {
+        if (-20..=19).contains(&prio) {
+            task.fair.set_priority(prio);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn set_policy(&mut self, task: &Self::SchedItem, policy: SchedPolicy, prio: isize) -> bool {
+        if !policy.priority_range().contains(&prio) {
+            return false;
+        }
+        let queued = self.remove_task(task);
+        if task.policy().is_realtime() && !policy.is_realtime() {
+            // do not let it run ahead of others with the stale vruntime
+            task.fair.reset_vruntime(self.min_vruntime);
+        }
+        task.policy.store(policy as u8, Ordering::Release);
+        task.rt_priority.store(prio, Ordering::Release);
+        task.reset_time_slice();
+        if let Some(task) = queued {
+            self.add_task(task);
+        }
+        true
+    }
+
+    fn should_preempt(&self, task: &Self::SchedItem, current: &Self::SchedItem) -> bool {
+        task.rt_priority() > current.rt_priority()
+    }
+}
@@ -28,6 +28,57 @@ macro_rules! def_test_sched {
                 assert_eq!(n, NUM_TASKS);
             }
 
+            #[test]
+            fn test_steal() {
+                const NUM_TASKS: usize = 10;
+
+                let mut scheduler = <$scheduler>::new();
+                for i in 0..NUM_TASKS {
+                    scheduler.add_task(Arc::new(<$task>::new(i)));
+                }
+
+                // the accepted task nearest to the tail is stolen
+                let stolen = scheduler.steal_task(&|t| *t.inner() % 2 == 0).unwrap();
+                assert_eq!(*stolen.inner(), NUM_TASKS - 2);
+                assert!(scheduler.steal_task(&|t| *t.inner() >= NUM_TASKS).is_none());
+
+                let mut n = 0;
+                while let Some(t) = scheduler.pick_next_task() {
+                    assert_ne!(*t.inner(), NUM_TASKS - 2);
+                    n += 1;
+                }
+                assert_eq!(n, NUM_TASKS - 1);
+            }
+
+            #[test]
+            fn test_ticks_to_resched() {
+                const NUM_TASKS: usize = 3;
+
+                let mut scheduler = <$scheduler>::new();
+                for i in 0..NUM_TASKS {
+                    scheduler.add_task(Arc::new(<$task>::new(i)));
+                }
+
+                for _ in 0..NUM_TASKS * 10 {
+                    let next = scheduler.pick_next_task().unwrap();
+                    // re-scheduling is required exactly on the predicted tick
+                    match scheduler.ticks_to_resched(&next) {
+                        Some(ticks) => {
+                            for _ in 1..ticks {
+                                assert!(!scheduler.task_tick(&next));
+                            }
+                            assert!(scheduler.task_tick(&next));
+                        }
+                        None => {
+                            for _ in 0..100 {
+                                assert!(!scheduler.task_tick(&next));
+                            }
+                        }
+                    }
+                    scheduler.put_prev_task(next, false);
+                }
+            }
+
             #[test]
             fn bench_yield() {
                 const NUM_TASKS: usize = 1_000_000;
@@ -82,3 +133,230 @@ macro_rules! def_test_sched {
 def_test_sched!(fifo, FifoScheduler::<usize>, FifoTask::<usize>);
 def_test_sched!(rr, RRScheduler::<usize, 5>, RRTask::<usize, 5>);
 def_test_sched!(cfs, CFScheduler::<usize>, CFSTask::<usize>);
+def_test_sched!(rt, RTScheduler::<usize, 5>, RTTask::<usize, 5>);
+
+mod cfs_nice {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    #[test]
+    fn test_ticks_to_resched() {
+        let mut scheduler = CFScheduler::<usize>::new();
+        for (i, nice) in [0, 5, -5].into_iter().enumerate() {
+            let task = Arc::new(CFSTask::new(i));
+            scheduler.add_task(task.clone());
+            let task = scheduler.remove_task(&task).unwrap();
+            assert!(scheduler.set_priority(&task, nice));
+            scheduler.add_task(task);
+        }
+
+        for _ in 0..30 {
+            let next = scheduler.pick_next_task().unwrap();
+            let ticks = scheduler.ticks_to_resched(&next).unwrap();
+            for _ in 1..ticks {
+                assert!(!scheduler.task_tick(&next));
+            }
+            assert!(scheduler.task_tick(&next));
+            scheduler.put_prev_task(next, false);
+        }
+    }
+}
+
+mod rt_policy {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    type Task = Arc<RTTask<usize, 5>>;
+
+    fn new_task(scheduler: &mut RTScheduler<usize, 5>, i: usize, policy: SchedPolicy) -> Task {
+        let task = Arc::new(RTTask::new(i));
+        let prio = *policy.priority_range().end() - i as isize;
+        assert!(scheduler.set_policy(&task, policy, prio));
+        scheduler.add_task(task.clone());
+        task
+    }
+
+    #[test]
+    fn test_priority_order() {
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        let normal = new_task(&mut scheduler, 0, SchedPolicy::Normal);
+        new_task(&mut scheduler, 2, SchedPolicy::Fifo);
+        new_task(&mut scheduler, 1, SchedPolicy::RR);
+
+        // real-time tasks run first, the higher priority the earlier
+        let next = scheduler.pick_next_task().unwrap();
+        assert_eq!(*next.inner(), 1);
+        assert!(scheduler.should_preempt(&next, &normal));
+        assert!(!scheduler.should_preempt(&normal, &next));
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 2);
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 0);
+        assert!(scheduler.pick_next_task().is_none());
+    }
+
+    #[test]
+    fn test_preempt_normal() {
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        let normal = new_task(&mut scheduler, 0, SchedPolicy::Normal);
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(!scheduler.task_tick(&curr));
+
+        // a ready real-time task preempts the normal one at the next tick
+        let rt = new_task(&mut scheduler, 1, SchedPolicy::Fifo);
+        assert!(scheduler.task_tick(&curr));
+        scheduler.put_prev_task(curr, true);
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &rt));
+        for _ in 0..100 {
+            assert!(!scheduler.task_tick(&curr));
+        }
+
+        // demoted to a normal task, it is scheduled by CFS again
+        assert!(scheduler.set_policy(&curr, SchedPolicy::Normal, 0));
+        assert_eq!(curr.policy(), SchedPolicy::Normal);
+        scheduler.put_prev_task(curr, false);
+        assert!(scheduler.remove_task(&normal).is_some());
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 1);
+    }
+
+    #[test]
+    fn test_round_robin() {
+        const NUM_TASKS: usize = 3;
+
+        let mut scheduler = RTScheduler::<usize, 5>::new();
+        let tasks: Vec<_> = (0..NUM_TASKS)
+            .map(|i| {
+                let task = Arc::new(RTTask::new(i));
+                assert!(scheduler.set_policy(&task, SchedPolicy::RR, 50));
+                scheduler.add_task(task.clone());
+                task
+            })
+            .collect();
+        assert!(!scheduler.set_policy(&tasks[0], SchedPolicy::RR, 100));
+        assert!(!scheduler.set_policy(&tasks[0], SchedPolicy::Normal, 1));
+
+        for i in 0..NUM_TASKS * 3 {
+            let next = scheduler.pick_next_task().unwrap();
+            assert_eq!(*next.inner(), i % NUM_TASKS);
+            while !scheduler.task_tick(&next) {}
+            scheduler.put_prev_task(next, true);
+        }
+
+        // raising the priority of a ready task moves it to the front
+        assert!(scheduler.set_policy(&tasks[2], SchedPolicy::Fifo, 60));
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 2);
+    }
+}
+def_test_sched!(edf, EdfScheduler::<usize, 5>, EdfTask::<usize, 5>);
+
+mod edf_params {
+    use crate::*;
+    use alloc::sync::Arc;
+
+    const fn params(runtime: u64, deadline: u64, period: u64) -> Option<EdfParams> {
+        Some(EdfParams {
+            runtime,
+            deadline,
+            period,
+        })
+    }
+
+    #[test]
+    fn test_admission() {
+        let mut scheduler = EdfScheduler::<usize, 5>::new();
+        let tasks: Vec<_> = (0..3).map(|i| Arc::new(EdfTask::new(i))).collect();
+        assert!(!scheduler.set_params(&tasks[0], params(0, 10, 10)));
+        assert!(!scheduler.set_params(&tasks[0], params(5, 4, 10)));
+        assert!(!scheduler.set_params(&tasks[0], params(5, 20, 10)));
+
+        assert!(scheduler.set_params(&tasks[0], params(5, 10, 10)));
+        assert!(scheduler.set_params(&tasks[1], params(3, 10, 10)));
+        // 5/10 + 3/10 + 3/10 > 1
+        assert!(!scheduler.set_params(&tasks[2], params(3, 10, 10)));
+        assert!(scheduler.set_params(&tasks[2], params(2, 10, 10)));
+        assert_eq!(tasks[2].params(), params(2, 10, 10));
+
+        // released bandwidth can be reserved again
+        assert!(!scheduler.set_params(&tasks[0], params(6, 10, 10)));
+        assert!(scheduler.set_params(&tasks[1], None));
+        assert!(tasks[1].params().is_none());
+        assert!(scheduler.set_params(&tasks[0], params(8, 10, 10)));
+    }
+
+    #[test]
+    fn test_earliest_deadline_first() {
+        let mut scheduler = EdfScheduler::<usize, 5>::new();
+        let best_effort = Arc::new(EdfTask::new(0));
+        scheduler.add_task(best_effort.clone());
+        for (i, deadline) in [(1, 30), (2, 10), (3, 20)] {
+            let task = Arc::new(EdfTask::new(i));
+            assert!(scheduler.set_params(&task, params(1, deadline, 100)));
+            scheduler.add_task(task);
+        }
+
+        let next = scheduler.pick_next_task().unwrap();
+        assert_eq!(*next.inner(), 2);
+        assert!(scheduler.should_preempt(&next, &best_effort));
+        assert!(!scheduler.should_preempt(&best_effort, &next));
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 3);
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 1);
+        assert_eq!(*scheduler.pick_next_task().unwrap().inner(), 0);
+        assert!(scheduler.pick_next_task().is_none());
+    }
+
+    #[test]
+    fn test_budget_enforcement() {
+        let mut scheduler = EdfScheduler::<usize, 100>::new();
+        let best_effort = Arc::new(EdfTask::new(0));
+        let task = Arc::new(EdfTask::new(1));
+        assert!(scheduler.set_params(&task, params(3, 5, 10)));
+        scheduler.add_task(best_effort.clone());
+        scheduler.add_task(task.clone());
+
+        for period in 0..3 {
+            // the budget is used up after 3 ticks
+            let curr = scheduler.pick_next_task().unwrap();
+            assert!(Arc::ptr_eq(&curr, &task));
+            assert_eq!(curr.deadline(), Some(period * 10 + 5));
+            assert!(!scheduler.task_tick(&curr));
+            assert!(!scheduler.task_tick(&curr));
+            assert!(scheduler.task_tick(&curr));
+            scheduler.put_prev_task(curr, true);
+
+            // throttled until the next period, which preempts others
+            let curr = scheduler.pick_next_task().unwrap();
+            assert!(Arc::ptr_eq(&curr, &best_effort));
+            for _ in 0..6 {
+                assert!(!scheduler.task_tick(&curr));
+            }
+            assert!(scheduler.task_tick(&curr));
+            scheduler.put_prev_task(curr, true);
+        }
+
+        // yielding gives up the rest of the budget
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &task));
+        scheduler.put_prev_task(curr, false);
+        assert!(Arc::ptr_eq(
+            &scheduler.pick_next_task().unwrap(),
+            &best_effort
+        ));
+        assert!(scheduler.pick_next_task().is_none());
+        for _ in 0..9 {
+            assert!(!scheduler.idle_tick());
+        }
+        assert!(scheduler.idle_tick());
+        assert_eq!(scheduler.clock(), 40);
+        assert_eq!(task.deadline(), Some(45));
+
+        // skipped ticks of an idle CPU are caught up at once
+        let curr = scheduler.pick_next_task().unwrap();
+        assert!(Arc::ptr_eq(&curr, &task));
+        scheduler.put_prev_task(curr, false);
+        assert!(scheduler.pick_next_task().is_none());
+        assert_eq!(scheduler.next_release(), Some(50));
+        assert!(!scheduler.idle_ticks(9));
+        assert!(scheduler.idle_ticks(5));
+        assert_eq!(scheduler.next_release(), None);
+        assert_eq!(task.deadline(), Some(59));
+    }
+}
@@ -161,6 +161,19 @@ impl<G: BaseGuard, T: ?Sized> BaseSpinLock<G, T> {
         self.lock.store(false, Ordering::Release);
     }
 
+    /// Returns a mutable pointer to the underlying data.
+    ///
+    /// This is mostly meant to be used for applications which require manual
+    /// unlocking, but where storing both the lock and the pointer to the inner
+    /// data gets inefficient.
+    ///
+    /// While this is safe, writing to the data is undefined behavior unless the
+    /// current thread has acquired the lock.
+    #[inline(always)]
+    pub fn as_mut_ptr(&self) -> *mut T {
+        self.data.get()
+    }
+
     /// Returns a mutable reference to the underlying data.
     ///
     /// Since this call borrows the [`BaseSpinLock`] mutably, and a mutable reference is guaranteed to be exclusive in
@@ -31,7 +31,8 @@
 
 extern crate alloc;
 
-use alloc::{boxed::Box, collections::BinaryHeap};
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BinaryHeap};
 use core::cmp::{Ord, Ordering, PartialOrd};
 use core::time::Duration;
 
@@ -48,6 +49,8 @@ pub trait TimerEvent {
 
 struct TimerEventWrapper<E> {
     deadline: TimeValue,
+    /// The latest time the event may be triggered, i.e., `deadline + slack`.
+    latest: TimeValue,
     event: E,
 }
 
@@ -55,8 +58,19 @@ struct TimerEventWrapper<E> {
 ///
 /// It internally uses a min-heap to store the events by deadline, make it
 /// possible to trigger these events sequentially.
+///
+/// An event may be given a slack, by which it can be triggered late. Timers
+/// can be programmed at [`next_expiry`] instead of [`next_deadline`], so that
+/// events with close deadlines are triggered together, with fewer wakeups.
+/// The latest times are kept in an ordered map to find the earliest one.
+///
+/// [`next_expiry`]: TimerList::next_expiry
+/// [`next_deadline`]: TimerList::next_deadline
 pub struct TimerList<E: TimerEvent> {
     events: BinaryHeap<TimerEventWrapper<E>>,
+    /// Number of events by the latest time to trigger them, to find the
+    /// earliest one without scanning all the events.
+    latest: BTreeMap<TimeValue, usize>,
 }
 
 impl<E> PartialOrd for TimerEventWrapper<E> {
@@ -84,6 +98,7 @@ impl<E: TimerEvent> TimerList<E> {
     pub fn new() -> Self {
         Self {
             events: BinaryHeap::new(),
+            latest: BTreeMap::new(),
         }
     }
 
@@ -95,7 +110,19 @@ impl<E: TimerEvent> TimerList<E> {
 
     /// Set a timed event that will be triggered at `deadline`.
     pub fn set(&mut self, deadline: TimeValue, event: E) {
-        self.events.push(TimerEventWrapper { deadline, event });
+        self.set_with_slack(deadline, TimeValue::ZERO, event);
+    }
+
+    /// Set a timed event that will be triggered at `deadline`, or as late as
+    /// `deadline + slack` to be coalesced with other events.
+    pub fn set_with_slack(&mut self, deadline: TimeValue, slack: TimeValue, event: E) {
+        let latest = deadline.saturating_add(slack);
+        *self.latest.entry(latest).or_insert(0) += 1;
+        self.events.push(TimerEventWrapper {
+            deadline,
+            latest,
+            event,
+        });
     }
 
     /// Cancel all events that meet the condition.
@@ -104,7 +131,14 @@ impl<E: TimerEvent> TimerList<E> {
         F: Fn(&E) -> bool,
     {
         // TODO: performance optimization
-        self.events.retain(|e| !condition(&e.event));
+        self.events.retain(|e| {
+            if condition(&e.event) {
+                remove_latest(&mut self.latest, e.latest);
+                false
+            } else {
+                true
+            }
+        });
     }
 
     /// Get the deadline of the most recent event.
@@ -113,6 +147,14 @@ impl<E: TimerEvent> TimerList<E> {
         self.events.peek().map(|e| e.deadline)
     }
 
+    /// Get the latest time to expire the events without exceeding the slack
+    /// of any one, which is no earlier than [`next_deadline`].
+    ///
+    /// [`next_deadline`]: TimerList::next_deadline
+    pub fn next_expiry(&self) -> Option<TimeValue> {
+        self.latest.first_key_value().map(|(&latest, _)| latest)
+    }
+
     /// Try to expire the earliest event that passed the deadline at the given
     /// time.
     ///
@@ -120,13 +162,24 @@ impl<E: TimerEvent> TimerList<E> {
     pub fn expire_one(&mut self, now: TimeValue) -> Option<(TimeValue, E)> {
         if let Some(e) = self.events.peek() {
             if e.deadline <= now {
-                return self.events.pop().map(|e| (e.deadline, e.event));
+                let e = self.events.pop().unwrap();
+                remove_latest(&mut self.latest, e.latest);
+                return Some((e.deadline, e.event));
             }
         }
         None
     }
 }
 
+fn remove_latest(latest: &mut BTreeMap<TimeValue, usize>, time: TimeValue) {
+    if let Some(count) = latest.get_mut(&time) {
+        *count -= 1;
+        if *count == 0 {
+            latest.remove(&time);
+        }
+    }
+}
+
 impl<E: TimerEvent> Default for TimerList<E> {
     fn default() -> Self {
         Self::new()
@@ -208,6 +261,52 @@ mod tests {
         assert_eq!(COUNT.load(Ordering::SeqCst), 4);
     }
 
+    #[test]
+    fn test_timer_list_slack() {
+        struct TestTimerEvent(usize);
+
+        impl TimerEvent for TestTimerEvent {
+            fn callback(self, _now: TimeValue) {}
+        }
+
+        let mut timer_list = TimerList::new();
+        assert_eq!(timer_list.next_expiry(), None);
+        timer_list.set_with_slack(
+            Duration::from_millis(10),
+            Duration::from_millis(5),
+            TestTimerEvent(0),
+        );
+        timer_list.set_with_slack(
+            Duration::from_millis(12),
+            Duration::from_millis(8),
+            TestTimerEvent(1),
+        );
+        timer_list.set(Duration::from_millis(30), TestTimerEvent(2));
+        assert_eq!(timer_list.next_deadline(), Some(Duration::from_millis(10)));
+        assert_eq!(timer_list.next_expiry(), Some(Duration::from_millis(15)));
+
+        // both events in the slack are expired at once
+        let now = timer_list.next_expiry().unwrap();
+        let mut expired = Vec::new();
+        while let Some((_deadline, event)) = timer_list.expire_one(now) {
+            expired.push(event.0);
+        }
+        assert_eq!(expired, [0, 1]);
+        assert_eq!(timer_list.next_expiry(), Some(Duration::from_millis(30)));
+
+        // canceled events are not waited for
+        timer_list.set_with_slack(
+            Duration::from_millis(20),
+            Duration::from_millis(5),
+            TestTimerEvent(3),
+        );
+        assert_eq!(timer_list.next_expiry(), Some(Duration::from_millis(25)));
+        timer_list.cancel(|e| e.0 == 3);
+        assert_eq!(timer_list.next_expiry(), Some(Duration::from_millis(30)));
+        timer_list.cancel(|_| true);
+        assert_eq!(timer_list.next_expiry(), None);
+    }
+
     #[test]
     fn test_timer_list_fn() {
         let mut timer_list = TimerList::new();
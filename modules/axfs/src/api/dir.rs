@@ -1,4 +1,5 @@
 use alloc::string::String;
+use axerrno::AxError;
 use axio::Result;
 use core::fmt;
 
@@ -23,9 +24,10 @@ pub struct DirEntry<'a> {
 }
 
 /// A builder used to create directories in various manners.
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct DirBuilder {
     recursive: bool,
+    mode: u32,
 }
 
 impl<'a> ReadDir<'a> {
@@ -116,11 +118,20 @@ impl fmt::Debug for DirEntry<'_> {
     }
 }
 
+impl Default for DirBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DirBuilder {
     /// Creates a new set of options with default mode/security settings for all
     /// platforms and also non-recursive.
     pub fn new() -> Self {
-        Self { recursive: false }
+        Self {
+            recursive: false,
+            mode: 0o777,
+        }
     }
 
     /// Indicates that directories should be created recursively, creating all
@@ -131,20 +142,43 @@ impl DirBuilder {
         self
     }
 
+    /// Sets the mode to create new directories with, before the umask of the
+    /// current task is applied. Defaults to `0o777`.
+    pub fn mode(&mut self, mode: u32) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
     /// Creates the specified directory with the options configured in this
     /// builder.
     pub fn create(&self, path: &str) -> Result<()> {
         if self.recursive {
             self.create_dir_all(path)
         } else {
-            crate::root::create_dir(None, path)
+            crate::root::create_dir(None, path, self.mode as u16)
         }
     }
 
-    fn create_dir_all(&self, _path: &str) -> Result<()> {
-        axerrno::ax_err!(
-            Unsupported,
-            "Recursive directory creation is not supported yet"
-        )
+    fn create_dir_all(&self, path: &str) -> Result<()> {
+        match crate::root::create_dir(None, path, self.mode as u16) {
+            Ok(()) => return Ok(()),
+            Err(AxError::NotFound) => {}
+            Err(_) if is_dir(path) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+        match path.trim_end_matches('/').rsplit_once('/') {
+            Some((parent, _)) if !parent.is_empty() => self.create_dir_all(parent)?,
+            _ => return axerrno::ax_err!(NotFound),
+        }
+        match crate::root::create_dir(None, path, self.mode as u16) {
+            Err(_) if is_dir(path) => Ok(()),
+            res => res,
+        }
     }
 }
+
+fn is_dir(path: &str) -> bool {
+    crate::root::lookup(None, path)
+        .and_then(|node| node.get_attr())
+        .is_ok_and(|attr| attr.is_dir())
+}
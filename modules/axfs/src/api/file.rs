@@ -64,6 +64,13 @@ impl OpenOptions {
         self
     }
 
+    /// Sets the mode to create a new file with, before the umask of the
+    /// current task is applied. Defaults to `0o666`.
+    pub fn mode(&mut self, mode: u32) -> &mut Self {
+        self.0.mode(mode);
+        self
+    }
+
     /// Opens a file at `path` with the options specified by `self`.
     pub fn open(&self, path: &str) -> Result<File> {
         fops::File::open(path, &self.0).map(|inner| File { inner })
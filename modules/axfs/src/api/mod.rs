@@ -5,6 +5,7 @@ mod file;
 
 pub use self::dir::{DirBuilder, DirEntry, ReadDir};
 pub use self::file::{File, FileType, Metadata, OpenOptions, Permissions};
+pub use crate::fops::FileSystemInfo;
 
 use alloc::{string::String, vec::Vec};
 use axio::{self as io, prelude::*};
@@ -88,6 +89,16 @@ pub fn rename(old: &str, new: &str) -> io::Result<()> {
     crate::root::rename(old, new)
 }
 
+/// Returns the attributes of the filesystem that contains the given path.
+pub fn statfs(path: &str) -> io::Result<FileSystemInfo> {
+    crate::root::statfs(path)
+}
+
+/// Returns the paths of all mount points, starting with the root `/`.
+pub fn mount_points() -> Vec<String> {
+    crate::root::mount_points()
+}
+
 #[allow(unreachable_code)]
 /// Returns the current user id as a [`u32`].
 pub fn current_uid() -> io::Result<u32> {
@@ -99,7 +110,7 @@ pub fn current_uid() -> io::Result<u32> {
 }
 
 #[allow(unreachable_code)]
-/// Changes the current working directory to the specified path.
+/// Changes the current user id to the specified id.
 pub fn set_current_uid(uid: u32) -> io::Result<()> {
     #[cfg(feature = "permission")]
     {
@@ -107,3 +118,49 @@ pub fn set_current_uid(uid: u32) -> io::Result<()> {
     }
     Ok(())
 }
+
+#[allow(unreachable_code)]
+/// Returns the current group id as a [`u32`].
+pub fn current_gid() -> io::Result<u32> {
+    #[cfg(feature = "permission")]
+    {
+        return crate::permission::current_gid();
+    }
+    Ok(0)
+}
+
+#[allow(unreachable_code)]
+/// Changes the current group id to the specified id.
+pub fn set_current_gid(gid: u32) -> io::Result<()> {
+    #[cfg(feature = "permission")]
+    {
+        return crate::permission::set_current_gid(gid);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "permission"))]
+static UMASK: core::sync::atomic::AtomicU16 = core::sync::atomic::AtomicU16::new(0o022);
+
+#[allow(unreachable_code)]
+/// Returns the file mode creation mask of the current task.
+pub fn current_umask() -> u16 {
+    #[cfg(feature = "permission")]
+    {
+        return crate::permission::current_umask();
+    }
+    #[cfg(not(feature = "permission"))]
+    return UMASK.load(core::sync::atomic::Ordering::Relaxed);
+}
+
+#[allow(unreachable_code)]
+/// Sets the file mode creation mask of the current task to `mask & 0o777`,
+/// and returns the previous mask.
+pub fn set_umask(mask: u16) -> u16 {
+    #[cfg(feature = "permission")]
+    {
+        return crate::permission::set_current_umask(mask);
+    }
+    #[cfg(not(feature = "permission"))]
+    return UMASK.swap(mask & 0o777, core::sync::atomic::Ordering::Relaxed);
+}
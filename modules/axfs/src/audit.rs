@@ -0,0 +1,214 @@
+//! Security audit log.
+//!
+//! Security relevant events, such as authentication attempts and permission
+//! denials of filesystem operations, are recorded as [`AuditEvent`]s. The
+//! most recent [`AUDIT_RING_SIZE`] events are kept in memory and can be
+//! queried with [`recent`], and every event is also appended as a line to
+//! [`AUDIT_LOG`] once the filesystem is initialized.
+//!
+//! The log file is created by the kernel at boot, owned by the superuser with
+//! mode `0600`, and is only ever written by appending.
+
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use axerrno::{ax_err, AxError, AxResult};
+use axsync::Mutex;
+use core::fmt;
+use core::time::Duration;
+
+use axfs_vfs::VfsNodeRef;
+
+use crate::fops::{FileAttr, FilePerm};
+
+/// Path of the audit log file.
+pub const AUDIT_LOG: &str = "/var/log/audit";
+/// Number of events kept in memory.
+pub const AUDIT_RING_SIZE: usize = 256;
+
+const AUDIT_LOG_DIR: &str = "/var/log";
+
+static AUDIT_RING: Mutex<VecDeque<AuditEvent>> = Mutex::new(VecDeque::new());
+static AUDIT_LOG_FILE: Mutex<Option<VfsNodeRef>> = Mutex::new(None);
+
+/// The kind of an audited operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    /// A password was checked.
+    Authenticate,
+    /// A user logged in.
+    Login,
+    /// The current task switched to another user.
+    Su,
+    /// A command was run with `sudo`.
+    Sudo,
+    /// A file or directory was opened.
+    Open,
+    /// A file or directory was removed.
+    Remove,
+    /// A file or directory was renamed.
+    Rename,
+    /// The owner or mode of a file was changed.
+    SetAttr,
+    /// The current directory was changed.
+    ChangeDir,
+}
+
+impl AuditAction {
+    /// Returns the name of the action used in the log.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Authenticate => "auth",
+            Self::Login => "login",
+            Self::Su => "su",
+            Self::Sudo => "sudo",
+            Self::Open => "open",
+            Self::Remove => "remove",
+            Self::Rename => "rename",
+            Self::SetAttr => "setattr",
+            Self::ChangeDir => "chdir",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A recorded security event.
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    /// Time since boot.
+    pub time: Duration,
+    /// ID of the task that caused the event.
+    pub task_id: u64,
+    /// Effective user ID of the task.
+    pub uid: u32,
+    /// The audited operation.
+    pub action: AuditAction,
+    /// The path, user or command the operation was applied to.
+    pub target: String,
+    /// Whether the operation was allowed and succeeded.
+    pub success: bool,
+}
+
+impl fmt::Display for AuditEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{:5}.{:06}] task={} uid={} action={} target={:?} result={}",
+            self.time.as_secs(),
+            self.time.subsec_micros(),
+            self.task_id,
+            self.uid,
+            self.action,
+            self.target,
+            if self.success { "success" } else { "failure" }
+        )
+    }
+}
+
+#[allow(unreachable_code)]
+fn current_task_id() -> u64 {
+    #[cfg(feature = "permission")]
+    {
+        return axtask::current_may_uninit().map_or(0, |curr| curr.id().as_u64());
+    }
+    0
+}
+
+/// Records an event caused by the current task.
+pub fn record(action: AuditAction, target: &str, success: bool) {
+    let event = AuditEvent {
+        time: axhal::time::current_time(),
+        task_id: current_task_id(),
+        uid: crate::api::current_uid().unwrap_or(0),
+        action,
+        target: target.to_string(),
+        success,
+    };
+    if !success {
+        info!("audit: {}", event);
+    }
+
+    if let Err(e) = append(&event) {
+        debug!("audit: failed to write {}: {:?}", AUDIT_LOG, e);
+    }
+    let mut ring = AUDIT_RING.lock();
+    if ring.len() == AUDIT_RING_SIZE {
+        ring.pop_front();
+    }
+    ring.push_back(event);
+}
+
+/// Returns up to `count` of the most recent events, oldest first.
+pub fn recent(count: usize) -> Vec<AuditEvent> {
+    let ring = AUDIT_RING.lock();
+    ring.iter()
+        .skip(ring.len().saturating_sub(count))
+        .cloned()
+        .collect()
+}
+
+/// Records that `action` on `path` was denied, and returns
+/// [`PermissionDenied`](axerrno::AxError::PermissionDenied).
+pub(crate) fn denied<T>(action: AuditAction, path: &str) -> AxResult<T> {
+    let target = crate::root::absolute_path(path).unwrap_or_else(|_| path.into());
+    record(action, &target, false);
+    ax_err!(PermissionDenied)
+}
+
+/// Creates the log file if it does not exist, and keeps it open to append
+/// events.
+///
+/// This is called once the root filesystem is mounted, in the context of the
+/// boot task, so the log and its directory are created by the kernel. Events
+/// are then written to the node directly, regardless of the credentials of the
+/// task that caused them.
+pub(crate) fn init() {
+    let res = crate::api::create_dir_all(AUDIT_LOG_DIR).and_then(|_| {
+        let node = match crate::root::lookup(None, AUDIT_LOG) {
+            Ok(node) => node,
+            Err(AxError::NotFound) => crate::root::create_file(None, AUDIT_LOG, 0o600)?,
+            Err(e) => return Err(e),
+        };
+        let attr = node.get_attr()?;
+        if attr.size() == 0 {
+            node.set_attr(FileAttr::new(
+                FilePerm::from_bits_truncate(0o600),
+                0,
+                0,
+                attr.file_type(),
+                attr.size(),
+                attr.blocks(),
+            ))?;
+        }
+        Ok(node)
+    });
+    match res {
+        Ok(node) => *AUDIT_LOG_FILE.lock() = Some(node),
+        Err(e) => warn!("audit: failed to open {}: {:?}", AUDIT_LOG, e),
+    }
+}
+
+/// Appends `event` to the log file.
+fn append(event: &AuditEvent) -> AxResult {
+    let log = AUDIT_LOG_FILE.lock();
+    let Some(node) = log.as_ref() else {
+        return Ok(());
+    };
+    let line = event.to_string() + "\n";
+    let mut buf = line.as_bytes();
+    let mut offset = node.get_attr()?.size();
+    while !buf.is_empty() {
+        let n = node.write_at(offset, buf)?;
+        if n == 0 {
+            return ax_err!(WriteZero);
+        }
+        buf = &buf[n..];
+        offset += n as u64;
+    }
+    Ok(())
+}
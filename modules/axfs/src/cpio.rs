@@ -0,0 +1,179 @@
+//! Parser of cpio archives in the "newc" format (as created by
+//! `find . | cpio -o -H newc`).
+
+use alloc::vec::Vec;
+use axerrno::{ax_err, AxResult};
+use axfs_vfs::VfsError;
+
+const HEADER_SIZE: usize = 110;
+const TRAILER: &str = "TRAILER!!!";
+
+/// An entry of a newc cpio archive.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Entry<'a> {
+    pub name: &'a str,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub data: &'a [u8],
+}
+
+/// Sequential reader of the entries of a newc cpio archive.
+struct Archive<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Archive<'a> {
+    fn field(header: &[u8], idx: usize) -> AxResult<u32> {
+        let hex = &header[6 + idx * 8..6 + (idx + 1) * 8];
+        core::str::from_utf8(hex)
+            .ok()
+            .and_then(|s| u32::from_str_radix(s, 16).ok())
+            .ok_or(VfsError::InvalidData)
+    }
+
+    fn take(&mut self, len: usize) -> AxResult<&'a [u8]> {
+        let end = self.pos.checked_add(len).ok_or(VfsError::InvalidData)?;
+        let bytes = self
+            .data
+            .get(self.pos..end)
+            .ok_or(VfsError::UnexpectedEof)?;
+        // Headers, names and file data are padded to a multiple of 4 bytes.
+        self.pos = (end + 3) & !3;
+        Ok(bytes)
+    }
+
+    fn next_entry(&mut self) -> AxResult<Option<Entry<'a>>> {
+        let start = self.pos;
+        let header = match self.data.get(start..start + HEADER_SIZE) {
+            Some(h) if &h[..6] == b"070701" || &h[..6] == b"070702" => h,
+            Some(_) => return ax_err!(InvalidData, "cpio: bad header"),
+            None => return ax_err!(UnexpectedEof, "cpio: missing trailer"),
+        };
+        let mode = Self::field(header, 1)?;
+        let uid = Self::field(header, 2)?;
+        let gid = Self::field(header, 3)?;
+        let file_size = Self::field(header, 6)? as usize;
+        let name_size = Self::field(header, 11)? as usize;
+
+        // The name is padded together with the header.
+        self.pos += HEADER_SIZE;
+        let name = self.take(name_size)?;
+        let name = core::str::from_utf8(name.strip_suffix(b"\0").unwrap_or(name))
+            .map_err(|_| VfsError::InvalidData)?;
+        if name == TRAILER {
+            return Ok(None);
+        }
+        let data = self.take(file_size)?;
+        Ok(Some(Entry {
+            name,
+            mode,
+            uid,
+            gid,
+            data,
+        }))
+    }
+}
+
+/// Parses the entries of the archive `data`, up to the trailer.
+///
+/// Returns [`VfsError::UnexpectedEof`] if the archive is truncated, or
+/// [`VfsError::InvalidData`] if it is malformed.
+pub(crate) fn parse(data: &[u8]) -> AxResult<Vec<Entry<'_>>> {
+    let mut archive = Archive { data, pos: 0 };
+    let mut entries = Vec::new();
+    while let Some(entry) = archive.next_entry()? {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    fn push_entry(archive: &mut Vec<u8>, name: &str, mode: u32, uid: u32, data: &[u8]) {
+        let fields = [0, mode, uid, 100, 1, 0, data.len() as u32, 0, 0, 0, 0];
+        let mut header = String::from("070701");
+        for f in fields {
+            header += &alloc::format!("{:08x}", f);
+        }
+        header += &alloc::format!("{:08x}{:08x}", name.len() + 1, 0);
+        archive.extend_from_slice(header.as_bytes());
+        archive.extend_from_slice(name.as_bytes());
+        archive.push(0);
+        archive.resize((archive.len() + 3) & !3, 0);
+        archive.extend_from_slice(data);
+        archive.resize((archive.len() + 3) & !3, 0);
+    }
+
+    fn sample() -> Vec<u8> {
+        let mut archive = Vec::new();
+        push_entry(&mut archive, ".", 0o040755, 0, b"");
+        push_entry(&mut archive, "etc", 0o040755, 0, b"");
+        push_entry(&mut archive, "etc/passwd", 0o100644, 0, b"root:x:0:0\n");
+        push_entry(&mut archive, "home/user/a", 0o100600, 1000, b"abc");
+        push_entry(&mut archive, "bin/sh", 0o120777, 0, b"/bin/busybox");
+        push_entry(&mut archive, TRAILER, 0, 0, b"");
+        archive
+    }
+
+    #[test]
+    fn test_parse() {
+        let archive = sample();
+        let entries = parse(&archive).unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name).collect();
+        assert_eq!(names, [".", "etc", "etc/passwd", "home/user/a", "bin/sh"]);
+        assert_eq!(
+            entries[3],
+            Entry {
+                name: "home/user/a",
+                mode: 0o100600,
+                uid: 1000,
+                gid: 100,
+                data: b"abc",
+            }
+        );
+        assert_eq!(entries[4].data, b"/bin/busybox");
+
+        // data after the trailer is ignored
+        let mut padded = archive.clone();
+        padded.resize(padded.len() + 512, 0);
+        assert_eq!(parse(&padded).unwrap(), entries);
+    }
+
+    #[test]
+    fn test_parse_truncated() {
+        let archive = sample();
+        let trailer_start = archive.len() - 124;
+        // in a header, a name, file data, and before the trailer
+        for len in [50, 345, 355, trailer_start] {
+            assert_eq!(
+                parse(&archive[..len]),
+                Err(VfsError::UnexpectedEof),
+                "truncated at {}",
+                len
+            );
+        }
+        assert_eq!(parse(&[]), Err(VfsError::UnexpectedEof));
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        let mut archive = sample();
+        archive[0] = b'1'; // magic
+        assert_eq!(parse(&archive), Err(VfsError::InvalidData));
+
+        let mut archive = sample();
+        archive[6 + 8] = b'x'; // mode of the first entry
+        assert_eq!(parse(&archive), Err(VfsError::InvalidData));
+
+        let mut archive = Vec::new();
+        push_entry(&mut archive, "bad\u{80}", 0o100644, 0, b"");
+        let pos = archive.iter().position(|&b| b == 0xc2).unwrap();
+        archive[pos] = 0xff;
+        assert_eq!(parse(&archive), Err(VfsError::InvalidData));
+    }
+}
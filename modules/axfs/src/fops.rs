@@ -1,13 +1,17 @@
 //! Low-level filesystem operations.
 
+use alloc::{string::String, sync::Arc};
 use axerrno::{ax_err, ax_err_type, AxResult};
-use axfs_vfs::{VfsError, VfsNodeRef};
+use axfs_vfs::{VfsError, VfsNodeRef, VfsOps};
 use axio::SeekFrom;
 use capability::{Cap, WithCap};
 use core::fmt;
 
+use crate::audit::{self, AuditAction};
+use crate::notify::{self, WatchMask};
+
 #[cfg(feature = "permission")]
-use permission::permission::fops_cap;
+use permission::permission::{current_credentials, fops_cap, PrivCap};
 
 #[cfg(feature = "myfs")]
 pub use crate::dev::Disk;
@@ -22,35 +26,48 @@ pub type DirEntry = axfs_vfs::VfsDirEntry;
 pub type FileAttr = axfs_vfs::VfsNodeAttr;
 /// Alias of [`axfs_vfs::VfsNodePerm`].
 pub type FilePerm = axfs_vfs::VfsNodePerm;
+/// Alias of [`axfs_vfs::FileSystemInfo`].
+pub type FileSystemInfo = axfs_vfs::FileSystemInfo;
 
 /// An opened file object, with open permissions and a cursor.
 pub struct File {
     node: WithCap<VfsNodeRef>,
+    /// The filesystem that contains the node.
+    fs: Arc<dyn VfsOps>,
     is_append: bool,
     offset: u64,
+    /// Absolute path used for change notifications, if known.
+    path: Option<String>,
 }
 
 /// An opened directory object, with open permissions and a cursor for
 /// [`read_dir`](Directory::read_dir).
 pub struct Directory {
     node: WithCap<VfsNodeRef>,
+    /// The filesystem that contains the node.
+    fs: Arc<dyn VfsOps>,
     entry_idx: usize,
 }
 
 pub type OpenOptions = crate::open_options::OpenOptions;
 
 impl File {
-    fn _open_at(dir: Option<&VfsNodeRef>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
+    fn _open_at(
+        dir: Option<&VfsNodeRef>,
+        fs: Arc<dyn VfsOps>,
+        path: &str,
+        opts: &OpenOptions,
+    ) -> AxResult<Self> {
         debug!("open file: {} {:?}", path, opts);
 
         let node_option = crate::root::lookup(dir, path);
-        if node_option.is_ok() {
+        let existed = node_option.is_ok();
+        if existed {
             let mut exec_dir = node_option.clone().unwrap().parent();
             while exec_dir.is_some() {
                 let attr = exec_dir.clone().unwrap().get_attr()?;
-                if !perm_to_cap(attr.perm(), attr.user_id(), attr.group_id()).contains(Cap::EXECUTE)
-                {
-                    return ax_err!(PermissionDenied);
+                if !perm_to_cap(&attr).contains(Cap::EXECUTE) {
+                    return audit::denied(AuditAction::Open, path);
                 }
                 exec_dir = exec_dir.clone().unwrap().parent();
             }
@@ -66,7 +83,7 @@ impl File {
                     node
                 }
                 // not exists, create new
-                Err(VfsError::NotFound) => crate::root::create_file(dir, path)?,
+                Err(VfsError::NotFound) => crate::root::create_file(dir, path, opts.mode as u16)?,
                 Err(e) => return Err(e),
             }
         } else {
@@ -81,30 +98,64 @@ impl File {
             return ax_err!(IsADirectory);
         }
         let access_cap = opts.into();
-        if !perm_to_cap(attr.perm(), attr.user_id(), attr.group_id()).contains(access_cap) {
-            return ax_err!(PermissionDenied);
+        if !perm_to_cap(&attr).contains(access_cap) {
+            return audit::denied(AuditAction::Open, path);
         }
 
         node.open()?;
-        if opts.truncate {
-            node.truncate(0)?;
-        }
-        Ok(Self {
+        let file = Self {
             node: WithCap::new(node, access_cap),
+            fs,
             is_append: opts.append,
             offset: 0,
-        })
+            path: if dir.is_none() || path.starts_with('/') {
+                crate::root::absolute_path(path).ok()
+            } else {
+                None
+            },
+        };
+        if opts.truncate {
+            file.node.access(Cap::empty())?.truncate(0)?;
+            if existed {
+                file.notify(WatchMask::MODIFY);
+            }
+        }
+        Ok(file)
+    }
+
+    fn notify(&self, mask: WatchMask) {
+        if let Some(path) = self.path.as_deref() {
+            notify::notify(path, mask);
+        }
     }
 
     /// Opens a file at the path relative to the current directory. Returns a
     /// [`File`] object.
     pub fn open(path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_at(None, path, opts)
+        Self::_open_at(None, crate::root::mounted_fs(path)?, path, opts)
+    }
+
+    /// Opens the file again with the same permissions, returning a handle
+    /// with its own cursor at the start.
+    ///
+    /// The handle can be used without the locks around this one, e.g., by a
+    /// memory mapping that reads the file on page faults.
+    pub fn duplicate(&self) -> AxResult<Self> {
+        let node = unsafe { self.node.access_unchecked() }.clone();
+        node.open()?;
+        Ok(Self {
+            node: WithCap::new(node, self.node.cap()),
+            fs: self.fs.clone(),
+            is_append: self.is_append,
+            offset: 0,
+            path: self.path.clone(),
+        })
     }
 
     /// Truncates the file to the specified size.
     pub fn truncate(&self, size: u64) -> AxResult {
         self.node.access(Cap::WRITE)?.truncate(size)?;
+        self.notify(WatchMask::MODIFY);
         Ok(())
     }
 
@@ -140,6 +191,7 @@ impl File {
         };
         let write_len = node.write_at(self.offset, buf)?;
         self.offset += write_len as u64;
+        self.notify(WatchMask::MODIFY);
         Ok(write_len)
     }
 
@@ -150,6 +202,7 @@ impl File {
     pub fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
         let node = self.node.access(Cap::WRITE)?;
         let write_len = node.write_at(offset, buf)?;
+        self.notify(WatchMask::MODIFY);
         Ok(write_len)
     }
 
@@ -173,27 +226,75 @@ impl File {
         Ok(new_offset)
     }
 
+    /// Whether the file is opened for reading.
+    pub fn is_readable(&self) -> bool {
+        self.node.can_access(Cap::READ)
+    }
+
+    /// Whether the file is opened for writing.
+    pub fn is_writable(&self) -> bool {
+        self.node.can_access(Cap::WRITE)
+    }
+
+    /// Whether the file is opened in append mode.
+    pub fn is_append(&self) -> bool {
+        self.is_append
+    }
+
+    /// Gets a pair that identifies the file the handle was opened on: the
+    /// filesystem and the [inode number](axfs_vfs::VfsNodeOps::ino) of the
+    /// node in it. Handles of the same file return the same pair.
+    pub fn file_id(&self) -> (usize, u64) {
+        let fs = Arc::as_ptr(&self.fs) as *const () as usize;
+        (fs, unsafe { self.node.access_unchecked() }.ino())
+    }
+
     /// Gets the file attributes.
     pub fn get_attr(&self) -> AxResult<FileAttr> {
         self.node.access(Cap::empty())?.get_attr()
     }
 
+    /// Gets the attributes of the filesystem that contains the file.
+    ///
+    /// It is still the filesystem the file was opened on after the file is
+    /// renamed or removed, or another filesystem is mounted over its path.
+    pub fn statfs(&self) -> AxResult<FileSystemInfo> {
+        self.fs.statfs()
+    }
+
     /// Sets the file attributes.
+    ///
+    /// Changing the owner requires [`PrivCap::CHOWN`], unless the owner of
+    /// the file changes its group to one of their own groups. Changing the
+    /// mode requires owning the file or [`PrivCap::FOWNER`].
     pub fn set_attr(&self, attr: FileAttr) -> AxResult {
-        self.node.access(Cap::empty())?.set_attr(attr)
+        let node = self.node.access(Cap::empty())?;
+        if !may_change_attr(&node.get_attr()?, &attr) {
+            let path = self.path.as_deref().unwrap_or_default();
+            audit::record(AuditAction::SetAttr, path, false);
+            return ax_err!(PermissionDenied);
+        }
+        node.set_attr(attr)?;
+        self.notify(WatchMask::ATTRIB);
+        Ok(())
     }
 }
 
 impl Directory {
-    fn _open_dir_at(dir: Option<&VfsNodeRef>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
+    fn _open_dir_at(
+        dir: Option<&VfsNodeRef>,
+        fs: Arc<dyn VfsOps>,
+        path: &str,
+        opts: &OpenOptions,
+    ) -> AxResult<Self> {
         debug!("open dir: {}", path);
 
         let node = crate::root::lookup(dir, path)?;
         let mut exec_dir = node.clone().parent();
         while exec_dir.is_some() {
             let attr = exec_dir.clone().unwrap().get_attr()?;
-            if !perm_to_cap(attr.perm(), attr.user_id(), attr.group_id()).contains(Cap::EXECUTE) {
-                return ax_err!(PermissionDenied);
+            if !perm_to_cap(&attr).contains(Cap::EXECUTE) {
+                return audit::denied(AuditAction::Open, path);
             }
             exec_dir = exec_dir.clone().unwrap().parent();
         }
@@ -203,13 +304,14 @@ impl Directory {
             return ax_err!(NotADirectory);
         }
         let access_cap = opts.into();
-        if !perm_to_cap(attr.perm(), attr.user_id(), attr.group_id()).contains(access_cap) {
-            return ax_err!(PermissionDenied);
+        if !perm_to_cap(&attr).contains(access_cap) {
+            return audit::denied(AuditAction::Open, path);
         }
 
         node.open()?;
         Ok(Self {
             node: WithCap::new(node, access_cap),
+            fs,
             entry_idx: 0,
         })
     }
@@ -222,32 +324,42 @@ impl Directory {
         }
     }
 
+    /// Returns the filesystem that contains the node at the path relative to
+    /// this directory.
+    fn fs_at(&self, path: &str) -> AxResult<Arc<dyn VfsOps>> {
+        if path.starts_with('/') {
+            crate::root::mounted_fs(path)
+        } else {
+            Ok(self.fs.clone())
+        }
+    }
+
     /// Opens a directory at the path relative to the current directory.
     /// Returns a [`Directory`] object.
     pub fn open_dir(path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_dir_at(None, path, opts)
+        Self::_open_dir_at(None, crate::root::mounted_fs(path)?, path, opts)
     }
 
     /// Opens a directory at the path relative to this directory. Returns a
     /// [`Directory`] object.
     pub fn open_dir_at(&self, path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_dir_at(self.access_at(path)?, path, opts)
+        Self::_open_dir_at(self.access_at(path)?, self.fs_at(path)?, path, opts)
     }
 
     /// Opens a file at the path relative to this directory. Returns a [`File`]
     /// object.
     pub fn open_file_at(&self, path: &str, opts: &OpenOptions) -> AxResult<File> {
-        File::_open_at(self.access_at(path)?, path, opts)
+        File::_open_at(self.access_at(path)?, self.fs_at(path)?, path, opts)
     }
 
     /// Creates an empty file at the path relative to this directory.
     pub fn create_file(&self, path: &str) -> AxResult<VfsNodeRef> {
-        crate::root::create_file(self.access_at(path)?, path)
+        crate::root::create_file(self.access_at(path)?, path, 0o666)
     }
 
     /// Creates an empty directory at the path relative to this directory.
     pub fn create_dir(&self, path: &str) -> AxResult {
-        crate::root::create_dir(self.access_at(path)?, path)
+        crate::root::create_dir(self.access_at(path)?, path, 0o777)
     }
 
     /// Removes a file at the path relative to this directory.
@@ -337,9 +449,34 @@ impl From<&OpenOptions> for Cap {
     }
 }
 
-pub fn perm_to_cap(perm: FilePerm, uid: u32, gid: u32) -> Cap {
+/// Whether the current task may replace the attributes `old` with `new`.
+#[allow(unused_variables)]
+fn may_change_attr(old: &FileAttr, new: &FileAttr) -> bool {
+    #[cfg(feature = "permission")]
+    {
+        let cred = current_credentials();
+        let is_owner = cred.euid == old.user_id();
+        let chown = cred.has_cap(PrivCap::CHOWN);
+        if new.user_id() != old.user_id() && !chown {
+            return false;
+        }
+        if new.group_id() != old.group_id()
+            && !chown
+            && !(is_owner && cred.in_group(new.group_id()))
+        {
+            return false;
+        }
+        if new.perm().bits() != old.perm().bits() && !is_owner && !cred.has_cap(PrivCap::FOWNER) {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn perm_to_cap(attr: &FileAttr) -> Cap {
     #[cfg(not(feature = "permission"))]
     {
+        let _ = attr;
         let mut cap = Cap::empty();
         cap |= Cap::READ;
         cap |= Cap::WRITE;
@@ -349,6 +486,6 @@ pub fn perm_to_cap(perm: FilePerm, uid: u32, gid: u32) -> Cap {
 
     #[cfg(feature = "permission")]
     {
-        fops_cap(perm, uid, gid)
+        fops_cap(attr)
     }
 }
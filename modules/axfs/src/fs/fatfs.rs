@@ -1,7 +1,9 @@
-use alloc::sync::Arc;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::{format, sync::Arc, vec::Vec};
 use core::cell::UnsafeCell;
 
-use axfs_vfs::{VfsDirEntry, VfsError, VfsNodePerm, VfsResult};
+use axfs_vfs::{FileSystemInfo, VfsDirEntry, VfsError, VfsNodePerm, VfsResult};
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps};
 use axsync::Mutex;
 use fatfs::{Dir, File, LossyOemCpConverter, NullTimeProvider, Read, Seek, SeekFrom, Write};
@@ -13,11 +15,111 @@ const BLOCK_SIZE: usize = 512;
 pub struct FatFileSystem {
     inner: fatfs::FileSystem<Disk, NullTimeProvider, LossyOemCpConverter>,
     root_dir: UnsafeCell<Option<VfsNodeRef>>,
+    attrs: AttrTable,
 }
 
-pub struct FileWrapper<'a>(Mutex<File<'a, Disk, NullTimeProvider, LossyOemCpConverter>>);
+pub struct FileWrapper<'a> {
+    file: Mutex<File<'a, Disk, NullTimeProvider, LossyOemCpConverter>>,
+    path: String,
+    attrs: &'a AttrTable,
+}
+
+pub struct DirWrapper<'a> {
+    dir: Dir<'a, Disk, NullTimeProvider, LossyOemCpConverter>,
+    path: String,
+    attrs: &'a AttrTable,
+}
+
+/// FAT has no owners, permission bits or inode numbers, so the ones set or
+/// handed out at runtime are kept in memory, keyed by the lowercase path of the
+/// node in the filesystem.
+///
+/// Nodes without an entry are owned by the superuser with mode `0o777`. The
+/// table is never written to the disk, so after a restart every node, including
+/// the ones that were made private with `chmod` or `chown`, is accessible to
+/// all users again. Permissions that must survive a restart need a filesystem
+/// that stores them, like ext2.
+///
+/// A node is given an inode number the first time it is asked for one. The
+/// number follows the node when it is renamed, so every lookup of a file
+/// returns the same number until the file is removed.
+#[derive(Default)]
+struct AttrTable(Mutex<AttrMaps>);
+
+#[derive(Default)]
+struct AttrMaps {
+    attrs: BTreeMap<String, (VfsNodePerm, u32, u32)>,
+    inos: BTreeMap<String, u64>,
+    next_ino: u64,
+}
+
+impl AttrTable {
+    fn get(&self, path: &str) -> (VfsNodePerm, u32, u32) {
+        let maps = self.0.lock();
+        maps.attrs
+            .get(path)
+            .copied()
+            .unwrap_or((VfsNodePerm::from_bits_truncate(0o777), 0, 0))
+    }
+
+    fn set(&self, path: &str, attr: &VfsNodeAttr) {
+        let entry = (attr.perm(), attr.user_id(), attr.group_id());
+        self.0.lock().attrs.insert(path.into(), entry);
+    }
+
+    fn ino(&self, path: &str) -> u64 {
+        let mut maps = self.0.lock();
+        if let Some(&ino) = maps.inos.get(path) {
+            return ino;
+        }
+        maps.next_ino += 1;
+        let ino = maps.next_ino;
+        maps.inos.insert(path.into(), ino);
+        ino
+    }
 
-pub struct DirWrapper<'a>(Dir<'a, Disk, NullTimeProvider, LossyOemCpConverter>);
+    fn remove(&self, path: &str) {
+        let mut maps = self.0.lock();
+        maps.attrs.remove(path);
+        maps.inos.remove(path);
+    }
+
+    /// Moves the entries of `src` and the nodes below it to `dst`.
+    fn rename(&self, src: &str, dst: &str) {
+        let mut maps = self.0.lock();
+        move_entries(&mut maps.attrs, src, dst);
+        move_entries(&mut maps.inos, src, dst);
+    }
+}
+
+fn move_entries<T>(table: &mut BTreeMap<String, T>, src: &str, dst: &str) {
+    table.remove(dst);
+    let prefix = format!("{}/", src);
+    let keys: Vec<String> = table
+        .keys()
+        .filter(|key| *key == src || key.starts_with(&prefix))
+        .cloned()
+        .collect();
+    for key in keys {
+        let entry = table.remove(&key).unwrap();
+        table.insert(format!("{}{}", dst, &key[src.len()..]), entry);
+    }
+}
+
+/// Returns the key of the node at `path` relative to the directory `base`.
+fn join_path(base: &str, path: &str) -> String {
+    let mut names: Vec<&str> = base.split('/').filter(|name| !name.is_empty()).collect();
+    for name in path.split('/') {
+        match name {
+            "" | "." => {}
+            ".." => {
+                names.pop();
+            }
+            _ => names.push(name),
+        }
+    }
+    names.join("/").to_ascii_lowercase()
+}
 
 unsafe impl Sync for FatFileSystem {}
 
@@ -41,6 +143,7 @@ impl FatFileSystem {
         Self {
             inner,
             root_dir: UnsafeCell::new(None),
+            attrs: AttrTable::default(),
         }
     }
 
@@ -51,20 +154,34 @@ impl FatFileSystem {
         Self {
             inner,
             root_dir: UnsafeCell::new(None),
+            attrs: AttrTable::default(),
         }
     }
 
     pub fn init(&'static self) {
         // must be called before later operations
-        unsafe { *self.root_dir.get() = Some(Self::new_dir(self.inner.root_dir())) }
+        let root_dir = Self::new_dir(self.inner.root_dir(), String::new(), &self.attrs);
+        unsafe { *self.root_dir.get() = Some(root_dir) }
     }
 
-    fn new_file(file: File<'_, Disk, NullTimeProvider, LossyOemCpConverter>) -> Arc<FileWrapper> {
-        Arc::new(FileWrapper(Mutex::new(file)))
+    fn new_file<'a>(
+        file: File<'a, Disk, NullTimeProvider, LossyOemCpConverter>,
+        path: String,
+        attrs: &'a AttrTable,
+    ) -> Arc<FileWrapper<'a>> {
+        Arc::new(FileWrapper {
+            file: Mutex::new(file),
+            path,
+            attrs,
+        })
     }
 
-    fn new_dir(dir: Dir<'_, Disk, NullTimeProvider, LossyOemCpConverter>) -> Arc<DirWrapper> {
-        Arc::new(DirWrapper(dir))
+    fn new_dir<'a>(
+        dir: Dir<'a, Disk, NullTimeProvider, LossyOemCpConverter>,
+        path: String,
+        attrs: &'a AttrTable,
+    ) -> Arc<DirWrapper<'a>> {
+        Arc::new(DirWrapper { dir, path, attrs })
     }
 }
 
@@ -72,34 +189,46 @@ impl VfsNodeOps for FileWrapper<'static> {
     axfs_vfs::impl_vfs_non_dir_default! {}
 
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        let size = self.0.lock().seek(SeekFrom::End(0)).map_err(as_vfs_err)?;
+        let size = self
+            .file
+            .lock()
+            .seek(SeekFrom::End(0))
+            .map_err(as_vfs_err)?;
         let blocks = (size + BLOCK_SIZE as u64 - 1) / BLOCK_SIZE as u64;
-        // FAT fs doesn't support permissions, we just set everything to 777
-        let perm = VfsNodePerm::from_bits_truncate(0o777);
+        let (perm, uid, gid) = self.attrs.get(&self.path);
         Ok(VfsNodeAttr::new(
             perm,
-            0,
-            0,
+            uid,
+            gid,
             VfsNodeType::File,
             size,
             blocks,
         ))
     }
 
+    fn set_attr(&self, attr: VfsNodeAttr) -> VfsResult {
+        self.attrs.set(&self.path, &attr);
+        Ok(())
+    }
+
+    fn ino(&self) -> u64 {
+        self.attrs.ino(&self.path)
+    }
+
     fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
-        let mut file = self.0.lock();
+        let mut file = self.file.lock();
         file.seek(SeekFrom::Start(offset)).map_err(as_vfs_err)?; // TODO: more efficient
         file.read(buf).map_err(as_vfs_err)
     }
 
     fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
-        let mut file = self.0.lock();
+        let mut file = self.file.lock();
         file.seek(SeekFrom::Start(offset)).map_err(as_vfs_err)?; // TODO: more efficient
         file.write(buf).map_err(as_vfs_err)
     }
 
     fn truncate(&self, size: u64) -> VfsResult {
-        let mut file = self.0.lock();
+        let mut file = self.file.lock();
         file.seek(SeekFrom::Start(size)).map_err(as_vfs_err)?; // TODO: more efficient
         file.truncate().map_err(as_vfs_err)
     }
@@ -109,21 +238,31 @@ impl VfsNodeOps for DirWrapper<'static> {
     axfs_vfs::impl_vfs_dir_default! {}
 
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        // FAT fs doesn't support permissions, we just set everything to 777
+        let (perm, uid, gid) = self.attrs.get(&self.path);
         Ok(VfsNodeAttr::new(
-            VfsNodePerm::from_bits_truncate(0o777),
-            0,
-            0,
+            perm,
+            uid,
+            gid,
             VfsNodeType::Dir,
             BLOCK_SIZE as u64,
             1,
         ))
     }
 
+    fn set_attr(&self, attr: VfsNodeAttr) -> VfsResult {
+        self.attrs.set(&self.path, &attr);
+        Ok(())
+    }
+
+    fn ino(&self) -> u64 {
+        self.attrs.ino(&self.path)
+    }
+
     fn parent(&self) -> Option<VfsNodeRef> {
-        self.0
-            .open_dir("..")
-            .map_or(None, |dir| Some(FatFileSystem::new_dir(dir)))
+        self.dir.open_dir("..").map_or(None, |dir| {
+            let path = join_path(&self.path, "..");
+            Some(FatFileSystem::new_dir(dir, path, self.attrs))
+        })
     }
 
     fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
@@ -137,10 +276,11 @@ impl VfsNodeOps for DirWrapper<'static> {
         }
 
         // TODO: use `fatfs::Dir::find_entry`, but it's not public.
-        if let Ok(file) = self.0.open_file(path) {
-            Ok(FatFileSystem::new_file(file))
-        } else if let Ok(dir) = self.0.open_dir(path) {
-            Ok(FatFileSystem::new_dir(dir))
+        let node_path = join_path(&self.path, path);
+        if let Ok(file) = self.dir.open_file(path) {
+            Ok(FatFileSystem::new_file(file, node_path, self.attrs))
+        } else if let Ok(dir) = self.dir.open_dir(path) {
+            Ok(FatFileSystem::new_dir(dir, node_path, self.attrs))
         } else {
             Err(VfsError::NotFound)
         }
@@ -158,11 +298,11 @@ impl VfsNodeOps for DirWrapper<'static> {
 
         match ty {
             VfsNodeType::File => {
-                self.0.create_file(path).map_err(as_vfs_err)?;
+                self.dir.create_file(path).map_err(as_vfs_err)?;
                 Ok(())
             }
             VfsNodeType::Dir => {
-                self.0.create_dir(path).map_err(as_vfs_err)?;
+                self.dir.create_dir(path).map_err(as_vfs_err)?;
                 Ok(())
             }
             _ => Err(VfsError::Unsupported),
@@ -176,11 +316,13 @@ impl VfsNodeOps for DirWrapper<'static> {
         if let Some(rest) = path.strip_prefix("./") {
             return self.remove(rest);
         }
-        self.0.remove(path).map_err(as_vfs_err)
+        self.dir.remove(path).map_err(as_vfs_err)?;
+        self.attrs.remove(&join_path(&self.path, path));
+        Ok(())
     }
 
     fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
-        let mut iter = self.0.iter().skip(start_idx);
+        let mut iter = self.dir.iter().skip(start_idx);
         for (i, out_entry) in dirents.iter_mut().enumerate() {
             let x = iter.next();
             match x {
@@ -207,13 +349,30 @@ impl VfsNodeOps for DirWrapper<'static> {
             src_path, dst_path
         );
 
-        self.0
-            .rename(src_path, &self.0, dst_path)
-            .map_err(as_vfs_err)
+        self.dir
+            .rename(src_path, &self.dir, dst_path)
+            .map_err(as_vfs_err)?;
+        self.attrs.rename(
+            &join_path(&self.path, src_path),
+            &join_path(&self.path, dst_path),
+        );
+        Ok(())
     }
 }
 
 impl VfsOps for FatFileSystem {
+    /// FAT has no inodes, so only the cluster usage is reported.
+    fn statfs(&self) -> VfsResult<FileSystemInfo> {
+        let stats = self.inner.stats().map_err(as_vfs_err)?;
+        Ok(FileSystemInfo::new(
+            stats.cluster_size() as u64,
+            stats.total_clusters() as u64,
+            stats.free_clusters() as u64,
+            0,
+            0,
+        ))
+    }
+
     fn root_dir(&self) -> VfsNodeRef {
         let root_dir = unsafe { (*self.root_dir.get()).as_ref().unwrap() };
         root_dir.clone()
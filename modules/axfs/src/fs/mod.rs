@@ -1,5 +1,8 @@
 pub mod fatfs;
 
+#[cfg(feature = "squashfs")]
+pub mod squashfs;
+
 #[cfg(feature = "devfs")]
 pub use axfs_devfs as devfs;
 
@@ -0,0 +1,41 @@
+//! Read-only [SquashFS](axfs_squashfs) images on a block device.
+
+use axfs_squashfs::ImageReader;
+use axfs_vfs::{VfsError, VfsResult};
+use axsync::Mutex;
+
+use crate::dev::Disk;
+
+pub use axfs_squashfs::SquashFileSystem;
+
+/// Reads a SquashFS image starting at the beginning of a disk.
+pub struct DiskReader(Mutex<Disk>);
+
+impl DiskReader {
+    pub fn new(disk: Disk) -> Self {
+        Self(Mutex::new(disk))
+    }
+}
+
+impl ImageReader for DiskReader {
+    fn read_exact_at(&self, offset: u64, mut buf: &mut [u8]) -> VfsResult {
+        let mut disk = self.0.lock();
+        if offset + buf.len() as u64 > disk.size() {
+            return Err(VfsError::UnexpectedEof);
+        }
+        disk.set_position(offset);
+        while !buf.is_empty() {
+            match disk.read_one(buf) {
+                Ok(0) => return Err(VfsError::UnexpectedEof),
+                Ok(n) => buf = &mut buf[n..],
+                Err(_) => return Err(VfsError::Io),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Opens the SquashFS image on `disk`.
+pub fn new_squashfs(disk: Disk) -> VfsResult<SquashFileSystem> {
+    SquashFileSystem::new(DiskReader::new(disk))
+}
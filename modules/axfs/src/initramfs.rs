@@ -0,0 +1,73 @@
+//! Initial RAM filesystem embedded in the kernel image.
+//!
+//! The archive is given by the `AX_INITRAMFS` environment variable at build
+//! time, which must be the absolute path of a cpio archive in the "newc"
+//! format (as created by `find . | cpio -o -H newc`). It is unpacked into the
+//! root directory during [`init_filesystems`](crate::init_filesystems).
+//!
+//! Directories, regular files and symbolic links are created with the modes,
+//! user ids and group ids recorded in the archive. Other file types are
+//! skipped, and hard links are unpacked as independent copies.
+
+use axerrno::AxResult;
+use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodePerm, VfsNodeRef, VfsNodeType, VfsResult};
+
+use crate::cpio::{self, Entry};
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+const S_IFLNK: u32 = 0o120000;
+
+fn unpack_entry(root: &VfsNodeRef, path: &str, entry: &Entry) -> VfsResult {
+    let ty = match entry.mode & S_IFMT {
+        S_IFDIR => VfsNodeType::Dir,
+        S_IFREG => VfsNodeType::File,
+        S_IFLNK => VfsNodeType::SymLink,
+        _ => {
+            warn!(
+                "initramfs: skip {}: unsupported mode {:#o}",
+                path, entry.mode
+            );
+            return Ok(());
+        }
+    };
+    match root.create(path, ty) {
+        // directories may already exist, e.g. mount points
+        Err(VfsError::AlreadyExists) if ty == VfsNodeType::Dir => {}
+        res => res?,
+    }
+    let node = root.clone().lookup(path)?;
+    if ty != VfsNodeType::Dir {
+        node.write_at(0, entry.data)?;
+    }
+    let size = entry.data.len() as u64;
+    node.set_attr(VfsNodeAttr::new(
+        VfsNodePerm::from_bits_truncate(entry.mode as u16 & 0o7777),
+        entry.uid,
+        entry.gid,
+        ty,
+        size,
+        size.div_ceil(512),
+    ))
+}
+
+/// Unpacks the embedded archive into `root`.
+///
+/// Entries that cannot be created are skipped with a warning; a malformed
+/// archive is an error, and nothing is unpacked.
+pub(crate) fn unpack(root: &VfsNodeRef) -> AxResult {
+    let data: &[u8] = include_bytes!(env!("AX_INITRAMFS"));
+    info!("Unpacking initramfs ({} bytes)...", data.len());
+    for entry in cpio::parse(data)? {
+        let path = entry.name.trim_start_matches("./").trim_start_matches('/');
+        if path.is_empty() || path == "." {
+            continue;
+        }
+        debug!("initramfs: {} {:#o}", path, entry.mode);
+        if let Err(e) = unpack_entry(root, path, &entry) {
+            warn!("initramfs: failed to unpack {}: {:?}", path, e);
+        }
+    }
+    Ok(())
+}
@@ -5,11 +5,28 @@
 //! # Cargo Features
 //!
 //! - `fatfs`: Use [FAT] as the main filesystem and mount it on `/`. This feature
-//!    is **enabled** by default.
+//!    is **enabled** by default. FAT cannot store owners or permission bits, so
+//!    the ones given to nodes by `chmod`, `chown` and the umask are only kept in
+//!    memory. They are lost when the system restarts, after which every node on
+//!    the disk is owned by the superuser with mode `0o777` again.
 //! - `devfs`: Mount [`axfs_devfs::DeviceFileSystem`] on `/dev`. This feature is
 //!    **enabled** by default.
 //! - `ramfs`: Mount [`axfs_ramfs::RamFileSystem`] on `/home`. This feature is
 //!    **enabled** by default.
+//! - `squashfs`: Use a read-only [SquashFS] image on the block device as the
+//!    main filesystem and mount it on `/`, instead of FAT. Blocks compressed by
+//!    LZ4 or zstd are supported. Since the image cannot be modified, it must
+//!    already contain the directories where other filesystems are mounted
+//!    (`/dev`, `/home`, `/tmp`, `/etc`, `/bin`, `/proc` and `/sys`). This
+//!    feature is **disabled** by default.
+//! - `initramfs`: Use a [`axfs_ramfs::RamFileSystem`] as the main filesystem,
+//!    and unpack a cpio archive embedded in the kernel image into it at boot.
+//!    The archive is given by the `AX_INITRAMFS` environment variable at build
+//!    time. No block device is required in this case. This feature is
+//!    **disabled** by default.
+//! - `multitask`: Add `/proc/tasks` to the procfs, which lists the tasks with
+//!    their states, CPU time and stack usage. The used stack is 0 unless the
+//!    `stack_usage` feature of `axtask` is enabled.
 //! - `myfs`: Allow users to define their custom filesystems to override the
 //!    default. In this case, [`MyFileSystemIf`] is required to be implemented
 //!    to create and initialize other filesystems. This feature is **disabled** by
@@ -17,6 +34,7 @@
 //!    both are enabled.
 //!
 //! [FAT]: https://en.wikipedia.org/wiki/File_Allocation_Table
+//! [SquashFS]: https://en.wikipedia.org/wiki/SquashFS
 //! [`MyFileSystemIf`]: fops::MyFileSystemIf
 
 #![cfg_attr(all(not(test), not(doc)), no_std)]
@@ -26,13 +44,19 @@
 extern crate log;
 extern crate alloc;
 
+#[cfg(any(feature = "initramfs", test))]
+mod cpio;
 mod dev;
 mod fs;
+#[cfg(feature = "initramfs")]
+mod initramfs;
 mod mounts;
 mod root;
 
 pub mod api;
+pub mod audit;
 pub mod fops;
+pub mod notify;
 mod open_options;
 
 use axdriver::{prelude::*, AxDeviceContainer};
@@ -43,7 +67,9 @@ use permission::permission;
 pub fn init_filesystems(mut blk_devs: AxDeviceContainer<AxBlockDevice>) {
     info!("Initialize filesystems...");
 
-    let dev = blk_devs.take_one().expect("No block device found!");
-    info!("  use block device 0: {:?}", dev.device_name());
-    self::root::init_rootfs(self::dev::Disk::new(dev));
+    let disk = blk_devs.take_one().map(|dev| {
+        info!("  use block device 0: {:?}", dev.device_name());
+        self::dev::Disk::new(dev)
+    });
+    self::root::init_rootfs(disk);
 }
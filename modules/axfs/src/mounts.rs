@@ -44,9 +44,89 @@ pub(crate) fn procfs() -> VfsResult<Arc<fs::ramfs::RamFileSystem>> {
     proc_root.create("self", VfsNodeType::Dir)?;
     proc_root.create("self/stat", VfsNodeType::File)?;
 
+    // Create /proc/tasks
+    #[cfg(feature = "multitask")]
+    procfs
+        .root_dir_node()
+        .add_node("tasks", Arc::new(tasks::TasksFile))?;
+
     Ok(Arc::new(procfs))
 }
 
+#[cfg(all(feature = "procfs", feature = "multitask"))]
+mod tasks {
+    use alloc::string::String;
+    use core::fmt::Write;
+
+    use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+    use axtask::{SchedPolicy, TaskState};
+
+    /// `/proc/tasks`, which lists all the tasks when read, one per line.
+    pub struct TasksFile;
+
+    impl TasksFile {
+        fn contents() -> String {
+            let mut buf = String::from(
+                "ID CPU STATE POLICY PRIO NICE RUNTIME_US SWITCHES STACK_USED STACK_SIZE NAME\n",
+            );
+            for task in axtask::tasks() {
+                let state = match task.state {
+                    TaskState::Running => "running",
+                    TaskState::Ready => "ready",
+                    TaskState::Blocked => "blocked",
+                    TaskState::Exited => "exited",
+                };
+                let policy = match task.policy {
+                    SchedPolicy::Normal => "normal",
+                    SchedPolicy::Fifo => "fifo",
+                    SchedPolicy::RR => "rr",
+                };
+                writeln!(
+                    buf,
+                    "{} {} {} {} {} {} {} {} {} {} {}",
+                    task.id.as_u64(),
+                    task.cpu_id,
+                    state,
+                    policy,
+                    task.rt_priority,
+                    task.nice,
+                    task.runtime.as_micros(),
+                    task.context_switches,
+                    task.stack_max_used,
+                    task.stack_size,
+                    task.name,
+                )
+                .unwrap();
+            }
+            buf
+        }
+    }
+
+    impl VfsNodeOps for TasksFile {
+        fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+            // the size is unknown until it is read
+            Ok(VfsNodeAttr::new(
+                VfsNodePerm::from_bits_truncate(0o444),
+                0,
+                0,
+                VfsNodeType::File,
+                0,
+                0,
+            ))
+        }
+
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+            let contents = Self::contents();
+            let start = contents.len().min(offset as usize);
+            let len = (contents.len() - start).min(buf.len());
+            buf[..len].copy_from_slice(&contents.as_bytes()[start..start + len]);
+            Ok(len)
+        }
+
+        axfs_vfs::impl_vfs_non_dir_default! {}
+    }
+}
+
 #[cfg(feature = "sysfs")]
 pub(crate) fn sysfs() -> VfsResult<Arc<fs::ramfs::RamFileSystem>> {
     let sysfs = fs::ramfs::RamFileSystem::new();
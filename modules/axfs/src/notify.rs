@@ -0,0 +1,304 @@
+//! Filesystem change notifications, similar to Linux `inotify`.
+//!
+//! An [`Inotify`] instance holds a set of watches on files or directories,
+//! identified by their absolute paths. Operations in [`crate::root`] and
+//! [`crate::fops`] emit events on the affected paths, which are queued on
+//! every instance with a matching watch:
+//!
+//! - a watch on a directory receives events about its direct children, with
+//!   the child name set in [`Event::name`];
+//! - a watch on the node itself receives events with an empty name, plus
+//!   [`WatchMask::DELETE_SELF`] / [`WatchMask::MOVE_SELF`] when it is removed
+//!   or renamed, after which the watch is dropped with [`WatchMask::IGNORED`].
+//!
+//! Paths relative to a [`Directory`](crate::fops::Directory) handle other than
+//! the current directory are not tracked.
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicI32, AtomicU32, AtomicUsize, Ordering};
+
+use axerrno::{ax_err, AxResult};
+#[cfg(feature = "multitask")]
+use axsync::Condvar;
+use axsync::Mutex;
+
+/// Maximum number of events queued on an instance before
+/// [`WatchMask::Q_OVERFLOW`] is reported.
+pub const MAX_QUEUED_EVENTS: usize = 16384;
+
+bitflags::bitflags! {
+    /// Event types of a watch, with the same values as Linux `IN_*` flags.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct WatchMask: u32 {
+        /// File was modified.
+        const MODIFY = 0x0000_0002;
+        /// Metadata (permissions, owner) changed.
+        const ATTRIB = 0x0000_0004;
+        /// File moved out of the watched directory.
+        const MOVED_FROM = 0x0000_0040;
+        /// File moved into the watched directory.
+        const MOVED_TO = 0x0000_0080;
+        /// File or directory created in the watched directory.
+        const CREATE = 0x0000_0100;
+        /// File or directory deleted from the watched directory.
+        const DELETE = 0x0000_0200;
+        /// The watched node itself was deleted.
+        const DELETE_SELF = 0x0000_0400;
+        /// The watched node itself was moved.
+        const MOVE_SELF = 0x0000_0800;
+
+        /// Event queue overflowed.
+        const Q_OVERFLOW = 0x0000_4000;
+        /// Watch was removed.
+        const IGNORED = 0x0000_8000;
+        /// The subject of the event is a directory.
+        const ISDIR = 0x4000_0000;
+
+        /// Both [`MOVED_FROM`](Self::MOVED_FROM) and [`MOVED_TO`](Self::MOVED_TO).
+        const MOVE = Self::MOVED_FROM.bits() | Self::MOVED_TO.bits();
+        /// All events that can be watched for.
+        const ALL_EVENTS = Self::MODIFY.bits()
+            | Self::ATTRIB.bits()
+            | Self::MOVE.bits()
+            | Self::CREATE.bits()
+            | Self::DELETE.bits()
+            | Self::DELETE_SELF.bits()
+            | Self::MOVE_SELF.bits();
+    }
+}
+
+/// A filesystem change event.
+#[derive(Debug, Clone)]
+pub struct Event {
+    /// Watch descriptor the event belongs to, or `-1` for queue overflow.
+    pub wd: i32,
+    /// Event type.
+    pub mask: WatchMask,
+    /// Connects the [`MOVED_FROM`](WatchMask::MOVED_FROM) and
+    /// [`MOVED_TO`](WatchMask::MOVED_TO) events of the same rename.
+    pub cookie: u32,
+    /// Name of the child in the watched directory, empty if the event is
+    /// about the watched node itself.
+    pub name: String,
+}
+
+struct Watch {
+    path: String,
+    mask: WatchMask,
+}
+
+struct InotifyInner {
+    watches: Mutex<BTreeMap<i32, Watch>>,
+    events: Mutex<VecDeque<Event>>,
+    /// Notified when an event is queued.
+    #[cfg(feature = "multitask")]
+    queued: Condvar,
+    next_wd: AtomicI32,
+}
+
+/// An inotify instance, holding a set of watches and a queue of events.
+pub struct Inotify {
+    inner: Arc<InotifyInner>,
+}
+
+static INSTANCES: Mutex<Vec<Weak<InotifyInner>>> = Mutex::new(Vec::new());
+static WATCH_COUNT: AtomicUsize = AtomicUsize::new(0);
+static NEXT_COOKIE: AtomicU32 = AtomicU32::new(1);
+
+impl InotifyInner {
+    fn push_event(&self, wd: i32, mask: WatchMask, cookie: u32, name: &str) {
+        let mut events = self.events.lock();
+        if events.len() >= MAX_QUEUED_EVENTS {
+            if !matches!(events.back(), Some(e) if e.mask == WatchMask::Q_OVERFLOW) {
+                events.push_back(Event {
+                    wd: -1,
+                    mask: WatchMask::Q_OVERFLOW,
+                    cookie: 0,
+                    name: String::new(),
+                });
+            }
+            return;
+        }
+        events.push_back(Event {
+            wd,
+            mask,
+            cookie,
+            name: name.into(),
+        });
+        #[cfg(feature = "multitask")]
+        self.queued.notify_all();
+    }
+}
+
+impl Inotify {
+    /// Creates a new instance with no watches.
+    pub fn new() -> Self {
+        let inner = Arc::new(InotifyInner {
+            watches: Mutex::new(BTreeMap::new()),
+            events: Mutex::new(VecDeque::new()),
+            #[cfg(feature = "multitask")]
+            queued: Condvar::new(),
+            next_wd: AtomicI32::new(1),
+        });
+        let mut instances = INSTANCES.lock();
+        instances.retain(|i| i.strong_count() > 0);
+        instances.push(Arc::downgrade(&inner));
+        Self { inner }
+    }
+
+    /// Adds a watch on the file or directory at `path`, or updates the mask
+    /// if it is already watched. Returns the watch descriptor.
+    pub fn add_watch(&self, path: &str, mask: WatchMask) -> AxResult<i32> {
+        let mask = mask & WatchMask::ALL_EVENTS;
+        if mask.is_empty() {
+            return ax_err!(InvalidInput);
+        }
+        crate::root::lookup(None, path)?;
+        let path = crate::root::absolute_path(path)?;
+
+        let mut watches = self.inner.watches.lock();
+        if let Some((&wd, watch)) = watches.iter_mut().find(|(_, w)| w.path == path) {
+            watch.mask = mask;
+            return Ok(wd);
+        }
+        let wd = self.inner.next_wd.fetch_add(1, Ordering::Relaxed);
+        watches.insert(wd, Watch { path, mask });
+        WATCH_COUNT.fetch_add(1, Ordering::Release);
+        Ok(wd)
+    }
+
+    /// Removes the watch `wd`, queueing a [`WatchMask::IGNORED`] event.
+    pub fn rm_watch(&self, wd: i32) -> AxResult {
+        if self.inner.watches.lock().remove(&wd).is_none() {
+            return ax_err!(InvalidInput);
+        }
+        WATCH_COUNT.fetch_sub(1, Ordering::Release);
+        self.inner.push_event(wd, WatchMask::IGNORED, 0, "");
+        Ok(())
+    }
+
+    /// Pops the oldest queued event, if any.
+    pub fn read_event(&self) -> Option<Event> {
+        self.inner.events.lock().pop_front()
+    }
+
+    /// Applies `f` to the oldest queued event without removing it.
+    pub fn peek_event<T>(&self, f: impl FnOnce(&Event) -> T) -> Option<T> {
+        self.inner.events.lock().front().map(f)
+    }
+
+    /// Whether there are events waiting to be read.
+    pub fn has_events(&self) -> bool {
+        !self.inner.events.lock().is_empty()
+    }
+
+    /// Blocks the current task until there are events waiting to be read.
+    #[cfg(feature = "multitask")]
+    pub fn wait_event(&self) {
+        let events = self.inner.events.lock();
+        drop(self.inner.queued.wait_while(events, |e| e.is_empty()));
+    }
+}
+
+impl Default for Inotify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Inotify {
+    fn drop(&mut self) {
+        let count = self.inner.watches.lock().len();
+        WATCH_COUNT.fetch_sub(count, Ordering::Release);
+        INSTANCES
+            .lock()
+            .retain(|i| i.strong_count() > 0 && !Weak::ptr_eq(i, &Arc::downgrade(&self.inner)));
+    }
+}
+
+/// Splits an absolute path into its parent directory and file name.
+fn split_parent(path: &str) -> (&str, &str) {
+    let path = path.trim_end_matches('/');
+    match path.rfind('/') {
+        Some(0) => ("/", &path[1..]),
+        Some(n) => (&path[..n], &path[n + 1..]),
+        None => ("", path),
+    }
+}
+
+/// Queues `mask` on every watch of `path` or its parent directory.
+///
+/// `path` must be absolute and canonical. When `mask` contains
+/// [`WatchMask::DELETE`] or [`WatchMask::MOVED_FROM`], watches on `path`
+/// itself receive `DELETE_SELF`/`MOVE_SELF` and are removed.
+fn notify_path(path: &str, mask: WatchMask, cookie: u32) {
+    let path = path.trim_end_matches('/');
+    let path = if path.is_empty() { "/" } else { path };
+    let (parent, name) = split_parent(path);
+    let type_mask = mask & WatchMask::ISDIR;
+    let self_mask = if mask.contains(WatchMask::DELETE) {
+        Some(WatchMask::DELETE_SELF)
+    } else if mask.contains(WatchMask::MOVED_FROM) {
+        Some(WatchMask::MOVE_SELF)
+    } else {
+        None
+    };
+
+    for inst in INSTANCES.lock().iter().filter_map(Weak::upgrade) {
+        let mut watches = inst.watches.lock();
+        let mut removed = Vec::new();
+        for (&wd, watch) in watches.iter() {
+            if watch.path == parent && watch.mask.intersects(mask) {
+                inst.push_event(wd, mask, cookie, name);
+            } else if watch.path == path {
+                match self_mask {
+                    Some(self_mask) => {
+                        if watch.mask.intersects(self_mask) {
+                            inst.push_event(wd, self_mask, 0, "");
+                        }
+                        removed.push(wd);
+                    }
+                    None if watch.mask.intersects(mask) => {
+                        inst.push_event(wd, mask | type_mask, cookie, "")
+                    }
+                    None => {}
+                }
+            }
+        }
+        for wd in removed {
+            watches.remove(&wd);
+            WATCH_COUNT.fetch_sub(1, Ordering::Release);
+            inst.push_event(wd, WatchMask::IGNORED, 0, "");
+        }
+    }
+}
+
+/// Whether any watch exists, so callers can skip building event paths.
+pub(crate) fn is_watching() -> bool {
+    WATCH_COUNT.load(Ordering::Acquire) > 0
+}
+
+/// Emits `mask` for the absolute path `path`.
+pub(crate) fn notify(path: &str, mask: WatchMask) {
+    if is_watching() {
+        notify_path(path, mask, 0);
+    }
+}
+
+/// Emits a pair of [`WatchMask::MOVED_FROM`] and [`WatchMask::MOVED_TO`]
+/// events sharing the same cookie.
+pub(crate) fn notify_rename(old: &str, new: &str, is_dir: bool) {
+    if is_watching() {
+        let cookie = NEXT_COOKIE.fetch_add(1, Ordering::Relaxed);
+        let dir = if is_dir {
+            WatchMask::ISDIR
+        } else {
+            WatchMask::empty()
+        };
+        notify_path(old, WatchMask::MOVED_FROM | dir, cookie);
+        notify_path(new, WatchMask::MOVED_TO | dir, cookie);
+    }
+}
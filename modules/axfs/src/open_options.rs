@@ -11,7 +11,7 @@ pub struct OpenOptions {
     pub create_new: bool,
     // system-specific
     pub _custom_flags: i32,
-    pub _mode: u32,
+    pub mode: u32,
 }
 
 impl OpenOptions {
@@ -28,7 +28,7 @@ impl OpenOptions {
             create_new: false,
             // system-specific
             _custom_flags: 0,
-            _mode: 0o666,
+            mode: 0o666,
         }
     }
     /// Sets the option for read access.
@@ -59,4 +59,9 @@ impl OpenOptions {
     pub fn create_new(&mut self, create_new: bool) {
         self.create_new = create_new;
     }
+    /// Sets the mode to create a new file with, before the umask of the
+    /// current task is applied.
+    pub fn mode(&mut self, mode: u32) {
+        self.mode = mode;
+    }
 }
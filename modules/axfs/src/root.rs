@@ -4,12 +4,15 @@
 
 use alloc::{string::String, sync::Arc, vec::Vec};
 use axerrno::{ax_err, AxError, AxResult};
-use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeRef, VfsNodeType, VfsOps, VfsResult};
+use axfs_vfs::{FileSystemInfo, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeRef};
+use axfs_vfs::{VfsNodeType, VfsOps, VfsResult};
 use axsync::Mutex;
 use capability::Cap;
 use lazy_init::LazyInit;
 
+use crate::audit::{self, AuditAction};
 use crate::fops::perm_to_cap;
+use crate::notify::{self, WatchMask};
 use crate::{api::FileType, fs, mounts};
 
 #[cfg(feature = "permission")]
@@ -147,21 +150,34 @@ impl VfsNodeOps for RootDirectory {
     }
 
     fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        let (dst_fs, dst_rest) =
+            self.lookup_mounted_fs(dst_path, |fs, rest_path| Ok((fs, String::from(rest_path))))?;
         self.lookup_mounted_fs(src_path, |fs, rest_path| {
-            if rest_path.is_empty() {
+            if rest_path.is_empty() || dst_rest.is_empty() {
                 ax_err!(PermissionDenied) // cannot rename mount points
+            } else if !Arc::ptr_eq(&fs, &dst_fs) {
+                ax_err!(Unsupported) // cannot move across filesystems
             } else {
-                fs.root_dir().rename(rest_path, dst_path)
+                fs.root_dir().rename(rest_path, &dst_rest)
             }
         })
     }
 }
 
-pub(crate) fn init_rootfs(disk: crate::dev::Disk) {
+pub(crate) fn init_rootfs(disk: Option<crate::dev::Disk>) {
     cfg_if::cfg_if! {
         if #[cfg(feature = "myfs")] { // override the default filesystem
-            let main_fs = fs::myfs::new_myfs(disk);
+            let main_fs = fs::myfs::new_myfs(disk.expect("No block device found!"));
+        } else if #[cfg(feature = "initramfs")] {
+            drop(disk); // files come from the initramfs instead
+            let main_fs = mounts::ramfs();
+        } else if #[cfg(feature = "squashfs")] {
+            let disk = disk.expect("No block device found!");
+            let main_fs = Arc::new(
+                fs::squashfs::new_squashfs(disk).expect("failed to initialize squashfs"),
+            );
         } else if #[cfg(feature = "fatfs")] {
+            let disk = disk.expect("No block device found!");
             static FAT_FS: LazyInit<Arc<fs::fatfs::FatFileSystem>> = LazyInit::new();
             FAT_FS.init_by(Arc::new(fs::fatfs::FatFileSystem::new(disk)));
             FAT_FS.init();
@@ -181,8 +197,19 @@ pub(crate) fn init_rootfs(disk: crate::dev::Disk) {
         root_dir
             .mount("/home", mounts::ramfs())
             .expect("failed to mount ramfs at /home");
+        let tmp = mounts::ramfs();
+        tmp.root_dir()
+            .set_attr(VfsNodeAttr::new(
+                VfsNodePerm::from_bits_truncate(0o1777),
+                0,
+                0,
+                VfsNodeType::Dir,
+                0,
+                0,
+            ))
+            .expect("failed to set the mode of /tmp");
         root_dir
-            .mount("/tmp", mounts::ramfs())
+            .mount("/tmp", tmp)
             .expect("failed to mount ramfs at /tmp");
         root_dir
             .mount("/etc", mounts::ramfs())
@@ -205,8 +232,12 @@ pub(crate) fn init_rootfs(disk: crate::dev::Disk) {
         .expect("fail to mount sysfs at /sys");
 
     ROOT_DIR.init_by(Arc::new(root_dir));
+    #[cfg(feature = "initramfs")]
+    crate::initramfs::unpack(&(ROOT_DIR.clone() as VfsNodeRef))
+        .expect("failed to unpack initramfs");
     CURRENT_DIR.init_by(Mutex::new(ROOT_DIR.clone()));
     *CURRENT_DIR_PATH.lock() = "/".into();
+    crate::audit::init();
 }
 
 fn parent_node_of(dir: Option<&VfsNodeRef>, path: &str) -> VfsNodeRef {
@@ -226,6 +257,16 @@ pub(crate) fn absolute_path(path: &str) -> AxResult<String> {
     }
 }
 
+/// Returns the absolute path of `path` for change notifications, or `None` if
+/// nobody is watching or it is relative to a directory handle.
+pub(crate) fn notify_path_of(dir: Option<&VfsNodeRef>, path: &str) -> Option<String> {
+    if !notify::is_watching() || (dir.is_some() && !path.starts_with('/')) {
+        None
+    } else {
+        absolute_path(path).ok()
+    }
+}
+
 pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
     if path.is_empty() {
         return ax_err!(NotFound);
@@ -238,41 +279,87 @@ pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRe
     }
 }
 
-pub(crate) fn create_file(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+/// Returns the directory that will contain the node at `path`.
+fn parent_dir_of(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+    let path = path.trim_end_matches('/');
+    match path.rfind('/') {
+        Some(idx) => lookup(dir, &path[..idx.max(1)]),
+        None => Ok(parent_node_of(dir, path)),
+    }
+}
+
+/// Returns the attributes of a new node of type `ty` at `path`, created with
+/// the permission bits `mode`.
+///
+/// The bits in the umask of the current task are cleared from `mode`, and the
+/// node is owned by the effective user and group of the task. In a directory
+/// with the set-group-ID bit, the node belongs to the group of the directory
+/// instead, and new subdirectories inherit the bit.
+fn new_node_attr(
+    dir: Option<&VfsNodeRef>,
+    path: &str,
+    ty: VfsNodeType,
+    mode: u16,
+) -> AxResult<VfsNodeAttr> {
+    let mut perm = VfsNodePerm::from_bits_truncate(mode & !crate::api::current_umask());
+    let mut gid = current_gid()?;
+    let parent = parent_dir_of(dir, path)?.get_attr()?;
+    if parent.perm().contains(VfsNodePerm::SET_GID) {
+        gid = parent.group_id();
+        if ty == VfsNodeType::Dir {
+            perm |= VfsNodePerm::SET_GID;
+        }
+    }
+    Ok(VfsNodeAttr::new(perm, current_uid()?, gid, ty, 0, 0))
+}
+
+/// Whether the current task may remove or rename the node with attributes
+/// `attr` in the directory with attributes `parent`.
+///
+/// In a directory with the sticky bit, like `/tmp`, this is only allowed to
+/// the owner of the node or of the directory, or with [`PrivCap::FOWNER`].
+///
+/// [`PrivCap::FOWNER`]: crate::permission::PrivCap::FOWNER
+fn may_unlink(parent: &VfsNodeAttr, attr: &VfsNodeAttr) -> AxResult<bool> {
+    if !parent.perm().contains(VfsNodePerm::STICKY) {
+        return Ok(true);
+    }
+    #[cfg(feature = "permission")]
+    let fowner = crate::permission::current_has_cap(crate::permission::PrivCap::FOWNER);
+    #[cfg(not(feature = "permission"))]
+    let fowner = true;
+    let uid = current_uid()?;
+    Ok(fowner || uid == attr.user_id() || uid == parent.user_id())
+}
+
+pub(crate) fn create_file(dir: Option<&VfsNodeRef>, path: &str, mode: u16) -> AxResult<VfsNodeRef> {
     if path.is_empty() {
         return ax_err!(NotFound);
     } else if path.ends_with('/') {
         return ax_err!(NotADirectory);
     }
+    let attr = new_node_attr(dir, path, VfsNodeType::File, mode)?;
     let parent = parent_node_of(dir, path);
     parent.create(path, VfsNodeType::File)?;
-    let new_node = parent.lookup(path);
-    new_node?.set_attr(VfsNodeAttr::new(
-        VfsNodePerm::default_file(),
-        current_uid()?,
-        current_gid()?,
-        VfsNodeType::File,
-        0,
-        0,
-    ))?;
-    parent_node_of(dir, path).lookup(path)
+    parent.clone().lookup(path)?.set_attr(attr)?;
+    if let Some(abs_path) = notify_path_of(dir, path) {
+        notify::notify(&abs_path, WatchMask::CREATE);
+    }
+    parent.lookup(path)
 }
 
-pub(crate) fn create_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
+pub(crate) fn create_dir(dir: Option<&VfsNodeRef>, path: &str, mode: u16) -> AxResult {
     match lookup(dir, path) {
         Ok(_) => ax_err!(AlreadyExists),
         Err(AxError::NotFound) => {
+            let attr = new_node_attr(dir, path, VfsNodeType::Dir, mode)?;
             let parent = parent_node_of(dir, path);
             parent.create(path, VfsNodeType::Dir)?;
-            let new_node = parent.lookup(path);
-            new_node?.set_attr(VfsNodeAttr::new(
-                VfsNodePerm::default_dir(),
-                current_uid()?,
-                current_gid()?,
-                VfsNodeType::Dir,
-                0,
-                0,
-            ))
+            parent.lookup(path)?.set_attr(attr)?;
+            if let Some(abs_path) = notify_path_of(dir, path) {
+                notify::notify(&abs_path, WatchMask::CREATE | WatchMask::ISDIR);
+            }
+            Ok(())
         }
         Err(e) => Err(e),
     }
@@ -283,10 +370,16 @@ pub(crate) fn remove_file(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
     let attr = node.get_attr()?;
     if attr.is_dir() {
         ax_err!(IsADirectory)
-    } else if !perm_to_cap(attr.perm(), attr.user_id(), attr.group_id()).contains(Cap::WRITE) {
-        ax_err!(PermissionDenied)
+    } else if !perm_to_cap(&attr).contains(Cap::WRITE)
+        || !may_unlink(&parent_dir_of(dir, path)?.get_attr()?, &attr)?
+    {
+        audit::denied(AuditAction::Remove, path)
     } else {
-        parent_node_of(dir, path).remove(path)
+        parent_node_of(dir, path).remove(path)?;
+        if let Some(abs_path) = notify_path_of(dir, path) {
+            notify::notify(&abs_path, WatchMask::DELETE);
+        }
+        Ok(())
     }
 }
 
@@ -312,13 +405,38 @@ pub(crate) fn remove_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
     let attr = node.get_attr()?;
     if !attr.is_dir() {
         ax_err!(NotADirectory)
-    } else if !perm_to_cap(attr.perm(), attr.user_id(), attr.group_id()).contains(Cap::WRITE) {
-        ax_err!(PermissionDenied)
+    } else if !perm_to_cap(&attr).contains(Cap::WRITE)
+        || !may_unlink(&parent_dir_of(dir, path)?.get_attr()?, &attr)?
+    {
+        audit::denied(AuditAction::Remove, path)
     } else {
-        parent_node_of(dir, path).remove(path)
+        parent_node_of(dir, path).remove(path)?;
+        if let Some(abs_path) = notify_path_of(dir, path) {
+            notify::notify(&abs_path, WatchMask::DELETE | WatchMask::ISDIR);
+        }
+        Ok(())
     }
 }
 
+pub(crate) fn statfs(path: &str) -> AxResult<FileSystemInfo> {
+    let abs_path = absolute_path(path)?;
+    lookup(None, &abs_path)?;
+    mounted_fs(&abs_path)?.statfs()
+}
+
+/// Returns the filesystem that contains the node at `path`, which is relative
+/// to the current directory if not absolute.
+pub(crate) fn mounted_fs(path: &str) -> AxResult<Arc<dyn VfsOps>> {
+    ROOT_DIR.lookup_mounted_fs(&absolute_path(path)?, |fs, _| Ok(fs))
+}
+
+pub(crate) fn mount_points() -> Vec<String> {
+    let mut paths = Vec::with_capacity(ROOT_DIR.mounts.len() + 1);
+    paths.push("/".into());
+    paths.extend(ROOT_DIR.mounts.iter().map(|mp| mp.path.into()));
+    paths
+}
+
 pub(crate) fn current_dir() -> AxResult<String> {
     Ok(CURRENT_DIR_PATH.lock().clone())
 }
@@ -338,8 +456,8 @@ pub(crate) fn set_current_dir(path: &str) -> AxResult {
     let attr = node.get_attr()?;
     if !attr.is_dir() {
         ax_err!(NotADirectory)
-    } else if !perm_to_cap(attr.perm(), attr.user_id(), attr.group_id()).contains(Cap::EXECUTE) {
-        ax_err!(PermissionDenied)
+    } else if !perm_to_cap(&attr).contains(Cap::EXECUTE) {
+        audit::denied(AuditAction::ChangeDir, &abs_path)
     } else {
         *CURRENT_DIR.lock() = node;
         *CURRENT_DIR_PATH.lock() = abs_path;
@@ -348,9 +466,35 @@ pub(crate) fn set_current_dir(path: &str) -> AxResult {
 }
 
 pub(crate) fn rename(old: &str, new: &str) -> AxResult {
-    if parent_node_of(None, new).lookup(new).is_ok() {
-        warn!("dst file already exist, now remove it");
-        remove_file(None, new)?;
+    let attr = lookup(None, old)?.get_attr()?;
+    if !may_unlink(&parent_dir_of(None, old)?.get_attr()?, &attr)? {
+        return audit::denied(AuditAction::Rename, old);
+    }
+    if let Ok(dst) = lookup(None, new) {
+        // replacing the destination removes it
+        let dst_attr = dst.get_attr()?;
+        if dst_attr.is_dir() {
+            return ax_err!(IsADirectory);
+        }
+        if !perm_to_cap(&dst_attr).contains(Cap::WRITE)
+            || !may_unlink(&parent_dir_of(None, new)?.get_attr()?, &dst_attr)?
+        {
+            return audit::denied(AuditAction::Rename, new);
+        }
+    }
+    let is_dir = attr.is_dir();
+    let parent = parent_node_of(None, old);
+    match parent.rename(old, new) {
+        // the filesystem cannot replace the destination by itself
+        Err(AxError::AlreadyExists) => {
+            warn!("dst file already exist, now remove it");
+            remove_file(None, new)?;
+            parent.rename(old, new)?;
+        }
+        res => res?,
+    }
+    if notify::is_watching() {
+        notify::notify_rename(&absolute_path(old)?, &absolute_path(new)?, is_dir);
     }
-    parent_node_of(None, old).rename(old, new)
+    Ok(())
 }
@@ -127,6 +127,15 @@ fn test_create_file_dir() -> Result<()> {
     assert!(fs::metadata(dirname)?.is_dir());
     assert_err!(fs::create_dir(dirname), AlreadyExists);
 
+    // create a directory with its missing parents
+    let dirname = "/recursive/dir/tree/";
+    println!("test create dir all {:?}:", dirname);
+    fs::create_dir_all(dirname)?;
+    assert!(fs::metadata("/recursive/dir")?.is_dir());
+    assert!(fs::metadata(dirname)?.is_dir());
+    fs::create_dir_all(dirname)?;
+    assert_err!(fs::create_dir_all("short.txt/dir"));
+
     println!("test_create_file_dir() OK!");
     Ok(())
 }
@@ -164,6 +173,29 @@ fn test_remove_file_dir() -> Result<()> {
     Ok(())
 }
 
+fn test_rename() -> Result<()> {
+    println!("test rename:");
+    fs::write("/rename-old.txt", "old")?;
+    fs::write("/rename-new.txt", "new")?;
+    // replace an existing file
+    fs::rename("/rename-new.txt", "/rename-old.txt")?;
+    assert_eq!(fs::read_to_string("/rename-old.txt")?, "new");
+    assert_err!(fs::metadata("/rename-new.txt"), NotFound);
+
+    // move to another directory
+    fs::create_dir("/rename-dir")?;
+    fs::rename("/rename-old.txt", "/rename-dir/moved.txt")?;
+    assert_eq!(fs::read_to_string("/rename-dir/moved.txt")?, "new");
+    assert_err!(fs::metadata("/rename-old.txt"), NotFound);
+    assert_err!(fs::rename("/rename-dir/moved.txt", "/very"), IsADirectory);
+    assert_err!(fs::rename("/rename-dir/none.txt", "/none.txt"), NotFound);
+
+    fs::remove_file("/rename-dir/moved.txt")?;
+    fs::remove_dir("/rename-dir")?;
+    println!("test_rename() OK!");
+    Ok(())
+}
+
 fn test_devfs_ramfs() -> Result<()> {
     const N: usize = 32;
     let mut buf = [1; N];
@@ -252,11 +284,238 @@ fn test_devfs_ramfs() -> Result<()> {
     Ok(())
 }
 
+fn test_statfs_open_file() -> Result<()> {
+    use axfs::fops;
+
+    println!("test statfs of open files:");
+    let mut opts = fops::OpenOptions::new();
+    opts.read(true);
+    // the filesystem mounted on /dev, not the main one
+    let null = fops::File::open("/dev/null", &opts)?;
+    assert_eq!(null.statfs()?.files(), fs::statfs("/dev")?.files());
+
+    // still the filesystem of /tmp after the file is gone
+    fs::write("/tmp/statfs.txt", "statfs")?;
+    let file = fops::File::open("/tmp/statfs.txt", &opts)?;
+    fs::remove_file("/tmp/statfs.txt")?;
+    assert_err!(fs::statfs("/tmp/statfs.txt"), NotFound);
+    assert_eq!(file.statfs()?.files(), fs::statfs("/tmp")?.files());
+    println!("test_statfs_open_file() OK!");
+    Ok(())
+}
+
+fn test_notify() -> Result<()> {
+    use axfs::notify::{Inotify, WatchMask};
+
+    let inotify = Inotify::new();
+    fs::create_dir("/tmp/watched")?;
+    let wd = inotify.add_watch("/tmp//watched/", WatchMask::ALL_EVENTS)?;
+    assert_eq!(inotify.add_watch("/tmp/watched", WatchMask::CREATE)?, wd);
+    inotify.add_watch("/tmp/watched", WatchMask::ALL_EVENTS)?;
+    assert_err!(
+        inotify.add_watch("/tmp/not-exist", WatchMask::ALL_EVENTS),
+        NotFound
+    );
+    assert!(!inotify.has_events());
+
+    let next_event = || {
+        let event = inotify.read_event().expect("missing event");
+        assert_eq!(event.wd, wd);
+        (event.mask, event.name)
+    };
+
+    fs::write("/tmp/watched/test.txt", "test")?;
+    assert_eq!(next_event(), (WatchMask::CREATE, "test.txt".into()));
+    assert_eq!(next_event(), (WatchMask::MODIFY, "test.txt".into()));
+    fs::create_dir("/tmp/watched/dir")?;
+    assert_eq!(
+        next_event(),
+        (WatchMask::CREATE | WatchMask::ISDIR, "dir".into())
+    );
+    fs::remove_dir("/tmp/watched/dir")?;
+    assert_eq!(
+        next_event(),
+        (WatchMask::DELETE | WatchMask::ISDIR, "dir".into())
+    );
+    fs::remove_file("/tmp/watched/test.txt")?;
+    assert_eq!(next_event(), (WatchMask::DELETE, "test.txt".into()));
+    fs::remove_dir("/tmp/watched")?;
+    assert_eq!(next_event(), (WatchMask::DELETE_SELF, "".into()));
+    assert_eq!(next_event(), (WatchMask::IGNORED, "".into()));
+    assert!(!inotify.has_events());
+    assert_err!(inotify.rm_watch(wd), InvalidInput);
+
+    println!("test_notify() OK!");
+    Ok(())
+}
+
+fn test_audit() -> Result<()> {
+    use axfs::audit::{self, AuditAction, AUDIT_LOG};
+
+    audit::record(AuditAction::Su, "nobody", false);
+    let events = audit::recent(1);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].action, AuditAction::Su);
+    assert_eq!(events[0].target, "nobody");
+    assert!(!events[0].success);
+
+    let log = fs::read_to_string(AUDIT_LOG)?;
+    assert!(log.ends_with(&format!("{}\n", events[0])));
+    assert!(audit::recent(usize::MAX).len() <= audit::AUDIT_RING_SIZE);
+
+    println!("test_audit() OK!");
+    Ok(())
+}
+
+fn test_umask() -> Result<()> {
+    use axfs::fops::{self, FileAttr, FilePerm};
+
+    let dirname = "/umask-dir";
+    println!("test umask {:?}:", dirname);
+    let old_mask = fs::set_umask(0o027);
+    fs::DirBuilder::new().mode(0o777).create(dirname)?;
+    assert_eq!(fs::metadata(dirname)?.permissions().bits(), 0o750);
+
+    let fname = "/umask-dir/new-file.txt";
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .mode(0o646)
+        .open(fname)?;
+    assert_eq!(fs::metadata(fname)?.permissions().bits(), 0o640);
+
+    // nodes created in a set-group-ID directory belong to its group
+    let mut opts = fops::OpenOptions::new();
+    opts.read(true);
+    let dir = fops::File::open(dirname, &opts)?;
+    let attr = dir.get_attr()?;
+    dir.set_attr(FileAttr::new(
+        attr.perm() | FilePerm::SET_GID,
+        attr.user_id(),
+        42,
+        attr.file_type(),
+        attr.size(),
+        attr.blocks(),
+    ))?;
+    fs::create_dir("/umask-dir/new-dir")?;
+    let attr = fops::File::open("/umask-dir/new-dir", &opts)?.get_attr()?;
+    assert_eq!(attr.group_id(), 42);
+    assert_eq!(attr.perm().bits(), 0o2750);
+
+    assert_eq!(fs::set_umask(old_mask), 0o027);
+    println!("test_umask() OK!");
+    Ok(())
+}
+
+#[cfg(feature = "permission")]
+fn test_sticky_bit() -> Result<()> {
+    use axtask::{Credentials, PrivCap};
+
+    fn user(uid: u32) -> Credentials {
+        Credentials {
+            ruid: uid,
+            euid: uid,
+            suid: uid,
+            rgid: uid,
+            egid: uid,
+            sgid: uid,
+            groups: Vec::new(),
+            cap_permitted: PrivCap::empty(),
+            cap_effective: PrivCap::empty(),
+        }
+    }
+
+    let fname = "/tmp/sticky.txt";
+    println!("test sticky bit {:?}:", fname);
+    let curr = axtask::current();
+    let old_mask = fs::set_umask(0);
+    curr.set_credentials(user(1000));
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .mode(0o666)
+        .open(fname)?;
+
+    // others may write the file, but not remove or rename it in `/tmp`
+    curr.set_credentials(user(1001));
+    fs::write(fname, "written by another user")?;
+    assert_err!(fs::remove_file(fname), PermissionDenied);
+    assert_err!(fs::rename(fname, "/tmp/moved.txt"), PermissionDenied);
+    assert!(fs::metadata(fname).is_ok());
+
+    // the owner may
+    curr.set_credentials(user(1000));
+    fs::rename(fname, "/tmp/moved.txt")?;
+    fs::remove_file("/tmp/moved.txt")?;
+
+    curr.set_credentials(Credentials::root());
+    fs::set_umask(old_mask);
+    println!("test_sticky_bit() OK!");
+    Ok(())
+}
+
+/// The superuser may read and write any file, but execute only the files
+/// someone may execute, and search any directory.
+#[cfg(feature = "permission")]
+fn test_dac_override() -> Result<()> {
+    use axfs::fops;
+
+    println!("test DAC override:");
+    let old_mask = fs::set_umask(0);
+    fs::create_dir("/tmp/private-dir")?;
+    for (fname, mode) in [("/tmp/private.txt", 0), ("/tmp/other-exec", 0o001)] {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .mode(mode)
+            .open(fname)?;
+    }
+    let mut opts = fops::OpenOptions::new();
+    opts.read(true);
+    opts.write(true);
+    fops::File::open("/tmp/private.txt", &opts)?;
+    opts.execute(true);
+    assert_err!(
+        fops::File::open("/tmp/private.txt", &opts),
+        PermissionDenied
+    );
+    fops::File::open("/tmp/other-exec", &opts)?;
+
+    let dir = fops::File::open("/tmp/private-dir", &fops::OpenOptions::new())?;
+    let attr = dir.get_attr()?;
+    dir.set_attr(fops::FileAttr::new(
+        fops::FilePerm::empty(),
+        attr.user_id(),
+        attr.group_id(),
+        attr.file_type(),
+        attr.size(),
+        attr.blocks(),
+    ))?;
+    fs::write("/tmp/private-dir/file.txt", "searched")?;
+
+    fs::remove_file("/tmp/private-dir/file.txt")?;
+    fs::remove_dir("/tmp/private-dir")?;
+    fs::remove_file("/tmp/private.txt")?;
+    fs::remove_file("/tmp/other-exec")?;
+    fs::set_umask(old_mask);
+    println!("test_dac_override() OK!");
+    Ok(())
+}
+
 pub fn test_all() {
     test_read_write_file().expect("test_read_write_file() failed");
     test_read_dir().expect("test_read_dir() failed");
     test_file_permission().expect("test_file_permission() failed");
     test_create_file_dir().expect("test_create_file_dir() failed");
     test_remove_file_dir().expect("test_remove_file_dir() failed");
+    test_rename().expect("test_rename() failed");
     test_devfs_ramfs().expect("test_devfs_ramfs() failed");
+    test_statfs_open_file().expect("test_statfs_open_file() failed");
+    test_notify().expect("test_notify() failed");
+    test_audit().expect("test_audit() failed");
+    test_umask().expect("test_umask() failed");
+    #[cfg(feature = "permission")]
+    test_sticky_bit().expect("test_sticky_bit() failed");
+    #[cfg(feature = "permission")]
+    test_dac_override().expect("test_dac_override() failed");
 }
@@ -8,6 +8,7 @@ use memory_addr::{PhysAddr, VirtAddr};
 use tock_registers::interfaces::{Readable, Writeable};
 
 pub use self::context::{FpState, TaskContext, TrapFrame};
+pub(crate) use self::trap::init_exception_stack;
 
 /// Allows the current CPU to respond to interrupts.
 #[inline]
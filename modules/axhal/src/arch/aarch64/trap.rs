@@ -1,7 +1,8 @@
 use core::arch::global_asm;
 
-use aarch64_cpu::registers::{ESR_EL1, FAR_EL1};
-use tock_registers::interfaces::Readable;
+use aarch64_cpu::registers::{ESR_EL1, FAR_EL1, SP_EL0};
+use page_table_entry::MappingFlags;
+use tock_registers::interfaces::{Readable, Writeable};
 
 use super::TrapFrame;
 
@@ -27,6 +28,19 @@ enum TrapSource {
     LowerAArch32 = 3,
 }
 
+/// Puts the top of the exception stack of the current CPU in `SP_EL0`, which
+/// the kernel switches to if its stack overflows (see `trap.S`). It must be
+/// done before any exception in EL1, and after each exception from EL0, which
+/// puts the user sp in `SP_EL0`.
+pub(crate) fn init_exception_stack() {
+    SP_EL0.set(crate::trap::exception_stack_top() as u64);
+}
+
+#[inline]
+fn from_el0(tf: &TrapFrame) -> bool {
+    tf.spsr & 0xf == 0 // SPSR_EL1.M: EL0t
+}
+
 #[no_mangle]
 fn invalid_exception(tf: &TrapFrame, kind: TrapKind, source: TrapSource) {
     panic!(
@@ -35,8 +49,48 @@ fn invalid_exception(tf: &TrapFrame, kind: TrapKind, source: TrapSource) {
     );
 }
 
+fn handle_page_fault(tf: &TrapFrame, is_instr: bool, is_user: bool) {
+    let iss = ESR_EL1.read(ESR_EL1::ISS);
+    let vaddr = FAR_EL1.get() as usize;
+    let access_flags = if is_instr {
+        MappingFlags::EXECUTE
+    } else if iss & (1 << 6) != 0 {
+        // WnR: the abort was caused by a write.
+        MappingFlags::WRITE
+    } else {
+        MappingFlags::READ
+    };
+    if crate::trap::handle_page_fault_extern(vaddr.into(), access_flags, is_user) {
+        return;
+    }
+    if is_user {
+        warn!(
+            "EL0 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}",
+            tf.elr, vaddr, iss
+        );
+    } else {
+        panic!(
+            "EL1 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}:\n{:#x?}",
+            tf.elr, vaddr, iss, tf,
+        );
+    }
+}
+
+#[no_mangle]
+fn handle_kernel_stack_overflow(tf: &TrapFrame) -> ! {
+    let vaddr = FAR_EL1.get() as usize;
+    crate::trap::handle_page_fault_extern(vaddr.into(), MappingFlags::WRITE, false);
+    panic!(
+        "Kernel stack overflow @ {:#x}, FAR={:#x}:\n{:#x?}",
+        tf.elr, vaddr, tf,
+    );
+}
+
 #[no_mangle]
 fn handle_sync_exception(tf: &mut TrapFrame) {
+    if from_el0(tf) {
+        init_exception_stack();
+    }
     let esr = ESR_EL1.extract();
     match esr.read_as_enum(ESR_EL1::EC) {
         Some(ESR_EL1::EC::Value::Brk64) => {
@@ -47,27 +101,10 @@ fn handle_sync_exception(tf: &mut TrapFrame) {
         Some(ESR_EL1::EC::Value::SVC64) => {
             warn!("No supervisor call is supported currently!");
         }
-        Some(ESR_EL1::EC::Value::DataAbortLowerEL)
-        | Some(ESR_EL1::EC::Value::InstrAbortLowerEL) => {
-            let iss = esr.read(ESR_EL1::ISS);
-            warn!(
-                "EL0 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}",
-                tf.elr,
-                FAR_EL1.get(),
-                iss
-            );
-        }
-        Some(ESR_EL1::EC::Value::DataAbortCurrentEL)
-        | Some(ESR_EL1::EC::Value::InstrAbortCurrentEL) => {
-            let iss = esr.read(ESR_EL1::ISS);
-            panic!(
-                "EL1 Page Fault @ {:#x}, FAR={:#x}, ISS={:#x}:\n{:#x?}",
-                tf.elr,
-                FAR_EL1.get(),
-                iss,
-                tf,
-            );
-        }
+        Some(ESR_EL1::EC::Value::DataAbortLowerEL) => handle_page_fault(tf, false, true),
+        Some(ESR_EL1::EC::Value::InstrAbortLowerEL) => handle_page_fault(tf, true, true),
+        Some(ESR_EL1::EC::Value::DataAbortCurrentEL) => handle_page_fault(tf, false, false),
+        Some(ESR_EL1::EC::Value::InstrAbortCurrentEL) => handle_page_fault(tf, true, false),
         _ => {
             panic!(
                 "Unhandled synchronous exception @ {:#x}: ESR={:#x} (EC {:#08b}, ISS {:#x})",
@@ -81,6 +118,9 @@ fn handle_sync_exception(tf: &mut TrapFrame) {
 }
 
 #[no_mangle]
-fn handle_irq_exception(_tf: &TrapFrame) {
+fn handle_irq_exception(tf: &TrapFrame) {
+    if from_el0(tf) {
+        init_exception_stack();
+    }
     crate::trap::handle_irq_extern(0)
 }
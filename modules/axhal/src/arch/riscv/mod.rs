@@ -9,6 +9,7 @@ use riscv::asm;
 use riscv::register::{satp, sstatus, stvec};
 
 pub use self::context::{GeneralRegisters, TaskContext, TrapFrame};
+pub(crate) use self::trap::init_exception_stack;
 
 /// Allows the current CPU to respond to interrupts.
 #[inline]
@@ -1,4 +1,6 @@
+use page_table_entry::MappingFlags;
 use riscv::register::scause::{self, Exception as E, Trap};
+use riscv::register::stval;
 
 use super::TrapFrame;
 
@@ -14,11 +16,52 @@ fn handle_breakpoint(sepc: &mut usize) {
     *sepc += 2
 }
 
+fn handle_page_fault(tf: &TrapFrame, access_flags: MappingFlags, from_user: bool) {
+    let vaddr = stval::read();
+    if !crate::trap::handle_page_fault_extern(vaddr.into(), access_flags, from_user) {
+        panic!(
+            "Unhandled {} Page Fault @ {:#x}, fault_vaddr={:#x} ({:?}):\n{:#x?}",
+            if from_user { "User" } else { "Supervisor" },
+            tf.sepc,
+            vaddr,
+            access_flags,
+            tf,
+        );
+    }
+}
+
+/// Sets up the exception stack of the current CPU, which the trap handler
+/// switches to if the kernel stack overflows (see `trap.S`). It must be done
+/// before any trap in S mode, and after each trap from U mode, which puts the
+/// user sp in `sscratch`.
+pub(crate) fn init_exception_stack() {
+    let top = crate::trap::exception_stack_top();
+    unsafe { core::arch::asm!("csrw sscratch, {}", in(reg) top) };
+}
+
 #[no_mangle]
-fn riscv_trap_handler(tf: &mut TrapFrame, _from_user: bool) {
+fn riscv_stack_overflow_handler(tf: &TrapFrame) -> ! {
+    let vaddr = stval::read();
+    crate::trap::handle_page_fault_extern(vaddr.into(), MappingFlags::WRITE, false);
+    panic!(
+        "Kernel stack overflow @ {:#x}, fault_vaddr={:#x}:\n{:#x?}",
+        tf.sepc, vaddr, tf,
+    );
+}
+
+#[no_mangle]
+fn riscv_trap_handler(tf: &mut TrapFrame, from_user: bool) {
+    if from_user {
+        init_exception_stack();
+    }
     let scause = scause::read();
     match scause.cause() {
         Trap::Exception(E::Breakpoint) => handle_breakpoint(&mut tf.sepc),
+        Trap::Exception(E::LoadPageFault) => handle_page_fault(tf, MappingFlags::READ, from_user),
+        Trap::Exception(E::StorePageFault) => handle_page_fault(tf, MappingFlags::WRITE, from_user),
+        Trap::Exception(E::InstructionPageFault) => {
+            handle_page_fault(tf, MappingFlags::EXECUTE, from_user)
+        }
         Trap::Interrupt(_) => crate::trap::handle_irq_extern(scause.bits()),
         _ => {
             panic!(
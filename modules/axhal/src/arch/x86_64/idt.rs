@@ -1,5 +1,6 @@
 use core::fmt;
 
+use x86::irq::DOUBLE_FAULT_VECTOR;
 use x86_64::addr::VirtAddr;
 use x86_64::structures::idt::{Entry, HandlerFunc, InterruptDescriptorTable};
 use x86_64::structures::DescriptorTablePointer;
@@ -13,6 +14,10 @@ pub struct IdtStruct {
 }
 
 impl IdtStruct {
+    /// Index of the interrupt stack table (IST) entry in the TSS, used as the
+    /// stack of the double fault handler.
+    pub const DOUBLE_FAULT_IST_INDEX: u16 = 0;
+
     /// Constructs a new IDT struct that filled with entries from
     /// `trap_handler_table`.
     #[allow(clippy::new_without_default)]
@@ -32,7 +37,12 @@ impl IdtStruct {
             )
         };
         for i in 0..NUM_INT {
-            entries[i].set_handler_fn(unsafe { core::mem::transmute(ENTRIES[i]) });
+            let opts = entries[i].set_handler_fn(unsafe { core::mem::transmute(ENTRIES[i]) });
+            if i == DOUBLE_FAULT_VECTOR as usize {
+                // a kernel stack overflow ends up with a double fault, whose
+                // handler must not run on the overflowed stack.
+                unsafe { opts.set_stack_index(Self::DOUBLE_FAULT_IST_INDEX) };
+            }
         }
         idt
     }
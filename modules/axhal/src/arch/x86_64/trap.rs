@@ -1,3 +1,4 @@
+use page_table_entry::MappingFlags;
 use x86::{controlregs::cr2, irq::*};
 
 use super::context::TrapFrame;
@@ -7,28 +8,46 @@ core::arch::global_asm!(include_str!("trap.S"));
 const IRQ_VECTOR_START: u8 = 0x20;
 const IRQ_VECTOR_END: u8 = 0xff;
 
+fn handle_page_fault(tf: &TrapFrame) {
+    let vaddr = unsafe { cr2() };
+    let access_flags = if tf.error_code & (1 << 4) != 0 {
+        MappingFlags::EXECUTE
+    } else if tf.error_code & (1 << 1) != 0 {
+        MappingFlags::WRITE
+    } else {
+        MappingFlags::READ
+    };
+    if crate::trap::handle_page_fault_extern(vaddr.into(), access_flags, tf.is_user()) {
+        return;
+    }
+    if tf.is_user() {
+        warn!(
+            "User #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}",
+            tf.rip, vaddr, tf.error_code,
+        );
+    } else {
+        panic!(
+            "Kernel #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}:\n{:#x?}",
+            tf.rip, vaddr, tf.error_code, tf,
+        );
+    }
+}
+
+fn handle_double_fault(tf: &TrapFrame) {
+    // Most likely a page fault on a kernel stack overflow, which faults again
+    // in pushing the trap frame. The handler runs on the exception stack, and
+    // the page fault handler does not return if it terminates the task.
+    let vaddr = unsafe { cr2() };
+    crate::trap::handle_page_fault_extern(vaddr.into(), MappingFlags::WRITE, false);
+    panic!("#DF @ {:#x}, fault_vaddr={:#x}:\n{:#x?}", tf.rip, vaddr, tf);
+}
+
 #[no_mangle]
 fn x86_trap_handler(tf: &TrapFrame) {
     match tf.vector as u8 {
-        PAGE_FAULT_VECTOR => {
-            if tf.is_user() {
-                warn!(
-                    "User #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}",
-                    tf.rip,
-                    unsafe { cr2() },
-                    tf.error_code,
-                );
-            } else {
-                panic!(
-                    "Kernel #PF @ {:#x}, fault_vaddr={:#x}, error_code={:#x}:\n{:#x?}",
-                    tf.rip,
-                    unsafe { cr2() },
-                    tf.error_code,
-                    tf,
-                );
-            }
-        }
+        PAGE_FAULT_VECTOR => handle_page_fault(tf),
         BREAKPOINT_VECTOR => debug!("#BP @ {:#x} ", tf.rip),
+        DOUBLE_FAULT_VECTOR => handle_double_fault(tf),
         GENERAL_PROTECTION_FAULT_VECTOR => {
             panic!(
                 "#GP @ {:#x}, error_code={:#x}:\n{:#x?}",
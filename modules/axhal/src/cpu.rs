@@ -40,10 +40,12 @@ pub fn current_task_ptr<T>() -> *const T {
         CURRENT_TASK_PTR.read_current_raw() as _
     }
     #[cfg(target_arch = "aarch64")]
-    {
-        // on ARM64, we use `SP_EL0` to store the task pointer.
-        use tock_registers::interfaces::Readable;
-        aarch64_cpu::registers::SP_EL0.get() as _
+    unsafe {
+        // on ARM64, we use `TPIDRRO_EL0` to store the task pointer, as `SP_EL0`
+        // holds the top of the exception stack.
+        let ptr: usize;
+        core::arch::asm!("mrs {}, tpidrro_el0", out(reg) ptr);
+        ptr as _
     }
 }
 
@@ -68,8 +70,7 @@ pub unsafe fn set_current_task_ptr<T>(ptr: *const T) {
     }
     #[cfg(target_arch = "aarch64")]
     {
-        use tock_registers::interfaces::Writeable;
-        aarch64_cpu::registers::SP_EL0.set(ptr as u64)
+        core::arch::asm!("msr tpidrro_el0, {}", in(reg) ptr as usize)
     }
 }
 
@@ -81,6 +82,12 @@ pub(crate) fn init_primary(cpu_id: usize) {
         CPU_ID.write_current_raw(cpu_id);
         IS_BSP.write_current_raw(true);
     }
+    #[cfg(any(
+        target_arch = "riscv32",
+        target_arch = "riscv64",
+        target_arch = "aarch64"
+    ))]
+    crate::arch::init_exception_stack();
 }
 
 #[allow(dead_code)]
@@ -90,4 +97,10 @@ pub(crate) fn init_secondary(cpu_id: usize) {
         CPU_ID.write_current_raw(cpu_id);
         IS_BSP.write_current_raw(false);
     }
+    #[cfg(any(
+        target_arch = "riscv32",
+        target_arch = "riscv64",
+        target_arch = "aarch64"
+    ))]
+    crate::arch::init_exception_stack();
 }
@@ -17,6 +17,7 @@ static mut BOOT_PT_L1: [A64PTE; 512] = [A64PTE::empty(); 512];
 unsafe fn switch_to_el1() {
     SPSel.write(SPSel::SP::ELx);
     SP_EL0.set(0);
+    core::arch::asm!("msr tpidrro_el0, xzr"); // no current task yet
     let current_el = CurrentEL.read(CurrentEL::EL);
     if current_el >= 2 {
         if current_el == 3 {
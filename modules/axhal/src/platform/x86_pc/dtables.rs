@@ -2,6 +2,7 @@
 
 use crate::arch::{GdtStruct, IdtStruct, TaskStateSegment};
 use lazy_init::LazyInit;
+use x86_64::VirtAddr;
 
 static IDT: LazyInit<IdtStruct> = LazyInit::new();
 
@@ -16,7 +17,12 @@ fn init_percpu() {
         IDT.load();
         let tss = TSS.current_ref_mut_raw();
         let gdt = GDT.current_ref_mut_raw();
-        tss.init_by(TaskStateSegment::new());
+        let mut new_tss = TaskStateSegment::new();
+        // the CPU switches to the exception stack even if the current stack
+        // overflows
+        new_tss.interrupt_stack_table[IdtStruct::DOUBLE_FAULT_IST_INDEX as usize] =
+            VirtAddr::new(crate::trap::exception_stack_top() as u64);
+        tss.init_by(new_tss);
         gdt.init_by(GdtStruct::new(tss));
         gdt.load();
         gdt.load_tss();
@@ -1,6 +1,10 @@
 //! Trap handling.
 
 use crate_interface::{call_interface, def_interface};
+use memory_addr::VirtAddr;
+
+#[doc(no_inline)]
+pub use page_table_entry::MappingFlags;
 
 /// Trap handler interface.
 ///
@@ -13,7 +17,28 @@ use crate_interface::{call_interface, def_interface};
 pub trait TrapHandler {
     /// Handles interrupt requests for the given IRQ number.
     fn handle_irq(irq_num: usize);
-    // more e.g.: handle_page_fault();
+    /// Handles a page fault at `vaddr` caused by an access of `access_flags`.
+    ///
+    /// Returns `true` if the fault is resolved and the faulting instruction
+    /// can be retried.
+    fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool) -> bool;
+}
+
+/// Size of the per-CPU stack that the trap handler switches to when the kernel
+/// stack overflows, so that it can still report the overflow.
+const EXCEPTION_STACK_SIZE: usize = 0x4000;
+
+#[repr(align(16))]
+struct ExceptionStacks([[u8; EXCEPTION_STACK_SIZE]; axconfig::SMP]);
+
+static mut EXCEPTION_STACKS: ExceptionStacks =
+    ExceptionStacks([[0; EXCEPTION_STACK_SIZE]; axconfig::SMP]);
+
+/// Returns the top of the exception stack of the current CPU.
+#[allow(dead_code)]
+pub(crate) fn exception_stack_top() -> usize {
+    let stacks = unsafe { core::ptr::addr_of!(EXCEPTION_STACKS) } as usize;
+    stacks + (crate::cpu::this_cpu_id() + 1) * EXCEPTION_STACK_SIZE
 }
 
 /// Call the external IRQ handler.
@@ -21,3 +46,13 @@ pub trait TrapHandler {
 pub(crate) fn handle_irq_extern(irq_num: usize) {
     call_interface!(TrapHandler::handle_irq, irq_num);
 }
+
+/// Call the external page fault handler.
+#[allow(dead_code)]
+pub(crate) fn handle_page_fault_extern(
+    vaddr: VirtAddr,
+    access_flags: MappingFlags,
+    is_user: bool,
+) -> bool {
+    call_interface!(TrapHandler::handle_page_fault, vaddr, access_flags, is_user)
+}
@@ -0,0 +1,125 @@
+//! Kernel stacks with guard pages.
+//!
+//! Each stack is mapped in a dedicated region of the kernel address space,
+//! with an unmapped guard page right below it, so that an overflowing task
+//! triggers a page fault instead of silently corrupting the memory nearby.
+//!
+//! The fault is handled on the per-CPU exception stack of `axhal`, as pushing
+//! the trap frame onto the overflowed stack would fault again.
+
+use alloc::collections::BTreeMap;
+
+use axerrno::{ax_err, AxError, AxResult};
+use axhal::mem::{phys_to_virt, virt_to_phys, PhysAddr, VirtAddr, PAGE_SIZE_4K};
+use axhal::paging::{MappingFlags, PageSize};
+use memory_addr::{align_up_4k, is_aligned_4k};
+use spinlock::SpinNoIrq;
+
+use crate::kernel_page_table;
+
+/// Start address of the region where kernel stacks are placed.
+pub const KSTACK_REGION_START: usize = axconfig::PHYS_VIRT_OFFSET + 0x30_0000_0000;
+/// Size of the region where kernel stacks are placed.
+pub const KSTACK_REGION_SIZE: usize = 0x8_0000_0000;
+/// Size of the unmapped guard page below each kernel stack.
+pub const KSTACK_GUARD_SIZE: usize = PAGE_SIZE_4K;
+
+const KSTACK_REGION_END: usize = KSTACK_REGION_START + KSTACK_REGION_SIZE;
+
+/// Size of the slot in the region taken by a stack of `size` bytes.
+const fn slot_size(size: usize) -> usize {
+    KSTACK_GUARD_SIZE + size
+}
+
+/// Slot start of each allocated stack, mapped to the stack size.
+static KSTACKS: SpinNoIrq<BTreeMap<usize, usize>> = SpinNoIrq::new(BTreeMap::new());
+
+/// Finds a free range of `len` bytes in the region.
+fn find_free(stacks: &BTreeMap<usize, usize>, len: usize) -> Option<usize> {
+    let mut start = KSTACK_REGION_START;
+    for (&s, &size) in stacks.iter() {
+        if s - start >= len {
+            return Some(start);
+        }
+        start = s + slot_size(size);
+    }
+    (KSTACK_REGION_END - start >= len).then_some(start)
+}
+
+/// Maps `[start, start + size)` to newly allocated frames. The pages mapped
+/// before a failure are left for [`unmap_pages`] to clean up.
+fn map_pages(start: usize, size: usize) -> AxResult {
+    let flags = MappingFlags::READ | MappingFlags::WRITE;
+    for vaddr in (start..start + size).step_by(PAGE_SIZE_4K) {
+        let frame = axalloc::global_allocator()
+            .alloc_pages(1, PAGE_SIZE_4K)
+            .map_err(|_| AxError::NoMemory)?;
+        let paddr = virt_to_phys(frame.into());
+        let res = kernel_page_table()
+            .lock()
+            .map(vaddr.into(), paddr, PageSize::Size4K, flags);
+        if let Err(e) = res {
+            warn!("kstack: failed to map page {:#x}: {:?}", vaddr, e);
+            dealloc_frame(paddr);
+            return ax_err!(NoMemory);
+        }
+        axhal::arch::flush_tlb(Some(vaddr.into()));
+    }
+    Ok(())
+}
+
+fn unmap_pages(start: usize, size: usize) {
+    for vaddr in (start..start + size).step_by(PAGE_SIZE_4K) {
+        let res = kernel_page_table().lock().unmap(vaddr.into());
+        if let Ok((paddr, _)) = res {
+            axhal::arch::flush_tlb(Some(vaddr.into()));
+            dealloc_frame(paddr);
+        }
+    }
+}
+
+fn dealloc_frame(paddr: PhysAddr) {
+    axalloc::global_allocator().dealloc_pages(phys_to_virt(paddr).as_usize(), 1);
+}
+
+/// Allocates a kernel stack of `size` bytes with a guard page below it, and
+/// returns its lowest address.
+pub fn alloc_kernel_stack(size: usize) -> AxResult<VirtAddr> {
+    if size == 0 {
+        return ax_err!(InvalidInput);
+    }
+    let size = align_up_4k(size);
+    let start = {
+        let mut stacks = KSTACKS.lock();
+        let start = find_free(&stacks, slot_size(size)).ok_or(AxError::NoMemory)?;
+        stacks.insert(start, size);
+        start
+    };
+
+    let bottom = start + KSTACK_GUARD_SIZE;
+    if let Err(e) = map_pages(bottom, size) {
+        unmap_pages(start, slot_size(size));
+        KSTACKS.lock().remove(&start);
+        return Err(e);
+    }
+    debug!("kstack: [{:#x}, {:#x})", bottom, bottom + size);
+    Ok(bottom.into())
+}
+
+/// Frees a kernel stack allocated by [`alloc_kernel_stack`], given its lowest
+/// address.
+///
+/// # Panics
+///
+/// Panics if `bottom` is not the lowest address of an allocated stack.
+pub fn dealloc_kernel_stack(bottom: VirtAddr) {
+    let bottom = bottom.as_usize();
+    assert!(is_aligned_4k(bottom));
+    let start = bottom - KSTACK_GUARD_SIZE;
+    let size = KSTACKS
+        .lock()
+        .remove(&start)
+        .expect("kstack: freeing an unallocated stack");
+    unmap_pages(start, slot_size(size));
+    debug!("kstack: free [{:#x}, {:#x})", bottom, bottom + size);
+}
@@ -0,0 +1,62 @@
+//! Virtual memory management of [ArceOS](https://github.com/rcore-os/arceos).
+//!
+//! It owns the kernel page table, and manages a dedicated region of the
+//! kernel address space for [`mmap`], whose pages are allocated lazily when
+//! they are first accessed. Kernel stacks of tasks are also allocated here by
+//! [`alloc_kernel_stack`], with guard pages to catch stack overflows.
+//!
+//! Since ArceOS runs all tasks in a single address space, every mapping is
+//! visible to every task.
+
+#![cfg_attr(not(test), no_std)]
+
+#[macro_use]
+extern crate log;
+extern crate alloc;
+
+mod kstack;
+mod mmap;
+
+pub use self::kstack::{
+    alloc_kernel_stack, dealloc_kernel_stack, KSTACK_GUARD_SIZE, KSTACK_REGION_SIZE,
+    KSTACK_REGION_START,
+};
+pub use self::mmap::{
+    handle_page_fault, mmap, mprotect, msync, munmap, read_file, write_file, MmapBackend,
+    MMAP_REGION_SIZE, MMAP_REGION_START,
+};
+
+use axhal::mem::{memory_regions, phys_to_virt};
+use axhal::paging::{PageTable, PagingResult};
+use lazy_init::LazyInit;
+use spinlock::SpinNoIrq;
+
+static KERNEL_PAGE_TABLE: LazyInit<SpinNoIrq<PageTable>> = LazyInit::new();
+
+/// Creates the kernel page table on the primary CPU and switches to it.
+///
+/// All physical memory regions are mapped to their linear addresses. On
+/// secondary CPUs, it only switches to the page table created by the primary
+/// CPU.
+pub fn init_kernel_page_table() -> PagingResult {
+    if axhal::cpu::this_cpu_is_bsp() {
+        let mut kernel_page_table = PageTable::try_new()?;
+        for r in memory_regions() {
+            kernel_page_table.map_region(
+                phys_to_virt(r.paddr),
+                r.paddr,
+                r.size,
+                r.flags.into(),
+                true,
+            )?;
+        }
+        KERNEL_PAGE_TABLE.init_by(SpinNoIrq::new(kernel_page_table));
+    }
+
+    unsafe { axhal::arch::write_page_table_root(KERNEL_PAGE_TABLE.lock().root_paddr()) };
+    Ok(())
+}
+
+fn kernel_page_table() -> &'static SpinNoIrq<PageTable> {
+    &KERNEL_PAGE_TABLE
+}
@@ -0,0 +1,931 @@
+//! Lazily allocated memory mappings.
+//!
+//! [`mmap`] only reserves a range in the mmap region. Physical frames are
+//! allocated in [`handle_page_fault`] on the first access to each page, and
+//! filled from the backing file for file-backed mappings.
+//!
+//! All shared mappings of a file map the same frame for each page of it, kept
+//! in the page cache of the file while it has shared mappings. Changes to them
+//! are written back to the file by [`msync`] and [`munmap`]. Reads and writes
+//! of the file by [`read_file`] and [`write_file`] go through the cached pages,
+//! so they see the changes not written back yet, and the mappings see their
+//! data. Private mappings have their own copies, and changes to them are
+//! discarded.
+//!
+//! Filling a page takes the locks of the backing file inside the filesystem.
+//! [`read_file`] and [`write_file`] never access the given buffer with such
+//! locks held, so it may be mapped memory, even of the same file.
+
+use alloc::collections::{btree_map::Entry, BTreeMap};
+use alloc::sync::{Arc, Weak};
+use alloc::{vec, vec::Vec};
+
+use axerrno::{ax_err, AxError, AxResult};
+use axhal::mem::{phys_to_virt, virt_to_phys, PhysAddr, VirtAddr, PAGE_SIZE_4K};
+use axhal::paging::{MappingFlags, PageSize, PagingError};
+use memory_addr::{align_down_4k, align_up_4k, is_aligned_4k};
+use spinlock::SpinNoIrq;
+
+use crate::kernel_page_table;
+
+/// Start address of the region where [`mmap`] places mappings.
+pub const MMAP_REGION_START: usize = axconfig::PHYS_VIRT_OFFSET + 0x20_0000_0000;
+/// Size of the region where [`mmap`] places mappings.
+pub const MMAP_REGION_SIZE: usize = 0x10_0000_0000;
+
+const MMAP_REGION_END: usize = MMAP_REGION_START + MMAP_REGION_SIZE;
+
+/// The object backing a file mapping.
+pub trait MmapBackend: Send + Sync {
+    /// Identifies the file. Shared mappings of backends with the same ID share
+    /// their pages.
+    fn file_id(&self) -> (usize, u64);
+    /// Reads data at `offset` into `buf`, returns the number of bytes read.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize>;
+    /// Writes `buf` at `offset`, returns the number of bytes written.
+    fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize>;
+    /// Returns the current size of the object.
+    fn size(&self) -> AxResult<u64>;
+    /// Whether changes of shared mappings can be written back.
+    fn writable(&self) -> bool;
+}
+
+/// The pages of a file mapped by its shared mappings.
+struct PageCache {
+    backend: Arc<dyn MmapBackend>,
+    pages: SpinNoIrq<CachedPages>,
+}
+
+struct CachedPages {
+    /// The frame of each cached page, by the page index in the file.
+    frames: BTreeMap<u64, PhysAddr>,
+    /// Number of writes to the file in progress.
+    writing: usize,
+    /// Number of writes to the file finished.
+    written: u64,
+}
+
+/// The page caches of the files with shared mappings, by file ID.
+static PAGE_CACHES: SpinNoIrq<BTreeMap<(usize, u64), Weak<PageCache>>> =
+    SpinNoIrq::new(BTreeMap::new());
+
+impl PageCache {
+    /// Returns the page cache of the file of `backend`, creating it if the
+    /// file has none.
+    fn of(backend: &Arc<dyn MmapBackend>) -> Arc<Self> {
+        let mut caches = PAGE_CACHES.lock();
+        let id = backend.file_id();
+        if let Some(cache) = caches.get(&id).and_then(Weak::upgrade) {
+            return cache;
+        }
+        let cache = Arc::new(Self {
+            backend: backend.clone(),
+            pages: SpinNoIrq::new(CachedPages {
+                frames: BTreeMap::new(),
+                writing: 0,
+                written: 0,
+            }),
+        });
+        caches.insert(id, Arc::downgrade(&cache));
+        cache
+    }
+
+    /// Returns the page cache of the file `id`, if it has one.
+    fn lookup(id: (usize, u64)) -> Option<Arc<Self>> {
+        PAGE_CACHES.lock().get(&id).and_then(Weak::upgrade)
+    }
+
+    /// Returns the frame of the page at `offset`, reading the page from the
+    /// file if it is not cached.
+    fn get_or_fill(&self, offset: u64) -> AxResult<PhysAddr> {
+        let index = offset / PAGE_SIZE_4K as u64;
+        loop {
+            let written = {
+                let pages = self.pages.lock();
+                if let Some(&frame) = pages.frames.get(&index) {
+                    return Ok(frame);
+                }
+                (pages.writing == 0).then_some(pages.written)
+            };
+            let frame = alloc_zeroed_frame()?;
+            // Read the file with no lock held. The page is read again if the
+            // file is written meanwhile, the read may have missed the data.
+            if let Err(e) = fill_page(self.backend.as_ref(), offset, frame_data(frame)) {
+                dealloc_frame(frame);
+                return Err(e);
+            }
+            let mut pages = self.pages.lock();
+            let unchanged = pages.writing == 0 && written == Some(pages.written);
+            match pages.frames.entry(index) {
+                Entry::Occupied(e) => {
+                    let cached = *e.get();
+                    drop(pages);
+                    dealloc_frame(frame);
+                    return Ok(cached);
+                }
+                Entry::Vacant(e) if unchanged => return Ok(*e.insert(frame)),
+                Entry::Vacant(_) => {
+                    drop(pages);
+                    dealloc_frame(frame);
+                }
+            }
+        }
+    }
+
+    /// Copies the cached data at `offset` into `buf`, which must not cross a
+    /// page boundary. Nothing is copied if the page is not cached.
+    fn read_cached(&self, offset: u64, buf: &mut [u8]) {
+        let index = offset / PAGE_SIZE_4K as u64;
+        let start = offset as usize % PAGE_SIZE_4K;
+        if let Some(&frame) = self.pages.lock().frames.get(&index) {
+            buf.copy_from_slice(&frame_data(frame)[start..start + buf.len()]);
+        }
+    }
+
+    /// Writes `buf`, which must not cross a page boundary, at `offset` of the
+    /// file by `backend`, and the bytes written into the page if it is cached.
+    fn write_through(&self, backend: &dyn MmapBackend, offset: u64, buf: &[u8]) -> AxResult<usize> {
+        let index = offset / PAGE_SIZE_4K as u64;
+        let start = offset as usize % PAGE_SIZE_4K;
+        // pages filled during the write may miss it, see `get_or_fill`
+        self.pages.lock().writing += 1;
+        let res = backend.write_at(offset, buf);
+        let mut pages = self.pages.lock();
+        if let (Ok(n), Some(&frame)) = (&res, pages.frames.get(&index)) {
+            frame_data(frame)[start..start + n].copy_from_slice(&buf[..*n]);
+        }
+        pages.writing -= 1;
+        pages.written += 1;
+        res
+    }
+
+    /// Writes the page at `offset` back to the file by `backend`, if it is
+    /// cached.
+    fn write_back(&self, backend: &dyn MmapBackend, offset: u64) -> AxResult {
+        let index = offset / PAGE_SIZE_4K as u64;
+        let frame = self.pages.lock().frames.get(&index).copied();
+        match frame {
+            Some(frame) => write_back_page(backend, offset, frame_data(frame)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for PageCache {
+    /// Frees the cached pages after the last shared mapping of the file is
+    /// removed, which wrote them back.
+    fn drop(&mut self) {
+        for &frame in self.pages.get_mut().frames.values() {
+            dealloc_frame(frame);
+        }
+        let mut caches = PAGE_CACHES.lock();
+        let id = self.backend.file_id();
+        if caches
+            .get(&id)
+            .is_some_and(|cache| cache.strong_count() == 0)
+        {
+            caches.remove(&id);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct MmapArea {
+    size: usize,
+    flags: MappingFlags,
+    shared: bool,
+    /// The backing object and the offset of the area start in it.
+    backend: Option<(Arc<dyn MmapBackend>, u64)>,
+    /// The page cache of the backing object, if the mapping is shared.
+    cache: Option<Arc<PageCache>>,
+}
+
+impl MmapArea {
+    /// Shrinks the area to `off` bytes, and returns the removed upper part.
+    fn split_off(&mut self, off: usize) -> Self {
+        let mut upper = self.clone();
+        upper.size = self.size - off;
+        if let Some((_, offset)) = &mut upper.backend {
+            *offset += off as u64;
+        }
+        self.size = off;
+        upper
+    }
+
+    /// The page cache and file offset of the page at `off` from the area
+    /// start, if the area is a shared file mapping.
+    fn cached_page(&self, off: usize) -> Option<(&Arc<PageCache>, u64)> {
+        match (&self.cache, &self.backend) {
+            (Some(cache), Some((_, offset))) => Some((cache, offset + off as u64)),
+            _ => None,
+        }
+    }
+
+    /// The page cache, backing object and file offset of the page at `off`
+    /// from the area start, if the area may have changed the page.
+    ///
+    /// Mappings by handles that cannot write the file cannot be written to,
+    /// so only mappings by writable handles write the pages back.
+    fn write_back_target(&self, off: usize) -> Option<WriteBackTarget> {
+        match (&self.cache, &self.backend) {
+            (Some(cache), Some((backend, offset))) if backend.writable() => {
+                Some((cache.clone(), backend.clone(), offset + off as u64))
+            }
+            _ => None,
+        }
+    }
+}
+
+type WriteBackTarget = (Arc<PageCache>, Arc<dyn MmapBackend>, u64);
+
+type AreaMap = BTreeMap<usize, MmapArea>;
+
+static AREAS: SpinNoIrq<AreaMap> = SpinNoIrq::new(BTreeMap::new());
+
+fn find_area(areas: &AreaMap, vaddr: usize) -> Option<(usize, &MmapArea)> {
+    match areas.range(..=vaddr).next_back() {
+        Some((&start, area)) if vaddr < start + area.size => Some((start, area)),
+        _ => None,
+    }
+}
+
+/// Splits the area containing `vaddr`, so that an area starts at `vaddr`.
+fn split_at(areas: &mut AreaMap, vaddr: usize) {
+    let upper = match areas.range_mut(..vaddr).next_back() {
+        Some((&start, area)) if vaddr < start + area.size => area.split_off(vaddr - start),
+        _ => return,
+    };
+    areas.insert(vaddr, upper);
+}
+
+/// Removes all areas in `[start, end)`, splitting the ones across the bounds.
+fn take_range(areas: &mut AreaMap, start: usize, end: usize) -> Vec<(usize, MmapArea)> {
+    split_at(areas, start);
+    split_at(areas, end);
+    let starts: Vec<usize> = areas.range(start..end).map(|(&s, _)| s).collect();
+    starts
+        .into_iter()
+        .map(|s| (s, areas.remove(&s).unwrap()))
+        .collect()
+}
+
+/// Whether `[start, end)` is fully covered by areas.
+fn is_covered(areas: &AreaMap, start: usize, end: usize) -> bool {
+    let first = find_area(areas, start).map_or(start, |(s, _)| s);
+    let mut cur = start;
+    for (&s, area) in areas.range(first..end) {
+        if s > cur {
+            return false;
+        }
+        cur = cur.max(s + area.size);
+    }
+    cur >= end
+}
+
+/// Returns the end of `[start, start + size)` if it is in the mmap region.
+fn region_end(start: usize, size: usize) -> Option<usize> {
+    start
+        .checked_add(size)
+        .filter(|&end| start >= MMAP_REGION_START && end <= MMAP_REGION_END)
+}
+
+fn is_free(areas: &AreaMap, start: usize, size: usize) -> bool {
+    match region_end(start, size) {
+        Some(end) => match areas.range(..end).next_back() {
+            Some((&s, area)) => s + area.size <= start,
+            None => true,
+        },
+        None => false,
+    }
+}
+
+/// Finds a free range of `size` bytes, trying `hint` first.
+fn find_free(areas: &AreaMap, hint: usize, size: usize) -> Option<usize> {
+    if hint != 0 && is_free(areas, hint, size) {
+        return Some(hint);
+    }
+    let mut start = MMAP_REGION_START;
+    for (&s, area) in areas.iter() {
+        if s - start >= size {
+            return Some(start);
+        }
+        start = s + area.size;
+    }
+    (MMAP_REGION_END - start >= size).then_some(start)
+}
+
+fn alloc_zeroed_frame() -> AxResult<PhysAddr> {
+    let vaddr = axalloc::global_allocator()
+        .alloc_pages(1, PAGE_SIZE_4K)
+        .map_err(|_| AxError::NoMemory)?;
+    unsafe { core::ptr::write_bytes(vaddr as *mut u8, 0, PAGE_SIZE_4K) };
+    Ok(virt_to_phys(vaddr.into()))
+}
+
+fn dealloc_frame(paddr: PhysAddr) {
+    axalloc::global_allocator().dealloc_pages(phys_to_virt(paddr).as_usize(), 1);
+}
+
+fn frame_data<'a>(paddr: PhysAddr) -> &'a mut [u8] {
+    unsafe { core::slice::from_raw_parts_mut(phys_to_virt(paddr).as_mut_ptr(), PAGE_SIZE_4K) }
+}
+
+/// Fills `buf` with the data at `offset` of `backend`. Bytes beyond the end of
+/// the object are left untouched.
+fn fill_page(backend: &dyn MmapBackend, offset: u64, buf: &mut [u8]) -> AxResult {
+    let mut pos = 0;
+    while pos < buf.len() {
+        let n = backend.read_at(offset + pos as u64, &mut buf[pos..])?;
+        if n == 0 {
+            break;
+        }
+        pos += n;
+    }
+    Ok(())
+}
+
+/// Writes `buf` back to `offset` of `backend`, without extending it.
+fn write_back_page(backend: &dyn MmapBackend, offset: u64, buf: &[u8]) -> AxResult {
+    let size = backend.size()?;
+    if offset >= size {
+        return Ok(());
+    }
+    let len = buf.len().min((size - offset) as usize);
+    let buf = &buf[..len];
+    let mut pos = 0;
+    while pos < len {
+        let n = backend.write_at(offset + pos as u64, &buf[pos..])?;
+        if n == 0 {
+            return ax_err!(WriteZero);
+        }
+        pos += n;
+    }
+    Ok(())
+}
+
+/// Unmaps the pages of a removed area, writes back shared file pages, and
+/// frees the other frames. Cached pages are freed with the page cache.
+fn release_area(start: usize, area: &MmapArea) -> AxResult {
+    let mut ret = Ok(());
+    for vaddr in (start..start + area.size).step_by(PAGE_SIZE_4K) {
+        let res = kernel_page_table().lock().unmap(vaddr.into());
+        if let Ok((paddr, _)) = res {
+            axhal::arch::flush_tlb(Some(vaddr.into()));
+            if area.cache.is_none() {
+                dealloc_frame(paddr);
+            } else if let Some((cache, backend, offset)) = area.write_back_target(vaddr - start) {
+                if let Err(e) = cache.write_back(backend.as_ref(), offset) {
+                    warn!("mmap: failed to write back page {:#x}: {:?}", vaddr, e);
+                    ret = Err(e);
+                }
+            }
+        }
+    }
+    ret
+}
+
+fn check_backend(
+    flags: MappingFlags,
+    shared: bool,
+    backend: &Option<(Arc<dyn MmapBackend>, u64)>,
+) -> AxResult {
+    match backend {
+        Some((backend, _)) if shared && flags.contains(MappingFlags::WRITE) => {
+            if backend.writable() {
+                Ok(())
+            } else {
+                ax_err!(PermissionDenied)
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Creates a mapping of `size` bytes with access `flags`, and returns its
+/// start address.
+///
+/// `backend` is the object to map with the offset in it, or `None` for an
+/// anonymous mapping. If `shared` is true, changes are written back to the
+/// object.
+///
+/// `hint` is used as the start address if the range is free. If `fixed` is
+/// true, the mapping is placed exactly at `hint`, replacing any existing
+/// mappings there.
+pub fn mmap(
+    hint: VirtAddr,
+    size: usize,
+    flags: MappingFlags,
+    shared: bool,
+    fixed: bool,
+    backend: Option<(Arc<dyn MmapBackend>, u64)>,
+) -> AxResult<VirtAddr> {
+    let hint = hint.as_usize();
+    if size == 0 || !is_aligned_4k(hint) {
+        return ax_err!(InvalidInput);
+    }
+    if let Some((_, offset)) = &backend {
+        if !is_aligned_4k(*offset as usize) {
+            return ax_err!(InvalidInput);
+        }
+    }
+    check_backend(flags, shared, &backend)?;
+    let size = align_up_4k(size);
+
+    if fixed {
+        if region_end(hint, size).is_none() {
+            return ax_err!(InvalidInput);
+        }
+        munmap(hint.into(), size)?;
+    }
+
+    let cache = match &backend {
+        Some((backend, _)) if shared => Some(PageCache::of(backend)),
+        _ => None,
+    };
+    let mut areas = AREAS.lock();
+    let start = if fixed {
+        if !is_free(&areas, hint, size) {
+            return ax_err!(AlreadyExists);
+        }
+        hint
+    } else {
+        find_free(&areas, hint, size).ok_or(AxError::NoMemory)?
+    };
+    areas.insert(
+        start,
+        MmapArea {
+            size,
+            flags,
+            shared,
+            backend,
+            cache,
+        },
+    );
+    debug!(
+        "mmap: [{:#x}, {:#x}) {:?} shared={}",
+        start,
+        start + size,
+        flags,
+        shared
+    );
+    Ok(start.into())
+}
+
+/// Removes the mappings in `[start, start + size)`.
+///
+/// Changes of shared file mappings are written back before the pages are
+/// freed. It is not an error if the range contains no mapping.
+pub fn munmap(start: VirtAddr, size: usize) -> AxResult {
+    let start = start.as_usize();
+    if size == 0 || !is_aligned_4k(start) {
+        return ax_err!(InvalidInput);
+    }
+    let end = start.saturating_add(align_up_4k(size));
+    let removed = take_range(&mut AREAS.lock(), start, end);
+
+    let mut ret = Ok(());
+    for (start, area) in removed {
+        if let Err(e) = release_area(start, &area) {
+            ret = Err(e);
+        }
+    }
+    ret
+}
+
+/// Writes back the changes of shared file mappings in `[start, start + size)`.
+///
+/// Returns [`AxError::NoMemory`] if the range is not fully mapped.
+pub fn msync(start: VirtAddr, size: usize) -> AxResult {
+    let start = start.as_usize();
+    if !is_aligned_4k(start) {
+        return ax_err!(InvalidInput);
+    }
+    let end = start.saturating_add(align_up_4k(size));
+
+    let mut pages = Vec::new();
+    {
+        let areas = AREAS.lock();
+        if !is_covered(&areas, start, end) {
+            return ax_err!(NoMemory);
+        }
+        let first = find_area(&areas, start).map_or(start, |(s, _)| s);
+        for (&s, area) in areas.range(first..end) {
+            let range_start = s.max(start);
+            let range_end = (s + area.size).min(end);
+            for vaddr in (range_start..range_end).step_by(PAGE_SIZE_4K) {
+                pages.extend(area.write_back_target(vaddr - s));
+            }
+        }
+    }
+
+    for (cache, backend, offset) in pages {
+        cache.write_back(backend.as_ref(), offset)?;
+    }
+    Ok(())
+}
+
+/// Changes the access flags of the mappings in `[start, start + size)`.
+///
+/// Returns [`AxError::NoMemory`] if the range is not fully mapped.
+pub fn mprotect(start: VirtAddr, size: usize, flags: MappingFlags) -> AxResult {
+    let start = start.as_usize();
+    if !is_aligned_4k(start) {
+        return ax_err!(InvalidInput);
+    }
+    let end = start.saturating_add(align_up_4k(size));
+
+    let mut areas = AREAS.lock();
+    if !is_covered(&areas, start, end) {
+        return ax_err!(NoMemory);
+    }
+    let first = find_area(&areas, start).map_or(start, |(s, _)| s);
+    for area in areas.range(first..end).map(|(_, area)| area) {
+        check_backend(flags, area.shared, &area.backend)?;
+    }
+
+    split_at(&mut areas, start);
+    split_at(&mut areas, end);
+    let mut page_table = kernel_page_table().lock();
+    for (&s, area) in areas.range_mut(start..end) {
+        area.flags = flags;
+        for vaddr in (s..s + area.size).step_by(PAGE_SIZE_4K) {
+            if page_table.query(vaddr.into()).is_ok() {
+                page_table.update(vaddr.into(), None, Some(flags)).ok();
+                axhal::arch::flush_tlb(Some(vaddr.into()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Handles a page fault at `vaddr` caused by an access of `access_flags`.
+///
+/// Returns `true` if `vaddr` is in a mapping allowing the access, in which
+/// case the page is mapped and the access can be retried.
+pub fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags) -> bool {
+    let page = align_down_4k(vaddr.as_usize());
+    if !(MMAP_REGION_START..MMAP_REGION_END).contains(&page) {
+        return false;
+    }
+
+    let (backend, cached) = {
+        let areas = AREAS.lock();
+        let (start, area) = match find_area(&areas, page) {
+            Some((start, area)) if area.flags.contains(access_flags) => (start, area),
+            _ => return false,
+        };
+        let mut page_table = kernel_page_table().lock();
+        if page_table.query(page.into()).is_ok() {
+            // Mapped by another task, or the TLB entry is stale.
+            page_table.update(page.into(), None, Some(area.flags)).ok();
+            axhal::arch::flush_tlb(Some(page.into()));
+            return true;
+        }
+        let backend = area
+            .backend
+            .as_ref()
+            .map(|(backend, offset)| (backend.clone(), offset + (page - start) as u64));
+        let cached = area
+            .cached_page(page - start)
+            .map(|(cache, offset)| (cache.clone(), offset));
+        (backend, cached)
+    };
+
+    // Read the file with no lock held, the mapping may change meanwhile.
+    let res = match &cached {
+        Some((cache, offset)) => cache.get_or_fill(*offset),
+        None => fill_private_page(backend.as_ref()),
+    };
+    let frame = match res {
+        Ok(frame) => frame,
+        Err(e) => {
+            warn!("mmap: failed to fill page {:#x}: {:?}", page, e);
+            return false;
+        }
+    };
+    // Cached pages are freed with the page cache.
+    let release = |frame| {
+        if cached.is_none() {
+            dealloc_frame(frame)
+        }
+    };
+
+    let areas = AREAS.lock();
+    let flags = match find_area(&areas, page) {
+        Some((start, area)) if area.flags.contains(access_flags) => {
+            let now_cached = area.cached_page(page - start);
+            let same_page = match (&cached, now_cached) {
+                (Some((cache, offset)), Some((now_cache, now_offset))) => {
+                    Arc::ptr_eq(cache, now_cache) && *offset == now_offset
+                }
+                (cached, now_cached) => cached.is_none() && now_cached.is_none(),
+            };
+            if !same_page {
+                // Mapped again meanwhile.
+                drop(areas);
+                release(frame);
+                return handle_page_fault(vaddr, access_flags);
+            }
+            area.flags
+        }
+        _ => {
+            release(frame);
+            return false;
+        }
+    };
+    let res = kernel_page_table()
+        .lock()
+        .map(page.into(), frame, PageSize::Size4K, flags);
+    match res {
+        Ok(()) => {
+            axhal::arch::flush_tlb(Some(page.into()));
+            true
+        }
+        Err(PagingError::AlreadyMapped) => {
+            release(frame);
+            true
+        }
+        Err(e) => {
+            warn!("mmap: failed to map page {:#x}: {:?}", page, e);
+            release(frame);
+            false
+        }
+    }
+}
+
+/// Allocates a frame for a page of a private mapping, filled with the data at
+/// the offset of the backing object if any. Changes of shared mappings of the
+/// object not written back yet are seen.
+fn fill_private_page(backend: Option<&(Arc<dyn MmapBackend>, u64)>) -> AxResult<PhysAddr> {
+    let frame = alloc_zeroed_frame()?;
+    if let Some((backend, offset)) = backend {
+        let buf = frame_data(frame);
+        if let Err(e) = fill_page(backend.as_ref(), *offset, buf) {
+            dealloc_frame(frame);
+            return Err(e);
+        }
+        if let Some(cache) = PageCache::lookup(backend.file_id()) {
+            cache.read_cached(*offset, buf);
+        }
+    }
+    Ok(frame)
+}
+
+/// Whether `[start, start + len)` overlaps the mmap region.
+fn in_mmap_region(start: usize, len: usize) -> bool {
+    start < MMAP_REGION_END && start.saturating_add(len) > MMAP_REGION_START
+}
+
+/// Reads the file of `backend` at `offset` into `buf`, and returns the number
+/// of bytes read.
+///
+/// The pages cached for shared mappings of the file are read from the cache,
+/// so the changes not written back yet are seen. `buf` is only accessed with
+/// no lock held, so it may be mapped memory, even of the same file.
+pub fn read_file(backend: &dyn MmapBackend, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+    let cache = PageCache::lookup(backend.file_id());
+    if cache.is_none() && !in_mmap_region(buf.as_ptr() as usize, buf.len()) {
+        return backend.read_at(offset, buf);
+    }
+    let mut page = vec![0; PAGE_SIZE_4K.min(buf.len())];
+    let mut pos = 0;
+    while pos < buf.len() {
+        let off = offset + pos as u64;
+        let len = (PAGE_SIZE_4K - off as usize % PAGE_SIZE_4K).min(buf.len() - pos);
+        let n = backend.read_at(off, &mut page[..len])?;
+        if let Some(cache) = &cache {
+            cache.read_cached(off, &mut page[..n]);
+        }
+        buf[pos..pos + n].copy_from_slice(&page[..n]);
+        pos += n;
+        if n < len {
+            break;
+        }
+    }
+    Ok(pos)
+}
+
+/// Writes `buf` to the file of `backend` at `offset`, and returns the number
+/// of bytes written.
+///
+/// The pages cached for shared mappings of the file are updated too, so the
+/// mappings see the data. `buf` is only accessed with no lock held, so it may
+/// be mapped memory, even of the same file.
+pub fn write_file(backend: &dyn MmapBackend, offset: u64, buf: &[u8]) -> AxResult<usize> {
+    let cache = PageCache::lookup(backend.file_id());
+    if cache.is_none() && !in_mmap_region(buf.as_ptr() as usize, buf.len()) {
+        return backend.write_at(offset, buf);
+    }
+    let mut page = vec![0; PAGE_SIZE_4K.min(buf.len())];
+    let mut pos = 0;
+    while pos < buf.len() {
+        let off = offset + pos as u64;
+        let len = (PAGE_SIZE_4K - off as usize % PAGE_SIZE_4K).min(buf.len() - pos);
+        page[..len].copy_from_slice(&buf[pos..pos + len]);
+        let n = match &cache {
+            Some(cache) => cache.write_through(backend, off, &page[..len])?,
+            None => backend.write_at(off, &page[..len])?,
+        };
+        pos += n;
+        if n < len {
+            break;
+        }
+    }
+    Ok(pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// A file whose reads and writes transfer at most `chunk` bytes.
+    struct MemFile {
+        data: Mutex<Vec<u8>>,
+        chunk: usize,
+    }
+
+    impl MemFile {
+        fn new(data: Vec<u8>, chunk: usize) -> Self {
+            Self {
+                data: Mutex::new(data),
+                chunk,
+            }
+        }
+    }
+
+    impl MmapBackend for MemFile {
+        fn file_id(&self) -> (usize, u64) {
+            (self as *const Self as usize, 0)
+        }
+
+        fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+            let data = self.data.lock().unwrap();
+            let start = data.len().min(offset as usize);
+            let n = buf.len().min(data.len() - start).min(self.chunk);
+            buf[..n].copy_from_slice(&data[start..start + n]);
+            Ok(n)
+        }
+
+        fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
+            let mut data = self.data.lock().unwrap();
+            let start = offset as usize;
+            let n = buf.len().min(self.chunk);
+            if data.len() < start + n {
+                data.resize(start + n, 0);
+            }
+            data[start..start + n].copy_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn size(&self) -> AxResult<u64> {
+            Ok(self.data.lock().unwrap().len() as u64)
+        }
+
+        fn writable(&self) -> bool {
+            self.chunk > 0
+        }
+    }
+
+    fn area(size: usize) -> MmapArea {
+        MmapArea {
+            size,
+            flags: MappingFlags::READ,
+            shared: false,
+            backend: None,
+            cache: None,
+        }
+    }
+
+    const PAGE: usize = PAGE_SIZE_4K;
+    const BASE: usize = MMAP_REGION_START;
+
+    #[test]
+    fn test_find_free() {
+        let mut areas = AreaMap::new();
+        assert_eq!(find_free(&areas, 0, PAGE), Some(BASE));
+        areas.insert(BASE, area(PAGE));
+        areas.insert(BASE + 3 * PAGE, area(PAGE));
+        assert_eq!(find_free(&areas, 0, 2 * PAGE), Some(BASE + PAGE));
+        assert_eq!(find_free(&areas, 0, 3 * PAGE), Some(BASE + 4 * PAGE));
+        // the hint is used if free, ignored otherwise
+        assert_eq!(
+            find_free(&areas, BASE + 2 * PAGE, PAGE),
+            Some(BASE + 2 * PAGE)
+        );
+        assert_eq!(
+            find_free(&areas, BASE + 2 * PAGE, 2 * PAGE),
+            Some(BASE + PAGE)
+        );
+        assert_eq!(find_free(&areas, PAGE, PAGE), Some(BASE + PAGE));
+        assert_eq!(find_free(&areas, 0, MMAP_REGION_SIZE), None);
+
+        assert!(is_free(&areas, BASE + PAGE, 2 * PAGE));
+        assert!(!is_free(&areas, BASE + PAGE, 3 * PAGE));
+        assert!(!is_free(&areas, MMAP_REGION_END - PAGE, 2 * PAGE));
+        assert!(!is_free(&areas, usize::MAX - PAGE + 1, 2 * PAGE));
+    }
+
+    #[test]
+    fn test_split_and_take() {
+        let mut areas = AreaMap::new();
+        let mut file_area = area(4 * PAGE);
+        file_area.backend = Some((Arc::new(MemFile::new(Vec::new(), PAGE)), 0x1000));
+        areas.insert(BASE, file_area);
+        areas.insert(BASE + 5 * PAGE, area(PAGE));
+
+        split_at(&mut areas, BASE + PAGE);
+        split_at(&mut areas, BASE + 4 * PAGE); // not in an area
+        let layout: Vec<_> = areas.iter().map(|(&s, a)| (s - BASE, a.size)).collect();
+        assert_eq!(layout, [(0, PAGE), (PAGE, 3 * PAGE), (5 * PAGE, PAGE)]);
+        let offset = areas[&(BASE + PAGE)].backend.as_ref().unwrap().1;
+        assert_eq!(offset, 0x1000 + PAGE as u64);
+
+        assert!(is_covered(&areas, BASE, BASE + 4 * PAGE));
+        assert!(is_covered(&areas, BASE + 2 * PAGE, BASE + 3 * PAGE));
+        assert!(!is_covered(&areas, BASE + 3 * PAGE, BASE + 6 * PAGE));
+        assert!(!is_covered(&areas, BASE + 4 * PAGE, BASE + 5 * PAGE));
+
+        let removed = take_range(&mut areas, BASE + 2 * PAGE, BASE + 6 * PAGE);
+        let removed: Vec<_> = removed.iter().map(|(s, a)| (s - BASE, a.size)).collect();
+        assert_eq!(removed, [(2 * PAGE, 2 * PAGE), (5 * PAGE, PAGE)]);
+        let layout: Vec<_> = areas.iter().map(|(&s, a)| (s - BASE, a.size)).collect();
+        assert_eq!(layout, [(0, PAGE), (PAGE, PAGE)]);
+    }
+
+    #[test]
+    fn test_fill_page() {
+        let data: Vec<u8> = (0..PAGE + 100).map(|i| i as u8).collect();
+        let file = MemFile::new(data.clone(), 300);
+
+        // short reads are continued
+        let mut buf = vec![0; PAGE];
+        fill_page(&file, 0, &mut buf).unwrap();
+        assert_eq!(buf, data[..PAGE]);
+
+        // the part past the end of the file is kept zero
+        let mut buf = vec![0; PAGE];
+        fill_page(&file, PAGE as u64, &mut buf).unwrap();
+        assert_eq!(buf[..100], data[PAGE..]);
+        assert!(buf[100..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_write_back_page() {
+        let file = MemFile::new(vec![0; PAGE + 100], 300);
+        let buf = vec![0xaa; PAGE];
+
+        // the file is not extended
+        write_back_page(&file, PAGE as u64, &buf).unwrap();
+        write_back_page(&file, 2 * PAGE as u64, &buf).unwrap();
+        let data = file.data.lock().unwrap().clone();
+        assert_eq!(data.len(), PAGE + 100);
+        assert!(data[..PAGE].iter().all(|&b| b == 0));
+        assert!(data[PAGE..].iter().all(|&b| b == 0xaa));
+
+        let file = MemFile::new(vec![0; PAGE], 0);
+        assert_eq!(write_back_page(&file, 0, &buf), Err(AxError::WriteZero));
+    }
+
+    #[test]
+    fn test_page_cache() {
+        let data: Vec<u8> = (0..2 * PAGE).map(|i| i as u8).collect();
+        let file = Arc::new(MemFile::new(data.clone(), 300));
+        let backend: Arc<dyn MmapBackend> = file.clone();
+        let cache = PageCache::of(&backend);
+        assert!(Arc::ptr_eq(&cache, &PageCache::of(&backend)));
+
+        // all shared mappings map the same frame
+        let frame = cache.get_or_fill(0).unwrap();
+        assert_eq!(cache.get_or_fill(0).unwrap(), frame);
+        assert_eq!(frame_data(frame), &data[..PAGE]);
+
+        // reads see the stores to mappings not written back yet
+        frame_data(frame)[10] = 0xff;
+        let mut buf = vec![0; 20];
+        assert_eq!(read_file(file.as_ref(), 5, &mut buf).unwrap(), 20);
+        assert_eq!(buf[5], 0xff);
+        assert_eq!(file.data.lock().unwrap()[10], 10);
+
+        // and mappings see the writes
+        assert_eq!(
+            write_file(file.as_ref(), PAGE as u64 - 1, &[0xee; 2]).unwrap(),
+            2
+        );
+        assert_eq!(frame_data(frame)[PAGE - 1], 0xee);
+        let frame1 = cache.get_or_fill(PAGE as u64).unwrap();
+        assert_eq!(frame_data(frame1)[0], 0xee);
+
+        cache.write_back(backend.as_ref(), 0).unwrap();
+        assert_eq!(file.data.lock().unwrap()[10], 0xff);
+
+        // reads stop at the end of the file
+        let mut buf = vec![0; 100];
+        let n = read_file(file.as_ref(), 2 * PAGE as u64 - 50, &mut buf).unwrap();
+        assert_eq!(n, 50);
+
+        drop(cache);
+        assert!(PageCache::lookup(file.file_id()).is_none());
+    }
+}
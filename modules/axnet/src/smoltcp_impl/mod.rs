@@ -10,8 +10,10 @@ use core::cell::RefCell;
 use core::ops::DerefMut;
 
 use axdriver::prelude::*;
+use axerrno::{ax_err, AxResult};
 use axhal::time::{current_time_nanos, NANOS_PER_MICROS};
 use axsync::Mutex;
+use axtask::PrivCap;
 use driver_net::{DevError, NetBufPtr};
 use lazy_init::LazyInit;
 use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet};
@@ -49,6 +51,8 @@ const TCP_TX_BUF_LEN: usize = 64 * 1024;
 const UDP_RX_BUF_LEN: usize = 64 * 1024;
 const UDP_TX_BUF_LEN: usize = 64 * 1024;
 const LISTEN_QUEUE_SIZE: usize = 512;
+/// Ports below this one need [`PrivCap::NET_BIND_SERVICE`] to be bound.
+const PRIVILEGED_PORT_END: u16 = 1024;
 
 static LISTEN_TABLE: LazyInit<ListenTable> = LazyInit::new();
 static SOCKET_SET: LazyInit<SocketSetWrapper> = LazyInit::new();
@@ -67,6 +71,14 @@ struct InterfaceWrapper {
     iface: Mutex<Interface>,
 }
 
+/// Checks that the current task may bind a socket to `port`.
+fn check_bind_port(port: u16) -> AxResult {
+    if port != 0 && port < PRIVILEGED_PORT_END && !axtask::capable(PrivCap::NET_BIND_SERVICE) {
+        return ax_err!(PermissionDenied, "socket bind() failed: privileged port");
+    }
+    Ok(())
+}
+
 impl<'a> SocketSetWrapper<'a> {
     fn new() -> Self {
         Self(Mutex::new(SocketSet::new(vec![])))
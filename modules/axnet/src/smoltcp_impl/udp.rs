@@ -11,7 +11,7 @@ use smoltcp::socket::udp::{self, BindError, SendError};
 use smoltcp::wire::{IpEndpoint, IpListenEndpoint};
 
 use super::addr::{from_core_sockaddr, into_core_sockaddr, is_unspecified, UNSPECIFIED_ENDPOINT};
-use super::{SocketSetWrapper, SOCKET_SET};
+use super::{check_bind_port, SocketSetWrapper, SOCKET_SET};
 
 /// A UDP socket that provides POSIX-like APIs.
 pub struct UdpSocket {
@@ -74,6 +74,7 @@ impl UdpSocket {
     /// It's must be called before [`send_to`](Self::send_to) and
     /// [`recv_from`](Self::recv_from).
     pub fn bind(&self, mut local_addr: SocketAddr) -> AxResult {
+        check_bind_port(local_addr.port())?;
         let mut self_local_addr = self.local_addr.write();
 
         if local_addr.port() == 0 {
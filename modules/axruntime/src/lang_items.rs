@@ -3,5 +3,8 @@ use core::panic::PanicInfo;
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
     error!("{}", info);
+    // only terminate the current task if possible
+    #[cfg(feature = "multitask")]
+    axtask::exit_on_panic(info);
     axhal::misc::terminate()
 }
@@ -8,6 +8,8 @@
 //! - `alloc`: Enable global memory allocator.
 //! - `paging`: Enable page table manipulation support.
 //! - `irq`: Enable interrupt handling support.
+//! - `tickless`: Stop the periodic timer tick, and let the task scheduler
+//!   program the timer on demand.
 //! - `multitask`: Enable multi-threading support.
 //! - `smp`: Enable SMP (symmetric multiprocessing) support.
 //! - `fs`: Enable filesystem support.
@@ -33,9 +35,6 @@ mod mp;
 #[cfg(feature = "smp")]
 pub use self::mp::rust_main_secondary;
 
-#[cfg(feature = "user")]
-use alloc::string::String;
-
 const LOGO: &str = r#"
        d8888                            .d88888b.   .d8888b.
       d88888                           d88P" "Y88b d88P  Y88b
@@ -148,7 +147,7 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
     #[cfg(feature = "paging")]
     {
         info!("Initialize kernel page table...");
-        remap_kernel_memory().expect("remap kernel memoy failed");
+        axmm::init_kernel_page_table().expect("remap kernel memoy failed");
     }
 
     info!("Initialize platform devices...");
@@ -165,6 +164,9 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
         #[cfg(feature = "fs")]
         axfs::init_filesystems(all_devices.block);
 
+        #[cfg(feature = "user")]
+        axuser::session::init();
+
         #[cfg(feature = "net")]
         axnet::init_network(all_devices.net);
 
@@ -194,76 +196,48 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
         core::hint::spin_loop();
     }
 
-    unsafe { main() };
+    #[cfg(feature = "user")]
+    axuser::session::getty(|| unsafe { main() });
 
-    #[cfg(feature = "multitask")]
-    axtask::exit(0);
-    #[cfg(not(feature = "multitask"))]
+    #[cfg(not(feature = "user"))]
     {
-        info!("main task exited: exit_code={}", 0);
-        axhal::misc::terminate();
+        unsafe { main() };
+
+        #[cfg(feature = "multitask")]
+        axtask::exit(0);
+        #[cfg(not(feature = "multitask"))]
+        {
+            info!("main task exited: exit_code={}", 0);
+            axhal::misc::terminate();
+        }
     }
 }
 
+/// Shuts down the system if the current task holds
+/// [`PrivCap::SYS_BOOT`](axtask::PrivCap::SYS_BOOT).
 pub fn terminate() {
-    #[cfg(feature = "fs")]
+    #[cfg(feature = "multitask")]
     {
-        if !axfs::api::current_uid().is_ok_and(|uid| uid == 0) {
+        if !axtask::capable(axtask::PrivCap::SYS_BOOT) {
+            warn!("terminate: permission denied");
             return;
         }
     }
     axhal::misc::terminate()
 }
 
+/// Exits the main function of the application.
+///
+/// With login sessions, this ends the session, and the console prompts for the
+/// next login. Otherwise the main function is started again.
 pub fn restart(exit_code: i32) {
     info!("main task exited: exit_code={}", exit_code as i8);
     #[cfg(feature = "user")]
+    axtask::exit(exit_code);
+    #[cfg(not(feature = "user"))]
     {
-        axfs::api::set_current_uid(0).unwrap();
-        let mn = "arceos login: ";
-        let p = "Password: ";
-        let f = "Login incorrect\n";
-        fn get_password() -> String {
-            let mut password = String::new();
-            const DL: u8 = b'\x7f';
-            const BS: u8 = b'\x08';
-            loop {
-                if let Some(c) =
-                    axhal::console::getchar().map(|c| if c == b'\r' { b'\n' } else { c })
-                {
-                    if c == b'\n' {
-                        break;
-                    }
-                    if c == DL || c == BS {
-                        password.pop();
-                        continue;
-                    }
-                    password.push(c as char);
-                }
-            }
-            axhal::console::putchar(b'\n');
-            password
-        }
-        loop {
-            for c in mn.as_bytes() {
-                axhal::console::putchar(*c);
-            }
-            let name = get_password();
-            for c in p.as_bytes() {
-                axhal::console::putchar(*c);
-            }
-            let password = get_password();
-            let uid = axuser::api::user_id(name);
-            if axuser::api::verify(uid, password) {
-                axfs::api::set_current_uid(uid).expect("set_current_uid failed");
-                break;
-            }
-            for c in f.as_bytes() {
-                axhal::console::putchar(*c);
-            }
-        }
+        unsafe { main() };
     }
-    unsafe { main() };
 }
 
 #[cfg(feature = "alloc")]
@@ -295,43 +269,20 @@ fn init_allocator() {
     }
 }
 
-#[cfg(feature = "paging")]
-fn remap_kernel_memory() -> Result<(), axhal::paging::PagingError> {
-    use axhal::mem::{memory_regions, phys_to_virt};
-    use axhal::paging::PageTable;
-    use lazy_init::LazyInit;
-
-    static KERNEL_PAGE_TABLE: LazyInit<PageTable> = LazyInit::new();
-
-    if axhal::cpu::this_cpu_is_bsp() {
-        let mut kernel_page_table = PageTable::try_new()?;
-        for r in memory_regions() {
-            kernel_page_table.map_region(
-                phys_to_virt(r.paddr),
-                r.paddr,
-                r.size,
-                r.flags.into(),
-                true,
-            )?;
-        }
-        KERNEL_PAGE_TABLE.init_by(kernel_page_table);
-    }
-
-    unsafe { axhal::arch::write_page_table_root(KERNEL_PAGE_TABLE.root_paddr()) };
-    Ok(())
-}
-
 #[cfg(feature = "irq")]
 fn init_interrupt() {
     use axhal::time::TIMER_IRQ_NUM;
 
     // Setup timer interrupt handler
+    #[cfg(not(feature = "tickless"))]
     const PERIODIC_INTERVAL_NANOS: u64 =
         axhal::time::NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64;
 
+    #[cfg(not(feature = "tickless"))]
     #[percpu::def_percpu]
     static NEXT_DEADLINE: u64 = 0;
 
+    #[cfg(not(feature = "tickless"))]
     fn update_timer() {
         let now_ns = axhal::time::current_time_nanos();
         // Safety: we have disabled preemption in IRQ handler.
@@ -344,6 +295,8 @@ fn init_interrupt() {
     }
 
     axhal::irq::register_handler(TIMER_IRQ_NUM, || {
+        // without the periodic tick, axtask programs the timer by itself
+        #[cfg(not(feature = "tickless"))]
         update_timer();
         #[cfg(feature = "multitask")]
         axtask::on_timer_tick();
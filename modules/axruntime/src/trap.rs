@@ -1,3 +1,6 @@
+use axhal::mem::VirtAddr;
+use axhal::trap::MappingFlags;
+
 struct TrapHandlerImpl;
 
 #[crate_interface::impl_interface]
@@ -10,4 +13,15 @@ impl axhal::trap::TrapHandler for TrapHandlerImpl {
             drop(guard); // rescheduling may occur when preemption is re-enabled.
         }
     }
+
+    fn handle_page_fault(_vaddr: VirtAddr, _access_flags: MappingFlags, _is_user: bool) -> bool {
+        #[cfg(feature = "paging")]
+        if !_is_user {
+            // terminates the task if it is a stack overflow
+            #[cfg(feature = "multitask")]
+            axtask::handle_stack_overflow(_vaddr);
+            return axmm::handle_page_fault(_vaddr, _access_flags);
+        }
+        false
+    }
 }
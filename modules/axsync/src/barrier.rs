@@ -0,0 +1,68 @@
+//! A barrier that synchronizes a fixed number of tasks.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axtask::WaitQueue;
+use spinlock::SpinNoIrq;
+
+/// A barrier enables multiple tasks to synchronize the beginning of some
+/// computation, similar to
+/// [`std::sync::Barrier`](https://doc.rust-lang.org/std/sync/struct.Barrier.html).
+///
+/// The barrier can be reused: once all tasks have rendezvoused, it is ready
+/// for the next round.
+pub struct Barrier {
+    wq: WaitQueue,
+    count: SpinNoIrq<usize>, // tasks arrived in the current round
+    generation: AtomicUsize,
+    num_tasks: usize,
+}
+
+/// The result of [`Barrier::wait`].
+#[derive(Debug, Clone, Copy)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    /// Returns `true` if this task is the "leader task" for the call to
+    /// [`Barrier::wait`], which is the last one to arrive.
+    ///
+    /// Only one task will have `true` returned from their result, all other
+    /// tasks will have `false` returned.
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl Barrier {
+    /// Creates a new barrier that can block a given number of tasks.
+    ///
+    /// A barrier will block `n - 1` tasks which call [`Barrier::wait`] and then
+    /// wake up all tasks at once when the `n`-th task calls it.
+    pub const fn new(n: usize) -> Self {
+        Self {
+            wq: WaitQueue::new(),
+            count: SpinNoIrq::new(0),
+            generation: AtomicUsize::new(0),
+            num_tasks: n,
+        }
+    }
+
+    /// Blocks the current task until all tasks have rendezvoused here.
+    pub fn wait(&self) -> BarrierWaitResult {
+        let mut count = self.count.lock();
+        let generation = self.generation.load(Ordering::Acquire);
+        *count += 1;
+        if *count < self.num_tasks {
+            drop(count);
+            self.wq
+                .wait_until(|| self.generation.load(Ordering::Acquire) != generation);
+            BarrierWaitResult(false)
+        } else {
+            *count = 0;
+            self.generation.fetch_add(1, Ordering::Release);
+            drop(count);
+            self.wq.notify_all_current(true);
+            BarrierWaitResult(true)
+        }
+    }
+}
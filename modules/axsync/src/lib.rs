@@ -2,27 +2,67 @@
 //!
 //! Currently supported primitives:
 //!
-//! - [`Mutex`]: A mutual exclusion primitive.
+//! - [`Mutex`]: A mutual exclusion primitive with priority inheritance.
+//! - [`RwLock`]: A readers-writer lock.
+//! - [`Condvar`]: A condition variable working with [`Mutex`].
+//! - [`Semaphore`]: A counting semaphore.
+//! - [`Barrier`]: A barrier for a fixed number of tasks.
+//! - [`Once`]: A primitive for one-time initialization.
+//! - mod [`mpsc`] and [`oneshot`]: channels for message passing between tasks.
 //! - mod [`spin`](spinlock): spin-locks.
 //!
+//! All primitives except the spin-locks block the current task in an
+//! [`axtask::WaitQueue`] while waiting. Methods with timeouts (e.g.,
+//! `Condvar::wait_timeout`) are available when the `irq` feature is enabled.
+//!
 //! # Cargo Features
 //!
 //! - `multitask`: For use in the multi-threaded environments. If the feature is
-//!   not enabled, [`Mutex`] will be an alias of [`spin::SpinNoIrq`]. This
-//!   feature is enabled by default.
+//!   not enabled, [`Mutex`] will be an alias of [`spin::SpinNoIrq`], and the
+//!   other blocking primitives are not available. This feature is enabled by
+//!   default.
+//! - `irq`: Interrupts are enabled, so that the timed waiting methods can be
+//!   used.
 
 #![cfg_attr(not(test), no_std)]
 #![feature(doc_cfg)]
 
 pub use spinlock as spin;
 
-#[cfg(feature = "multitask")]
-mod mutex;
+cfg_if::cfg_if! {
+    if #[cfg(feature = "multitask")] {
+        extern crate alloc;
+
+        mod barrier;
+        mod condvar;
+        mod mutex;
+        mod once;
+        mod rwlock;
+        mod semaphore;
+
+        pub mod mpsc;
+        pub mod oneshot;
 
-#[cfg(feature = "multitask")]
-#[doc(cfg(feature = "multitask"))]
-pub use self::mutex::{Mutex, MutexGuard};
+        #[cfg(test)]
+        mod tests;
 
-#[cfg(not(feature = "multitask"))]
-#[doc(cfg(not(feature = "multitask")))]
-pub use spinlock::{SpinNoIrq as Mutex, SpinNoIrqGuard as MutexGuard};
+        #[doc(cfg(feature = "multitask"))]
+        pub use self::barrier::{Barrier, BarrierWaitResult};
+        #[doc(cfg(feature = "multitask"))]
+        pub use self::condvar::Condvar;
+        #[cfg(feature = "irq")]
+        #[doc(cfg(all(feature = "multitask", feature = "irq")))]
+        pub use self::condvar::WaitTimeoutResult;
+        #[doc(cfg(feature = "multitask"))]
+        pub use self::mutex::{Mutex, MutexGuard};
+        #[doc(cfg(feature = "multitask"))]
+        pub use self::once::Once;
+        #[doc(cfg(feature = "multitask"))]
+        pub use self::rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+        #[doc(cfg(feature = "multitask"))]
+        pub use self::semaphore::{Semaphore, SemaphoreGuard};
+    } else {
+        #[doc(cfg(not(feature = "multitask")))]
+        pub use spinlock::{SpinNoIrq as Mutex, SpinNoIrqGuard as MutexGuard};
+    }
+}
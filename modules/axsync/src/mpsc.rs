@@ -0,0 +1,203 @@
+//! Multi-producer, single-consumer FIFO queue communication primitives.
+//!
+//! Similar to [`std::sync::mpsc`](https://doc.rust-lang.org/std/sync/mpsc/index.html),
+//! but only the unbounded [`channel`] is provided.
+
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::cell::Cell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use axtask::WaitQueue;
+use spinlock::SpinNoIrq;
+
+struct Channel<T> {
+    queue: SpinNoIrq<VecDeque<T>>,
+    wq: WaitQueue, // the receiver waits here
+    senders: AtomicUsize,
+    receiver_alive: AtomicBool,
+}
+
+impl<T> Channel<T> {
+    fn disconnected(&self) -> bool {
+        self.senders.load(Ordering::Acquire) == 0
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.queue.lock().is_empty() || self.disconnected()
+    }
+}
+
+/// The sending-half of the [`channel`] type. It can be cloned to send to
+/// the same channel from multiple tasks.
+pub struct Sender<T> {
+    chan: Arc<Channel<T>>,
+}
+
+/// The receiving half of the [`channel`] type. It can not be shared between
+/// tasks.
+pub struct Receiver<T> {
+    chan: Arc<Channel<T>>,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+/// An error returned from [`Sender::send`] when the [`Receiver`] has been
+/// dropped. It contains the data that could not be sent.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct SendError<T>(pub T);
+
+/// An error returned from [`Receiver::recv`] when all [`Sender`]s have been
+/// dropped and the channel is empty.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct RecvError;
+
+/// An error returned from [`Receiver::try_recv`].
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum TryRecvError {
+    /// The channel is currently empty, but the [`Sender`]s are still alive.
+    Empty,
+    /// All [`Sender`]s have been dropped and the channel is empty.
+    Disconnected,
+}
+
+/// An error returned from [`Receiver::recv_timeout`].
+#[cfg(feature = "irq")]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum RecvTimeoutError {
+    /// No data arrived before the timeout.
+    Timeout,
+    /// All [`Sender`]s have been dropped and the channel is empty.
+    Disconnected,
+}
+
+/// Creates a new asynchronous channel, returning the sender/receiver halves.
+///
+/// Data sent on the [`Sender`] will become available on the [`Receiver`] in
+/// the same order as it was sent, and no [`Sender::send`] will block the
+/// calling task. [`Receiver::recv`] will block until a message is available
+/// or all senders are dropped.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let chan = Arc::new(Channel {
+        queue: SpinNoIrq::new(VecDeque::new()),
+        wq: WaitQueue::new(),
+        senders: AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+    });
+    let receiver = Receiver {
+        chan: chan.clone(),
+        _not_sync: PhantomData,
+    };
+    (Sender { chan }, receiver)
+}
+
+impl<T> Sender<T> {
+    /// Sends a value on this channel, and wakes up the receiver.
+    ///
+    /// Returns the value back in a [`SendError`] if the [`Receiver`] has been
+    /// dropped.
+    pub fn send(&self, t: T) -> Result<(), SendError<T>> {
+        if !self.chan.receiver_alive.load(Ordering::Acquire) {
+            return Err(SendError(t));
+        }
+        self.chan.queue.lock().push_back(t);
+        self.chan.wq.notify_one(true);
+        Ok(())
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.chan.senders.fetch_add(1, Ordering::Relaxed);
+        Self {
+            chan: self.chan.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.chan.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // let the receiver see the disconnection
+            self.chan.wq.notify_all_current(true);
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    fn pop(&self) -> Option<T> {
+        self.chan.queue.lock().pop_front()
+    }
+
+    /// Attempts to receive a value from this channel without blocking.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        // check for disconnection first, in case of a message sent just before
+        let disconnected = self.chan.disconnected();
+        match self.pop() {
+            Some(t) => Ok(t),
+            None if disconnected => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Receives a value from this channel, blocking the current task until
+    /// one is available.
+    ///
+    /// Returns [`RecvError`] if all [`Sender`]s have been dropped and there
+    /// is no more data in the channel.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.chan.wq.wait_until(|| self.chan.is_ready());
+        self.pop().ok_or(RecvError)
+    }
+
+    /// Receives a value from this channel, blocking the current task until
+    /// one is available or the given duration has elapsed.
+    #[cfg(feature = "irq")]
+    pub fn recv_timeout(&self, dur: core::time::Duration) -> Result<T, RecvTimeoutError> {
+        if self
+            .chan
+            .wq
+            .wait_timeout_until(dur, || self.chan.is_ready())
+        {
+            return Err(RecvTimeoutError::Timeout);
+        }
+        self.pop().ok_or(RecvTimeoutError::Disconnected)
+    }
+
+    /// Returns an iterator that blocks waiting for messages, until all
+    /// [`Sender`]s have been dropped.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        core::iter::from_fn(|| self.recv().ok())
+    }
+
+    /// Returns an iterator over the messages currently in the channel,
+    /// without blocking.
+    pub fn try_iter(&self) -> impl Iterator<Item = T> + '_ {
+        core::iter::from_fn(|| self.try_recv().ok())
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.chan.receiver_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
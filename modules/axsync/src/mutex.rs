@@ -1,21 +1,30 @@
-//! A naïve sleeping mutex.
+//! A naïve sleeping mutex with priority inheritance.
 
 use core::cell::UnsafeCell;
 use core::fmt;
 use core::ops::{Deref, DerefMut};
-use core::sync::atomic::{AtomicU64, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
-use axtask::{current, WaitQueue};
+use axtask::{current, AxTaskRef, WaitQueue};
+use spinlock::SpinNoIrq;
 
 /// A mutual exclusion primitive useful for protecting shared data, similar to
 /// [`std::sync::Mutex`](https://doc.rust-lang.org/std/sync/struct.Mutex.html).
 ///
 /// When the mutex is locked, the current task will block and be put into the
-/// wait queue. When the mutex is unlocked, all tasks waiting on the queue
-/// will be woken up.
+/// wait queue. When the mutex is unlocked, the waiting task with the highest
+/// priority will be woken up.
+///
+/// While tasks are waiting, the owner of the mutex inherits their priority if
+/// it is higher (see [`axtask::inherit_priority`]), so that they are not held
+/// up by less important tasks.
 pub struct Mutex<T: ?Sized> {
     wq: WaitQueue,
     owner_id: AtomicU64,
+    owner: SpinNoIrq<Option<AxTaskRef>>,
+    /// Whether a waiter may have lent its priority to the current owner, which
+    /// has to give it back when releasing the lock.
+    priority_lent: AtomicBool,
     data: UnsafeCell<T>,
 }
 
@@ -23,7 +32,7 @@ pub struct Mutex<T: ?Sized> {
 ///
 /// When the guard falls out of scope it will release the lock.
 pub struct MutexGuard<'a, T: ?Sized + 'a> {
-    lock: &'a Mutex<T>,
+    pub(crate) lock: &'a Mutex<T>,
     data: *mut T,
 }
 
@@ -38,6 +47,8 @@ impl<T> Mutex<T> {
         Self {
             wq: WaitQueue::new(),
             owner_id: AtomicU64::new(0),
+            owner: SpinNoIrq::new(None),
+            priority_lent: AtomicBool::new(false),
             data: UnsafeCell::new(data),
         }
     }
@@ -69,24 +80,43 @@ impl<T: ?Sized> Mutex<T> {
     /// The returned value may be dereferenced for data access
     /// and the lock will be dropped when the guard falls out of scope.
     pub fn lock(&self) -> MutexGuard<T> {
-        let current_id = current().id().as_u64();
+        let curr = current();
+        let current_id = curr.id().as_u64();
         loop {
-            // Can fail to lock even if the spinlock is not locked. May be more efficient than `try_lock`
-            // when called in a loop.
-            match self.owner_id.compare_exchange_weak(
+            // The owner is updated with the lock held and preemption disabled,
+            // so that waiters always know whom to lend their priority to.
+            let mut owner = self.owner.lock();
+            match self.owner_id.compare_exchange(
                 0,
                 current_id,
                 Ordering::Acquire,
                 Ordering::Relaxed,
             ) {
-                Ok(_) => break,
+                Ok(_) => {
+                    *owner = Some(curr.as_task_ref().clone());
+                    break;
+                }
                 Err(owner_id) => {
                     assert_ne!(
                         owner_id,
                         current_id,
                         "{} tried to acquire mutex it already owns.",
-                        current().id_name()
+                        curr.id_name()
                     );
+                    // Not held while lending the priority, which locks the run
+                    // queue of the owner on whichever CPU it is. The owner is
+                    // checked again there, in case it has released the lock.
+                    let owner_task = owner.clone();
+                    drop(owner);
+                    if let Some(owner) = owner_task {
+                        axtask::inherit_priority(&owner, self.lock_id(), || {
+                            // Set before checking the owner, which clears the
+                            // flag after releasing the lock: either the check
+                            // fails or the owner sees the flag.
+                            self.priority_lent.store(true, Ordering::SeqCst);
+                            self.owner_id.load(Ordering::SeqCst) == owner_id
+                        });
+                    }
                     // Wait until the lock looks unlocked before retrying
                     self.wq.wait_until(|| !self.is_locked());
                 }
@@ -101,7 +131,9 @@ impl<T: ?Sized> Mutex<T> {
     /// Try to lock this [`Mutex`], returning a lock guard if successful.
     #[inline(always)]
     pub fn try_lock(&self) -> Option<MutexGuard<T>> {
-        let current_id = current().id().as_u64();
+        let curr = current();
+        let current_id = curr.id().as_u64();
+        let mut owner = self.owner.lock();
         // The reason for using a strong compare_exchange is explained here:
         // https://github.com/Amanieu/parking_lot/pull/207#issuecomment-575869107
         if self
@@ -109,6 +141,7 @@ impl<T: ?Sized> Mutex<T> {
             .compare_exchange(0, current_id, Ordering::Acquire, Ordering::Relaxed)
             .is_ok()
         {
+            *owner = Some(curr.as_task_ref().clone());
             Some(MutexGuard {
                 lock: self,
                 data: unsafe { &mut *self.data.get() },
@@ -126,14 +159,45 @@ impl<T: ?Sized> Mutex<T> {
     /// thread. However, this can be useful in some instances for exposing
     /// the lock to FFI that doesn’t know how to deal with RAII.
     pub unsafe fn force_unlock(&self) {
-        let owner_id = self.owner_id.swap(0, Ordering::Release);
+        // The flag is taken before the owner lock is released, so that it is
+        // not set for the next owner yet.
+        let (owner_id, priority_lent) = {
+            let mut owner = self.owner.lock();
+            owner.take();
+            let owner_id = self.owner_id.swap(0, Ordering::SeqCst);
+            (owner_id, self.priority_lent.swap(false, Ordering::SeqCst))
+        };
         assert_eq!(
             owner_id,
             current().id().as_u64(),
             "{} tried to release mutex it doesn't own",
             current().id_name()
         );
-        self.wq.notify_one(true);
+        // wake up the waiter before giving back its priority, otherwise tasks
+        // less important than it may run first
+        self.wq.notify_highest(true);
+        if priority_lent {
+            axtask::restore_priority(self.lock_id());
+        }
+    }
+
+    /// Creates a guard for the [`Mutex`] locked by the current task, e.g., to
+    /// wait on a [`Condvar`](crate::Condvar) with a lock taken over FFI.
+    ///
+    /// # Safety
+    ///
+    /// The current task must hold the lock, and there must be no other guard
+    /// for it (i.e., it has been forgotten).
+    pub unsafe fn make_guard_unchecked(&self) -> MutexGuard<T> {
+        MutexGuard {
+            lock: self,
+            data: self.data.get(),
+        }
+    }
+
+    /// Identifies the mutex when lending priorities to its owner.
+    fn lock_id(&self) -> usize {
+        self as *const Self as *const () as usize
     }
 
     /// Returns a mutable reference to the underlying data.
@@ -201,9 +265,6 @@ impl<'a, T: ?Sized> Drop for MutexGuard<'a, T> {
 mod tests {
     use crate::Mutex;
     use axtask as thread;
-    use std::sync::Once;
-
-    static INIT: Once = Once::new();
 
     fn may_interrupt() {
         // simulate interrupts
@@ -214,7 +275,7 @@ mod tests {
 
     #[test]
     fn lots_and_lots() {
-        INIT.call_once(thread::init_scheduler);
+        let _lock = crate::tests::setup();
 
         const NUM_TASKS: u32 = 10;
         const NUM_ITERS: u32 = 10_000;
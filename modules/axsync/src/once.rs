@@ -0,0 +1,64 @@
+//! A synchronization primitive for one-time initialization.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use axtask::WaitQueue;
+
+const INCOMPLETE: u8 = 0;
+const RUNNING: u8 = 1;
+const COMPLETE: u8 = 2;
+
+/// A synchronization primitive which can be used to run a one-time global
+/// initialization, similar to
+/// [`std::sync::Once`](https://doc.rust-lang.org/std/sync/struct.Once.html).
+///
+/// Tasks that come while the initialization is running block in the wait
+/// queue until it completes.
+pub struct Once {
+    wq: WaitQueue,
+    state: AtomicU8,
+}
+
+impl Once {
+    /// Creates a new [`Once`] value.
+    pub const fn new() -> Self {
+        Self {
+            wq: WaitQueue::new(),
+            state: AtomicU8::new(INCOMPLETE),
+        }
+    }
+
+    /// Returns `true` if some [`Once::call_once`] call has completed
+    /// successfully.
+    pub fn is_completed(&self) -> bool {
+        self.state.load(Ordering::Acquire) == COMPLETE
+    }
+
+    /// Performs an initialization routine once and only once.
+    ///
+    /// If another task is running the routine, the current task blocks until
+    /// it completes. It is guaranteed that the routine has completed when this
+    /// function returns.
+    pub fn call_once<F: FnOnce()>(&self, f: F) {
+        if self.is_completed() {
+            return;
+        }
+        if self
+            .state
+            .compare_exchange(INCOMPLETE, RUNNING, Ordering::Acquire, Ordering::Acquire)
+            .is_ok()
+        {
+            f();
+            self.state.store(COMPLETE, Ordering::Release);
+            self.wq.notify_all_current(true);
+        } else {
+            self.wq.wait_until(|| self.is_completed());
+        }
+    }
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,133 @@
+//! A channel for sending a single value between tasks.
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use axtask::WaitQueue;
+use spinlock::SpinNoIrq;
+
+pub use crate::mpsc::{RecvError, TryRecvError};
+
+#[cfg(feature = "irq")]
+pub use crate::mpsc::RecvTimeoutError;
+
+struct Channel<T> {
+    value: SpinNoIrq<Option<T>>,
+    wq: WaitQueue, // the receiver waits here
+    sender_alive: AtomicBool,
+    receiver_alive: AtomicBool,
+}
+
+impl<T> Channel<T> {
+    fn is_ready(&self) -> bool {
+        self.value.lock().is_some() || !self.sender_alive.load(Ordering::Acquire)
+    }
+}
+
+/// The sending half of the [`channel`] type, which is consumed by sending.
+pub struct Sender<T> {
+    chan: Arc<Channel<T>>,
+}
+
+/// The receiving half of the [`channel`] type.
+pub struct Receiver<T> {
+    chan: Arc<Channel<T>>,
+}
+
+/// Creates a new oneshot channel, returning the sender/receiver halves.
+///
+/// At most one value can be sent through it. [`Receiver::recv`] blocks until
+/// the value is sent, or returns an error if the [`Sender`] is dropped without
+/// sending anything.
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let chan = Arc::new(Channel {
+        value: SpinNoIrq::new(None),
+        wq: WaitQueue::new(),
+        sender_alive: AtomicBool::new(true),
+        receiver_alive: AtomicBool::new(true),
+    });
+    (Sender { chan: chan.clone() }, Receiver { chan })
+}
+
+impl<T> Sender<T> {
+    /// Sends the value, and wakes up the receiver.
+    ///
+    /// Returns the value back if the [`Receiver`] has been dropped.
+    pub fn send(self, t: T) -> Result<(), T> {
+        if !self.chan.receiver_alive.load(Ordering::Acquire) {
+            return Err(t);
+        }
+        *self.chan.value.lock() = Some(t);
+        Ok(()) // the receiver is woken up when `self` is dropped
+    }
+
+    /// Returns `true` if the [`Receiver`] has been dropped.
+    pub fn is_closed(&self) -> bool {
+        !self.chan.receiver_alive.load(Ordering::Acquire)
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.chan.sender_alive.store(false, Ordering::Release);
+        self.chan.wq.notify_all_current(true);
+    }
+}
+
+impl<T> Receiver<T> {
+    fn take(&self) -> Option<T> {
+        self.chan.value.lock().take()
+    }
+
+    /// Attempts to receive the value without blocking.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        let disconnected = !self.chan.sender_alive.load(Ordering::Acquire);
+        match self.take() {
+            Some(t) => Ok(t),
+            None if disconnected => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Receives the value, blocking the current task until it is sent.
+    ///
+    /// Returns [`RecvError`] if the [`Sender`] has been dropped without
+    /// sending a value.
+    pub fn recv(self) -> Result<T, RecvError> {
+        self.chan.wq.wait_until(|| self.chan.is_ready());
+        self.take().ok_or(RecvError)
+    }
+
+    /// Receives the value, blocking the current task until it is sent or the
+    /// given duration has elapsed.
+    #[cfg(feature = "irq")]
+    pub fn recv_timeout(&mut self, dur: core::time::Duration) -> Result<T, RecvTimeoutError> {
+        if self
+            .chan
+            .wq
+            .wait_timeout_until(dur, || self.chan.is_ready())
+        {
+            return Err(RecvTimeoutError::Timeout);
+        }
+        self.take().ok_or(RecvTimeoutError::Disconnected)
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.chan.receiver_alive.store(false, Ordering::Release);
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sender").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,305 @@
+//! A sleeping readers-writer lock.
+
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axtask::WaitQueue;
+
+const WRITER: usize = 1 << (usize::BITS - 1);
+
+/// A reader-writer lock, similar to
+/// [`std::sync::RwLock`](https://doc.rust-lang.org/std/sync/struct.RwLock.html).
+///
+/// It allows any number of readers or at most one writer at any point in time.
+/// Tasks that can not acquire the lock block in the wait queue, and are all
+/// woken up when it is released.
+///
+/// Writers are preferred: once a writer is waiting, new readers block until it
+/// has acquired and released the lock, so that a stream of readers can not
+/// starve it. As a consequence, a task that already holds a read lock must not
+/// try to acquire another one.
+pub struct RwLock<T: ?Sized> {
+    wq: WaitQueue,
+    state: AtomicUsize, // the `WRITER` bit, or the number of readers
+    writers_waiting: AtomicUsize,
+    data: UnsafeCell<T>,
+}
+
+/// A guard that provides immutable data access.
+///
+/// When the guard falls out of scope it will release the read lock.
+pub struct RwLockReadGuard<'a, T: ?Sized + 'a> {
+    lock: &'a RwLock<T>,
+    data: *const T,
+}
+
+/// A guard that provides mutable data access.
+///
+/// When the guard falls out of scope it will release the write lock.
+pub struct RwLockWriteGuard<'a, T: ?Sized + 'a> {
+    lock: &'a RwLock<T>,
+    data: *mut T,
+}
+
+// Same unsafe impls as `std::sync::RwLock`
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+unsafe impl<T: ?Sized + Sync> Sync for RwLockReadGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for RwLockWriteGuard<'_, T> {}
+
+impl<T> RwLock<T> {
+    /// Creates a new [`RwLock`] wrapping the supplied data.
+    #[inline(always)]
+    pub const fn new(data: T) -> Self {
+        Self {
+            wq: WaitQueue::new(),
+            state: AtomicUsize::new(0),
+            writers_waiting: AtomicUsize::new(0),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Consumes this [`RwLock`] and unwraps the underlying data.
+    #[inline(always)]
+    pub fn into_inner(self) -> T {
+        let RwLock { data, .. } = self;
+        data.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    /// Returns `true` if a writer holds the lock.
+    ///
+    /// Like [`Mutex::is_locked`](crate::Mutex::is_locked), the result should
+    /// only be used as a heuristic.
+    #[inline(always)]
+    pub fn is_write_locked(&self) -> bool {
+        self.state.load(Ordering::Relaxed) & WRITER != 0
+    }
+
+    /// Returns the number of readers currently holding the lock.
+    #[inline(always)]
+    pub fn reader_count(&self) -> usize {
+        let state = self.state.load(Ordering::Relaxed);
+        if state & WRITER != 0 {
+            0
+        } else {
+            state
+        }
+    }
+
+    fn acquire_read(&self) -> bool {
+        let mut state = self.state.load(Ordering::Relaxed);
+        while state & WRITER == 0 {
+            match self.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(s) => state = s,
+            }
+        }
+        false
+    }
+
+    fn acquire_write(&self) -> bool {
+        self.state
+            .compare_exchange(0, WRITER, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    fn read_guard(&self) -> RwLockReadGuard<T> {
+        RwLockReadGuard {
+            lock: self,
+            data: self.data.get(),
+        }
+    }
+
+    fn write_guard(&self) -> RwLockWriteGuard<T> {
+        RwLockWriteGuard {
+            lock: self,
+            data: self.data.get(),
+        }
+    }
+
+    /// Locks this [`RwLock`] with shared read access, blocking the current
+    /// task until it can be acquired.
+    pub fn read(&self) -> RwLockReadGuard<T> {
+        self.wq.wait_until(|| {
+            self.writers_waiting.load(Ordering::Acquire) == 0 && self.acquire_read()
+        });
+        self.read_guard()
+    }
+
+    /// Attempts to acquire this [`RwLock`] with shared read access without
+    /// blocking.
+    #[inline(always)]
+    pub fn try_read(&self) -> Option<RwLockReadGuard<T>> {
+        if self.acquire_read() {
+            Some(self.read_guard())
+        } else {
+            None
+        }
+    }
+
+    /// Locks this [`RwLock`] with shared read access, blocking the current
+    /// task until it can be acquired or the given duration has elapsed.
+    ///
+    /// Returns `None` if the lock is not acquired before the timeout.
+    #[cfg(feature = "irq")]
+    pub fn read_timeout(&self, dur: core::time::Duration) -> Option<RwLockReadGuard<T>> {
+        if self.acquire_read() {
+            return Some(self.read_guard());
+        }
+        let timeout = self.wq.wait_timeout_until(dur, || {
+            self.writers_waiting.load(Ordering::Acquire) == 0 && self.acquire_read()
+        });
+        (!timeout).then(|| self.read_guard())
+    }
+
+    /// Locks this [`RwLock`] with exclusive write access, blocking the current
+    /// task until it can be acquired.
+    pub fn write(&self) -> RwLockWriteGuard<T> {
+        if !self.acquire_write() {
+            self.writers_waiting.fetch_add(1, Ordering::AcqRel);
+            self.wq.wait_until(|| self.acquire_write());
+            self.writers_waiting.fetch_sub(1, Ordering::AcqRel);
+        }
+        self.write_guard()
+    }
+
+    /// Attempts to acquire this [`RwLock`] with exclusive write access without
+    /// blocking.
+    #[inline(always)]
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<T>> {
+        if self.acquire_write() {
+            Some(self.write_guard())
+        } else {
+            None
+        }
+    }
+
+    /// Locks this [`RwLock`] with exclusive write access, blocking the current
+    /// task until it can be acquired or the given duration has elapsed.
+    ///
+    /// Returns `None` if the lock is not acquired before the timeout.
+    #[cfg(feature = "irq")]
+    pub fn write_timeout(&self, dur: core::time::Duration) -> Option<RwLockWriteGuard<T>> {
+        if self.acquire_write() {
+            return Some(self.write_guard());
+        }
+        self.writers_waiting.fetch_add(1, Ordering::AcqRel);
+        let timeout = self.wq.wait_timeout_until(dur, || self.acquire_write());
+        if self.writers_waiting.fetch_sub(1, Ordering::AcqRel) == 1 && timeout {
+            // readers may be held back by us
+            self.wq.notify_all_current(true);
+        }
+        (!timeout).then(|| self.write_guard())
+    }
+
+    /// Force unlock a read lock of this [`RwLock`].
+    ///
+    /// # Safety
+    ///
+    /// The current task must hold a read lock, whose guard has been forgotten.
+    pub unsafe fn force_read_unlock(&self) {
+        let old = self.state.fetch_sub(1, Ordering::Release);
+        debug_assert!(old != 0 && old & WRITER == 0, "no reader holds the lock");
+        if old == 1 {
+            self.wq.notify_all_current(true);
+        }
+    }
+
+    /// Force unlock the write lock of this [`RwLock`].
+    ///
+    /// # Safety
+    ///
+    /// The current task must hold the write lock, whose guard has been
+    /// forgotten.
+    pub unsafe fn force_write_unlock(&self) {
+        let old = self.state.swap(0, Ordering::Release);
+        debug_assert_eq!(old, WRITER, "no writer holds the lock");
+        self.wq.notify_all_current(true);
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// Since this call borrows the [`RwLock`] mutably, no actual locking needs
+    /// to take place.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.data.get() }
+    }
+}
+
+impl<T: ?Sized + Default> Default for RwLock<T> {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.try_read() {
+            Some(guard) => write!(f, "RwLock {{ data: ")
+                .and_then(|()| (*guard).fmt(f))
+                .and_then(|()| write!(f, "}}")),
+            None => write!(f, "RwLock {{ <locked> }}"),
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Deref for RwLockReadGuard<'a, T> {
+    type Target = T;
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        unsafe { &*self.data }
+    }
+}
+
+impl<'a, T: ?Sized> Deref for RwLockWriteGuard<'a, T> {
+    type Target = T;
+    #[inline(always)]
+    fn deref(&self) -> &T {
+        unsafe { &*self.data }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for RwLockWriteGuard<'a, T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.data }
+    }
+}
+
+impl<'a, T: ?Sized + fmt::Debug> fmt::Debug for RwLockReadGuard<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, T: ?Sized + fmt::Debug> fmt::Debug for RwLockWriteGuard<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<'a, T: ?Sized> Drop for RwLockReadGuard<'a, T> {
+    /// The dropping of the [`RwLockReadGuard`] will release the read lock.
+    fn drop(&mut self) {
+        unsafe { self.lock.force_read_unlock() }
+    }
+}
+
+impl<'a, T: ?Sized> Drop for RwLockWriteGuard<'a, T> {
+    /// The dropping of the [`RwLockWriteGuard`] will release the write lock.
+    fn drop(&mut self) {
+        unsafe { self.lock.force_write_unlock() }
+    }
+}
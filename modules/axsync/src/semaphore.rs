@@ -0,0 +1,85 @@
+//! A counting semaphore.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axtask::WaitQueue;
+
+/// A counting semaphore.
+///
+/// It maintains a number of permits. [`Semaphore::acquire`] takes one of them,
+/// blocking the current task until a permit is available, and
+/// [`Semaphore::release`] gives one back and wakes up a waiting task.
+pub struct Semaphore {
+    wq: WaitQueue,
+    count: AtomicUsize,
+}
+
+/// A guard that releases the acquired permit when dropped, returned by
+/// [`Semaphore::access`].
+pub struct SemaphoreGuard<'a> {
+    sem: &'a Semaphore,
+}
+
+impl Semaphore {
+    /// Creates a new semaphore with the initial number of permits.
+    pub const fn new(count: usize) -> Self {
+        Self {
+            wq: WaitQueue::new(),
+            count: AtomicUsize::new(count),
+        }
+    }
+
+    /// Returns the number of available permits.
+    pub fn available(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+
+    /// Acquires a permit, blocking the current task until one is available.
+    pub fn acquire(&self) {
+        self.wq.wait_until(|| self.try_acquire());
+    }
+
+    /// Acquires a permit without blocking, and returns `true` on success.
+    pub fn try_acquire(&self) -> bool {
+        let mut count = self.count.load(Ordering::Relaxed);
+        while count > 0 {
+            match self.count.compare_exchange_weak(
+                count,
+                count - 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return true,
+                Err(c) => count = c,
+            }
+        }
+        false
+    }
+
+    /// Acquires a permit, blocking the current task until one is available or
+    /// the given duration has elapsed.
+    ///
+    /// Returns `true` if a permit is acquired before the timeout.
+    #[cfg(feature = "irq")]
+    pub fn acquire_timeout(&self, dur: core::time::Duration) -> bool {
+        self.try_acquire() || !self.wq.wait_timeout_until(dur, || self.try_acquire())
+    }
+
+    /// Acquires a permit, and returns a guard that releases it when dropped.
+    pub fn access(&self) -> SemaphoreGuard {
+        self.acquire();
+        SemaphoreGuard { sem: self }
+    }
+
+    /// Releases a permit, and wakes up a task waiting for it.
+    pub fn release(&self) {
+        self.count.fetch_add(1, Ordering::Release);
+        self.wq.notify_one(true);
+    }
+}
+
+impl Drop for SemaphoreGuard<'_> {
+    fn drop(&mut self) {
+        self.sem.release();
+    }
+}
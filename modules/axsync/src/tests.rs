@@ -0,0 +1,311 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex as StdMutex, MutexGuard as StdMutexGuard, Once as StdOnce};
+
+use axtask as thread;
+
+use crate::{mpsc, oneshot, Barrier, Condvar, Mutex, Once, RwLock, Semaphore};
+
+static INIT: StdOnce = StdOnce::new();
+static SERIAL: StdMutex<()> = StdMutex::new(());
+
+/// Initializes the scheduler once, and runs the tests one by one as they all
+/// share it.
+pub(crate) fn setup() -> StdMutexGuard<'static, ()> {
+    let guard = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    INIT.call_once(thread::init_scheduler);
+    guard
+}
+
+fn wait_for(counter: &AtomicUsize, value: usize) {
+    while counter.load(Ordering::Acquire) < value {
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn test_rwlock() {
+    let _lock = setup();
+
+    const NUM_READERS: usize = 5;
+    const NUM_ITERS: usize = 100;
+    static LOCK: RwLock<(usize, usize)> = RwLock::new((0, 0));
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_READERS {
+        thread::spawn(|| {
+            for _ in 0..NUM_ITERS {
+                let val = LOCK.read();
+                assert_eq!(val.0, val.1); // never see a half-done write
+                thread::yield_now();
+                drop(val);
+            }
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+    thread::spawn(|| {
+        for _ in 0..NUM_ITERS {
+            let mut val = LOCK.write();
+            val.0 += 1;
+            thread::yield_now();
+            val.1 += 1;
+        }
+        FINISHED.fetch_add(1, Ordering::Release);
+    });
+
+    wait_for(&FINISHED, NUM_READERS + 1);
+    assert_eq!(*LOCK.read(), (NUM_ITERS, NUM_ITERS));
+
+    let read = LOCK.try_read().unwrap();
+    assert_eq!(LOCK.reader_count(), 1);
+    assert!(LOCK.try_write().is_none());
+    drop(read);
+    let write = LOCK.try_write().unwrap();
+    assert!(LOCK.is_write_locked());
+    assert!(LOCK.try_read().is_none());
+    drop(write);
+}
+
+#[test]
+fn test_condvar() {
+    let _lock = setup();
+
+    const NUM_TASKS: usize = 5;
+    static READY: Mutex<usize> = Mutex::new(0);
+    static CVAR: Condvar = Condvar::new();
+    static GO: Mutex<bool> = Mutex::new(false);
+    static GO_CVAR: Condvar = Condvar::new();
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            *READY.lock() += 1;
+            CVAR.notify_one();
+            let go = GO_CVAR.wait_while(GO.lock(), |go| !*go);
+            assert!(*go);
+            drop(go);
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+
+    let ready = CVAR.wait_while(READY.lock(), |ready| *ready < NUM_TASKS);
+    assert_eq!(*ready, NUM_TASKS);
+    drop(ready);
+    assert_eq!(FINISHED.load(Ordering::Acquire), 0);
+
+    *GO.lock() = true;
+    GO_CVAR.notify_all();
+    wait_for(&FINISHED, NUM_TASKS);
+}
+
+#[test]
+fn test_semaphore() {
+    let _lock = setup();
+
+    const NUM_TASKS: usize = 10;
+    const NUM_PERMITS: usize = 3;
+    static SEM: Semaphore = Semaphore::new(NUM_PERMITS);
+    static INSIDE: AtomicUsize = AtomicUsize::new(0);
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            let _permit = SEM.access();
+            let inside = INSIDE.fetch_add(1, Ordering::AcqRel) + 1;
+            assert!(inside <= NUM_PERMITS);
+            thread::yield_now();
+            INSIDE.fetch_sub(1, Ordering::AcqRel);
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+
+    wait_for(&FINISHED, NUM_TASKS);
+    assert_eq!(SEM.available(), NUM_PERMITS);
+    for _ in 0..NUM_PERMITS {
+        assert!(SEM.try_acquire());
+    }
+    assert!(!SEM.try_acquire());
+    for _ in 0..NUM_PERMITS {
+        SEM.release();
+    }
+}
+
+#[test]
+fn test_barrier() {
+    let _lock = setup();
+
+    const NUM_TASKS: usize = 5;
+    const NUM_ROUNDS: usize = 3;
+    static BARRIER: Barrier = Barrier::new(NUM_TASKS);
+    static ARRIVED: AtomicUsize = AtomicUsize::new(0);
+    static LEADERS: AtomicUsize = AtomicUsize::new(0);
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            for round in 1..=NUM_ROUNDS {
+                ARRIVED.fetch_add(1, Ordering::AcqRel);
+                if BARRIER.wait().is_leader() {
+                    LEADERS.fetch_add(1, Ordering::AcqRel);
+                }
+                // nobody passes before all tasks have arrived
+                assert!(ARRIVED.load(Ordering::Acquire) >= round * NUM_TASKS);
+            }
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+
+    wait_for(&FINISHED, NUM_TASKS);
+    assert_eq!(LEADERS.load(Ordering::Acquire), NUM_ROUNDS);
+}
+
+#[test]
+fn test_once() {
+    let _lock = setup();
+
+    const NUM_TASKS: usize = 5;
+    static ONCE: Once = Once::new();
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    static FINISHED: AtomicUsize = AtomicUsize::new(0);
+
+    for _ in 0..NUM_TASKS {
+        thread::spawn(|| {
+            ONCE.call_once(|| {
+                thread::yield_now(); // let others come and wait
+                CALLS.fetch_add(1, Ordering::AcqRel);
+            });
+            assert!(ONCE.is_completed());
+            assert_eq!(CALLS.load(Ordering::Acquire), 1);
+            FINISHED.fetch_add(1, Ordering::Release);
+        });
+    }
+
+    wait_for(&FINISHED, NUM_TASKS);
+    assert_eq!(CALLS.load(Ordering::Acquire), 1);
+}
+
+#[test]
+fn test_mpsc() {
+    let _lock = setup();
+
+    const NUM_SENDERS: usize = 4;
+    const NUM_MSGS: usize = 50;
+
+    let (tx, rx) = mpsc::channel();
+    assert_eq!(rx.try_recv(), Err(mpsc::TryRecvError::Empty));
+    for i in 0..NUM_SENDERS {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for j in 0..NUM_MSGS {
+                tx.send((i, j)).unwrap();
+                thread::yield_now();
+            }
+        });
+    }
+    drop(tx);
+
+    let mut next = [0; NUM_SENDERS];
+    for (i, j) in rx.iter() {
+        assert_eq!(next[i], j); // messages from one sender are in order
+        next[i] += 1;
+    }
+    assert_eq!(next, [NUM_MSGS; NUM_SENDERS]);
+    assert_eq!(rx.recv(), Err(mpsc::RecvError));
+    assert_eq!(rx.try_recv(), Err(mpsc::TryRecvError::Disconnected));
+
+    let (tx, rx) = mpsc::channel();
+    drop(rx);
+    assert_eq!(tx.send(1), Err(mpsc::SendError(1)));
+}
+
+#[test]
+fn test_oneshot() {
+    let _lock = setup();
+
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        thread::yield_now();
+        tx.send(42).unwrap();
+    });
+    assert_eq!(rx.recv(), Ok(42));
+
+    let (tx, rx) = oneshot::channel::<u32>();
+    thread::spawn(move || drop(tx));
+    assert_eq!(rx.recv(), Err(oneshot::RecvError));
+
+    let (tx, mut rx) = oneshot::channel();
+    assert_eq!(rx.try_recv(), Err(oneshot::TryRecvError::Empty));
+    tx.send(1).unwrap();
+    assert_eq!(rx.try_recv(), Ok(1));
+
+    let (tx, rx) = oneshot::channel();
+    drop(rx);
+    assert!(tx.is_closed());
+    assert_eq!(tx.send(1), Err(1));
+}
+
+#[test]
+fn test_mutex_priority_inheritance() {
+    use thread::SchedPolicy;
+
+    let _lock = setup();
+
+    /// The policy and priority that the current task runs with.
+    fn effective_priority() -> (SchedPolicy, isize) {
+        let id = thread::current().id();
+        let info = thread::tasks().into_iter().find(|t| t.id == id).unwrap();
+        (info.policy, info.rt_priority)
+    }
+
+    static LOCK: Mutex<()> = Mutex::new(());
+    static LOCKED: AtomicUsize = AtomicUsize::new(0);
+    static WQ: thread::WaitQueue = thread::WaitQueue::new();
+    static EVENTS: StdMutex<Vec<&str>> = StdMutex::new(Vec::new());
+
+    let high = thread::current().as_task_ref().clone();
+    // real-time policies need the `sched_rt` feature of axtask
+    if !thread::set_sched_policy(&high, SchedPolicy::Fifo, 90) {
+        return;
+    }
+
+    let low = thread::spawn(|| {
+        let guard = LOCK.lock();
+        LOCKED.store(1, Ordering::Release);
+        WQ.notify_one(true); // WQ.wait_until()
+        for _ in 0..3 {
+            // the high priority task is waiting for the lock
+            assert_eq!(effective_priority(), (SchedPolicy::Fifo, 90));
+            EVENTS.lock().unwrap().push("low");
+            thread::yield_now();
+        }
+        drop(guard);
+        assert_eq!(effective_priority().0, SchedPolicy::Normal);
+    });
+    WQ.wait_until(|| LOCKED.load(Ordering::Acquire) == 1);
+
+    // it would keep the low priority task from releasing the lock
+    let medium = thread::spawn_with_policy(
+        || {
+            for _ in 0..3 {
+                EVENTS.lock().unwrap().push("medium");
+                thread::yield_now();
+            }
+        },
+        "medium".into(),
+        0x8000,
+        SchedPolicy::Fifo,
+        50,
+    )
+    .unwrap();
+
+    let guard = LOCK.lock();
+    EVENTS.lock().unwrap().push("high");
+    drop(guard);
+
+    assert!(thread::set_sched_policy(&high, SchedPolicy::Normal, 0));
+    low.join().unwrap();
+    medium.join().unwrap();
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        ["low", "low", "low", "high", "medium", "medium", "medium"]
+    );
+}
@@ -1,22 +1,47 @@
 //! Task APIs for multi-task configuration.
 
 use alloc::{string::String, sync::Arc};
+#[cfg(feature = "sched_edf")]
+use core::time::Duration;
 
-pub(crate) use crate::run_queue::{AxRunQueue, RUN_QUEUE};
+pub(crate) use crate::run_queue::{current_run_queue, AxRunQueue};
 
 #[doc(cfg(feature = "multitask"))]
-pub use crate::task::{CurrentTask, TaskId, TaskInner};
+pub use crate::cred::Credentials;
+#[doc(cfg(feature = "multitask"))]
+pub use crate::task::{CurrentTask, JoinError, TaskId, TaskInfo, TaskInner, TaskState};
+#[doc(cfg(feature = "multitask"))]
+pub use crate::task_local::LocalKey;
 #[doc(cfg(feature = "multitask"))]
 pub use crate::wait_queue::WaitQueue;
+#[cfg(feature = "sched_edf")]
+#[doc(cfg(feature = "sched_edf"))]
+pub use scheduler::EdfParams;
+#[doc(cfg(feature = "multitask"))]
+pub use scheduler::SchedPolicy;
 
 /// The reference type of a task.
 pub type AxTaskRef = Arc<AxTask>;
 
+/// A set of CPUs, in which bit `i` stands for the CPU with ID `i`.
+pub type CpuMask = usize;
+
+/// The exit code of the tasks terminated by panics.
+pub const PANIC_EXIT_CODE: i32 = 101;
+
 cfg_if::cfg_if! {
     if #[cfg(feature = "sched_rr")] {
         const MAX_TIME_SLICE: usize = 5;
         pub(crate) type AxTask = scheduler::RRTask<TaskInner, MAX_TIME_SLICE>;
         pub(crate) type Scheduler = scheduler::RRScheduler<TaskInner, MAX_TIME_SLICE>;
+    } else if #[cfg(feature = "sched_rt")] {
+        const MAX_TIME_SLICE: usize = 5;
+        pub(crate) type AxTask = scheduler::RTTask<TaskInner, MAX_TIME_SLICE>;
+        pub(crate) type Scheduler = scheduler::RTScheduler<TaskInner, MAX_TIME_SLICE>;
+    } else if #[cfg(feature = "sched_edf")] {
+        const MAX_TIME_SLICE: usize = 5;
+        pub(crate) type AxTask = scheduler::EdfTask<TaskInner, MAX_TIME_SLICE>;
+        pub(crate) type Scheduler = scheduler::EdfScheduler<TaskInner, MAX_TIME_SLICE>;
     } else if #[cfg(feature = "sched_cfs")] {
         pub(crate) type AxTask = scheduler::CFSTask<TaskInner>;
         pub(crate) type Scheduler = scheduler::CFScheduler<TaskInner>;
@@ -61,6 +86,23 @@ pub fn current() -> CurrentTask {
     CurrentTask::get()
 }
 
+/// Whether the current task holds the capability `cap`.
+///
+/// Before the scheduler is initialized, all capabilities are granted.
+pub fn capable(cap: capability::PrivCap) -> bool {
+    current_may_uninit().map_or(true, |curr| curr.credentials().has_cap(cap))
+}
+
+/// Takes snapshots of all the tasks that are not dropped yet, including the
+/// exited ones, in the order of their IDs.
+pub fn tasks() -> alloc::vec::Vec<TaskInfo> {
+    // the tasks may be dropped here, which must not be done in the lock
+    crate::task::all_tasks()
+        .iter()
+        .map(|task| task.info())
+        .collect()
+}
+
 /// Initializes the task scheduler (for the primary CPU).
 pub fn init_scheduler() {
     info!("Initialize scheduling...");
@@ -75,16 +117,20 @@ pub fn init_scheduler() {
 /// Initializes the task scheduler for secondary CPUs.
 pub fn init_scheduler_secondary() {
     crate::run_queue::init_secondary();
+    #[cfg(feature = "irq")]
+    crate::timers::init();
 }
 
 /// Handles periodic timer ticks for the task manager.
 ///
-/// For example, advance scheduler states, checks timed events, etc.
+/// For example, advance scheduler states, checks timed events, etc. With the
+/// `tickless` feature, it also programs the one-shot timer of this CPU for the
+/// next time it has to be called.
 #[cfg(feature = "irq")]
 #[doc(cfg(feature = "irq"))]
 pub fn on_timer_tick() {
     crate::timers::check_events();
-    RUN_QUEUE.lock().scheduler_timer_tick();
+    current_run_queue().scheduler_timer_tick();
 }
 
 /// Spawns a new task with the given parameters.
@@ -95,7 +141,7 @@ where
     F: FnOnce() + Send + 'static,
 {
     let task = TaskInner::new(f, name, stack_size);
-    RUN_QUEUE.lock().add_task(task.clone());
+    current_run_queue().add_task(task.clone());
     task
 }
 
@@ -112,6 +158,31 @@ where
     spawn_raw(f, "".into(), axconfig::TASK_STACK_SIZE)
 }
 
+/// Spawns a new task with the given scheduling policy and static priority,
+/// which takes effect before the task runs.
+///
+/// Returns [`None`] if the scheduler does not support the policy, or `prio`
+/// is out of its [range](SchedPolicy::priority_range).
+pub fn spawn_with_policy<F>(
+    f: F,
+    name: String,
+    stack_size: usize,
+    policy: SchedPolicy,
+    prio: isize,
+) -> Option<AxTaskRef>
+where
+    F: FnOnce() + Send + 'static,
+{
+    let task = TaskInner::new(f, name, stack_size);
+    let mut rq = current_run_queue();
+    if rq.set_task_policy(&task, policy, prio) {
+        rq.add_task(task.clone());
+        Some(task)
+    } else {
+        None
+    }
+}
+
 /// Set the priority for current task.
 ///
 /// The range of the priority is dependent on the underlying scheduler. For
@@ -122,13 +193,109 @@ where
 ///
 /// [CFS]: https://en.wikipedia.org/wiki/Completely_Fair_Scheduler
 pub fn set_priority(prio: isize) -> bool {
-    RUN_QUEUE.lock().set_current_priority(prio)
+    current_run_queue().set_current_priority(prio)
+}
+
+/// Sets the scheduling policy and the static priority of `task`, like
+/// `sched_setscheduler(2)`.
+///
+/// Real-time policies are only supported by the real-time scheduler (the
+/// `sched_rt` feature), in which real-time tasks always run before normal
+/// ones. Returns `false` if the scheduler does not support the policy, or
+/// `prio` is out of its [range](SchedPolicy::priority_range).
+pub fn set_sched_policy(task: &AxTaskRef, policy: SchedPolicy, prio: isize) -> bool {
+    crate::run_queue::set_sched_policy(task, policy, prio)
+}
+
+/// Returns the scheduling policy and the static priority of `task`, not
+/// including the ones inherited from other tasks.
+pub fn sched_policy(task: &AxTaskRef) -> (SchedPolicy, isize) {
+    let params = task.base_sched_params();
+    (params.policy, params.rt_priority)
+}
+
+/// Lends the priority of the current task to `owner`, which holds the lock
+/// identified by `lock_id` that the current task is going to wait for, until
+/// `owner` releases the lock with [`restore_priority`].
+///
+/// It is used by sleeping locks like `axsync::Mutex` to implement priority
+/// inheritance: the owner runs with the highest priority of its own and the
+/// waiters, so that a waiter is not held up by tasks less important than it
+/// for longer than the critical section. The priority is not passed on if
+/// `owner` is waiting for another lock itself.
+///
+/// The priority is lent only if `holds_lock` returns `true`, which is checked
+/// with the run queue of `owner` locked. As `owner` releases the lock before
+/// calling [`restore_priority`], which takes the same run queue lock, the
+/// priority lent cannot outlive the ownership, even if `owner` releases the
+/// lock concurrently. So the caller does not need to hold any lock of its own
+/// (which would be nested in run queue locks) to keep the owner stable.
+pub fn inherit_priority(owner: &AxTaskRef, lock_id: usize, holds_lock: impl FnOnce() -> bool) {
+    crate::run_queue::inherit_priority(owner, lock_id, holds_lock)
+}
+
+/// Gives back the priorities lent to the current task by the waiters of the
+/// lock `lock_id` with [`inherit_priority`], when the lock is released.
+pub fn restore_priority(lock_id: usize) {
+    crate::run_queue::restore_priority(lock_id)
+}
+
+/// Makes `task` a deadline task of the [EDF scheduler] that runs for
+/// `runtime` before `deadline` in every `period`, like `sched_setattr(2)` with
+/// `SCHED_DEADLINE`, or a best-effort task again if `params` is [`None`].
+///
+/// The bandwidth is reserved on the CPU that the task is on, and it stays on
+/// that CPU until it becomes a best-effort task. Returns `false` if the
+/// parameters are invalid or less than a timer tick, or the CPU does not have
+/// enough bandwidth left.
+///
+/// [EDF scheduler]: scheduler::EdfScheduler
+#[cfg(feature = "sched_edf")]
+#[doc(cfg(feature = "sched_edf"))]
+pub fn set_deadline(task: &AxTaskRef, params: Option<(Duration, Duration, Duration)>) -> bool {
+    let to_ticks = |dur: Duration| {
+        (dur.as_nanos() * axconfig::TICKS_PER_SEC as u128 / axhal::time::NANOS_PER_SEC as u128)
+            as u64
+    };
+    let params = params.map(|(runtime, deadline, period)| EdfParams {
+        runtime: to_ticks(runtime),
+        deadline: to_ticks(deadline),
+        period: to_ticks(period),
+    });
+    crate::run_queue::set_deadline(task, params)
+}
+
+/// Returns the reservation parameters of `task` in timer ticks, or [`None`]
+/// if it is a best-effort task.
+#[cfg(feature = "sched_edf")]
+#[doc(cfg(feature = "sched_edf"))]
+pub fn deadline_params(task: &AxTaskRef) -> Option<EdfParams> {
+    task.params()
+}
+
+/// Sets the CPUs that `task` is allowed to run on, like `sched_setaffinity(2)`.
+///
+/// If the current task may not run on its CPU anymore, it moves to another
+/// CPU immediately. Other tasks move when they are scheduled next time.
+///
+/// Returns `false` if none of the CPUs in `mask` is online, or `task` is a
+/// deadline task, which cannot leave the CPU holding its bandwidth.
+pub fn set_affinity(task: &AxTaskRef, mask: CpuMask) -> bool {
+    if mask & crate::run_queue::online_cpus() == 0 {
+        return false;
+    }
+    #[cfg(feature = "sched_edf")]
+    if task.params().is_some() {
+        return false;
+    }
+    crate::run_queue::set_affinity(task, mask);
+    true
 }
 
 /// Current task gives up the CPU time voluntarily, and switches to another
 /// ready task.
 pub fn yield_now() {
-    RUN_QUEUE.lock().yield_current();
+    current_run_queue().yield_current();
 }
 
 /// Current task is going to sleep for the given duration.
@@ -143,14 +310,100 @@ pub fn sleep(dur: core::time::Duration) {
 /// If the feature `irq` is not enabled, it uses busy-wait instead.
 pub fn sleep_until(deadline: axhal::time::TimeValue) {
     #[cfg(feature = "irq")]
-    RUN_QUEUE.lock().sleep_until(deadline);
+    current_run_queue().sleep_until(deadline);
     #[cfg(not(feature = "irq"))]
     axhal::time::busy_wait_until(deadline);
 }
 
 /// Exits the current task.
+///
+/// The task-local values of the task are dropped first.
 pub fn exit(exit_code: i32) -> ! {
-    RUN_QUEUE.lock().exit_current(exit_code)
+    current().drop_locals();
+    current_run_queue().exit_current(exit_code)
+}
+
+/// Terminates the current task for the panic `info`, which is reported to the
+/// joiners as [`JoinError::Panicked`]. It is called by the panic handler.
+///
+/// There is no unwinding, so the destructors of the objects on the stack and
+/// the task-local values of the task are not run, and the sleeping locks it
+/// holds are never released.
+///
+/// It returns if the panic cannot be confined to the current task, and the
+/// whole system should be terminated instead: in the main or the idle task,
+/// on a nested panic, or with IRQs or preemption disabled (e.g., in an
+/// interrupt handler or holding a spinlock).
+pub fn exit_on_panic(info: &core::panic::PanicInfo) {
+    exit_on_panic_with(info.message());
+}
+
+/// Like [`exit_on_panic`], for a panic with `message`.
+pub(crate) fn exit_on_panic_with(message: impl core::fmt::Display) {
+    use alloc::string::ToString;
+    if can_exit_on_panic() {
+        exit_panicked(message.to_string());
+    }
+}
+
+fn can_exit_on_panic() -> bool {
+    #[cfg(feature = "irq")]
+    if !axhal::arch::irqs_enabled() {
+        return false;
+    }
+    current_may_uninit().is_some_and(|curr| can_exit_alone(&curr))
+}
+
+/// Whether the current task can be terminated without the whole system, and
+/// marks it as panicking if so.
+fn can_exit_alone(curr: &CurrentTask) -> bool {
+    if curr.is_init() || curr.is_idle() {
+        return false;
+    }
+    #[cfg(feature = "preempt")]
+    if !curr.can_preempt(0) {
+        return false;
+    }
+    curr.start_panicking()
+}
+
+/// Handles a kernel page fault at `vaddr` if it is in the guard page below the
+/// stack of the current task, and returns otherwise. It is called by the page
+/// fault handler.
+///
+/// The overflow is reported with the name of the task, which is terminated as
+/// if it panicked. The handler runs on the exception stack of the CPU, which
+/// the task leaves for good. The whole system panics instead if the task
+/// cannot be terminated alone, as for [`exit_on_panic`].
+#[cfg(feature = "paging")]
+pub fn handle_stack_overflow(vaddr: axhal::mem::VirtAddr) {
+    let Some(curr) = current_may_uninit() else {
+        return;
+    };
+    if !curr.is_stack_guard(vaddr) {
+        return;
+    }
+    let message = alloc::format!(
+        "task stack overflow: Task({}, {:?}), fault_vaddr={:#x}",
+        curr.id().as_u64(),
+        curr.name(),
+        vaddr
+    );
+    if can_exit_alone(&curr) {
+        error!("{}", message);
+        drop(curr);
+        exit_panicked(message);
+    }
+    panic!("{}", message);
+}
+
+/// Terminates the current task as if it panicked with `message`.
+pub(crate) fn exit_panicked(message: alloc::string::String) -> ! {
+    let curr = current();
+    warn!("task panicked: {}", curr.id_name());
+    curr.leak_locals();
+    curr.set_panic_message(message);
+    current_run_queue().exit_current(PANIC_EXIT_CODE)
 }
 
 /// The idle task routine.
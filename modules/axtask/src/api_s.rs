@@ -1,5 +1,7 @@
 //! Task APIs for single-task configuration.
 
+use capability::PrivCap;
+
 /// For single-task situation, we just relax the CPU and wait for incoming
 /// interrupts.
 pub fn yield_now() {
@@ -20,3 +22,8 @@ pub fn sleep(dur: core::time::Duration) {
 pub fn sleep_until(deadline: axhal::time::TimeValue) {
     axhal::time::busy_wait_until(deadline);
 }
+
+/// For single-task situation, the only task holds all capabilities.
+pub fn capable(_cap: PrivCap) -> bool {
+    true
+}
@@ -0,0 +1,305 @@
+use alloc::vec::Vec;
+use capability::PrivCap;
+
+/// User and group identities of a task.
+///
+/// The effective IDs and the supplementary groups are used for permission
+/// checks, and the effective capabilities for privileged operations. A new
+/// task inherits the credentials of the task that spawns it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+    /// Real user ID.
+    pub ruid: u32,
+    /// Effective user ID.
+    pub euid: u32,
+    /// Saved set-user-ID.
+    pub suid: u32,
+    /// Real group ID.
+    pub rgid: u32,
+    /// Effective group ID.
+    pub egid: u32,
+    /// Saved set-group-ID.
+    pub sgid: u32,
+    /// Supplementary group IDs.
+    pub groups: Vec<u32>,
+    /// Capabilities the task may make effective.
+    pub cap_permitted: PrivCap,
+    /// Capabilities used for privilege checks.
+    pub cap_effective: PrivCap,
+}
+
+impl Credentials {
+    /// Returns the credentials of the superuser, with all IDs set to 0, no
+    /// supplementary groups and all capabilities.
+    pub const fn root() -> Self {
+        Self {
+            ruid: 0,
+            euid: 0,
+            suid: 0,
+            rgid: 0,
+            egid: 0,
+            sgid: 0,
+            groups: Vec::new(),
+            cap_permitted: PrivCap::all(),
+            cap_effective: PrivCap::all(),
+        }
+    }
+
+    /// Whether the effective user is the superuser.
+    pub const fn is_privileged(&self) -> bool {
+        self.euid == 0
+    }
+
+    /// Whether `cap` is in the effective capabilities.
+    pub const fn has_cap(&self, cap: PrivCap) -> bool {
+        self.cap_effective.contains(cap)
+    }
+
+    /// Whether `gid` is the effective group or one of the supplementary
+    /// groups.
+    pub fn in_group(&self, gid: u32) -> bool {
+        self.egid == gid || self.groups.contains(&gid)
+    }
+
+    /// Changes the user IDs like `setuid(2)`.
+    ///
+    /// With [`PrivCap::SETUID`], the real, effective and saved user IDs are
+    /// all set to `uid`. Otherwise only the effective user ID is changed, and
+    /// `uid` must be the real or saved user ID. The capabilities change with
+    /// the user IDs, see [`Credentials::update_caps`].
+    ///
+    /// Returns `false` if the change is not permitted.
+    pub fn setuid(&mut self, uid: u32) -> bool {
+        let old = self.clone();
+        if self.has_cap(PrivCap::SETUID) {
+            self.ruid = uid;
+            self.euid = uid;
+            self.suid = uid;
+        } else if uid == self.ruid || uid == self.suid {
+            self.euid = uid;
+        } else {
+            return false;
+        }
+        self.update_caps(&old);
+        true
+    }
+
+    /// Changes the group IDs like `setgid(2)`.
+    ///
+    /// With [`PrivCap::SETGID`], the real, effective and saved group IDs are
+    /// all set to `gid`. Otherwise only the effective group ID is changed, and `gid` must
+    /// be the real or saved group ID.
+    ///
+    /// Returns `false` if the change is not permitted.
+    pub fn setgid(&mut self, gid: u32) -> bool {
+        if self.has_cap(PrivCap::SETGID) {
+            self.rgid = gid;
+            self.egid = gid;
+            self.sgid = gid;
+        } else if gid == self.rgid || gid == self.sgid {
+            self.egid = gid;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Replaces the supplementary groups like `setgroups(2)`.
+    ///
+    /// Returns `false` without [`PrivCap::SETGID`].
+    pub fn setgroups(&mut self, groups: &[u32]) -> bool {
+        if !self.has_cap(PrivCap::SETGID) {
+            return false;
+        }
+        self.groups = groups.to_vec();
+        true
+    }
+
+    /// Replaces the capabilities like `capset(2)`.
+    ///
+    /// The permitted capabilities can only be reduced, and the effective ones
+    /// must be a subset of the new permitted ones. Returns `false` otherwise.
+    pub fn set_caps(&mut self, permitted: PrivCap, effective: PrivCap) -> bool {
+        if !self.cap_permitted.contains(permitted) || !permitted.contains(effective) {
+            return false;
+        }
+        self.cap_permitted = permitted;
+        self.cap_effective = effective;
+        true
+    }
+
+    /// Adjusts the capabilities after the user IDs changed from those in
+    /// `old`, following the rules of `capabilities(7)`:
+    ///
+    /// - If one of the user IDs was 0 and none of them is any more, all
+    ///   capabilities are dropped.
+    /// - If the effective user ID changes from 0 to nonzero, the effective
+    ///   capabilities are cleared.
+    /// - If it changes from nonzero to 0, the permitted capabilities become
+    ///   effective.
+    pub fn update_caps(&mut self, old: &Credentials) {
+        let was_root = old.ruid == 0 || old.euid == 0 || old.suid == 0;
+        let is_root = self.ruid == 0 || self.euid == 0 || self.suid == 0;
+        if was_root && !is_root {
+            self.cap_permitted = PrivCap::empty();
+            self.cap_effective = PrivCap::empty();
+        } else if old.euid == 0 && self.euid != 0 {
+            self.cap_effective = PrivCap::empty();
+        } else if old.euid != 0 && self.euid == 0 {
+            self.cap_effective = self.cap_permitted;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(uid: u32, gid: u32) -> Credentials {
+        Credentials {
+            ruid: uid,
+            euid: uid,
+            suid: uid,
+            rgid: gid,
+            egid: gid,
+            sgid: gid,
+            ..Default::default()
+        }
+    }
+
+    fn uids(cred: &Credentials) -> (u32, u32, u32) {
+        (cred.ruid, cred.euid, cred.suid)
+    }
+
+    #[test]
+    fn test_setuid_privileged() {
+        let mut cred = Credentials::root();
+        assert!(cred.setuid(1000));
+        assert_eq!(uids(&cred), (1000, 1000, 1000));
+        // all user IDs left 0, so the capabilities are gone for good
+        assert_eq!(cred.cap_permitted, PrivCap::empty());
+        assert_eq!(cred.cap_effective, PrivCap::empty());
+        assert!(!cred.is_privileged());
+        assert!(!cred.setuid(0));
+        assert_eq!(uids(&cred), (1000, 1000, 1000));
+    }
+
+    #[test]
+    fn test_setuid_unprivileged() {
+        // a set-user-ID program of user 2000 run by user 1000
+        let mut cred = user(1000, 1000);
+        cred.euid = 2000;
+        cred.suid = 2000;
+
+        assert!(cred.setuid(1000));
+        assert_eq!(uids(&cred), (1000, 1000, 2000));
+        assert!(cred.setuid(2000));
+        assert_eq!(uids(&cred), (1000, 2000, 2000));
+        assert!(!cred.setuid(0));
+        assert!(!cred.setuid(3000));
+        assert_eq!(uids(&cred), (1000, 2000, 2000));
+    }
+
+    #[test]
+    fn test_setuid_saved_root() {
+        // a set-user-ID root program run by user 1000
+        let mut cred = Credentials::root();
+        cred.ruid = 1000;
+        cred.cap_effective = PrivCap::empty();
+        cred.euid = 1000;
+
+        // the effective capabilities come back with the effective user ID
+        assert!(!cred.setuid(2000));
+        assert!(cred.setuid(0));
+        assert_eq!(uids(&cred), (1000, 0, 0));
+        assert_eq!(cred.cap_effective, PrivCap::all());
+
+        // now privileged, it sets all user IDs and drops the capabilities
+        assert!(cred.setuid(1000));
+        assert_eq!(uids(&cred), (1000, 1000, 1000));
+        assert_eq!(cred.cap_permitted, PrivCap::empty());
+    }
+
+    #[test]
+    fn test_update_caps() {
+        let root = Credentials::root();
+
+        // the effective user ID leaves 0, the saved one stays
+        let mut cred = root.clone();
+        cred.euid = 1000;
+        cred.update_caps(&root);
+        assert_eq!(cred.cap_permitted, PrivCap::all());
+        assert_eq!(cred.cap_effective, PrivCap::empty());
+
+        // and comes back
+        let old = cred.clone();
+        cred.euid = 0;
+        cred.update_caps(&old);
+        assert_eq!(cred.cap_effective, PrivCap::all());
+
+        // no user ID changes from or to 0
+        let mut cred = user(1000, 1000);
+        cred.cap_permitted = PrivCap::SYS_NICE;
+        let old = cred.clone();
+        cred.euid = 2000;
+        cred.update_caps(&old);
+        assert_eq!(cred.cap_permitted, PrivCap::SYS_NICE);
+        assert_eq!(cred.cap_effective, PrivCap::empty());
+    }
+
+    #[test]
+    fn test_setgid() {
+        let mut cred = Credentials::root();
+        assert!(cred.setgid(100));
+        assert_eq!((cred.rgid, cred.egid, cred.sgid), (100, 100, 100));
+        // group IDs do not affect the capabilities
+        assert!(cred.has_cap(PrivCap::SETGID));
+
+        let mut cred = user(1000, 100);
+        cred.sgid = 200;
+        assert!(cred.setgid(200));
+        assert_eq!((cred.rgid, cred.egid, cred.sgid), (100, 200, 200));
+        assert!(cred.setgid(100));
+        assert!(!cred.setgid(0));
+        assert_eq!((cred.rgid, cred.egid, cred.sgid), (100, 100, 200));
+    }
+
+    #[test]
+    fn test_groups() {
+        let mut cred = Credentials::root();
+        assert!(cred.setgroups(&[10, 20]));
+        assert!(cred.in_group(0));
+        assert!(cred.in_group(20));
+        assert!(!cred.in_group(30));
+
+        let mut cred = user(1000, 100);
+        assert!(!cred.setgroups(&[0]));
+        assert!(cred.groups.is_empty());
+        assert!(cred.in_group(100));
+        assert!(!cred.in_group(0));
+    }
+
+    #[test]
+    fn test_caps() {
+        let mut cred = Credentials::root();
+        assert!(cred.is_privileged());
+        assert!(cred.has_cap(PrivCap::SYS_NICE | PrivCap::SETUID));
+
+        let nice = PrivCap::SYS_NICE | PrivCap::SETUID;
+        assert!(cred.set_caps(nice, PrivCap::SYS_NICE));
+        assert!(cred.has_cap(PrivCap::SYS_NICE));
+        assert!(!cred.has_cap(PrivCap::SETUID));
+        assert!(!cred.has_cap(PrivCap::CHOWN));
+        // still privileged by user ID, but not by capability
+        assert!(cred.is_privileged());
+        assert!(!cred.setgroups(&[]));
+
+        // effective not in permitted
+        assert!(!cred.set_caps(PrivCap::SYS_NICE, PrivCap::SETUID));
+        // permitted cannot grow
+        assert!(!cred.set_caps(PrivCap::all(), PrivCap::empty()));
+        assert!(cred.set_caps(nice, nice));
+        assert!(cred.has_cap(PrivCap::SETUID));
+        assert_eq!(cred.cap_permitted, nice);
+    }
+}
@@ -4,6 +4,16 @@
 //! creation, scheduling, sleeping, termination, etc. The scheduler algorithm
 //! is configurable by cargo features.
 //!
+//! Each CPU has its own run queue. Newly spawned and woken tasks are placed on
+//! the least loaded CPU they are allowed to run on, an idle CPU steals ready
+//! tasks from the busiest one, and when `irq` is enabled the queues are also
+//! rebalanced periodically on timer ticks. Tasks can be pinned to a set of
+//! CPUs with [`set_affinity`].
+//!
+//! A panic in a spawned task only terminates the task (see [`exit_on_panic`]),
+//! and it is reported to the joiners as [`JoinError::Panicked`]. Tasks can
+//! also keep their own values with [`task_local!`].
+//!
 //! # Cargo Features
 //!
 //! - `multitask`: Enable multi-task support. If it's enabled, complex task
@@ -13,6 +23,19 @@
 //!    APIs can be used, such as [`sleep`], [`sleep_until`], and
 //!    [`WaitQueue::wait_timeout`].
 //! - `preempt`: Enable preemptive scheduling.
+//! - `tickless`: Stop the periodic timer tick. The one-shot timer of each CPU
+//!   is programmed for the end of the time slice of the running task, or for
+//!   the next timer event, which may be delayed by the [timer slack] of its
+//!   task to be coalesced with others. Without IPIs, a task woken up by
+//!   another CPU may wait for up to 10 ticks before its CPU notices it on SMP.
+//!   It also enables the `irq` feature.
+//! - `paging`: Allocate task stacks with guard pages, so that a task that
+//!   overflows its stack faults before corrupting other memory. Otherwise, it
+//!   is detected by a canary at the bottom of the stack on context switches.
+//!   A fault in the guard page terminates the task, and the canary panics the
+//!   system, both with the name of the task.
+//! - `stack_usage`: Fill task stacks with a known value when they are
+//!   allocated, so that [`tasks`] can report the most bytes used of them.
 //! - `sched_fifo`: Use the [FIFO cooperative scheduler][1]. It also enables the
 //!   `multitask` feature if it is enabled. This feature is enabled by default,
 //!   and it can be overriden by other scheduler features.
@@ -20,10 +43,21 @@
 //!   the `multitask` and `preempt` features if it is enabled.
 //! - `sched_cfs`: Use the [Completely Fair Scheduler][3]. It also enables the
 //!   the `multitask` and `preempt` features if it is enabled.
+//! - `sched_rt`: Use the [real-time scheduler][4], in which tasks can be given
+//!   the `SCHED_FIFO` or `SCHED_RR` policy with [`set_sched_policy`], and
+//!   always preempt normal tasks scheduled by CFS. It also enables the
+//!   `multitask` and `preempt` features if it is enabled.
+//! - `sched_edf`: Use the [earliest deadline first scheduler][5], in which
+//!   tasks can reserve CPU time with [`set_deadline`], and run before
+//!   best-effort ones in round-robin. It also enables the `multitask` and
+//!   `preempt` features if it is enabled.
 //!
 //! [1]: scheduler::FifoScheduler
 //! [2]: scheduler::RRScheduler
 //! [3]: scheduler::CFScheduler
+//! [4]: scheduler::RTScheduler
+//! [5]: scheduler::EdfScheduler
+//! [timer slack]: TaskInner::set_timer_slack
 
 #![cfg_attr(not(test), no_std)]
 #![feature(doc_cfg)]
@@ -35,6 +69,7 @@ cfg_if::cfg_if! {
         extern crate log;
         extern crate alloc;
 
+        mod cred;
         mod run_queue;
         mod task;
         mod api;
@@ -43,11 +78,20 @@ cfg_if::cfg_if! {
         #[cfg(feature = "irq")]
         mod timers;
 
+        #[cfg(test)]
+        mod tests;
+
         #[doc(cfg(feature = "multitask"))]
         pub use self::api::*;
         pub use self::api::{sleep, sleep_until, yield_now};
     } else {
         mod api_s;
-        pub use self::api_s::{sleep, sleep_until, yield_now};
+        pub use self::api_s::{capable, sleep, sleep_until, yield_now};
     }
 }
+
+// not in `cfg_if!`, so that the exported macro can be used in this crate
+#[cfg(feature = "multitask")]
+mod task_local;
+
+pub use capability::PrivCap;
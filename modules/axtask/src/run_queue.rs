@@ -1,16 +1,49 @@
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use kernel_guard::NoPreemptIrqSave;
 use lazy_init::LazyInit;
-use scheduler::BaseScheduler;
-use spinlock::SpinNoIrq;
+use scheduler::{BaseScheduler, SchedPolicy};
+use spinlock::{SpinNoIrq, SpinRaw};
 
-use crate::task::{CurrentTask, TaskState};
-use crate::{AxTaskRef, Scheduler, TaskInner, WaitQueue};
+use crate::task::{CurrentTask, TaskPriority, TaskState};
+use crate::{AxTaskRef, CpuMask, Scheduler, TaskInner, WaitQueue};
 
-// TODO: per-CPU
-pub(crate) static RUN_QUEUE: LazyInit<SpinNoIrq<AxRunQueue>> = LazyInit::new();
+/// Timer ticks between two periodic load balancing passes on a CPU.
+#[cfg(feature = "irq")]
+const BALANCE_INTERVAL: usize = 10;
+
+/// Nanoseconds between two timer ticks.
+#[cfg(feature = "tickless")]
+const TICK_NANOS: u64 = axhal::time::NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64;
+
+/// The most ticks that an idle CPU may sleep for at once, which also keeps the
+/// one-shot timers within their hardware range.
+#[cfg(feature = "tickless")]
+const MAX_IDLE_TICKS: u64 = axconfig::TICKS_PER_SEC as u64;
+
+/// The run queue of each CPU.
+///
+/// The lock is held across context switches, and released by the task that
+/// runs next on the CPU (see [`CurrentRunQueue`]). So a task in the run queue
+/// or the wake list of a CPU can be switched to only by holders of the lock,
+/// after it has completely switched out.
+#[percpu::def_percpu]
+static RUN_QUEUE: LazyInit<SpinRaw<AxRunQueue>> = LazyInit::new();
+
+/// Tasks woken up or migrated by other CPUs, which are moved into the run queue
+/// of this CPU at the next reschedule.
+#[percpu::def_percpu]
+static WAKE_LIST: SpinNoIrq<VecDeque<AxTaskRef>> = SpinNoIrq::new(VecDeque::new());
+
+/// Number of ready tasks in the run queue and the wake list of this CPU.
+#[percpu::def_percpu]
+static NR_READY: AtomicUsize = AtomicUsize::new(0);
+
+/// CPUs whose run queues are initialized.
+static ONLINE_CPUS: AtomicUsize = AtomicUsize::new(0);
 
-// TODO: per-CPU
 static EXITED_TASKS: SpinNoIrq<VecDeque<AxTaskRef>> = SpinNoIrq::new(VecDeque::new());
 
 static WAIT_FOR_EXIT: WaitQueue = WaitQueue::new();
@@ -19,30 +52,220 @@ static WAIT_FOR_EXIT: WaitQueue = WaitQueue::new();
 static IDLE_TASK: LazyInit<AxTaskRef> = LazyInit::new();
 
 pub(crate) struct AxRunQueue {
+    cpu_id: usize,
     scheduler: Scheduler,
+    /// The previous task that is not allowed to run on this CPU anymore. It is
+    /// handed over to another CPU once it has switched out.
+    migrating: Option<AxTaskRef>,
+    #[cfg(feature = "irq")]
+    ticks: usize,
+    /// The time of the last tick accounted, in nanoseconds.
+    #[cfg(feature = "tickless")]
+    last_tick_ns: u64,
+}
+
+/// Exclusive access to the run queue of the current CPU.
+///
+/// Dropping it unlocks the run queue of the CPU that the task runs on *now*,
+/// which is not the locked one if the task has been migrated to another CPU
+/// while it was switched out.
+pub(crate) struct CurrentRunQueue {
+    _guard: NoPreemptIrqSave,
+}
+
+/// Locks the run queue of the current CPU.
+pub(crate) fn current_run_queue() -> CurrentRunQueue {
+    let guard = NoPreemptIrqSave::new();
+    // released in `unlock_current()`
+    core::mem::forget(local_run_queue().lock());
+    CurrentRunQueue { _guard: guard }
+}
+
+/// Unlocks the run queue of the current CPU, which was held across the last
+/// context switch, and hands over the task that switched out if it needs to
+/// run on another CPU.
+///
+/// # Safety
+///
+/// The run queue of the current CPU must be locked by the current task, or by
+/// the task that switched to it.
+pub(crate) unsafe fn unlock_current() {
+    let rq = local_run_queue();
+    if let Some(task) = (*rq.as_mut_ptr()).migrating.take() {
+        wake_remote(select_cpu(&task), task);
+    }
+    rq.force_unlock();
+}
+
+impl Deref for CurrentRunQueue {
+    type Target = AxRunQueue;
+    fn deref(&self) -> &AxRunQueue {
+        unsafe { &*local_run_queue().as_mut_ptr() }
+    }
+}
+
+impl DerefMut for CurrentRunQueue {
+    fn deref_mut(&mut self) -> &mut AxRunQueue {
+        unsafe { &mut *local_run_queue().as_mut_ptr() }
+    }
+}
+
+impl Drop for CurrentRunQueue {
+    fn drop(&mut self) {
+        unsafe { unlock_current() };
+    }
+}
+
+fn local_run_queue() -> &'static SpinRaw<AxRunQueue> {
+    // Safety: IRQs and preemption are disabled by the caller.
+    unsafe { RUN_QUEUE.current_ref_raw() }.deref()
+}
+
+pub(crate) fn online_cpus() -> CpuMask {
+    ONLINE_CPUS.load(Ordering::Acquire)
+}
+
+fn nr_ready(cpu_id: usize) -> &'static AtomicUsize {
+    unsafe { NR_READY.remote_ref_raw(cpu_id) }
+}
+
+/// Selects the CPU to run `task` on: the least loaded one that it is allowed
+/// to run on, preferring the CPU it ran on last. If none of the allowed CPUs
+/// is online yet, it stays on its last CPU.
+fn select_cpu(task: &AxTaskRef) -> usize {
+    let last = task.cpu_id();
+    let allowed = task.cpumask() & online_cpus();
+    if allowed & (1 << last) != 0 && nr_ready(last).load(Ordering::Relaxed) == 0 {
+        return last;
+    }
+    (0..axconfig::SMP)
+        .filter(|&cpu| allowed & (1 << cpu) != 0)
+        .min_by_key(|&cpu| (nr_ready(cpu).load(Ordering::Relaxed), cpu != last))
+        .unwrap_or(last)
+}
+
+/// Puts `task` into the wake list of CPU `cpu_id`.
+///
+/// It must not be running on any CPU, or be switching out on `cpu_id`.
+fn wake_remote(cpu_id: usize, task: AxTaskRef) {
+    task.set_cpu_id(cpu_id);
+    nr_ready(cpu_id).fetch_add(1, Ordering::Relaxed);
+    unsafe { WAKE_LIST.remote_ref_raw(cpu_id) }
+        .lock()
+        .push_back(task);
 }
 
 impl AxRunQueue {
-    pub fn new() -> SpinNoIrq<Self> {
-        let gc_task = TaskInner::new(gc_entry, "gc".into(), axconfig::TASK_STACK_SIZE);
-        let mut scheduler = Scheduler::new();
-        scheduler.add_task(gc_task);
-        SpinNoIrq::new(Self { scheduler })
+    fn new(cpu_id: usize) -> SpinRaw<Self> {
+        SpinRaw::new(Self {
+            cpu_id,
+            scheduler: Scheduler::new(),
+            migrating: None,
+            #[cfg(feature = "irq")]
+            ticks: 0,
+            #[cfg(feature = "tickless")]
+            last_tick_ns: axhal::time::current_time_nanos(),
+        })
     }
 
     pub fn add_task(&mut self, task: AxTaskRef) {
         debug!("task spawn: {}", task.id_name());
         assert!(task.is_ready());
-        self.scheduler.add_task(task);
+        let cpu_id = select_cpu(&task);
+        if cpu_id == self.cpu_id {
+            let resched = self
+                .scheduler
+                .should_preempt(&task, crate::current().as_task_ref());
+            self.enqueue(task);
+            if resched {
+                #[cfg(feature = "preempt")]
+                crate::current().set_preempt_pending(true);
+            }
+            // the running task may have to share the CPU earlier
+            #[cfg(feature = "tickless")]
+            self.program_timer(crate::current().as_task_ref());
+        } else {
+            wake_remote(cpu_id, task);
+        }
     }
 
     #[cfg(feature = "irq")]
     pub fn scheduler_timer_tick(&mut self) {
         let curr = crate::current();
-        if !curr.is_idle() && self.scheduler.task_tick(curr.as_task_ref()) {
-            #[cfg(feature = "preempt")]
-            curr.set_preempt_pending(true);
+        let ticks = self.elapsed_ticks();
+        if ticks > 0 {
+            // time of deadline tasks goes on even if none of them is running
+            #[cfg(feature = "sched_edf")]
+            if curr.is_idle() && self.scheduler.idle_ticks(ticks as u64) {
+                curr.set_preempt_pending(true);
+            }
+            // the ticks skipped without the periodic tick were all run by it
+            if !curr.is_idle() {
+                let mut resched = false;
+                for _ in 0..ticks {
+                    resched |= self.scheduler.task_tick(curr.as_task_ref());
+                }
+                if resched {
+                    #[cfg(feature = "preempt")]
+                    curr.set_preempt_pending(true);
+                }
+            }
+            let old_ticks = self.ticks;
+            self.ticks += ticks;
+            if self.ticks / BALANCE_INTERVAL != old_ticks / BALANCE_INTERVAL {
+                self.balance_load();
+            }
         }
+        #[cfg(feature = "tickless")]
+        self.program_timer(curr.as_task_ref());
+    }
+
+    /// Returns the number of ticks since the last one accounted. Without the
+    /// periodic tick, it may be more than 1 after the CPU idles, or 0 if the
+    /// timer goes off for a timer event in between.
+    #[cfg(feature = "irq")]
+    fn elapsed_ticks(&mut self) -> usize {
+        #[cfg(feature = "tickless")]
+        {
+            let now_ns = axhal::time::current_time_nanos();
+            let ticks = now_ns.saturating_sub(self.last_tick_ns) / TICK_NANOS;
+            self.last_tick_ns += ticks * TICK_NANOS;
+            ticks as usize
+        }
+        #[cfg(not(feature = "tickless"))]
+        1
+    }
+
+    /// Programs the timer of this CPU for the tick on which `curr`, the task
+    /// running on it, is to be preempted, such as at the end of its time
+    /// slice. An idle CPU skips the ticks until a deadline task is released.
+    /// Timer events may make the timer go off earlier.
+    ///
+    /// With more than one CPU, the timer still goes off every
+    /// `BALANCE_INTERVAL` ticks, to balance the load and to pick up the tasks
+    /// woken up by other CPUs, for there are no IPIs to notify it. So such a
+    /// task may wait for up to `BALANCE_INTERVAL` ticks before it runs, or
+    /// preempts the running task.
+    #[cfg(feature = "tickless")]
+    fn program_timer(&self, curr: &AxTaskRef) {
+        let max_ticks = if axconfig::SMP > 1 {
+            BALANCE_INTERVAL as u64
+        } else {
+            MAX_IDLE_TICKS
+        };
+        let ticks = if curr.is_idle() {
+            max_ticks
+        } else {
+            self.scheduler
+                .ticks_to_resched(curr)
+                .map_or(max_ticks, |ticks| (ticks as u64).clamp(1, max_ticks))
+        };
+        #[cfg(feature = "sched_edf")]
+        let ticks = match self.scheduler.next_release() {
+            Some(release) => ticks.min(release.saturating_sub(self.scheduler.clock()).max(1)),
+            None => ticks,
+        };
+        crate::timers::program_timer(self.last_tick_ns + ticks * TICK_NANOS);
     }
 
     pub fn yield_current(&mut self) {
@@ -53,8 +276,52 @@ impl AxRunQueue {
     }
 
     pub fn set_current_priority(&mut self, prio: isize) -> bool {
-        self.scheduler
-            .set_priority(crate::current().as_task_ref(), prio)
+        let curr = crate::current();
+        let task = curr.as_task_ref();
+        if !self.scheduler.set_priority(task, prio) {
+            return false;
+        }
+        task.update_priority(|p| p.base.nice = prio);
+        // it may be still raised by priority inheritance
+        self.scheduler.set_priority(task, task.sched_params().nice);
+        true
+    }
+
+    /// Sets the scheduling policy of a task in this run queue, or of a new
+    /// task that is not in any run queue yet.
+    pub fn set_task_policy(&mut self, task: &AxTaskRef, policy: SchedPolicy, prio: isize) -> bool {
+        if !self.scheduler.set_policy(task, policy, prio) {
+            return false;
+        }
+        task.update_priority(|p| {
+            p.base.policy = policy;
+            p.base.rt_priority = prio;
+        });
+        // it may be still raised by priority inheritance
+        let params = task.sched_params();
+        if (params.policy, params.rt_priority) != (policy, prio) {
+            self.scheduler
+                .set_policy(task, params.policy, params.rt_priority);
+        }
+        true
+    }
+
+    /// Changes the priority of a task in this run queue (or running on this
+    /// CPU) with `f`, and returns `true` if the scheduling parameters that it
+    /// runs with are changed.
+    fn update_task_priority(
+        &mut self,
+        task: &AxTaskRef,
+        f: impl FnOnce(&mut TaskPriority),
+    ) -> bool {
+        let (old, new) = task.update_priority(f);
+        if (new.policy, new.rt_priority) != (old.policy, old.rt_priority) {
+            self.scheduler.set_policy(task, new.policy, new.rt_priority);
+        }
+        if new.nice != old.nice {
+            self.scheduler.set_priority(task, new.nice);
+        }
+        new != old
     }
 
     #[cfg(feature = "preempt")]
@@ -63,10 +330,9 @@ impl AxRunQueue {
         assert!(curr.is_running());
 
         // When we get the mutable reference of the run queue, we must
-        // have held the `SpinNoIrq` lock with both IRQs and preemption
-        // disabled. So we need to set `current_disable_count` to 1 in
-        // `can_preempt()` to obtain the preemption permission before
-        //  locking the run queue.
+        // have held the lock with both IRQs and preemption disabled. So we
+        // need to set `current_disable_count` to 1 in `can_preempt()` to
+        // obtain the preemption permission before locking the run queue.
         let can_preempt = curr.can_preempt(1);
 
         debug!(
@@ -91,6 +357,9 @@ impl AxRunQueue {
             axhal::misc::terminate();
         } else {
             curr.set_state(TaskState::Exited);
+            // release the bandwidth reserved by a deadline task
+            #[cfg(feature = "sched_edf")]
+            self.scheduler.set_params(curr.as_task_ref(), None);
             curr.notify_exit(exit_code, self);
             EXITED_TASKS.lock().push_back(curr.clone());
             WAIT_FOR_EXIT.notify_one_locked(false, self);
@@ -119,12 +388,27 @@ impl AxRunQueue {
 
     pub fn unblock_task(&mut self, task: AxTaskRef, resched: bool) {
         debug!("task unblock: {}", task.id_name());
-        if task.is_blocked() {
-            task.set_state(TaskState::Ready);
-            self.scheduler.add_task(task); // TODO: priority
-            if resched {
-                #[cfg(feature = "preempt")]
-                crate::current().set_preempt_pending(true);
+        // Other CPUs may try to wake it up at the same time.
+        if task.transition_state(TaskState::Blocked, TaskState::Ready) {
+            // It may be still switching out on its CPU, so only that CPU can
+            // put it into the run queue.
+            let cpu_id = task.cpu_id();
+            if cpu_id != self.cpu_id {
+                wake_remote(cpu_id, task);
+            } else if task.is_allowed_on(cpu_id) {
+                let resched = resched
+                    || self
+                        .scheduler
+                        .should_preempt(&task, crate::current().as_task_ref());
+                self.enqueue(task);
+                if resched {
+                    #[cfg(feature = "preempt")]
+                    crate::current().set_preempt_pending(true);
+                }
+                #[cfg(feature = "tickless")]
+                self.program_timer(crate::current().as_task_ref());
+            } else {
+                wake_remote(select_cpu(&task), task);
             }
         }
     }
@@ -138,14 +422,97 @@ impl AxRunQueue {
 
         let now = axhal::time::current_time();
         if now < deadline {
-            crate::timers::set_alarm_wakeup(deadline, curr.clone());
+            // Block first, the alarm may go off on another CPU right away.
             curr.set_state(TaskState::Blocked);
+            crate::timers::set_alarm_wakeup(deadline, curr.clone());
             self.resched(false);
         }
     }
 }
 
 impl AxRunQueue {
+    fn enqueue(&mut self, task: AxTaskRef) {
+        task.set_cpu_id(self.cpu_id);
+        nr_ready(self.cpu_id).fetch_add(1, Ordering::Relaxed);
+        self.scheduler.add_task(task);
+    }
+
+    /// Moves the tasks in the wake list into the run queue.
+    fn drain_wake_list(&mut self) {
+        let tasks = core::mem::take(&mut *unsafe { WAKE_LIST.remote_ref_raw(self.cpu_id) }.lock());
+        for task in tasks {
+            if task.is_allowed_on(self.cpu_id) {
+                // already counted in `wake_remote()`
+                self.scheduler.add_task(task);
+            } else {
+                nr_ready(self.cpu_id).fetch_sub(1, Ordering::Relaxed);
+                wake_remote(select_cpu(&task), task);
+            }
+        }
+    }
+
+    /// Picks the next task allowed to run on this CPU, and moves away the
+    /// tasks whose affinity has been changed.
+    fn pick_next_task(&mut self) -> Option<AxTaskRef> {
+        while let Some(task) = self.scheduler.pick_next_task() {
+            nr_ready(self.cpu_id).fetch_sub(1, Ordering::Relaxed);
+            if task.is_allowed_on(self.cpu_id) {
+                return Some(task);
+            }
+            wake_remote(select_cpu(&task), task);
+        }
+        None
+    }
+
+    /// Takes a ready task that is allowed to run on this CPU from the busiest
+    /// other CPU whose run queue is not locked at the moment.
+    fn steal_task(&mut self) -> Option<AxTaskRef> {
+        let this = self.cpu_id;
+        let busiest = (0..axconfig::SMP)
+            .filter(|&cpu| cpu != this && online_cpus() & (1 << cpu) != 0)
+            .max_by_key(|&cpu| nr_ready(cpu).load(Ordering::Relaxed))?;
+        if nr_ready(busiest).load(Ordering::Relaxed) == 0 {
+            return None;
+        }
+
+        // never spin on another run queue while holding ours
+        let mut victim = unsafe { RUN_QUEUE.remote_ref_raw(busiest).get_unchecked() }.try_lock()?;
+        let filter = |t: &AxTaskRef| t.is_allowed_on(this);
+        let task = victim.scheduler.steal_task(&filter).or_else(|| {
+            let mut wake_list = unsafe { WAKE_LIST.remote_ref_raw(busiest) }.lock();
+            let idx = wake_list.iter().position(filter)?;
+            wake_list.remove(idx)
+        })?;
+        nr_ready(busiest).fetch_sub(1, Ordering::Relaxed);
+        drop(victim);
+
+        debug!(
+            "task migrate: {}, CPU {} -> {}",
+            task.id_name(),
+            busiest,
+            this
+        );
+        task.set_cpu_id(this);
+        Some(task)
+    }
+
+    /// Pulls a task from the busiest CPU if it has at least two more ready
+    /// tasks than this one.
+    #[cfg(feature = "irq")]
+    fn balance_load(&mut self) {
+        let load = nr_ready(self.cpu_id).load(Ordering::Relaxed);
+        let max_load = (0..axconfig::SMP)
+            .filter(|&cpu| online_cpus() & (1 << cpu) != 0)
+            .map(|cpu| nr_ready(cpu).load(Ordering::Relaxed))
+            .max()
+            .unwrap_or(0);
+        if max_load > load + 1 {
+            if let Some(task) = self.steal_task() {
+                self.enqueue(task);
+            }
+        }
+    }
+
     /// Common reschedule subroutine. If `preempt`, keep current task's time
     /// slice, otherwise reset it.
     fn resched(&mut self, preempt: bool) {
@@ -153,13 +520,22 @@ impl AxRunQueue {
         if prev.is_running() {
             prev.set_state(TaskState::Ready);
             if !prev.is_idle() {
-                self.scheduler.put_prev_task(prev.clone(), preempt);
+                if prev.is_allowed_on(self.cpu_id) {
+                    nr_ready(self.cpu_id).fetch_add(1, Ordering::Relaxed);
+                    self.scheduler.put_prev_task(prev.clone(), preempt);
+                } else {
+                    self.migrating = Some(prev.clone());
+                }
             }
         }
-        let next = self.scheduler.pick_next_task().unwrap_or_else(|| unsafe {
-            // Safety: IRQs must be disabled at this time.
-            IDLE_TASK.current_ref_raw().get_unchecked().clone()
-        });
+        self.drain_wake_list();
+        let next = self
+            .pick_next_task()
+            .or_else(|| self.steal_task())
+            .unwrap_or_else(|| unsafe {
+                // Safety: IRQs must be disabled at this time.
+                IDLE_TASK.current_ref_raw().get_unchecked().clone()
+            });
         self.switch_to(prev, next);
     }
 
@@ -172,9 +548,18 @@ impl AxRunQueue {
         #[cfg(feature = "preempt")]
         next_task.set_preempt_pending(false);
         next_task.set_state(TaskState::Running);
+        // the time slice may have been reset even if it keeps running
+        #[cfg(feature = "tickless")]
+        self.program_timer(&next_task);
         if prev_task.ptr_eq(&next_task) {
             return;
         }
+        #[cfg(not(feature = "paging"))]
+        prev_task.check_stack_overflow();
+
+        let now = axhal::time::current_time_nanos();
+        prev_task.account_switch(false, now);
+        next_task.account_switch(true, now);
 
         unsafe {
             let prev_ctx_ptr = prev_task.ctx_mut_ptr();
@@ -199,6 +584,12 @@ fn gc_entry() {
             // Do not do the slow drops in the critical section.
             let task = EXITED_TASKS.lock().pop_front();
             if let Some(task) = task {
+                // Wait until the CPU it exited on has switched away from its
+                // stack, which is done before the run queue is unlocked.
+                {
+                    let _guard = NoPreemptIrqSave::new();
+                    drop(unsafe { RUN_QUEUE.remote_ref_raw(task.cpu_id()).get_unchecked() }.lock());
+                }
                 if Arc::strong_count(&task) == 1 {
                     // If I'm the last holder of the task, drop it immediately.
                     drop(task);
@@ -213,21 +604,118 @@ fn gc_entry() {
     }
 }
 
+/// Sets the CPUs that `task` is allowed to run on.
+///
+/// A running task moves to an allowed CPU at its next reschedule, and a ready
+/// or blocked one when it is picked or woken up.
+pub(crate) fn set_affinity(task: &AxTaskRef, mask: CpuMask) {
+    task.set_cpumask(mask);
+    let curr = crate::current();
+    if curr.ptr_eq(task) {
+        if !task.is_allowed_on(task.cpu_id()) {
+            current_run_queue().yield_current();
+        }
+    } else {
+        #[cfg(feature = "preempt")]
+        if task.is_running() && !task.is_allowed_on(task.cpu_id()) {
+            task.set_preempt_pending(true);
+        }
+    }
+}
+
+/// Locks the run queue of the CPU that `task` is on, and calls `f` with it.
+fn with_task_run_queue<R>(task: &AxTaskRef, f: impl FnOnce(&mut AxRunQueue) -> R) -> R {
+    let _guard = NoPreemptIrqSave::new();
+    loop {
+        let cpu_id = task.cpu_id();
+        let mut rq = unsafe { RUN_QUEUE.remote_ref_raw(cpu_id).get_unchecked() }.lock();
+        // it may have been moved to another CPU before we got the lock
+        if task.cpu_id() == cpu_id {
+            return f(&mut rq);
+        }
+    }
+}
+
+pub(crate) fn set_sched_policy(task: &AxTaskRef, policy: SchedPolicy, prio: isize) -> bool {
+    with_task_run_queue(task, |rq| {
+        if !rq.set_task_policy(task, policy, prio) {
+            return false;
+        }
+        // let the highest priority task run, the current one may be lowered
+        if rq.cpu_id == axhal::cpu::this_cpu_id() {
+            #[cfg(feature = "preempt")]
+            crate::current().set_preempt_pending(true);
+        }
+        true
+    })
+}
+
+pub(crate) fn inherit_priority(
+    owner: &AxTaskRef,
+    lock_id: usize,
+    holds_lock: impl FnOnce() -> bool,
+) {
+    let params = crate::current().sched_params();
+    with_task_run_queue(owner, |rq| {
+        if holds_lock() {
+            rq.update_task_priority(owner, |p| p.inherit(lock_id, params));
+        }
+    });
+}
+
+pub(crate) fn restore_priority(lock_id: usize) {
+    let curr = crate::current();
+    with_task_run_queue(curr.as_task_ref(), |rq| {
+        if rq.update_task_priority(curr.as_task_ref(), |p| p.disinherit(lock_id)) {
+            // let the waiters more important than us run
+            #[cfg(feature = "preempt")]
+            curr.set_preempt_pending(true);
+        }
+    });
+}
+
+#[cfg(feature = "sched_edf")]
+pub(crate) fn set_deadline(task: &AxTaskRef, params: Option<scheduler::EdfParams>) -> bool {
+    with_task_run_queue(task, |rq| {
+        // it is leaving this CPU for its new affinity
+        if params.is_some() && !task.is_allowed_on(rq.cpu_id) {
+            return false;
+        }
+        if !rq.scheduler.set_params(task, params) {
+            return false;
+        }
+        // the earliest deadline may have changed
+        if rq.cpu_id == axhal::cpu::this_cpu_id() {
+            crate::current().set_preempt_pending(true);
+        }
+        true
+    })
+}
+
 pub(crate) fn init() {
     const IDLE_TASK_STACK_SIZE: usize = 4096;
+    let cpu_id = axhal::cpu::this_cpu_id();
     let idle_task = TaskInner::new(|| crate::run_idle(), "idle".into(), IDLE_TASK_STACK_SIZE);
     IDLE_TASK.with_current(|i| i.init_by(idle_task.clone()));
 
     let main_task = TaskInner::new_init("main".into());
     main_task.set_state(TaskState::Running);
 
-    RUN_QUEUE.init_by(AxRunQueue::new());
+    let gc_task = TaskInner::new(gc_entry, "gc".into(), axconfig::TASK_STACK_SIZE);
+    let mut rq = AxRunQueue::new(cpu_id);
+    rq.get_mut().enqueue(gc_task);
+    RUN_QUEUE.with_current(|r| r.init_by(rq));
+    ONLINE_CPUS.fetch_or(1 << cpu_id, Ordering::Release);
     unsafe { CurrentTask::init_current(main_task) }
 }
 
 pub(crate) fn init_secondary() {
+    let cpu_id = axhal::cpu::this_cpu_id();
     let idle_task = TaskInner::new_init("idle".into());
     idle_task.set_state(TaskState::Running);
     IDLE_TASK.with_current(|i| i.init_by(idle_task.clone()));
+
+    RUN_QUEUE.with_current(|r| r.init_by(AxRunQueue::new(cpu_id)));
+    ONLINE_CPUS.fetch_or(1 << cpu_id, Ordering::Release);
     unsafe { CurrentTask::init_current(idle_task) }
 }
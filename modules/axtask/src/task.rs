@@ -1,33 +1,163 @@
-use alloc::{boxed::Box, string::String, sync::Arc};
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::any::Any;
 use core::ops::Deref;
-use core::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicU8, Ordering};
-use core::{alloc::Layout, cell::UnsafeCell, fmt, ptr::NonNull};
-
-#[cfg(feature = "preempt")]
-use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::{
+    AtomicBool, AtomicI32, AtomicU16, AtomicU64, AtomicU8, AtomicUsize, Ordering,
+};
+use core::{cell::UnsafeCell, fmt, ptr::NonNull, time::Duration};
 
 #[cfg(feature = "tls")]
 use axhal::tls::TlsArea;
 
 use axhal::arch::TaskContext;
 use memory_addr::{align_up_4k, VirtAddr};
+use scheduler::SchedPolicy;
+use spinlock::SpinNoIrq;
+
+use crate::{AxRunQueue, AxTask, AxTaskRef, CpuMask, Credentials, WaitQueue};
+
+/// The file mode creation mask of the first tasks.
+const DEFAULT_UMASK: u16 = 0o022;
+
+/// The timer slack (in nanoseconds) of the first tasks.
+#[cfg(feature = "irq")]
+const DEFAULT_TIMER_SLACK_NS: u64 = 50_000;
+
+/// The value that the task stacks are filled with, to find out how much of
+/// them has been used.
+#[cfg(feature = "stack_usage")]
+const STACK_FILL: u64 = 0x5a5a_5a5a_5a5a_5a5a;
 
-use crate::{AxRunQueue, AxTask, AxTaskRef, WaitQueue};
+/// The value at the bottom of the task stacks without guard pages, which is
+/// overwritten when a task overflows its stack.
+#[cfg(not(feature = "paging"))]
+const STACK_CANARY: u64 = 0x57ac_ca4a_57ac_ca4a;
+
+/// All the tasks that are not dropped yet, indexed by their IDs.
+static TASKS: SpinNoIrq<BTreeMap<u64, Weak<AxTask>>> = SpinNoIrq::new(BTreeMap::new());
 
 /// A unique identifier for a thread.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct TaskId(u64);
 
+/// The error returned by [`TaskInner::join`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoinError {
+    /// The task is detached, so it cannot be joined.
+    Detached,
+    /// The task was terminated by a panic, with the panic message.
+    Panicked(String),
+}
+
 /// The possible states of a task.
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
-pub(crate) enum TaskState {
+pub enum TaskState {
+    /// Running on a CPU.
     Running = 1,
+    /// Waiting in a run queue to run.
     Ready = 2,
+    /// Waiting for an event, e.g., in a wait queue or sleeping.
     Blocked = 3,
+    /// Exited, but not dropped yet.
     Exited = 4,
 }
 
+/// A snapshot of a task, returned by [`tasks`](crate::tasks).
+#[derive(Debug, Clone)]
+pub struct TaskInfo {
+    /// The task ID.
+    pub id: TaskId,
+    /// The task name.
+    pub name: String,
+    /// The task state.
+    pub state: TaskState,
+    /// The CPU that the task runs on, or ran on last.
+    pub cpu_id: usize,
+    /// The scheduling policy, which may be raised by priority inheritance.
+    pub policy: SchedPolicy,
+    /// The static priority of real-time tasks.
+    pub rt_priority: isize,
+    /// The nice value of normal tasks.
+    pub nice: isize,
+    /// The total time that the task has been running.
+    pub runtime: Duration,
+    /// The number of times that the task has been switched out.
+    pub context_switches: u64,
+    /// The size of the kernel stack, or 0 if the task runs on a boot stack.
+    pub stack_size: usize,
+    /// The most bytes of the kernel stack that have been used, or 0 without
+    /// the `stack_usage` feature.
+    pub stack_max_used: usize,
+}
+
+/// The scheduling policy, static priority and nice value of a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SchedParams {
+    pub policy: SchedPolicy,
+    pub rt_priority: isize,
+    pub nice: isize,
+}
+
+impl SchedParams {
+    const NORMAL: Self = Self {
+        policy: SchedPolicy::Normal,
+        rt_priority: 0,
+        nice: 0,
+    };
+
+    /// Returns a value which is larger if the task should run first: real-time
+    /// tasks by their static priorities, then normal tasks by their nice
+    /// values.
+    pub fn rank(&self) -> isize {
+        if self.policy.is_realtime() {
+            100 + self.rt_priority
+        } else {
+            -self.nice
+        }
+    }
+}
+
+/// The scheduling parameters set for a task, and the ones lent to it by the
+/// tasks waiting for the locks it holds.
+pub(crate) struct TaskPriority {
+    pub base: SchedParams,
+    inherited: Vec<(usize, SchedParams)>, // (lock id, params)
+}
+
+impl TaskPriority {
+    /// Returns the parameters that the task runs with, which are the highest
+    /// of its own and the inherited ones.
+    fn effective(&self) -> SchedParams {
+        self.inherited
+            .iter()
+            .map(|(_, params)| *params)
+            .fold(self.base, |max, params| {
+                if params.rank() > max.rank() {
+                    params
+                } else {
+                    max
+                }
+            })
+    }
+
+    /// Lends `params` to the task through the lock `lock_id`.
+    pub fn inherit(&mut self, lock_id: usize, params: SchedParams) {
+        match self.inherited.iter_mut().find(|(id, _)| *id == lock_id) {
+            Some((_, lent)) if params.rank() > lent.rank() => *lent = params,
+            Some(_) => {}
+            None => self.inherited.push((lock_id, params)),
+        }
+    }
+
+    /// Takes back all the parameters lent through the lock `lock_id`.
+    pub fn disinherit(&mut self, lock_id: usize) {
+        self.inherited.retain(|(id, _)| *id != lock_id);
+    }
+}
+
 /// The inner task structure.
 pub struct TaskInner {
     id: TaskId,
@@ -35,20 +165,48 @@ pub struct TaskInner {
     is_idle: bool,
     is_init: bool,
 
-    entry: Option<*mut dyn FnOnce()>,
+    entry: UnsafeCell<Option<*mut dyn FnOnce()>>,
     state: AtomicU8,
 
+    /// The CPU whose run queue the task is in, or that it runs or ran on last.
+    cpu_id: AtomicUsize,
+    cpumask: AtomicUsize,
+
     in_wait_queue: AtomicBool,
     #[cfg(feature = "irq")]
     in_timer_list: AtomicBool,
+    /// The CPU whose timer list the task was put in last.
+    #[cfg(feature = "irq")]
+    timer_cpu: AtomicUsize,
+    /// How late (in nanoseconds) its timed waits may be woken up, so that the
+    /// wakeups close in time can be coalesced.
+    #[cfg(feature = "irq")]
+    timer_slack_ns: AtomicU64,
 
     #[cfg(feature = "preempt")]
     need_resched: AtomicBool,
     #[cfg(feature = "preempt")]
     preempt_disable_count: AtomicUsize,
 
+    /// Time spent running, and the time it was switched in last, in
+    /// nanoseconds.
+    runtime_ns: AtomicU64,
+    switched_in_ns: AtomicU64,
+    context_switches: AtomicU64,
+
     exit_code: AtomicI32,
     wait_for_exit: WaitQueue,
+    detached: AtomicBool,
+    panicking: AtomicBool,
+    panic_message: SpinNoIrq<Option<String>>,
+
+    /// Task-local values, only accessed by the task itself.
+    locals: UnsafeCell<BTreeMap<usize, Box<dyn Any>>>,
+
+    cred: SpinNoIrq<Credentials>,
+    umask: AtomicU16,
+
+    priority: SpinNoIrq<TaskPriority>,
 
     kstack: Option<TaskStack>,
     ctx: UnsafeCell<TaskContext>,
@@ -101,13 +259,81 @@ impl TaskInner {
         alloc::format!("Task({}, {:?})", self.id.as_u64(), self.name)
     }
 
+    /// Returns the ID of the CPU that the task runs on, or ran on last.
+    pub fn cpu_id(&self) -> usize {
+        self.cpu_id.load(Ordering::Acquire)
+    }
+
+    /// Returns the set of CPUs that the task is allowed to run on.
+    pub fn cpumask(&self) -> CpuMask {
+        self.cpumask.load(Ordering::Acquire)
+    }
+
+    /// Returns a copy of the credentials of the task.
+    pub fn credentials(&self) -> Credentials {
+        self.cred.lock().clone()
+    }
+
+    /// Replaces the credentials of the task.
+    pub fn set_credentials(&self, cred: Credentials) {
+        *self.cred.lock() = cred;
+    }
+
+    /// Returns the file mode creation mask of the task.
+    pub fn umask(&self) -> u16 {
+        self.umask.load(Ordering::Relaxed)
+    }
+
+    /// Sets the file mode creation mask of the task to `mask & 0o777`, and
+    /// returns the previous mask.
+    pub fn set_umask(&self, mask: u16) -> u16 {
+        self.umask.swap(mask & 0o777, Ordering::Relaxed)
+    }
+
+    /// Returns the timer slack of the task, by which its timed waits (e.g.,
+    /// sleeps) may be woken up late, to be coalesced with other timer events.
+    #[cfg(feature = "irq")]
+    pub fn timer_slack(&self) -> Duration {
+        Duration::from_nanos(self.timer_slack_ns.load(Ordering::Relaxed))
+    }
+
+    /// Sets the timer slack of the task. The new tasks spawned by it inherit
+    /// the timer slack, which is 50 µs by default.
+    #[cfg(feature = "irq")]
+    pub fn set_timer_slack(&self, slack: Duration) {
+        let slack_ns = slack.as_nanos().min(u64::MAX as u128) as u64;
+        self.timer_slack_ns.store(slack_ns, Ordering::Relaxed);
+    }
+
     /// Wait for the task to exit, and return the exit code.
     ///
     /// It will return immediately if the task has already exited (but not dropped).
-    pub fn join(&self) -> Option<i32> {
+    /// Returns an error if the task is detached, or it was terminated by a
+    /// panic.
+    pub fn join(&self) -> Result<i32, JoinError> {
+        if self.is_detached() {
+            return Err(JoinError::Detached);
+        }
         self.wait_for_exit
             .wait_until(|| self.state() == TaskState::Exited);
-        Some(self.exit_code.load(Ordering::Acquire))
+        match self.panic_message.lock().clone() {
+            Some(message) => Err(JoinError::Panicked(message)),
+            None => Ok(self.exit_code.load(Ordering::Acquire)),
+        }
+    }
+
+    /// Detaches the task, so that nobody is going to [`join`] it.
+    ///
+    /// Returns `false` if it was already detached.
+    ///
+    /// [`join`]: TaskInner::join
+    pub fn detach(&self) -> bool {
+        !self.detached.swap(true, Ordering::AcqRel)
+    }
+
+    /// Whether the task is detached.
+    pub fn is_detached(&self) -> bool {
+        self.detached.load(Ordering::Acquire)
     }
 }
 
@@ -119,17 +345,36 @@ impl TaskInner {
             name,
             is_idle: false,
             is_init: false,
-            entry: None,
+            entry: UnsafeCell::new(None),
             state: AtomicU8::new(TaskState::Ready as u8),
+            cpu_id: AtomicUsize::new(axhal::cpu::this_cpu_id()),
+            cpumask: AtomicUsize::new(CpuMask::MAX),
             in_wait_queue: AtomicBool::new(false),
             #[cfg(feature = "irq")]
             in_timer_list: AtomicBool::new(false),
+            #[cfg(feature = "irq")]
+            timer_cpu: AtomicUsize::new(0),
+            #[cfg(feature = "irq")]
+            timer_slack_ns: AtomicU64::new(DEFAULT_TIMER_SLACK_NS),
             #[cfg(feature = "preempt")]
             need_resched: AtomicBool::new(false),
             #[cfg(feature = "preempt")]
             preempt_disable_count: AtomicUsize::new(0),
+            runtime_ns: AtomicU64::new(0),
+            switched_in_ns: AtomicU64::new(0),
+            context_switches: AtomicU64::new(0),
             exit_code: AtomicI32::new(0),
             wait_for_exit: WaitQueue::new(),
+            detached: AtomicBool::new(false),
+            panicking: AtomicBool::new(false),
+            panic_message: SpinNoIrq::new(None),
+            locals: UnsafeCell::new(BTreeMap::new()),
+            cred: SpinNoIrq::new(Credentials::root()),
+            umask: AtomicU16::new(DEFAULT_UMASK),
+            priority: SpinNoIrq::new(TaskPriority {
+                base: SchedParams::NORMAL,
+                inherited: Vec::new(),
+            }),
             kstack: None,
             ctx: UnsafeCell::new(TaskContext::new()),
             #[cfg(feature = "tls")]
@@ -144,6 +389,16 @@ impl TaskInner {
     {
         let mut t = Self::new_common(TaskId::new(), name);
         debug!("new task: {}", t.id_name());
+        // inherit the credentials, umask and CPU affinity of the spawning task
+        if let Some(curr) = crate::current_may_uninit() {
+            *t.cred.get_mut() = curr.credentials();
+            *t.umask.get_mut() = curr.umask();
+            *t.cpumask.get_mut() = curr.cpumask();
+            #[cfg(feature = "irq")]
+            {
+                *t.timer_slack_ns.get_mut() = curr.timer_slack_ns.load(Ordering::Relaxed);
+            }
+        }
         let kstack = TaskStack::alloc(align_up_4k(stack_size));
 
         #[cfg(feature = "tls")]
@@ -151,13 +406,13 @@ impl TaskInner {
         #[cfg(not(feature = "tls"))]
         let tls = VirtAddr::from(0);
 
-        t.entry = Some(Box::into_raw(Box::new(entry)));
+        *t.entry.get_mut() = Some(Box::into_raw(Box::new(entry)));
         t.ctx.get_mut().init(task_entry as usize, kstack.top(), tls);
         t.kstack = Some(kstack);
         if t.name == "idle" {
             t.is_idle = true;
         }
-        Arc::new(AxTask::new(t))
+        register(Arc::new(AxTask::new(t)))
     }
 
     /// Creates an "init task" using the current CPU states, to use as the
@@ -174,7 +429,69 @@ impl TaskInner {
         if t.name == "idle" {
             t.is_idle = true;
         }
-        Arc::new(AxTask::new(t))
+        // it is running already
+        *t.switched_in_ns.get_mut() = axhal::time::current_time_nanos();
+        register(Arc::new(AxTask::new(t)))
+    }
+
+    /// Takes a snapshot of the task.
+    pub(crate) fn info(&self) -> TaskInfo {
+        let params = self.sched_params();
+        let mut runtime = self.runtime_ns.load(Ordering::Acquire);
+        if self.is_running() {
+            let now = axhal::time::current_time_nanos();
+            runtime += now.saturating_sub(self.switched_in_ns.load(Ordering::Acquire));
+        }
+        let stack_size = self.kstack.as_ref().map_or(0, |kstack| kstack.size());
+        #[cfg(feature = "stack_usage")]
+        let stack_max_used = self.kstack.as_ref().map_or(0, |kstack| kstack.max_used());
+        #[cfg(not(feature = "stack_usage"))]
+        let stack_max_used = 0;
+        TaskInfo {
+            id: self.id,
+            name: self.name.clone(),
+            state: self.state(),
+            cpu_id: self.cpu_id(),
+            policy: params.policy,
+            rt_priority: params.rt_priority,
+            nice: params.nice,
+            runtime: Duration::from_nanos(runtime),
+            context_switches: self.context_switches.load(Ordering::Relaxed),
+            stack_size,
+            stack_max_used,
+        }
+    }
+
+    /// Accounts the running time of the task when it is switched in or out
+    /// at `now` (in nanoseconds).
+    pub(crate) fn account_switch(&self, switch_in: bool, now: u64) {
+        if switch_in {
+            self.switched_in_ns.store(now, Ordering::Release);
+        } else {
+            let runtime = now.saturating_sub(self.switched_in_ns.load(Ordering::Acquire));
+            self.runtime_ns.fetch_add(runtime, Ordering::AcqRel);
+            self.context_switches.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Panics if the task has overflowed its stack, i.e., the canary at the
+    /// bottom of the stack has been overwritten.
+    ///
+    /// It is too late to terminate the task alone, as the memory below the
+    /// stack may have been corrupted.
+    #[cfg(not(feature = "paging"))]
+    pub(crate) fn check_stack_overflow(&self) {
+        if self.kstack.as_ref().is_some_and(|s| s.is_overflowed()) {
+            // not to be terminated alone by the panic handler
+            self.start_panicking();
+            panic!("task stack overflow: {}", self.id_name());
+        }
+    }
+
+    /// Whether `vaddr` is in the guard page below the stack of the task.
+    #[cfg(feature = "paging")]
+    pub(crate) fn is_stack_guard(&self, vaddr: VirtAddr) -> bool {
+        self.kstack.as_ref().is_some_and(|s| s.is_guard(vaddr))
     }
 
     #[inline]
@@ -187,6 +504,53 @@ impl TaskInner {
         self.state.store(state as u8, Ordering::Release)
     }
 
+    /// Changes the state from `from` to `to`, and returns `false` if the task
+    /// is not in `from` state.
+    #[inline]
+    pub(crate) fn transition_state(&self, from: TaskState, to: TaskState) -> bool {
+        self.state
+            .compare_exchange(from as u8, to as u8, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    #[inline]
+    pub(crate) fn set_cpu_id(&self, cpu_id: usize) {
+        self.cpu_id.store(cpu_id, Ordering::Release)
+    }
+
+    #[inline]
+    pub(crate) fn set_cpumask(&self, mask: CpuMask) {
+        self.cpumask.store(mask, Ordering::Release)
+    }
+
+    #[inline]
+    pub(crate) fn is_allowed_on(&self, cpu_id: usize) -> bool {
+        self.cpumask() & (1 << cpu_id) != 0
+    }
+
+    /// Returns the scheduling parameters set for the task.
+    pub(crate) fn base_sched_params(&self) -> SchedParams {
+        self.priority.lock().base
+    }
+
+    /// Returns the scheduling parameters that the task runs with, which may be
+    /// raised by priority inheritance.
+    pub(crate) fn sched_params(&self) -> SchedParams {
+        self.priority.lock().effective()
+    }
+
+    /// Changes the priority of the task with `f`, and returns the scheduling
+    /// parameters that it runs with before and after.
+    pub(crate) fn update_priority(
+        &self,
+        f: impl FnOnce(&mut TaskPriority),
+    ) -> (SchedParams, SchedParams) {
+        let mut priority = self.priority.lock();
+        let old = priority.effective();
+        f(&mut priority);
+        (old, priority.effective())
+    }
+
     #[inline]
     pub(crate) fn is_running(&self) -> bool {
         matches!(self.state(), TaskState::Running)
@@ -197,11 +561,6 @@ impl TaskInner {
         matches!(self.state(), TaskState::Ready)
     }
 
-    #[inline]
-    pub(crate) fn is_blocked(&self) -> bool {
-        matches!(self.state(), TaskState::Blocked)
-    }
-
     #[inline]
     pub(crate) const fn is_init(&self) -> bool {
         self.is_init
@@ -234,6 +593,18 @@ impl TaskInner {
         self.in_timer_list.store(in_timer_list, Ordering::Release);
     }
 
+    #[inline]
+    #[cfg(feature = "irq")]
+    pub(crate) fn timer_cpu(&self) -> usize {
+        self.timer_cpu.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    #[cfg(feature = "irq")]
+    pub(crate) fn set_timer_cpu(&self, cpu_id: usize) {
+        self.timer_cpu.store(cpu_id, Ordering::Release);
+    }
+
     #[inline]
     #[cfg(feature = "preempt")]
     pub(crate) fn set_preempt_pending(&self, pending: bool) {
@@ -265,13 +636,64 @@ impl TaskInner {
     fn current_check_preempt_pending() {
         let curr = crate::current();
         if curr.need_resched.load(Ordering::Acquire) && curr.can_preempt(0) {
-            let mut rq = crate::RUN_QUEUE.lock();
+            let mut rq = crate::current_run_queue();
             if curr.need_resched.load(Ordering::Acquire) {
                 rq.preempt_resched();
             }
         }
     }
 
+    /// Marks the current task as panicking, and returns `false` if it is
+    /// panicking already.
+    pub(crate) fn start_panicking(&self) -> bool {
+        !self.panicking.swap(true, Ordering::AcqRel)
+    }
+
+    pub(crate) fn set_panic_message(&self, message: String) {
+        *self.panic_message.lock() = Some(message);
+    }
+
+    /// Returns the task-local value for `key` of the current task, initializing
+    /// it with `init` first if it is absent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `init` initializes the same value recursively.
+    pub(crate) fn local_value(&self, key: usize, init: impl FnOnce() -> Box<dyn Any>) -> &dyn Any {
+        // Safety: only the current task accesses its task-local values, and
+        // the boxed values do not move when the map is modified.
+        if let Some(value) = unsafe { &*self.locals.get() }.get(&key) {
+            return unsafe { &*(&**value as *const dyn Any) };
+        }
+        // it may access other task-local values
+        let value = init();
+        let locals = unsafe { &mut *self.locals.get() };
+        assert!(
+            !locals.contains_key(&key),
+            "task-local value initialized recursively"
+        );
+        let value = &**locals.entry(key).or_insert(value) as *const dyn Any;
+        unsafe { &*value }
+    }
+
+    /// Drops the task-local values of the current task, including the ones
+    /// created by the destructors of others.
+    pub(crate) fn drop_locals(&self) {
+        loop {
+            let locals = core::mem::take(unsafe { &mut *self.locals.get() });
+            if locals.is_empty() {
+                break;
+            }
+            drop(locals);
+        }
+    }
+
+    /// Forgets the task-local values of the current task without running
+    /// their destructors.
+    pub(crate) fn leak_locals(&self) {
+        core::mem::forget(core::mem::take(unsafe { &mut *self.locals.get() }));
+    }
+
     pub(crate) fn notify_exit(&self, exit_code: i32, rq: &mut AxRunQueue) {
         self.exit_code.store(exit_code, Ordering::Release);
         self.wait_for_exit.notify_all_locked(false, rq);
@@ -296,34 +718,108 @@ impl fmt::Debug for TaskInner {
 impl Drop for TaskInner {
     fn drop(&mut self) {
         debug!("task drop: {}", self.id_name());
+        TASKS.lock().remove(&self.id.as_u64());
+        // the task never ran, e.g., it failed to be spawned
+        if let Some(entry) = self.entry.get_mut().take() {
+            drop(unsafe { Box::from_raw(entry) });
+        }
     }
 }
 
+/// The stack of a task.
+///
+/// With the `paging` feature, it is mapped with a guard page below it, so an
+/// overflow faults before corrupting other memory. Otherwise it is allocated
+/// from the heap, with a canary at the bottom that is checked on context
+/// switches.
 struct TaskStack {
     ptr: NonNull<u8>,
-    layout: Layout,
+    size: usize,
 }
 
 impl TaskStack {
     pub fn alloc(size: usize) -> Self {
-        let layout = Layout::from_size_align(size, 16).unwrap();
-        Self {
-            ptr: NonNull::new(unsafe { alloc::alloc::alloc(layout) }).unwrap(),
-            layout,
+        #[cfg(feature = "paging")]
+        let ptr = axmm::alloc_kernel_stack(size)
+            .expect("failed to allocate the task stack")
+            .as_mut_ptr();
+        #[cfg(not(feature = "paging"))]
+        let ptr = unsafe { alloc::alloc::alloc(Self::layout(size)) };
+        let ptr = NonNull::new(ptr).unwrap();
+
+        #[cfg(feature = "stack_usage")]
+        for i in 0..size / 8 {
+            unsafe { (ptr.as_ptr() as *mut u64).add(i).write(STACK_FILL) };
         }
+        #[cfg(not(feature = "paging"))]
+        unsafe {
+            (ptr.as_ptr() as *mut u64).write(STACK_CANARY)
+        };
+        Self { ptr, size }
+    }
+
+    #[cfg(not(feature = "paging"))]
+    fn layout(size: usize) -> core::alloc::Layout {
+        core::alloc::Layout::from_size_align(size, 16).unwrap()
     }
 
     pub const fn top(&self) -> VirtAddr {
-        unsafe { core::mem::transmute(self.ptr.as_ptr().add(self.layout.size())) }
+        unsafe { core::mem::transmute(self.ptr.as_ptr().add(self.size)) }
+    }
+
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the most bytes that have been used, by finding the lowest word
+    /// that has been overwritten, as the stack grows downwards.
+    #[cfg(feature = "stack_usage")]
+    pub fn max_used(&self) -> usize {
+        let words = self.ptr.as_ptr() as *const u64;
+        let first = if cfg!(feature = "paging") { 0 } else { 1 };
+        let unused = (first..self.size / 8)
+            .take_while(|&i| unsafe { words.add(i).read_volatile() } == STACK_FILL)
+            .count();
+        self.size - unused * 8
+    }
+
+    /// Whether the canary at the bottom of the stack has been overwritten.
+    #[cfg(not(feature = "paging"))]
+    pub fn is_overflowed(&self) -> bool {
+        unsafe { (self.ptr.as_ptr() as *const u64).read_volatile() != STACK_CANARY }
+    }
+
+    /// Whether `vaddr` is in the guard page below the stack.
+    #[cfg(feature = "paging")]
+    pub fn is_guard(&self, vaddr: VirtAddr) -> bool {
+        let bottom = self.ptr.as_ptr() as usize;
+        (bottom - axmm::KSTACK_GUARD_SIZE..bottom).contains(&vaddr.as_usize())
     }
 }
 
 impl Drop for TaskStack {
     fn drop(&mut self) {
-        unsafe { alloc::alloc::dealloc(self.ptr.as_ptr(), self.layout) }
+        #[cfg(feature = "paging")]
+        axmm::dealloc_kernel_stack(VirtAddr::from(self.ptr.as_ptr() as usize));
+        #[cfg(not(feature = "paging"))]
+        unsafe {
+            alloc::alloc::dealloc(self.ptr.as_ptr(), Self::layout(self.size))
+        }
     }
 }
 
+fn register(task: AxTaskRef) -> AxTaskRef {
+    TASKS
+        .lock()
+        .insert(task.id().as_u64(), Arc::downgrade(&task));
+    task
+}
+
+/// Returns all the tasks that are not dropped yet, in the order of their IDs.
+pub(crate) fn all_tasks() -> Vec<AxTaskRef> {
+    TASKS.lock().values().filter_map(Weak::upgrade).collect()
+}
+
 use core::mem::ManuallyDrop;
 
 /// A wrapper of [`AxTaskRef`] as the current task.
@@ -380,12 +876,39 @@ impl Deref for CurrentTask {
 
 extern "C" fn task_entry() -> ! {
     // release the lock that was implicitly held across the reschedule
-    unsafe { crate::RUN_QUEUE.force_unlock() };
+    unsafe { crate::run_queue::unlock_current() };
     #[cfg(feature = "irq")]
     axhal::arch::enable_irqs();
     let task = crate::current();
-    if let Some(entry) = task.entry {
+    if let Some(entry) = unsafe { (*task.entry.get()).take() } {
         unsafe { Box::from_raw(entry)() };
     }
     crate::exit(0);
 }
+
+#[cfg(all(test, not(feature = "paging")))]
+mod tests {
+    use super::*;
+
+    /// Overwrites the bottom word of `stack`, as a task that overflows it does.
+    fn overflow(stack: &TaskStack) {
+        unsafe { (stack.ptr.as_ptr() as *mut u64).write_volatile(0) };
+    }
+
+    #[test]
+    fn test_stack_canary() {
+        let stack = TaskStack::alloc(0x1000);
+        assert!(!stack.is_overflowed());
+        overflow(&stack);
+        assert!(stack.is_overflowed());
+    }
+
+    #[test]
+    #[should_panic(expected = "task stack overflow: Task(")]
+    fn test_stack_overflow_panics() {
+        let task = TaskInner::new(|| {}, "overflow".into(), 0x1000);
+        task.check_stack_overflow();
+        overflow(task.kstack.as_ref().unwrap());
+        task.check_stack_overflow();
+    }
+}
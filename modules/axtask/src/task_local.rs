@@ -0,0 +1,70 @@
+//! Task-local storage.
+
+use alloc::boxed::Box;
+
+/// A key to the task-local values, which is declared by [`task_local!`].
+///
+/// Each task has its own value of the key, initialized lazily on the first
+/// [`with`] call of the task, and dropped when the task exits.
+///
+/// [`with`]: LocalKey::with
+/// [`task_local!`]: crate::task_local
+pub struct LocalKey<T: 'static> {
+    init: fn() -> T,
+}
+
+impl<T: 'static> LocalKey<T> {
+    #[doc(hidden)]
+    pub const fn new(init: fn() -> T) -> Self {
+        Self { init }
+    }
+
+    /// Acquires a reference to the value of the current task, initializing it
+    /// first if it has not been accessed by the task yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current task is not initialized, or the initializer
+    /// accesses the key recursively.
+    pub fn with<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        // statics never share an address
+        let key = self as *const Self as usize;
+        let curr = crate::current();
+        let value = curr.local_value(key, || Box::new((self.init)()));
+        f(value.downcast_ref().unwrap())
+    }
+}
+
+/// Declares new task-local storage keys of type [`LocalKey`].
+///
+/// It takes the same syntax as `std::thread_local!`, and the values are
+/// usually wrapped in `Cell` or `RefCell` to be changed.
+///
+/// # Examples
+///
+/// ```ignore
+/// use core::cell::Cell;
+///
+/// axtask::task_local! {
+///     static COUNTER: Cell<u32> = Cell::new(0);
+/// }
+///
+/// COUNTER.with(|c| c.set(c.get() + 1));
+/// ```
+#[macro_export]
+macro_rules! task_local {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr);* $(;)?) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::LocalKey<$t> = $crate::LocalKey::new({
+                fn __init() -> $t {
+                    $init
+                }
+                __init
+            });
+        )*
+    };
+}
@@ -1,7 +1,12 @@
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Mutex, Once};
 
-use crate::{self as axtask, current, WaitQueue};
+use crate::{self as axtask, current, AxTaskRef, JoinError, WaitQueue};
+
+/// The stack size of the tasks that print. Unoptimized builds of the BTreeMap
+/// operations in the run queues of CFS and the RT scheduler take most of 4K,
+/// which leaves too little for `println!`.
+const TEST_STACK_SIZE: usize = 0x4000;
 
 static INIT: Once = Once::new();
 static SERIAL: Mutex<()> = Mutex::new(());
@@ -23,7 +28,7 @@ fn test_sched_fifo() {
                 assert_eq!(order, i); // FIFO scheduler
             },
             format!("T{}", i),
-            0x1000,
+            TEST_STACK_SIZE,
         );
     }
 
@@ -38,6 +43,7 @@ fn test_fp_state_switch() {
     INIT.call_once(axtask::init_scheduler);
 
     const NUM_TASKS: usize = 5;
+    #[allow(clippy::approx_constant)]
     const FLOATS: [f64; NUM_TASKS] = [
         3.141592653589793,
         2.718281828459045,
@@ -120,11 +126,370 @@ fn test_task_join() {
                 axtask::exit(i as _);
             },
             format!("T{}", i),
-            0x1000,
+            TEST_STACK_SIZE,
         ));
     }
 
-    for i in 0..NUM_TASKS {
-        assert_eq!(tasks[i].join(), Some(i as _));
+    for (i, task) in tasks.iter().enumerate() {
+        assert_eq!(task.join(), Ok(i as _));
+    }
+}
+
+std::thread_local! {
+    static UNWINDING_TASK: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+}
+
+/// Spawns a task that is terminated like by the panic handler of the kernel
+/// if `f` panics, after the panic unwinds to the task entry.
+fn spawn_unwinding<F>(f: F) -> AxTaskRef
+where
+    F: FnOnce() + Send + 'static,
+{
+    // backtraces cannot be walked beyond the task stack
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if UNWINDING_TASK.get() {
+                eprintln!("task {info}");
+            } else {
+                default_hook(info);
+            }
+        }));
+    });
+    axtask::spawn(move || {
+        UNWINDING_TASK.set(true);
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+        UNWINDING_TASK.set(false);
+        if let Err(payload) = res {
+            let message = match payload.downcast_ref::<&str>() {
+                Some(s) => s,
+                None => payload.downcast_ref::<String>().map_or("", String::as_str),
+            };
+            crate::api::exit_on_panic_with(message);
+        }
+    })
+}
+
+#[test]
+fn test_task_panic_and_detach() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static PROGRESS: AtomicUsize = AtomicUsize::new(0);
+    let sibling = axtask::spawn(|| {
+        for _ in 0..10 {
+            PROGRESS.fetch_add(1, Ordering::Relaxed);
+            axtask::yield_now();
+        }
+    });
+    let task = spawn_unwinding(|| {
+        axtask::yield_now();
+        let empty: Vec<usize> = Vec::new();
+        println!("unreachable: {}", empty[std::hint::black_box(1)]);
+    });
+    let message = "index out of bounds: the len is 0 but the index is 1";
+    assert_eq!(task.join(), Err(JoinError::Panicked(message.into())));
+    // reported to all joiners
+    assert_eq!(task.join(), Err(JoinError::Panicked(message.into())));
+    // other tasks keep running
+    assert_eq!(sibling.join(), Ok(0));
+    assert_eq!(PROGRESS.load(Ordering::Relaxed), 10);
+
+    let task = spawn_unwinding(|| panic!("boom"));
+    assert_eq!(task.join(), Err(JoinError::Panicked("boom".into())));
+
+    static FINISHED: AtomicBool = AtomicBool::new(false);
+    let task = axtask::spawn(|| {
+        axtask::yield_now();
+        FINISHED.store(true, Ordering::Release);
+    });
+    assert!(task.detach());
+    assert!(!task.detach());
+    assert_eq!(task.join(), Err(JoinError::Detached));
+    drop(task);
+    while !FINISHED.load(Ordering::Acquire) {
+        axtask::yield_now();
+    }
+}
+
+#[test]
+fn test_task_local() {
+    use core::cell::{Cell, RefCell};
+
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Guard;
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    crate::task_local! {
+        static COUNTER: Cell<usize> = Cell::new(0);
+        static NAMES: RefCell<Vec<usize>> = RefCell::new(vec![COUNTER.with(Cell::get)]);
+        static GUARD: Guard = Guard;
+    }
+
+    const NUM_TASKS: usize = 5;
+    let tasks: Vec<_> = (0..NUM_TASKS)
+        .map(|i| {
+            axtask::spawn(move || {
+                GUARD.with(|_| {});
+                for _ in 0..=i {
+                    COUNTER.with(|c| c.set(c.get() + 1));
+                    axtask::yield_now();
+                }
+                NAMES.with(|names| names.borrow_mut().push(i));
+                assert_eq!(COUNTER.with(Cell::get), i + 1);
+                assert_eq!(*NAMES.with(|names| names.borrow().clone()), [i + 1, i]);
+            })
+        })
+        .collect();
+    for task in tasks {
+        assert_eq!(task.join(), Ok(0));
+    }
+    assert_eq!(DROPPED.load(Ordering::Relaxed), NUM_TASKS);
+}
+
+#[test]
+fn test_task_snapshot() {
+    use crate::TaskState;
+
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static WQ: WaitQueue = WaitQueue::new();
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    static DONE: AtomicBool = AtomicBool::new(false);
+
+    let task = axtask::spawn_raw(
+        || {
+            let buf = [1u8; 0x1000];
+            assert_eq!(core::hint::black_box(buf)[0x800], 1);
+            STARTED.store(true, Ordering::Release);
+            WQ.wait_until(|| DONE.load(Ordering::Acquire));
+        },
+        "snapshot".into(),
+        0x4000,
+    );
+    while !STARTED.load(Ordering::Acquire) || task.state() != TaskState::Blocked {
+        axtask::yield_now();
     }
+
+    let tasks = axtask::tasks();
+    assert!(tasks
+        .windows(2)
+        .all(|w| w[0].id.as_u64() < w[1].id.as_u64()));
+    let info = tasks.iter().find(|t| t.id == task.id()).unwrap();
+    assert_eq!(info.name, "snapshot");
+    assert_eq!(info.state, TaskState::Blocked);
+    assert!(info.context_switches >= 1);
+    assert_eq!(info.stack_size, 0x4000);
+    #[cfg(feature = "stack_usage")]
+    assert!(info.stack_max_used > 0x1000 && info.stack_max_used < 0x4000);
+    let curr = tasks.iter().find(|t| t.id == current().id()).unwrap();
+    assert_eq!(curr.state, TaskState::Running);
+
+    DONE.store(true, Ordering::Release);
+    WQ.notify_one(true);
+    task.join().unwrap();
+    let info = axtask::tasks().into_iter().find(|t| t.id == task.id());
+    assert_eq!(info.unwrap().state, TaskState::Exited);
+}
+
+#[test]
+#[cfg(feature = "irq")]
+fn test_timer_slack() {
+    use core::time::Duration;
+
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    let curr = current();
+    let old_slack = curr.timer_slack();
+    curr.set_timer_slack(Duration::from_millis(2));
+    let task = axtask::spawn(|| {
+        assert_eq!(current().timer_slack(), Duration::from_millis(2));
+        current().set_timer_slack(Duration::ZERO);
+    });
+    task.join().unwrap();
+    assert_eq!(task.timer_slack(), Duration::ZERO);
+    assert_eq!(curr.timer_slack(), Duration::from_millis(2));
+    curr.set_timer_slack(old_slack);
+}
+
+/// A sleeping lock whose owner inherits the priority of the waiters, like
+/// `axsync::Mutex`.
+#[cfg(feature = "sched_rt")]
+struct InheritLock {
+    owner: Mutex<Option<AxTaskRef>>,
+    wq: WaitQueue,
+}
+
+#[cfg(feature = "sched_rt")]
+impl InheritLock {
+    const fn new() -> Self {
+        Self {
+            owner: Mutex::new(None),
+            wq: WaitQueue::new(),
+        }
+    }
+
+    fn id(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    fn lock(&self) {
+        loop {
+            let mut owner = self.owner.lock().unwrap();
+            let Some(task) = owner.clone() else {
+                *owner = Some(current().as_task_ref().clone());
+                return;
+            };
+            drop(owner);
+            axtask::inherit_priority(&task, self.id(), || {
+                self.owner.lock().unwrap().as_ref().map(|t| t.id()) == Some(task.id())
+            });
+            self.wq.wait_until(|| self.owner.lock().unwrap().is_none());
+        }
+    }
+
+    fn unlock(&self) {
+        self.owner.lock().unwrap().take();
+        self.wq.notify_one(true);
+        axtask::restore_priority(self.id());
+    }
+}
+
+/// The policy and priority that the current task runs with.
+#[cfg(feature = "sched_rt")]
+fn effective_priority() -> (axtask::SchedPolicy, isize) {
+    let id = current().id();
+    let info = axtask::tasks().into_iter().find(|t| t.id == id).unwrap();
+    (info.policy, info.rt_priority)
+}
+
+#[test]
+#[cfg(feature = "sched_rt")]
+fn test_priority_inheritance() {
+    use crate::SchedPolicy;
+
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static LOCK: InheritLock = InheritLock::new();
+    static LOCKED: AtomicBool = AtomicBool::new(false);
+    static WQ: WaitQueue = WaitQueue::new();
+    static EVENTS: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+    let high = current().as_task_ref().clone();
+    assert!(axtask::set_sched_policy(&high, SchedPolicy::Fifo, 90));
+
+    let low = axtask::spawn(|| {
+        LOCK.lock();
+        LOCKED.store(true, Ordering::Release);
+        WQ.notify_one(true); // WQ.wait_until()
+        for _ in 0..3 {
+            // the high priority task is waiting for the lock
+            assert_eq!(effective_priority(), (SchedPolicy::Fifo, 90));
+            EVENTS.lock().unwrap().push("low");
+            axtask::yield_now();
+        }
+        LOCK.unlock();
+        assert_eq!(effective_priority().0, SchedPolicy::Normal);
+    });
+    WQ.wait_until(|| LOCKED.load(Ordering::Acquire));
+
+    // it would keep the low priority task from releasing the lock, so the
+    // high priority one would wait for more than the critical section
+    let medium = axtask::spawn_with_policy(
+        || {
+            for _ in 0..3 {
+                EVENTS.lock().unwrap().push("medium");
+                axtask::yield_now();
+            }
+        },
+        "medium".into(),
+        TEST_STACK_SIZE,
+        SchedPolicy::Fifo,
+        50,
+    )
+    .unwrap();
+
+    LOCK.lock();
+    EVENTS.lock().unwrap().push("high");
+    LOCK.unlock();
+
+    assert!(axtask::set_sched_policy(&high, SchedPolicy::Normal, 0));
+    low.join().unwrap();
+    medium.join().unwrap();
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        ["low", "low", "low", "high", "medium", "medium", "medium"]
+    );
+}
+
+#[test]
+#[cfg(feature = "sched_rt")]
+fn test_priority_restore() {
+    use crate::SchedPolicy;
+
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    static LOCK_A: InheritLock = InheritLock::new();
+    static LOCK_B: InheritLock = InheritLock::new();
+    static STAGE: AtomicUsize = AtomicUsize::new(0);
+    static WQ: WaitQueue = WaitQueue::new();
+
+    let high = current().as_task_ref().clone();
+    assert!(axtask::set_sched_policy(&high, SchedPolicy::Fifo, 90));
+
+    let low = axtask::spawn(|| {
+        LOCK_A.lock();
+        LOCK_B.lock();
+        STAGE.store(1, Ordering::Release);
+        WQ.notify_one(true);
+        // the medium priority task waits for `LOCK_B`
+        while effective_priority() != (SchedPolicy::Fifo, 70) {
+            axtask::yield_now();
+        }
+        STAGE.store(2, Ordering::Release);
+        WQ.notify_one(true);
+        // the high priority task waits for `LOCK_A`
+        assert_eq!(effective_priority(), (SchedPolicy::Fifo, 90));
+        LOCK_A.unlock();
+        // only the priority lent through `LOCK_A` is given back
+        assert_eq!(effective_priority(), (SchedPolicy::Fifo, 70));
+        LOCK_B.unlock();
+        assert_eq!(effective_priority().0, SchedPolicy::Normal);
+    });
+    WQ.wait_until(|| STAGE.load(Ordering::Acquire) == 1);
+
+    let medium = axtask::spawn_with_policy(
+        || {
+            LOCK_B.lock();
+            LOCK_B.unlock();
+        },
+        "medium".into(),
+        TEST_STACK_SIZE,
+        SchedPolicy::Fifo,
+        70,
+    )
+    .unwrap();
+    WQ.wait_until(|| STAGE.load(Ordering::Acquire) == 2);
+
+    LOCK_A.lock();
+    LOCK_A.unlock();
+    assert_eq!(effective_priority(), (SchedPolicy::Fifo, 90));
+
+    assert!(axtask::set_sched_policy(&high, SchedPolicy::Normal, 0));
+    low.join().unwrap();
+    medium.join().unwrap();
 }
@@ -1,32 +1,64 @@
 use alloc::sync::Arc;
+use core::ops::Deref;
+
 use axhal::time::current_time;
+use kernel_guard::NoPreemptIrqSave;
 use lazy_init::LazyInit;
 use spinlock::SpinNoIrq;
 use timer_list::{TimeValue, TimerEvent, TimerList};
 
-use crate::{AxTaskRef, RUN_QUEUE};
+use crate::{current_run_queue, AxTaskRef};
 
-// TODO: per-CPU
+/// The timer list of each CPU, whose events expire on the timer interrupts of
+/// that CPU.
+#[percpu::def_percpu]
 static TIMER_LIST: LazyInit<SpinNoIrq<TimerList<TaskWakeupEvent>>> = LazyInit::new();
 
+/// The time (in nanoseconds) that the one-shot timer of each CPU is programmed
+/// to go off at.
+#[cfg(feature = "tickless")]
+#[percpu::def_percpu]
+static NEXT_TIMER_NS: u64 = 0;
+
 struct TaskWakeupEvent(AxTaskRef);
 
 impl TimerEvent for TaskWakeupEvent {
     fn callback(self, _now: TimeValue) {
-        let mut rq = RUN_QUEUE.lock();
+        let mut rq = current_run_queue();
         self.0.set_in_timer_list(false);
         rq.unblock_task(self.0, true);
     }
 }
 
+fn timer_list(cpu_id: usize) -> &'static SpinNoIrq<TimerList<TaskWakeupEvent>> {
+    unsafe { TIMER_LIST.remote_ref_raw(cpu_id) }.deref()
+}
+
+fn local_timer_list() -> &'static SpinNoIrq<TimerList<TaskWakeupEvent>> {
+    // Safety: IRQs and preemption are disabled by the caller.
+    unsafe { TIMER_LIST.current_ref_raw() }.deref()
+}
+
+/// Wakes up `task` at `deadline`, or later by at most its timer slack.
 pub fn set_alarm_wakeup(deadline: TimeValue, task: AxTaskRef) {
-    let mut timers = TIMER_LIST.lock();
+    let _guard = NoPreemptIrqSave::new();
+    let slack = task.timer_slack();
+    let mut timers = local_timer_list().lock();
+    task.set_timer_cpu(axhal::cpu::this_cpu_id());
     task.set_in_timer_list(true);
-    timers.set(deadline, TaskWakeupEvent(task));
+    timers.set_with_slack(deadline, slack, TaskWakeupEvent(task));
+
+    #[cfg(feature = "tickless")]
+    {
+        let latest_ns = (deadline + slack).as_nanos() as u64;
+        if latest_ns < unsafe { NEXT_TIMER_NS.read_current_raw() } {
+            program_timer_at(latest_ns);
+        }
+    }
 }
 
 pub fn cancel_alarm(task: &AxTaskRef) {
-    let mut timers = TIMER_LIST.lock();
+    let mut timers = timer_list(task.timer_cpu()).lock();
     task.set_in_timer_list(false);
     timers.cancel(|t| Arc::ptr_eq(&t.0, task));
 }
@@ -34,7 +66,7 @@ pub fn cancel_alarm(task: &AxTaskRef) {
 pub fn check_events() {
     loop {
         let now = current_time();
-        let event = TIMER_LIST.lock().expire_one(now);
+        let event = local_timer_list().lock().expire_one(now);
         if let Some((_deadline, event)) = event {
             event.callback(now);
         } else {
@@ -43,6 +75,23 @@ pub fn check_events() {
     }
 }
 
+/// Programs the one-shot timer of this CPU to go off at `deadline_ns`, or at
+/// the latest time of the next timer event if it is earlier.
+///
+/// IRQs must be disabled.
+#[cfg(feature = "tickless")]
+pub fn program_timer(deadline_ns: u64) {
+    let next_event = local_timer_list().lock().next_expiry();
+    program_timer_at(next_event.map_or(deadline_ns, |t| deadline_ns.min(t.as_nanos() as u64)));
+}
+
+#[cfg(feature = "tickless")]
+fn program_timer_at(deadline_ns: u64) {
+    unsafe { NEXT_TIMER_NS.write_current_raw(deadline_ns) };
+    axhal::time::set_oneshot_timer(deadline_ns);
+}
+
+/// Initializes the timer list of this CPU.
 pub fn init() {
-    TIMER_LIST.init_by(SpinNoIrq::new(TimerList::new()));
+    TIMER_LIST.with_current(|t| t.init_by(SpinNoIrq::new(TimerList::new())));
 }
@@ -1,8 +1,9 @@
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use core::cmp::Reverse;
 use spinlock::SpinRaw;
 
-use crate::{AxRunQueue, AxTaskRef, CurrentTask, RUN_QUEUE};
+use crate::{current_run_queue, AxRunQueue, AxTaskRef, CurrentTask};
 
 /// A queue to store sleeping tasks.
 ///
@@ -65,7 +66,7 @@ impl WaitQueue {
     /// Blocks the current task and put it into the wait queue, until other task
     /// notifies it.
     pub fn wait(&self) {
-        RUN_QUEUE.lock().block_current(|task| {
+        current_run_queue().block_current(|task| {
             task.set_in_wait_queue(true);
             self.queue.lock().push_back(task)
         });
@@ -82,7 +83,7 @@ impl WaitQueue {
         F: Fn() -> bool,
     {
         loop {
-            let mut rq = RUN_QUEUE.lock();
+            let mut rq = current_run_queue();
             if condition() {
                 break;
             }
@@ -107,7 +108,7 @@ impl WaitQueue {
         );
         crate::timers::set_alarm_wakeup(deadline, curr.clone());
 
-        RUN_QUEUE.lock().block_current(|task| {
+        current_run_queue().block_current(|task| {
             task.set_in_wait_queue(true);
             self.queue.lock().push_back(task)
         });
@@ -137,7 +138,7 @@ impl WaitQueue {
 
         let mut timeout = true;
         while axhal::time::current_time() < deadline {
-            let mut rq = RUN_QUEUE.lock();
+            let mut rq = current_run_queue();
             if condition() {
                 timeout = false;
                 break;
@@ -156,7 +157,7 @@ impl WaitQueue {
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_one(&self, resched: bool) -> bool {
-        let mut rq = RUN_QUEUE.lock();
+        let mut rq = current_run_queue();
         if !self.queue.lock().is_empty() {
             self.notify_one_locked(resched, &mut rq)
         } else {
@@ -164,13 +165,35 @@ impl WaitQueue {
         }
     }
 
+    /// Wakes up the task with the highest priority in the wait queue, or the
+    /// first one of them if there are several.
+    ///
+    /// If `resched` is true, the current task will be preempted when the
+    /// preemption is enabled.
+    pub fn notify_highest(&self, resched: bool) -> bool {
+        let mut rq = current_run_queue();
+        let mut wq = self.queue.lock();
+        let highest = wq
+            .iter()
+            .enumerate()
+            .max_by_key(|(idx, task)| (task.sched_params().rank(), Reverse(*idx)))
+            .map(|(idx, _)| idx);
+        if let Some(task) = highest.and_then(|idx| wq.remove(idx)) {
+            task.set_in_wait_queue(false);
+            rq.unblock_task(task, resched);
+            true
+        } else {
+            false
+        }
+    }
+
     /// Wakes all tasks in the wait queue.
     ///
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_all(&self, resched: bool) {
         loop {
-            let mut rq = RUN_QUEUE.lock();
+            let mut rq = current_run_queue();
             if let Some(task) = self.queue.lock().pop_front() {
                 task.set_in_wait_queue(false);
                 rq.unblock_task(task, resched);
@@ -181,12 +204,30 @@ impl WaitQueue {
         }
     }
 
+    /// Wakes all tasks currently in the wait queue.
+    ///
+    /// Unlike [`WaitQueue::notify_all`], tasks that start waiting during the
+    /// call are not woken up, so it finishes even if the woken tasks run and
+    /// wait on this queue again right away (e.g., with a condition that is
+    /// still false).
+    ///
+    /// If `resched` is true, the current task will be preempted when the
+    /// preemption is enabled.
+    pub fn notify_all_current(&self, resched: bool) {
+        let mut rq = current_run_queue();
+        let tasks = core::mem::take(&mut *self.queue.lock());
+        for task in tasks {
+            task.set_in_wait_queue(false);
+            rq.unblock_task(task, resched);
+        }
+    }
+
     /// Wake up the given task in the wait queue.
     ///
     /// If `resched` is true, the current task will be preempted when the
     /// preemption is enabled.
     pub fn notify_task(&mut self, resched: bool, task: &AxTaskRef) -> bool {
-        let mut rq = RUN_QUEUE.lock();
+        let mut rq = current_run_queue();
         let mut wq = self.queue.lock();
         if let Some(index) = wq.iter().position(|t| Arc::ptr_eq(t, task)) {
             task.set_in_wait_queue(false);
@@ -0,0 +1,374 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use axerrno::{AxError, AxResult};
+use axfs::fops::{File, FileAttr, FilePerm, OpenOptions};
+use axsync::Mutex;
+use core::fmt;
+use permission::permission::current_credentials;
+
+use crate::crypt::hash_password;
+use crate::user::{user_list, UserInfo};
+
+/// Serializes all rewrites of the user database files.
+pub(crate) static DB_LOCK: Mutex<()> = Mutex::new(());
+
+const PASSWD: &str = "/etc/passwd";
+const SHADOW: &str = "/etc/shadow";
+const GROUP: &str = "/etc/group";
+const NAME_MAX: usize = 32;
+
+/// Errors of the user administration functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserError {
+    /// The caller is not the superuser.
+    PermissionDenied,
+    /// A user or group with the name already exists.
+    AlreadyExists,
+    /// The user does not exist.
+    NoSuchUser,
+    /// The name or a field contains characters that are not allowed.
+    InvalidName,
+    /// The superuser cannot be deleted.
+    ProtectedUser,
+    /// A line in the user database is malformed.
+    CorruptDatabase,
+    /// Reading or writing the user database failed.
+    Io(AxError),
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PermissionDenied => write!(f, "permission denied"),
+            Self::AlreadyExists => write!(f, "user or group already exists"),
+            Self::NoSuchUser => write!(f, "user does not exist"),
+            Self::InvalidName => write!(f, "invalid name or field"),
+            Self::ProtectedUser => write!(f, "the superuser cannot be changed this way"),
+            Self::CorruptDatabase => write!(f, "user database is corrupted"),
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<AxError> for UserError {
+    fn from(e: AxError) -> Self {
+        match e {
+            AxError::InvalidData => Self::CorruptDatabase,
+            e => Self::Io(e),
+        }
+    }
+}
+
+impl From<UserError> for AxError {
+    fn from(e: UserError) -> Self {
+        match e {
+            UserError::PermissionDenied | UserError::ProtectedUser => AxError::PermissionDenied,
+            UserError::AlreadyExists => AxError::AlreadyExists,
+            UserError::NoSuchUser => AxError::NotFound,
+            UserError::InvalidName => AxError::InvalidInput,
+            UserError::CorruptDatabase => AxError::InvalidData,
+            UserError::Io(e) => e,
+        }
+    }
+}
+
+pub type UserResult<T = ()> = Result<T, UserError>;
+
+/// Fields of a user entry to change. Fields that are [`None`] are kept.
+#[derive(Debug, Clone, Default)]
+pub struct UserChanges {
+    pub comment: Option<String>,
+    pub home: Option<String>,
+    pub shell: Option<String>,
+}
+
+/// Replaces the contents of `path` without leaving a partially written file.
+///
+/// The new contents are written to a temporary file with the permissions and
+/// owner of the old file, which then replaces the old one. If `path` does not
+/// exist yet, it is created with `new_perm` and owned by the superuser.
+pub(crate) fn write_atomic(path: &str, content: &str, new_perm: FilePerm) -> AxResult {
+    let tmp = format!("{}+", path);
+    let (perm, uid, gid) = match File::open(path, &OpenOptions::new()).and_then(|f| f.get_attr()) {
+        Ok(attr) => (attr.perm(), attr.user_id(), attr.group_id()),
+        Err(_) => (new_perm, 0, 0),
+    };
+
+    let mut opt = OpenOptions::new();
+    opt.write(true);
+    opt.create(true);
+    opt.truncate(true);
+    let mut file = File::open(&tmp, &opt)?;
+    write_all(&mut file, content.as_bytes())?;
+    file.flush()?;
+    set_attr(&file, perm, uid, gid)?;
+    drop(file);
+    axfs::api::rename(&tmp, path)
+}
+
+/// Writes the whole `buf` to `file`, retrying after short writes.
+pub(crate) fn write_all(file: &mut File, mut buf: &[u8]) -> AxResult {
+    while !buf.is_empty() {
+        match file.write(buf)? {
+            0 => return Err(AxError::WriteZero),
+            n => buf = &buf[n..],
+        }
+    }
+    Ok(())
+}
+
+fn set_attr(file: &File, perm: FilePerm, uid: u32, gid: u32) -> AxResult {
+    let attr = file.get_attr()?;
+    file.set_attr(FileAttr::new(
+        perm,
+        uid,
+        gid,
+        attr.file_type(),
+        attr.size(),
+        attr.blocks(),
+    ))
+}
+
+pub(crate) fn read_lines(path: &str) -> AxResult<Vec<String>> {
+    let content = match axfs::api::read_to_string(path) {
+        Ok(c) => c,
+        Err(AxError::NotFound) => String::new(),
+        Err(e) => return Err(e),
+    };
+    Ok(content
+        .split('\n')
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.to_string())
+        .collect())
+}
+
+fn write_lines(path: &str, lines: &[String]) -> AxResult {
+    let mut content = lines.join("\n");
+    content.push('\n');
+    let new_perm = if path == SHADOW {
+        FilePerm::from_bits_truncate(0o600)
+    } else {
+        FilePerm::default_file()
+    };
+    write_atomic(path, &content, new_perm)
+}
+
+fn check_privileged() -> UserResult {
+    if current_credentials().is_privileged() {
+        Ok(())
+    } else {
+        Err(UserError::PermissionDenied)
+    }
+}
+
+fn check_name(name: &str) -> UserResult {
+    let valid = !name.is_empty()
+        && name.len() <= NAME_MAX
+        && !name.starts_with('-')
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-' || b == b'.');
+    if valid {
+        Ok(())
+    } else {
+        Err(UserError::InvalidName)
+    }
+}
+
+fn check_field(field: &str) -> UserResult {
+    if field.contains([':', '\n']) {
+        Err(UserError::InvalidName)
+    } else {
+        Ok(())
+    }
+}
+
+fn find_user(name: &str) -> UserResult<UserInfo> {
+    user_list()?
+        .into_values()
+        .find(|u| u.username == name)
+        .ok_or(UserError::NoSuchUser)
+}
+
+fn first_field(line: &str) -> &str {
+    line.split(':').next().unwrap_or_default()
+}
+
+fn passwd_line(u: &UserInfo) -> String {
+    format!(
+        "{}:{}:{}:{}:{}:{}:{}",
+        u.username, u.password, u.uid, u.gid, u.comment, u.home, u.shell
+    )
+}
+
+/// Creates a user with a group of the same name, a locked password and a
+/// home directory `/home/<name>` owned by the new user.
+pub fn add_user(name: &str) -> UserResult<u32> {
+    check_privileged()?;
+    check_name(name)?;
+    let _guard = DB_LOCK.lock();
+
+    let users = user_list()?;
+    if users.values().any(|u| u.username == name) {
+        return Err(UserError::AlreadyExists);
+    }
+    let mut groups = read_lines(GROUP)?;
+    if groups.iter().any(|l| first_field(l) == name) {
+        return Err(UserError::AlreadyExists);
+    }
+    let uid = users.keys().max().map_or(0, |m| m + 1);
+    let gid = groups
+        .iter()
+        .filter_map(|l| l.split(':').nth(2)?.parse::<u32>().ok())
+        .chain(users.values().map(|u| u.gid))
+        .max()
+        .map_or(0, |m| m + 1);
+    let user = UserInfo {
+        username: name.to_string(),
+        password: "x".to_string(),
+        uid,
+        gid,
+        comment: ",,,".to_string(),
+        home: format!("/home/{}", name),
+        shell: "/bin/sh".to_string(),
+    };
+
+    groups.push(format!("{}:x:{}:", name, gid));
+    write_lines(GROUP, &groups)?;
+    let mut passwd = read_lines(PASSWD)?;
+    passwd.push(passwd_line(&user));
+    write_lines(PASSWD, &passwd)?;
+    // locked: the new user cannot log in until a password is set
+    let mut shadow = read_lines(SHADOW)?;
+    shadow.retain(|l| first_field(l) != uid.to_string());
+    shadow.push(format!("{}:!", uid));
+    write_lines(SHADOW, &shadow)?;
+
+    match axfs::api::create_dir(&user.home) {
+        Ok(()) | Err(AxError::AlreadyExists) => {}
+        Err(e) => return Err(e.into()),
+    }
+    let dir = File::open(&user.home, &OpenOptions::new())?;
+    set_attr(&dir, FilePerm::from_bits_truncate(0o700), uid, gid)?;
+    Ok(uid)
+}
+
+/// Deletes a user, its password and its group of the same name, and removes
+/// it from the member lists of other groups. The home directory is kept.
+pub fn delete_user(name: &str) -> UserResult {
+    check_privileged()?;
+    let _guard = DB_LOCK.lock();
+
+    let user = find_user(name)?;
+    if user.uid == 0 {
+        return Err(UserError::ProtectedUser);
+    }
+    let mut passwd = read_lines(PASSWD)?;
+    passwd.retain(|l| first_field(l) != name);
+    write_lines(PASSWD, &passwd)?;
+
+    let uid = user.uid.to_string();
+    let mut shadow = read_lines(SHADOW)?;
+    shadow.retain(|l| first_field(l) != uid);
+    write_lines(SHADOW, &shadow)?;
+
+    let own_gid = user.gid.to_string();
+    let groups: Vec<String> = read_lines(GROUP)?
+        .into_iter()
+        .filter(|l| !(first_field(l) == name && l.split(':').nth(2) == Some(own_gid.as_str())))
+        .map(|l| {
+            let mut v: Vec<String> = l.split(':').map(|s| s.to_string()).collect();
+            if let Some(members) = v.get_mut(3) {
+                *members = members
+                    .split(',')
+                    .filter(|m| *m != name)
+                    .collect::<Vec<_>>()
+                    .join(",");
+            }
+            v.join(":")
+        })
+        .collect();
+    write_lines(GROUP, &groups)?;
+    Ok(())
+}
+
+/// Changes the comment, home directory or login shell of a user.
+pub fn modify_user(name: &str, changes: &UserChanges) -> UserResult {
+    check_privileged()?;
+    for field in [&changes.comment, &changes.home, &changes.shell]
+        .into_iter()
+        .flatten()
+    {
+        check_field(field)?;
+    }
+    let _guard = DB_LOCK.lock();
+
+    let mut user = find_user(name)?;
+    if let Some(comment) = &changes.comment {
+        user.comment = comment.clone();
+    }
+    if let Some(home) = &changes.home {
+        user.home = home.clone();
+    }
+    if let Some(shell) = &changes.shell {
+        user.shell = shell.clone();
+    }
+    let passwd: Vec<String> = read_lines(PASSWD)?
+        .into_iter()
+        .map(|l| {
+            if first_field(&l) == name {
+                passwd_line(&user)
+            } else {
+                l
+            }
+        })
+        .collect();
+    write_lines(PASSWD, &passwd)?;
+    Ok(())
+}
+
+/// Locks or unlocks the password of a user, like `passwd -l` and `passwd -u`.
+///
+/// A locked user cannot log in, and unlocking restores the old password.
+pub fn lock_user(name: &str, lock: bool) -> UserResult {
+    check_privileged()?;
+    let _guard = DB_LOCK.lock();
+
+    let uid = find_user(name)?.uid.to_string();
+    let mut shadow = read_lines(SHADOW)?;
+    match shadow.iter_mut().find(|l| first_field(l) == uid) {
+        Some(line) => {
+            let hash = line.split_once(':').map_or("", |(_, h)| h);
+            *line = match (lock, hash.strip_prefix('!')) {
+                (true, None) => format!("{}:!{}", uid, hash),
+                (false, Some(unlocked)) => format!("{}:{}", uid, unlocked),
+                _ => return Ok(()),
+            };
+        }
+        None if lock => shadow.push(format!("{}:!", uid)),
+        None => return Ok(()),
+    }
+    write_lines(SHADOW, &shadow)?;
+    Ok(())
+}
+
+/// Sets the password of a user, like `passwd <name>` run by the superuser.
+///
+/// The user is unlocked if it was locked.
+pub fn set_user_password(name: &str, password: &str) -> UserResult {
+    check_privileged()?;
+    let _guard = DB_LOCK.lock();
+
+    let uid = find_user(name)?.uid.to_string();
+    let mut shadow = read_lines(SHADOW)?;
+    shadow.retain(|l| first_field(l) != uid);
+    shadow.push(format!("{}:{}", uid, hash_password(password)));
+    write_lines(SHADOW, &shadow)?;
+    Ok(())
+}
+
+/// Returns all users in `/etc/passwd`, ordered by user id.
+pub fn list_users() -> UserResult<Vec<UserInfo>> {
+    Ok(user_list()?.into_values().collect())
+}
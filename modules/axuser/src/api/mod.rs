@@ -1,9 +1,18 @@
 //! shell-like high-level user information manipulation operations.
 
 use alloc::string::String;
+use alloc::vec::Vec;
 use axio as io;
 
+pub use crate::admin::{UserChanges, UserError, UserResult};
+pub use crate::group::GroupInfo;
+pub use crate::sudoers::SudoPermission;
+pub use crate::user::UserInfo;
+
 /// Verify the password.
+///
+/// It fails if the user has no entry in `/etc/shadow` or the account is
+/// locked. An entry with an empty hash, i.e., without a password, is locked.
 pub fn verify(uid: u32, password: String) -> bool {
     crate::user::verify(uid, password)
 }
@@ -13,11 +22,43 @@ pub fn user_name(uid: u32) -> String {
     crate::user::user_name(uid)
 }
 
-/// Is the user sudoer.
+/// Returns the primary group id of the user, or [`None`] if the user does
+/// not exist.
+pub fn user_gid(uid: u32) -> Option<u32> {
+    crate::user::user_gid(uid)
+}
+
+/// Is the user sudoer, i.e. does any entry in `/etc/sudoers` allow the user
+/// to run some command.
 pub fn is_sudoer(name: String) -> bool {
     crate::user::is_sudoer(name)
 }
 
+/// Decides by `/etc/sudoers` whether `user` may run `command` (the command
+/// name followed by its arguments) as the user `runas`.
+pub fn sudo_permission(user: &str, command: &str, runas: &str) -> SudoPermission {
+    crate::sudo::sudo_permission(user, command, runas)
+}
+
+/// Whether the user has authenticated for `sudo` recently enough to not be
+/// asked for the password again.
+///
+/// The timeout is `Defaults timestamp_timeout` in `/etc/sudoers` (in minutes,
+/// 5 by default; 0 always asks).
+pub fn sudo_auth_cached(uid: u32) -> bool {
+    crate::sudo::auth_cached(uid)
+}
+
+/// Records a successful `sudo` authentication of the user.
+pub fn sudo_record_auth(uid: u32) {
+    crate::sudo::record_auth(uid)
+}
+
+/// Forgets the `sudo` authentication of the user, like `sudo -k`.
+pub fn sudo_invalidate_auth(uid: u32) {
+    crate::sudo::invalidate_auth(uid)
+}
+
 /// Returns the current user password as a [`String`].
 pub fn set_password(password: String) -> io::Result<()> {
     crate::user::set_password(password)
@@ -27,3 +68,76 @@ pub fn set_password(password: String) -> io::Result<()> {
 pub fn user_id(name: String) -> u32 {
     crate::user::user_id(name)
 }
+
+/// Looks up a group in `/etc/group` by its name.
+pub fn group_by_name(name: &str) -> Option<GroupInfo> {
+    crate::group::group_by_name(name)
+}
+
+/// Looks up a group in `/etc/group` by its id.
+pub fn group_by_gid(gid: u32) -> Option<GroupInfo> {
+    crate::group::group_by_gid(gid)
+}
+
+/// Returns the ids of all groups the user belongs to, starting with the
+/// primary group. It is empty if the user does not exist.
+pub fn groups_of_user(uid: u32) -> Vec<u32> {
+    crate::group::groups_of_user(uid)
+}
+
+/// Adds the user to the member list of the group.
+///
+/// Only the superuser may do this.
+pub fn add_user_to_group(username: &str, group: &str) -> io::Result<()> {
+    crate::group::add_user_to_group(username, group)
+}
+
+/// Sets the supplementary groups of the current task to all groups of the
+/// user, like `initgroups(3)`, without any permission check.
+pub fn init_groups(uid: u32) {
+    let mut cred = permission::permission::current_credentials();
+    cred.groups = crate::group::groups_of_user(uid);
+    permission::permission::set_current_credentials(cred);
+}
+
+/// Creates a user with a group of the same name, a locked password and a home
+/// directory `/home/<name>` owned by the new user. Returns the new user id.
+///
+/// Only the superuser may do this.
+pub fn add_user(name: &str) -> UserResult<u32> {
+    crate::admin::add_user(name)
+}
+
+/// Deletes a user, its password and its group of the same name. The home
+/// directory is kept.
+///
+/// Only the superuser may do this.
+pub fn delete_user(name: &str) -> UserResult {
+    crate::admin::delete_user(name)
+}
+
+/// Changes the comment, home directory or login shell of a user.
+///
+/// Only the superuser may do this.
+pub fn modify_user(name: &str, changes: &UserChanges) -> UserResult {
+    crate::admin::modify_user(name, changes)
+}
+
+/// Locks (`lock` is true) or unlocks the password of a user.
+///
+/// Only the superuser may do this.
+pub fn lock_user(name: &str, lock: bool) -> UserResult {
+    crate::admin::lock_user(name, lock)
+}
+
+/// Sets the password of a user, and unlocks it if it was locked.
+///
+/// Only the superuser may do this.
+pub fn set_user_password(name: &str, password: &str) -> UserResult {
+    crate::admin::set_user_password(name, password)
+}
+
+/// Returns all users, ordered by user id.
+pub fn list_users() -> UserResult<Vec<UserInfo>> {
+    crate::admin::list_users()
+}
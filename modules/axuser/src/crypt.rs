@@ -0,0 +1,317 @@
+//! Password hashing compatible with `crypt(3)`.
+//!
+//! New hashes use SHA-512-crypt (`$6$rounds=<n>$<salt>$<hash>`) with a random
+//! per-password salt. Hashes in the legacy format of this crate (an unsalted
+//! SHA-1 digest written with the letters `a`–`p`) are still accepted.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::sha1::sha1;
+use crate::sha512::Sha512;
+
+/// Number of rounds used for new hashes.
+const ROUNDS: u32 = 10000;
+const ROUNDS_DEFAULT: u32 = 5000;
+const ROUNDS_MIN: u32 = 1000;
+const ROUNDS_MAX: u32 = 999_999_999;
+const SALT_LEN_MAX: usize = 16;
+
+const B64: &[u8; 64] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Order in which the digest bytes are encoded, in groups of three.
+const PERMUTATION: [[usize; 3]; 21] = [
+    [0, 21, 42],
+    [22, 43, 1],
+    [44, 2, 23],
+    [3, 24, 45],
+    [25, 46, 4],
+    [47, 5, 26],
+    [6, 27, 48],
+    [28, 49, 7],
+    [50, 8, 29],
+    [9, 30, 51],
+    [31, 52, 10],
+    [53, 11, 32],
+    [12, 33, 54],
+    [34, 55, 13],
+    [56, 14, 35],
+    [15, 36, 57],
+    [37, 58, 16],
+    [59, 17, 38],
+    [18, 39, 60],
+    [40, 61, 19],
+    [62, 20, 41],
+];
+
+fn push_b64(out: &mut String, mut w: u32, n: usize) {
+    for _ in 0..n {
+        out.push(B64[(w & 0x3f) as usize] as char);
+        w >>= 6;
+    }
+}
+
+/// Appends `src` repeatedly to `h` until `len` bytes have been added.
+fn update_repeated(h: &mut Sha512, src: &[u8], len: usize) {
+    let mut left = len;
+    while left >= src.len() {
+        h.update(src);
+        left -= src.len();
+    }
+    h.update(&src[..left]);
+}
+
+/// Computes the SHA-512-crypt digest of `key` and encodes it.
+fn sha512_crypt_raw(key: &[u8], salt: &[u8], rounds: u32) -> String {
+    let mut h = Sha512::new();
+    h.update(key);
+    h.update(salt);
+    h.update(key);
+    let b = h.finalize();
+
+    let mut h = Sha512::new();
+    h.update(key);
+    h.update(salt);
+    update_repeated(&mut h, &b, key.len());
+    let mut n = key.len();
+    while n > 0 {
+        if n & 1 != 0 {
+            h.update(&b);
+        } else {
+            h.update(key);
+        }
+        n >>= 1;
+    }
+    let a = h.finalize();
+
+    let mut h = Sha512::new();
+    for _ in 0..key.len() {
+        h.update(key);
+    }
+    let dp = h.finalize();
+    let mut p = Vec::with_capacity(key.len());
+    while p.len() < key.len() {
+        let n = (key.len() - p.len()).min(dp.len());
+        p.extend_from_slice(&dp[..n]);
+    }
+
+    let mut h = Sha512::new();
+    for _ in 0..16 + a[0] as usize {
+        h.update(salt);
+    }
+    let ds = h.finalize();
+    let s = &ds[..salt.len()];
+
+    let mut c = a;
+    for i in 0..rounds {
+        let mut h = Sha512::new();
+        if i & 1 != 0 {
+            h.update(&p);
+        } else {
+            h.update(&c);
+        }
+        if i % 3 != 0 {
+            h.update(s);
+        }
+        if i % 7 != 0 {
+            h.update(&p);
+        }
+        if i & 1 != 0 {
+            h.update(&c);
+        } else {
+            h.update(&p);
+        }
+        c = h.finalize();
+    }
+
+    let mut out = String::with_capacity(86);
+    for [i, j, k] in PERMUTATION {
+        let w = ((c[i] as u32) << 16) | ((c[j] as u32) << 8) | c[k] as u32;
+        push_b64(&mut out, w, 4);
+    }
+    push_b64(&mut out, c[63] as u32, 2);
+    out
+}
+
+/// Hashes `key` with the settings (`$6$[rounds=<n>$]<salt>`) at the start of
+/// `setting`, and returns the full hash string as `crypt(3)` does.
+///
+/// Returns [`None`] if `setting` is not a SHA-512-crypt setting.
+fn sha512_crypt(key: &[u8], setting: &str) -> Option<String> {
+    let rest = setting.strip_prefix("$6$")?;
+    let (rounds, rest) = match rest.strip_prefix("rounds=") {
+        Some(r) => {
+            let (n, rest) = r.split_once('$')?;
+            let n = n.parse::<u32>().ok()?;
+            (Some(n.clamp(ROUNDS_MIN, ROUNDS_MAX)), rest)
+        }
+        None => (None, rest),
+    };
+    let salt = rest.split('$').next().unwrap_or_default();
+    let salt = salt.get(..salt.len().min(SALT_LEN_MAX))?;
+
+    let digest = sha512_crypt_raw(key, salt.as_bytes(), rounds.unwrap_or(ROUNDS_DEFAULT));
+    let prefix = match rounds {
+        Some(n) => alloc::format!("$6$rounds={}$", n),
+        None => String::from("$6$"),
+    };
+    Some(prefix + salt + "$" + &digest)
+}
+
+/// Encodes the SHA-1 digest of `password` in the legacy format.
+fn legacy_hash(password: &str) -> String {
+    let mut s1h = String::new();
+    for i in sha1(password) {
+        s1h.push(char::from((i / 16) + b'a'));
+        s1h.push(char::from((i % 16) + b'a'));
+    }
+    s1h
+}
+
+/// Generates a random salt of [`SALT_LEN_MAX`] characters.
+fn gen_salt() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut h = Sha512::new();
+    h.update(&axhal::time::current_ticks().to_le_bytes());
+    h.update(&COUNTER.fetch_add(1, Ordering::Relaxed).to_le_bytes());
+    h.update(&(&COUNTER as *const _ as usize).to_le_bytes());
+    let seed = h.finalize();
+    seed[..SALT_LEN_MAX]
+        .iter()
+        .map(|b| B64[(b & 0x3f) as usize] as char)
+        .collect()
+}
+
+/// Compares two strings in time independent of where they differ.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// Hashes `password` with a new random salt.
+pub fn hash_password(password: &str) -> String {
+    let setting = alloc::format!("$6$rounds={}${}", ROUNDS, gen_salt());
+    sha512_crypt(password.as_bytes(), &setting).unwrap()
+}
+
+/// Whether the stored `hash` marks a locked account.
+///
+/// Like `passwd -l`, an account is locked by prefixing its hash with `!`. A
+/// hash of `*` means no password can ever match. An empty hash, left by
+/// accounts created without a password, is locked as well: login without a
+/// password is never allowed.
+pub fn is_locked(hash: &str) -> bool {
+    hash.is_empty() || hash.starts_with('!') || hash.starts_with('*')
+}
+
+/// Checks `password` against the stored `hash`.
+///
+/// Locked accounts and unrecognized hash formats never match.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    if is_locked(hash) {
+        return false;
+    }
+    if hash.starts_with("$6$") {
+        return sha512_crypt(password.as_bytes(), hash)
+            .is_some_and(|computed| constant_time_eq(&computed, hash));
+    }
+    if hash.len() == 40 && hash.bytes().all(|b| (b'a'..=b'p').contains(&b)) {
+        return constant_time_eq(&legacy_hash(password), hash);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test vectors of SHA-512-crypt from glibc (`crypt/sha512c-test.c`).
+    const VECTORS: [(&str, &str, &str); 7] = [
+        (
+            "$6$saltstring",
+            "Hello world!",
+            "$6$saltstring$svn8UoSVapNtMuq1ukKS4tPQd8iKwSMHWjl/O817G3uBnIFNjnQJu\
+             esI68u4OTLiBFdcbYEdFCoEOfaS35inz1",
+        ),
+        (
+            "$6$rounds=10000$saltstringsaltstring",
+            "Hello world!",
+            "$6$rounds=10000$saltstringsaltst$OW1/O6BYHV6BcXZu8QVeXbDWra3Oeqh0sbHbbMCV\
+             NSnCM/UrjmM0Dp8vOuZeHBy/YTBmSK6H9qs/y3RnOaw5v.",
+        ),
+        (
+            "$6$rounds=5000$toolongsaltstring",
+            "This is just a test",
+            "$6$rounds=5000$toolongsaltstrin$lQ8jolhgVRVhY4b5pZKaysCLi0QBxGoNeKQzQ3glMhwllF\
+             7oGDZxUhx1yxdYcz/e1JSbq3y6JMxxl8audkUEm0",
+        ),
+        (
+            "$6$rounds=1400$anotherlongsaltstring",
+            "a very much longer text to encrypt.  This one even stretches over more\
+             than one line.",
+            "$6$rounds=1400$anotherlongsalts$POfYwTEok97VWcjxIiSOjiykti.o/pQs.wPvMxQ6Fm7I6I\
+             oYN3CmLs66x9t0oSwbtEW7o7UmJEiDwGqd8p4ur1",
+        ),
+        (
+            "$6$rounds=77777$short",
+            "we have a short salt string but not a short password",
+            "$6$rounds=77777$short$WuQyW2YR.hBNpjjRhpYD/ifIw05xdfeEyQoMxIXbkvr0g\
+             ge1a1x3yRULJ5CCaUeOxFmtlcGZelFl5CxtgfiAc0",
+        ),
+        (
+            "$6$rounds=123456$asaltof16chars..",
+            "a short string",
+            "$6$rounds=123456$asaltof16chars..$BtCwjqMJGx5hrJhZywWvt0RLE8uZ4oPwc\
+             elCjmw2kSYu.Ec6ycULevoBK25fs2xXgMNrCzIMVcgEJAstJeonj1",
+        ),
+        (
+            "$6$rounds=10$roundstoolow",
+            "the minimum number is still observed",
+            "$6$rounds=1000$roundstoolow$kUMsbe306n21p9R.FRkW3IGn.S9NPN0x50YhH1x\
+             hLsPuWGsUSklZt58jaTfF4ZEQpyUNGc0dqbpBYYBaHHrsX.",
+        ),
+    ];
+
+    #[test]
+    fn test_sha512_crypt() {
+        for (setting, key, expected) in VECTORS {
+            assert_eq!(sha512_crypt(key.as_bytes(), setting).unwrap(), expected);
+            assert!(verify_password(key, expected));
+            assert!(!verify_password("wrong", expected));
+        }
+        assert_eq!(sha512_crypt(b"key", "$5$salt"), None);
+    }
+
+    #[test]
+    fn test_hash_password() {
+        let hash = hash_password("secret");
+        assert!(hash.starts_with("$6$rounds=10000$"));
+        assert!(verify_password("secret", &hash));
+        assert!(!verify_password("Secret", &hash));
+        assert_ne!(hash_password("secret"), hash);
+    }
+
+    #[test]
+    fn test_locked() {
+        let hash = hash_password("secret");
+        for locked in ["", "!", "*", &alloc::format!("!{}", hash)] {
+            assert!(is_locked(locked));
+            assert!(!verify_password("", locked));
+            assert!(!verify_password("secret", locked));
+        }
+        assert!(!is_locked(&hash));
+    }
+
+    #[test]
+    fn test_legacy_hash() {
+        let hash = legacy_hash("secret");
+        assert_eq!(hash.len(), 40);
+        assert!(verify_password("secret", &hash));
+        assert!(!verify_password("secret2", &hash));
+        assert!(!verify_password("secret", "not a hash"));
+    }
+}
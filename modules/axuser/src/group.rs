@@ -0,0 +1,134 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use axerrno::{AxError, AxResult};
+use axfs::fops::{File, FilePerm, OpenOptions};
+use log::debug;
+use permission::permission::current_credentials;
+
+use crate::admin::{write_atomic, DB_LOCK};
+use crate::user::user_list;
+
+pub struct GroupInfo {
+    pub name: String,
+    pub password: String,
+    pub gid: u32,
+    pub members: Vec<String>,
+}
+
+impl GroupInfo {
+    fn parse(line: &str) -> AxResult<Self> {
+        let v: Vec<&str> = line.split(':').collect();
+        if v.len() < 3 {
+            return Err(AxError::InvalidData);
+        }
+        let gid = v[2].parse::<u32>().map_err(|_| AxError::InvalidData)?;
+        let members = match v.get(3) {
+            Some(m) => m
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(|s| s.to_string())
+                .collect(),
+            None => Vec::new(),
+        };
+        Ok(Self {
+            name: v[0].to_string(),
+            password: v[1].to_string(),
+            gid,
+            members,
+        })
+    }
+
+    fn to_line(&self) -> String {
+        alloc::format!(
+            "{}:{}:{}:{}",
+            self.name,
+            self.password,
+            self.gid,
+            self.members.join(",")
+        )
+    }
+}
+
+fn group_list() -> AxResult<Vec<GroupInfo>> {
+    let mut opt = OpenOptions::new();
+    opt.read(true);
+    let mut file = File::open("/etc/group", &opt)?;
+    let mut content = String::new();
+    loop {
+        let mut buf = [0u8; 256];
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        content += String::from_utf8_lossy(&(buf[..n])).as_ref();
+    }
+    content
+        .split('\n')
+        .filter(|line| !line.trim().is_empty())
+        .map(GroupInfo::parse)
+        .collect()
+}
+
+pub fn group_by_name(name: &str) -> Option<GroupInfo> {
+    match group_list() {
+        Ok(gl) => gl.into_iter().find(|g| g.name == name),
+        Err(_e) => {
+            debug!("{}", _e);
+            None
+        }
+    }
+}
+
+pub fn group_by_gid(gid: u32) -> Option<GroupInfo> {
+    match group_list() {
+        Ok(gl) => gl.into_iter().find(|g| g.gid == gid),
+        Err(_e) => {
+            debug!("{}", _e);
+            None
+        }
+    }
+}
+
+pub fn groups_of_user(uid: u32) -> Vec<u32> {
+    let user = match user_list() {
+        Ok(mut ul) => ul.remove(&uid),
+        Err(_e) => {
+            debug!("{}", _e);
+            None
+        }
+    };
+    let user = match user {
+        Some(u) => u,
+        None => return Vec::new(),
+    };
+    let mut groups = alloc::vec![user.gid];
+    for g in group_list().unwrap_or_default() {
+        if !groups.contains(&g.gid) && g.members.contains(&user.username) {
+            groups.push(g.gid);
+        }
+    }
+    groups
+}
+
+pub fn add_user_to_group(username: &str, group: &str) -> AxResult {
+    if !current_credentials().is_privileged() {
+        return Err(AxError::PermissionDenied);
+    }
+    if !user_list()?.values().any(|u| u.username == username) {
+        return Err(AxError::NotFound);
+    }
+    let _guard = DB_LOCK.lock();
+    let mut gl = group_list()?;
+    let g = gl
+        .iter_mut()
+        .find(|g| g.name == group)
+        .ok_or(AxError::NotFound)?;
+    if g.members.iter().any(|m| m == username) {
+        return Ok(());
+    }
+    g.members.push(username.to_string());
+
+    let content: String = gl.iter().map(|g| g.to_line() + "\n").collect();
+    write_atomic("/etc/group", &content, FilePerm::default_file())
+}
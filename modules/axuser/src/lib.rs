@@ -4,8 +4,15 @@
 
 #![cfg_attr(not(test), no_std)]
 
+mod admin;
 pub mod api;
+mod crypt;
+pub mod group;
+pub mod session;
 mod sha1;
+mod sha512;
+mod sudo;
+pub mod sudoers;
 pub mod user;
 
 extern crate alloc;
@@ -0,0 +1,574 @@
+//! Login sessions on the console.
+//!
+//! [`getty`] runs the console like `getty(8)` and `login(1)`: it prompts for a
+//! user name and a password, runs the login shell of the user in their home
+//! directory as a [`Session`], and prompts again when the shell exits.
+//!
+//! Active sessions are listed in [`UTMP`], and every boot, login and logout is
+//! appended to [`WTMP`], which [`who`] and [`last`] read back. Times are
+//! measured since boot.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+use axerrno::AxResult;
+use axfs::audit::{self, AuditAction};
+use axfs::fops::{File, FilePerm, OpenOptions};
+use axsync::Mutex;
+use core::fmt;
+use core::time::Duration;
+use log::{debug, warn};
+
+use crate::admin::{read_lines, write_all, write_atomic};
+use crate::user::{user_list, UserInfo};
+
+/// Path of the list of active sessions.
+pub const UTMP: &str = "/var/run/utmp";
+/// Path of the history of boots, logins and logouts.
+pub const WTMP: &str = "/var/log/wtmp";
+/// Name of the terminal of console sessions.
+pub const CONSOLE_TTY: &str = "console";
+
+/// Path of the built-in shell, which is the only login shell that can be
+/// started.
+pub const DEFAULT_SHELL: &str = "/bin/sh";
+
+const SESSION_DIRS: [&str; 2] = ["/var/run", "/var/log"];
+/// Login shells that refuse interactive logins.
+const NOLOGIN_SHELLS: [&str; 3] = ["/sbin/nologin", "/usr/sbin/nologin", "/bin/false"];
+const DEFAULT_PATH: &str = "/bin";
+/// Failed attempts before the prompt is locked for [`LOCKOUT_DELAY`].
+const LOGIN_TRIES: u32 = 3;
+/// Delay after the first failed attempt, doubled after each further one.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const LOCKOUT_DELAY: Duration = Duration::from_secs(10);
+
+static CURRENT: Mutex<Option<Session>> = Mutex::new(None);
+
+/// A logged in user.
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// Name of the user.
+    pub user: String,
+    /// User ID.
+    pub uid: u32,
+    /// Primary group ID.
+    pub gid: u32,
+    /// Terminal of the session.
+    pub tty: String,
+    /// ID of the task that logged in.
+    pub task_id: u64,
+    /// Login time, since boot.
+    pub login_time: Duration,
+    /// Home directory, and the working directory at login.
+    pub home: String,
+    /// Login shell.
+    pub shell: String,
+    /// Environment variables of the session.
+    pub env: Vec<(String, String)>,
+}
+
+impl Session {
+    /// Returns the value of the environment variable `key`.
+    pub fn var(&self, key: &str) -> Option<&str> {
+        self.env
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// The kind of a [`SessionRecord`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    /// The system booted.
+    Boot,
+    /// A user logged in.
+    Login,
+    /// A user logged out.
+    Logout,
+}
+
+impl RecordKind {
+    /// Returns the name of the kind used in the files.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Boot => "boot",
+            Self::Login => "login",
+            Self::Logout => "logout",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "boot" => Some(Self::Boot),
+            "login" => Some(Self::Login),
+            "logout" => Some(Self::Logout),
+            _ => None,
+        }
+    }
+}
+
+/// A line of [`UTMP`] or [`WTMP`].
+#[derive(Debug, Clone)]
+pub struct SessionRecord {
+    /// What happened.
+    pub kind: RecordKind,
+    /// Name of the user, or `reboot` for [`RecordKind::Boot`].
+    pub user: String,
+    /// Terminal of the session.
+    pub tty: String,
+    /// ID of the task of the session.
+    pub task_id: u64,
+    /// Time of the event, since boot.
+    pub time: Duration,
+}
+
+impl SessionRecord {
+    fn new(kind: RecordKind, session: &Session) -> Self {
+        Self {
+            kind,
+            user: session.user.clone(),
+            tty: session.tty.clone(),
+            task_id: session.task_id,
+            time: axhal::time::current_time(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 5 {
+            return None;
+        }
+        let (secs, micros) = fields[4].split_once('.')?;
+        Some(Self {
+            kind: RecordKind::from_str(fields[0])?,
+            user: fields[1].to_string(),
+            tty: fields[2].to_string(),
+            task_id: fields[3].parse().ok()?,
+            time: Duration::from_secs(secs.parse().ok()?)
+                + Duration::from_micros(micros.parse().ok()?),
+        })
+    }
+}
+
+impl fmt::Display for SessionRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}.{:06}",
+            self.kind.as_str(),
+            self.user,
+            self.tty,
+            self.task_id,
+            self.time.as_secs(),
+            self.time.subsec_micros()
+        )
+    }
+}
+
+/// How a session listed by [`last`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEnd {
+    /// The user is still logged in.
+    LoggedIn,
+    /// The user logged out at the given time.
+    Logout(Duration),
+    /// The system was rebooted before the user logged out.
+    Reboot,
+}
+
+/// A past or active session, as listed by [`last`].
+#[derive(Debug, Clone)]
+pub struct LastEntry {
+    /// Name of the user.
+    pub user: String,
+    /// Terminal of the session.
+    pub tty: String,
+    /// Login time, since the boot the session belongs to.
+    pub login_time: Duration,
+    /// How the session ended.
+    pub end: SessionEnd,
+}
+
+/// Clears the active sessions of the previous boot and records the boot.
+///
+/// It must be called once after the filesystems are initialized.
+pub fn init() {
+    let res = create_dirs()
+        .and_then(|_| write_atomic(UTMP, "", FilePerm::default_file()))
+        .and_then(|_| {
+            append_wtmp(&SessionRecord {
+                kind: RecordKind::Boot,
+                user: "reboot".into(),
+                tty: "~".into(),
+                task_id: 0,
+                time: axhal::time::current_time(),
+            })
+        });
+    if let Err(e) = res {
+        warn!(
+            "session: failed to initialize {} and {}: {:?}",
+            UTMP, WTMP, e
+        );
+    }
+}
+
+/// Returns the session of the console, if a user is logged in.
+pub fn current() -> Option<Session> {
+    CURRENT.lock().clone()
+}
+
+/// Runs the console like `getty(8)`, and never returns.
+///
+/// It prompts until a user logs in, and runs `shell` as their login shell in a
+/// new task with their credentials. When the task exits, the session ends and
+/// the prompt is shown again. `shell` is the built-in shell installed as
+/// [`DEFAULT_SHELL`], so users with another login shell are refused.
+///
+/// It must be called by the superuser.
+pub fn getty(shell: fn()) -> ! {
+    loop {
+        let user = login();
+        let name = user.username.clone();
+        let task = axtask::spawn(move || {
+            start(user);
+            shell();
+        });
+        match task.join() {
+            Ok(code) => debug!("session: shell of {} exited: exit_code={}", name, code),
+            Err(e) => warn!("session: shell of {} failed: {:?}", name, e),
+        }
+        logout();
+    }
+}
+
+/// Prompts on the console until a user logs in, and returns the user.
+///
+/// The password is not echoed. Every failed attempt delays the next prompt
+/// twice as long as the previous one, and after [`LOGIN_TRIES`] failures the
+/// prompt is locked for a while. Users whose login shell is `nologin` or
+/// `false`, or cannot be started, are refused.
+fn login() -> UserInfo {
+    let mut failures = 0;
+    loop {
+        let name = prompt("arceos login: ", true);
+        if name.is_empty() {
+            continue;
+        }
+        let password = prompt("Password: ", false);
+        let user = find_user(&name);
+        let ok = match &user {
+            Some(u) => crate::user::verify(u.uid, password),
+            None => {
+                // do not tell whether the user exists
+                audit::record(AuditAction::Authenticate, &name, false);
+                false
+            }
+        };
+        if let (true, Some(user)) = (ok, user) {
+            if NOLOGIN_SHELLS.contains(&user.shell.as_str()) {
+                audit::record(AuditAction::Login, &name, false);
+                print("This account is currently not available.\n");
+                continue;
+            }
+            if !user.shell.is_empty() && user.shell != DEFAULT_SHELL {
+                audit::record(AuditAction::Login, &name, false);
+                print(&format!("Cannot execute {}\n", user.shell));
+                continue;
+            }
+            audit::record(AuditAction::Login, &name, true);
+            return user;
+        }
+
+        audit::record(AuditAction::Login, &name, false);
+        failures += 1;
+        axtask::sleep(RETRY_DELAY * (1 << (failures - 1)));
+        print("Login incorrect\n");
+        if failures == LOGIN_TRIES {
+            print("Maximum number of tries exceeded\n");
+            axtask::sleep(LOCKOUT_DELAY);
+            failures = 0;
+        }
+    }
+}
+
+/// Ends the session of the console, if any, and returns to `/`.
+fn logout() {
+    let _ = axfs::api::set_current_dir("/");
+    let Some(session) = CURRENT.lock().take() else {
+        return;
+    };
+    let res = remove_utmp(&session)
+        .and_then(|_| append_wtmp(&SessionRecord::new(RecordKind::Logout, &session)));
+    if let Err(e) = res {
+        warn!(
+            "session: failed to record the logout of {}: {:?}",
+            session.user, e
+        );
+    }
+}
+
+/// Returns the active sessions, like `who(1)`.
+pub fn who() -> AxResult<Vec<SessionRecord>> {
+    Ok(read_lines(UTMP)?
+        .iter()
+        .filter_map(|line| SessionRecord::parse(line))
+        .collect())
+}
+
+/// Returns up to `count` of the most recent sessions, newest first, like
+/// `last(1)`.
+pub fn last(count: usize) -> AxResult<Vec<LastEntry>> {
+    let records: Vec<SessionRecord> = read_lines(WTMP)?
+        .iter()
+        .filter_map(|line| SessionRecord::parse(line))
+        .collect();
+    Ok(last_entries(records, count))
+}
+
+/// Pairs the logins in `records` with their logouts, and returns up to `count`
+/// of the sessions, newest first.
+fn last_entries(records: Vec<SessionRecord>, count: usize) -> Vec<LastEntry> {
+    let mut entries: Vec<LastEntry> = Vec::new();
+    // indices in `entries` of the sessions that have not ended yet
+    let mut open: Vec<(u64, String, usize)> = Vec::new();
+    for record in records {
+        match record.kind {
+            RecordKind::Boot => {
+                for (_, _, idx) in open.drain(..) {
+                    entries[idx].end = SessionEnd::Reboot;
+                }
+            }
+            RecordKind::Login => {
+                open.push((record.task_id, record.tty.clone(), entries.len()));
+                entries.push(LastEntry {
+                    user: record.user,
+                    tty: record.tty,
+                    login_time: record.time,
+                    end: SessionEnd::LoggedIn,
+                });
+            }
+            RecordKind::Logout => {
+                if let Some(pos) = open
+                    .iter()
+                    .position(|(id, tty, _)| *id == record.task_id && *tty == record.tty)
+                {
+                    let (_, _, idx) = open.remove(pos);
+                    entries[idx].end = SessionEnd::Logout(record.time);
+                }
+            }
+        }
+    }
+    entries.into_iter().rev().take(count).collect()
+}
+
+fn find_user(name: &str) -> Option<UserInfo> {
+    match user_list() {
+        Ok(users) => users.into_values().find(|u| u.username == name),
+        Err(e) => {
+            debug!("{:?}", e);
+            None
+        }
+    }
+}
+
+/// Records the session, switches the current task to the user and enters their
+/// home directory.
+fn start(user: UserInfo) {
+    let shell = if user.shell.is_empty() {
+        DEFAULT_SHELL.to_string()
+    } else {
+        user.shell
+    };
+    let mut session = Session {
+        user: user.username,
+        uid: user.uid,
+        gid: user.gid,
+        tty: CONSOLE_TTY.into(),
+        task_id: axtask::current().id().as_u64(),
+        login_time: axhal::time::current_time(),
+        home: user.home,
+        shell,
+        env: Vec::new(),
+    };
+    let res = create_dirs()
+        .and_then(|_| append_utmp(&session))
+        .and_then(|_| append_wtmp(&SessionRecord::new(RecordKind::Login, &session)));
+    if let Err(e) = res {
+        warn!(
+            "session: failed to record the login of {}: {:?}",
+            session.user, e
+        );
+    }
+
+    switch_user(session.uid, session.gid);
+    if axfs::api::set_current_dir(&session.home).is_err() {
+        print("No directory, logging in with HOME=/\n");
+        session.home = "/".into();
+        let _ = axfs::api::set_current_dir("/");
+    }
+    session.env = vec![
+        ("HOME".into(), session.home.clone()),
+        ("USER".into(), session.user.clone()),
+        ("LOGNAME".into(), session.user.clone()),
+        ("SHELL".into(), session.shell.clone()),
+        ("PATH".into(), DEFAULT_PATH.into()),
+    ];
+    *CURRENT.lock() = Some(session);
+}
+
+/// Switches the current task to the user with all of their groups.
+fn switch_user(uid: u32, gid: u32) {
+    axfs::api::set_current_gid(gid).expect("set_current_gid failed");
+    crate::api::init_groups(uid);
+    axfs::api::set_current_uid(uid).expect("set_current_uid failed");
+}
+
+fn create_dirs() -> AxResult {
+    for dir in SESSION_DIRS {
+        axfs::api::create_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+fn append_utmp(session: &Session) -> AxResult {
+    append_line(UTMP, &SessionRecord::new(RecordKind::Login, session))
+}
+
+fn remove_utmp(session: &Session) -> AxResult {
+    let lines: Vec<String> = read_lines(UTMP)?
+        .into_iter()
+        .filter(|line| {
+            SessionRecord::parse(line).map_or(true, |r| {
+                r.task_id != session.task_id || r.tty != session.tty
+            })
+        })
+        .map(|line| line + "\n")
+        .collect();
+    write_atomic(UTMP, &lines.concat(), FilePerm::default_file())
+}
+
+fn append_wtmp(record: &SessionRecord) -> AxResult {
+    append_line(WTMP, record)
+}
+
+fn append_line(path: &str, record: &SessionRecord) -> AxResult {
+    let mut opts = OpenOptions::new();
+    opts.append(true);
+    opts.create(true);
+    opts.mode(0o644);
+    let mut file = File::open(path, &opts)?;
+    write_all(&mut file, format!("{}\n", record).as_bytes())
+}
+
+fn print(s: &str) {
+    for c in s.bytes() {
+        axhal::console::putchar(c);
+    }
+}
+
+/// Prints `msg` and reads a line from the console, echoing it if `echo`.
+fn prompt(msg: &str, echo: bool) -> String {
+    const DL: u8 = b'\x7f';
+    const BS: u8 = b'\x08';
+    print(msg);
+    let mut line = String::new();
+    loop {
+        let c = match axhal::console::getchar() {
+            Some(b'\r') | Some(b'\n') => break,
+            Some(c) => c,
+            None => {
+                axtask::yield_now();
+                continue;
+            }
+        };
+        if c == DL || c == BS {
+            if line.pop().is_some() && echo {
+                print("\x08 \x08");
+            }
+        } else if c.is_ascii() && !c.is_ascii_control() {
+            line.push(c as char);
+            if echo {
+                axhal::console::putchar(c);
+            }
+        }
+    }
+    axhal::console::putchar(b'\n');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(lines: &[&str]) -> Vec<SessionRecord> {
+        lines
+            .iter()
+            .map(|line| SessionRecord::parse(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        let line = "login alice console 7 12.000345";
+        let record = SessionRecord::parse(line).unwrap();
+        assert_eq!(record.kind, RecordKind::Login);
+        assert_eq!(record.user, "alice");
+        assert_eq!(record.tty, "console");
+        assert_eq!(record.task_id, 7);
+        assert_eq!(record.time, Duration::new(12, 345_000));
+        assert_eq!(record.to_string(), line);
+
+        let record = SessionRecord::parse("boot  reboot ~ 0 0.000000\n").unwrap();
+        assert_eq!(record.kind, RecordKind::Boot);
+        assert_eq!(record.time, Duration::ZERO);
+    }
+
+    #[test]
+    fn parse_malformed() {
+        for line in [
+            "",
+            "login alice console 7",
+            "login alice console 7 12.000000 extra",
+            "shutdown alice console 7 12.000000",
+            "login alice console seven 12.000000",
+            "login alice console 7 12",
+            "login alice console 7 12.abc",
+        ] {
+            assert!(SessionRecord::parse(line).is_none(), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn last_sessions() {
+        let wtmp = records(&[
+            "boot reboot ~ 0 0.000000",
+            "login alice console 3 10.000000",
+            "logout alice console 3 20.000000",
+            "login bob console 4 30.000000",
+            "logout bob console 5 40.000000",
+            "boot reboot ~ 0 0.000000",
+            "login root console 3 5.000000",
+        ]);
+        let entries = last_entries(wtmp.clone(), usize::MAX);
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|e| (e.user.as_str(), e.login_time.as_secs(), e.end))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("root", 5, SessionEnd::LoggedIn),
+                // the logout of another task does not end the session
+                ("bob", 30, SessionEnd::Reboot),
+                ("alice", 10, SessionEnd::Logout(Duration::from_secs(20))),
+            ]
+        );
+
+        let newest = last_entries(wtmp, 1);
+        assert_eq!(newest.len(), 1);
+        assert_eq!(newest[0].user, "root");
+        assert!(last_entries(Vec::new(), 10).is_empty());
+    }
+}
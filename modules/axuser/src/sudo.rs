@@ -0,0 +1,99 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use axhal::time::{current_time, TimeValue};
+use axsync::Mutex;
+use permission::permission::{current_credentials, set_current_credentials, set_current_uid};
+
+use crate::group::{group_by_gid, groups_of_user};
+use crate::sudoers::{Subject, SudoPermission, Sudoers};
+use crate::user::user_list;
+
+/// Minutes an authentication is remembered if `sudoers` does not set
+/// `timestamp_timeout`.
+const TIMESTAMP_TIMEOUT_DEFAULT: u64 = 5;
+
+/// Time of the last successful authentication of each user.
+static TIMESTAMPS: Mutex<BTreeMap<u32, TimeValue>> = Mutex::new(BTreeMap::new());
+
+fn load_sudoers() -> Option<Sudoers> {
+    let cred = current_credentials();
+    set_current_uid(0).unwrap();
+    let content = axfs::api::read_to_string("/etc/sudoers");
+    set_current_credentials(cred);
+    content.ok().map(|c| Sudoers::parse(&c))
+}
+
+/// Returns the uid and the names of all groups of the user.
+fn lookup_user(name: &str) -> Option<(u32, Vec<String>)> {
+    let uid = user_list()
+        .ok()?
+        .into_values()
+        .find(|u| u.username == name)?
+        .uid;
+    let groups = groups_of_user(uid)
+        .into_iter()
+        .filter_map(|gid| group_by_gid(gid).map(|g| g.name))
+        .collect();
+    Some((uid, groups))
+}
+
+pub fn is_sudoer(name: &str) -> bool {
+    let sudoers = match load_sudoers() {
+        Some(s) => s,
+        None => return false,
+    };
+    match lookup_user(name) {
+        Some((uid, groups)) => sudoers.has_entry(&Subject {
+            name,
+            uid,
+            groups: &groups,
+        }),
+        None => false,
+    }
+}
+
+pub fn sudo_permission(user: &str, command: &str, runas: &str) -> SudoPermission {
+    let sudoers = load_sudoers();
+    let (user_info, target_info) = (lookup_user(user), lookup_user(runas));
+    match (sudoers, user_info, target_info) {
+        (Some(sudoers), Some((uid, groups)), Some((target_uid, target_groups))) => sudoers.check(
+            &Subject {
+                name: user,
+                uid,
+                groups: &groups,
+            },
+            command,
+            &Subject {
+                name: runas,
+                uid: target_uid,
+                groups: &target_groups,
+            },
+        ),
+        _ => SudoPermission::Denied,
+    }
+}
+
+pub fn auth_cached(uid: u32) -> bool {
+    let timeout = load_sudoers()
+        .and_then(|s| s.timestamp_timeout)
+        .unwrap_or(TIMESTAMP_TIMEOUT_DEFAULT);
+    let timeout = TimeValue::from_secs(timeout * 60);
+    let mut timestamps = TIMESTAMPS.lock();
+    match timestamps.get(&uid) {
+        Some(&t) if current_time().saturating_sub(t) < timeout => true,
+        Some(_) => {
+            timestamps.remove(&uid);
+            false
+        }
+        None => false,
+    }
+}
+
+pub fn record_auth(uid: u32) {
+    TIMESTAMPS.lock().insert(uid, current_time());
+}
+
+pub fn invalidate_auth(uid: u32) {
+    TIMESTAMPS.lock().remove(&uid);
+}
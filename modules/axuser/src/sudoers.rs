@@ -0,0 +1,503 @@
+//! A subset of the `sudoers(5)` policy language.
+//!
+//! Supported are comments, line continuations with `\`, the alias
+//! definitions `User_Alias`, `Runas_Alias`, `Host_Alias` and `Cmnd_Alias`,
+//! `Defaults timestamp_timeout=<minutes>`, and user specifications like
+//!
+//! ```text
+//! root      ALL=(ALL:ALL) ALL
+//! %wheel    ALL=(ALL) NOPASSWD: /bin/ls, PASSWD: cat
+//! ADMINS    arceos=(root) SHUTDOWN, !halt
+//! ```
+//!
+//! Users are given as names, `#uid`, `%group`, aliases or `ALL`, and any item
+//! can be negated with `!`. A command is a name or path, optionally followed
+//! by the exact arguments it may be run with (`""` for none); a path ending
+//! with `/` allows every command in that directory. The run-as list and the
+//! `NOPASSWD:`/`PASSWD:` tags carry over to the following commands of the
+//! same line. As in `sudo`, the last matching entry decides.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Host names this machine answers to in `sudoers` host lists.
+const HOSTNAMES: [&str; 2] = ["arceos", "localhost"];
+/// Maximum depth of nested aliases.
+const ALIAS_DEPTH_MAX: usize = 8;
+
+/// The user a permission is checked for.
+pub struct Subject<'a> {
+    pub name: &'a str,
+    pub uid: u32,
+    /// Names of all groups of the user.
+    pub groups: &'a [String],
+}
+
+/// The answer of the policy to a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SudoPermission {
+    /// The command may not be run.
+    Denied,
+    /// The command may be run after the user has entered their password.
+    Password,
+    /// The command may be run without a password.
+    NoPassword,
+}
+
+#[derive(Debug, Clone)]
+struct Member {
+    negated: bool,
+    name: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Runas {
+    users: Option<Vec<Member>>,
+    groups: Option<Vec<Member>>,
+}
+
+#[derive(Debug, Clone)]
+struct Cmnd {
+    runas: Runas,
+    nopasswd: bool,
+    cmnd: Member,
+}
+
+#[derive(Debug, Clone)]
+struct UserSpec {
+    users: Vec<Member>,
+    hosts: Vec<Member>,
+    cmnds: Vec<Cmnd>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum AliasKind {
+    User,
+    Runas,
+    Host,
+    Cmnd,
+}
+
+/// A parsed `sudoers` file.
+#[derive(Debug, Default)]
+pub struct Sudoers {
+    aliases: [BTreeMap<String, Vec<Member>>; 4],
+    specs: Vec<UserSpec>,
+    /// Value of `Defaults timestamp_timeout`, in minutes.
+    pub timestamp_timeout: Option<u64>,
+}
+
+fn is_alias_name(s: &str) -> bool {
+    s != "ALL"
+        && s.starts_with(|c: char| c.is_ascii_uppercase())
+        && s.bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_')
+}
+
+/// Splits `s` at commas outside of parentheses.
+fn split_list(s: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(s[start..].trim());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+fn parse_member(s: &str) -> Member {
+    let s = s.trim();
+    let negated = s.starts_with('!');
+    Member {
+        negated,
+        name: s.trim_start_matches('!').trim().to_string(),
+    }
+}
+
+fn parse_members(s: &str) -> Vec<Member> {
+    split_list(s).into_iter().map(parse_member).collect()
+}
+
+/// Parses `(users[:groups])` at the start of `s`, and returns the rest.
+fn parse_runas(s: &str) -> Option<(Runas, &str)> {
+    let s = s.strip_prefix('(')?;
+    let end = s.find(')')?;
+    let (inner, rest) = (&s[..end], &s[end + 1..]);
+    let (users, groups) = match inner.split_once(':') {
+        Some((u, g)) => (u, Some(g)),
+        None => (inner, None),
+    };
+    let users = users.trim();
+    let runas = Runas {
+        users: (!users.is_empty()).then(|| parse_members(users)),
+        groups: groups.map(parse_members),
+    };
+    Some((runas, rest.trim_start()))
+}
+
+/// Removes the comment at the end of `line`.
+fn strip_comment(line: &str) -> &str {
+    for (i, _) in line.match_indices('#') {
+        // `#uid` is a user, not a comment
+        if !line[i + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+            return &line[..i];
+        }
+    }
+    line
+}
+
+/// Splits the part before `=` into the user list and the host list.
+fn split_user_host(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim();
+    let mut prev_comma = false;
+    for (i, c) in s.char_indices() {
+        if c.is_whitespace() {
+            let rest = s[i..].trim_start();
+            if !prev_comma && !rest.starts_with(',') {
+                return Some((&s[..i], rest));
+            }
+        } else {
+            prev_comma = c == ',';
+        }
+    }
+    None
+}
+
+impl Sudoers {
+    /// Parses the contents of a `sudoers` file. Malformed lines are skipped.
+    pub fn parse(content: &str) -> Self {
+        let mut sudoers = Self::default();
+        let mut logical = String::new();
+        for line in content.lines() {
+            let line = strip_comment(line);
+            if let Some(l) = line.trim_end().strip_suffix('\\') {
+                logical.push_str(l);
+                logical.push(' ');
+                continue;
+            }
+            logical.push_str(line);
+            let full = core::mem::take(&mut logical);
+            if !full.trim().is_empty() && sudoers.parse_line(full.trim()).is_none() {
+                log::warn!("sudoers: ignoring malformed line: {}", full.trim());
+            }
+        }
+        sudoers
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<()> {
+        let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let kind = match first {
+            "User_Alias" => Some(AliasKind::User),
+            "Runas_Alias" => Some(AliasKind::Runas),
+            "Host_Alias" => Some(AliasKind::Host),
+            "Cmnd_Alias" => Some(AliasKind::Cmnd),
+            "Defaults" => {
+                for option in split_list(rest) {
+                    if let Some(v) = option.strip_prefix("timestamp_timeout") {
+                        let v = v.trim_start().strip_prefix('=')?.trim();
+                        self.timestamp_timeout = Some(v.parse().ok()?);
+                    }
+                }
+                return Some(());
+            }
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            // NAME = item, ... [: NAME = item, ...]
+            for def in rest.split(':') {
+                let (name, items) = def.split_once('=')?;
+                let name = name.trim();
+                if !is_alias_name(name) {
+                    return None;
+                }
+                self.aliases[kind as usize].insert(name.to_string(), parse_members(items));
+            }
+            return Some(());
+        }
+
+        let (left, right) = line.split_once('=')?;
+        let (users, hosts) = split_user_host(left)?;
+        let mut runas = Runas::default();
+        let mut nopasswd = false;
+        let mut cmnds = Vec::new();
+        for item in split_list(right) {
+            let mut item = item;
+            if item.starts_with('(') {
+                let (r, rest) = parse_runas(item)?;
+                runas = r;
+                item = rest;
+            }
+            loop {
+                if let Some(rest) = item.strip_prefix("NOPASSWD:") {
+                    nopasswd = true;
+                    item = rest.trim_start();
+                } else if let Some(rest) = item.strip_prefix("PASSWD:") {
+                    nopasswd = false;
+                    item = rest.trim_start();
+                } else {
+                    break;
+                }
+            }
+            if item.is_empty() {
+                return None;
+            }
+            cmnds.push(Cmnd {
+                runas: runas.clone(),
+                nopasswd,
+                cmnd: parse_member(item),
+            });
+        }
+        self.specs.push(UserSpec {
+            users: parse_members(users),
+            hosts: parse_members(hosts),
+            cmnds,
+        });
+        Some(())
+    }
+
+    /// Evaluates `list`: the last item that matches decides, and a negated
+    /// item turns a match into a rejection.
+    fn eval_list(
+        &self,
+        kind: AliasKind,
+        list: &[Member],
+        depth: usize,
+        matches: &dyn Fn(&str) -> bool,
+    ) -> Option<bool> {
+        let mut result = None;
+        for m in list {
+            let matched = if m.name == "ALL" {
+                Some(true)
+            } else if is_alias_name(&m.name) {
+                match self.aliases[kind as usize].get(&m.name) {
+                    Some(items) if depth < ALIAS_DEPTH_MAX => {
+                        self.eval_list(kind, items, depth + 1, matches)
+                    }
+                    _ => None,
+                }
+            } else if matches(&m.name) {
+                Some(true)
+            } else {
+                None
+            };
+            if let Some(allowed) = matched {
+                result = Some(allowed != m.negated);
+            }
+        }
+        result
+    }
+
+    fn user_matches(&self, kind: AliasKind, list: &[Member], user: &Subject) -> bool {
+        let matches = |name: &str| {
+            if let Some(group) = name.strip_prefix('%') {
+                user.groups.iter().any(|g| g == group)
+            } else if let Some(uid) = name.strip_prefix('#') {
+                uid.parse() == Ok(user.uid)
+            } else {
+                name == user.name
+            }
+        };
+        self.eval_list(kind, list, 0, &matches) == Some(true)
+    }
+
+    fn host_matches(&self, list: &[Member]) -> bool {
+        let matches = |name: &str| HOSTNAMES.contains(&name);
+        self.eval_list(AliasKind::Host, list, 0, &matches) == Some(true)
+    }
+
+    fn runas_matches(&self, runas: &Runas, user: &Subject, target: &Subject) -> bool {
+        match &runas.users {
+            Some(users) => self.user_matches(AliasKind::Runas, users, target),
+            // `(:group)` only allows running as the invoking user
+            None if runas.groups.is_some() => target.uid == user.uid,
+            None => target.uid == 0,
+        }
+    }
+
+    /// Returns whether `command` (a command name followed by its arguments)
+    /// matches the `sudoers` command `pattern`.
+    fn command_matches(pattern: &str, command: &str) -> bool {
+        let (pname, pargs) = match pattern.split_once(char::is_whitespace) {
+            Some((n, a)) => (n, Some(a.trim())),
+            None => (pattern, None),
+        };
+        let (cname, cargs) = command
+            .trim()
+            .split_once(char::is_whitespace)
+            .unwrap_or((command.trim(), ""));
+        let name_ok = if pname.ends_with('/') {
+            cname.starts_with(pname) && !cname[pname.len()..].contains('/')
+        } else {
+            pname == cname || (!cname.contains('/') && pname.rsplit('/').next() == Some(cname))
+        };
+        let args_ok = match pargs {
+            None => true,
+            Some("\"\"") => cargs.trim().is_empty(),
+            Some(pargs) => pargs.split_whitespace().eq(cargs.split_whitespace()),
+        };
+        name_ok && args_ok
+    }
+
+    /// Decides whether `user` may run `command` as `target`.
+    pub fn check(&self, user: &Subject, command: &str, target: &Subject) -> SudoPermission {
+        let mut permission = SudoPermission::Denied;
+        let matches = |pattern: &str| Self::command_matches(pattern, command);
+        for spec in &self.specs {
+            if !self.user_matches(AliasKind::User, &spec.users, user)
+                || !self.host_matches(&spec.hosts)
+            {
+                continue;
+            }
+            for c in &spec.cmnds {
+                if !self.runas_matches(&c.runas, user, target) {
+                    continue;
+                }
+                let list = core::slice::from_ref(&c.cmnd);
+                permission = match self.eval_list(AliasKind::Cmnd, list, 0, &matches) {
+                    Some(true) if c.nopasswd => SudoPermission::NoPassword,
+                    Some(true) => SudoPermission::Password,
+                    Some(false) => SudoPermission::Denied,
+                    None => continue,
+                };
+            }
+        }
+        permission
+    }
+
+    /// Whether any entry allows `user` to run something.
+    pub fn has_entry(&self, user: &Subject) -> bool {
+        self.specs.iter().any(|spec| {
+            self.user_matches(AliasKind::User, &spec.users, user)
+                && self.host_matches(&spec.hosts)
+                && spec.cmnds.iter().any(|c| !c.cmnd.negated)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SudoPermission::*;
+
+    fn subject<'a>(name: &'a str, uid: u32, groups: &'a [String]) -> Subject<'a> {
+        Subject { name, uid, groups }
+    }
+
+    fn check(sudoers: &Sudoers, user: &Subject, command: &str) -> SudoPermission {
+        let root = subject("root", 0, &[]);
+        sudoers.check(user, command, &root)
+    }
+
+    #[test]
+    fn test_comments() {
+        let sudoers = Sudoers::parse(
+            "# full line comment\n\
+             alice ALL=(ALL) /bin/ls # trailing comment # with another #\n\
+             #1001 ALL=(ALL) NOPASSWD: cat #comment after #uid\n\
+             \t  # indented comment\n",
+        );
+        assert_eq!(sudoers.specs.len(), 2);
+        let alice = subject("alice", 1000, &[]);
+        assert_eq!(check(&sudoers, &alice, "ls -l"), Password);
+        assert_eq!(check(&sudoers, &alice, "cat"), Denied);
+        let bob = subject("bob", 1001, &[]);
+        assert_eq!(check(&sudoers, &bob, "cat"), NoPassword);
+        assert_eq!(check(&sudoers, &bob, "ls"), Denied);
+    }
+
+    #[test]
+    fn test_malformed_lines() {
+        let sudoers = Sudoers::parse(
+            "alice ALL\n\
+             alice ALL=(root ALL\n\
+             alice ALL=(ALL) NOPASSWD:\n\
+             User_Alias admins = alice\n\
+             Defaults timestamp_timeout=soon\n\
+             ALL=ALL\n\
+             alice ALL=(ALL) /bin/ls\n",
+        );
+        // only the last line is kept
+        assert_eq!(sudoers.specs.len(), 1);
+        assert!(sudoers.aliases.iter().all(|a| a.is_empty()));
+        assert_eq!(sudoers.timestamp_timeout, None);
+        let alice = subject("alice", 1000, &[]);
+        assert_eq!(check(&sudoers, &alice, "ls"), Password);
+        assert_eq!(check(&sudoers, &alice, "cat"), Denied);
+    }
+
+    #[test]
+    fn test_all() {
+        let sudoers = Sudoers::parse("root ALL=(ALL:ALL) ALL\nALL ALL=(ALL) NOPASSWD: /bin/ls");
+        let root = subject("root", 0, &[]);
+        let alice = subject("alice", 1000, &[]);
+        assert_eq!(check(&sudoers, &root, "anything at all"), Password);
+        assert_eq!(check(&sudoers, &root, "ls"), NoPassword);
+        assert_eq!(check(&sudoers, &alice, "/bin/ls /"), NoPassword);
+        assert_eq!(check(&sudoers, &alice, "cat"), Denied);
+        assert!(sudoers.has_entry(&alice));
+        // `(ALL)` allows any target user
+        assert_eq!(sudoers.check(&alice, "ls", &root), NoPassword);
+        assert_eq!(
+            sudoers.check(&alice, "ls", &subject("bob", 1001, &[])),
+            NoPassword
+        );
+    }
+
+    #[test]
+    fn test_group_rules() {
+        let sudoers = Sudoers::parse(
+            "%wheel ALL=(ALL) ALL, !halt\n\
+             %ops,!mallory ALL=(root) NOPASSWD: /sbin/\n",
+        );
+        let wheel = [String::from("users"), String::from("wheel")];
+        let ops = [String::from("ops")];
+        let alice = subject("alice", 1000, &wheel);
+        let bob = subject("bob", 1001, &ops);
+        let mallory = subject("mallory", 1002, &ops);
+        let eve = subject("eve", 1003, &[]);
+
+        assert_eq!(check(&sudoers, &alice, "ls"), Password);
+        assert_eq!(check(&sudoers, &alice, "halt"), Denied);
+        assert_eq!(check(&sudoers, &bob, "/sbin/reboot"), NoPassword);
+        assert_eq!(check(&sudoers, &bob, "/sbin/x/y"), Denied);
+        assert_eq!(check(&sudoers, &bob, "ls"), Denied);
+        assert_eq!(check(&sudoers, &mallory, "/sbin/reboot"), Denied);
+        assert!(!sudoers.has_entry(&mallory));
+        assert!(!sudoers.has_entry(&eve));
+        // `(root)` only allows root as the target
+        assert_eq!(sudoers.check(&bob, "/sbin/reboot", &alice), Denied);
+    }
+
+    #[test]
+    fn test_aliases_and_continuation() {
+        let sudoers = Sudoers::parse(
+            "User_Alias ADMINS = alice, #1001\n\
+             Cmnd_Alias SHUTDOWN = /sbin/halt, \\\n\
+             \t/sbin/reboot \"\"\n\
+             Defaults timestamp_timeout=5\n\
+             ADMINS arceos=(root) SHUTDOWN, PASSWD: !/sbin/reboot\n",
+        );
+        assert_eq!(sudoers.timestamp_timeout, Some(5));
+        let alice = subject("alice", 1000, &[]);
+        let bob = subject("bob", 1001, &[]);
+        assert_eq!(check(&sudoers, &alice, "/sbin/halt"), Password);
+        assert_eq!(check(&sudoers, &bob, "halt -f"), Password);
+        // the last matching entry decides
+        assert_eq!(check(&sudoers, &alice, "/sbin/reboot"), Denied);
+        assert_eq!(
+            check(&sudoers, &subject("carol", 1002, &[]), "halt"),
+            Denied
+        );
+    }
+}
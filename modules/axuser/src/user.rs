@@ -1,12 +1,15 @@
-use crate::sha1::sha1;
+use crate::admin::{write_atomic, DB_LOCK};
+use crate::crypt::{hash_password, verify_password};
 use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use axerrno::{AxError, AxResult};
-use axfs::fops::{File, OpenOptions};
+use axfs::audit::AuditAction;
+use axfs::fops::{File, FilePerm, OpenOptions};
 use log::debug;
-use permission::permission::{current_uid, set_current_uid};
+use permission::permission::{current_credentials, set_current_credentials, set_current_uid};
 
+#[derive(Debug, Clone)]
 pub struct UserInfo {
     pub username: String,
     pub password: String,
@@ -17,7 +20,7 @@ pub struct UserInfo {
     pub shell: String,
 }
 
-fn user_list() -> AxResult<BTreeMap<u32, UserInfo>> {
+pub(crate) fn user_list() -> AxResult<BTreeMap<u32, UserInfo>> {
     let mut opt = OpenOptions::new();
     opt.read(true);
     let mut file = File::open("/etc/passwd", &opt)?;
@@ -76,6 +79,16 @@ pub fn user_name(uid: u32) -> String {
     }
 }
 
+pub fn user_gid(uid: u32) -> Option<u32> {
+    match user_list() {
+        Ok(ul) => ul.get(&uid).map(|ui| ui.gid),
+        Err(_e) => {
+            debug!("{}", _e);
+            None
+        }
+    }
+}
+
 pub fn user_id(name: String) -> u32 {
     let ul = user_list();
     if let Err(_e) = ul {
@@ -92,48 +105,29 @@ pub fn user_id(name: String) -> u32 {
 }
 
 pub fn is_sudoer(name: String) -> bool {
-    let cuid = current_uid().unwrap();
-    set_current_uid(0).unwrap();
-    let mut opt = OpenOptions::new();
-    opt.read(true);
-    let file_result = File::open("/etc/sudoers", &opt);
-    if file_result.is_err() {
-        set_current_uid(cuid).unwrap();
-        return false;
-    }
-    let mut file = file_result.unwrap();
-    let mut content = String::new();
-    loop {
-        let mut buf = [0u8; 256];
-        let n = file.read(&mut buf);
-        if n.is_err() || n.is_ok_and(|x| x == 0) {
-            break;
-        }
-        content += String::from_utf8_lossy(&(buf[..n.unwrap()])).as_ref();
-    }
-    let lines: Vec<&str> = content.split('\n').collect();
-    for line in lines {
-        if line.trim().is_empty() {
-            continue;
-        }
-        let v: Vec<&str> = line.trim().split(' ').collect();
-        if v[0].to_string() == name {
-            set_current_uid(cuid).unwrap();
-            return true;
-        }
-    }
-    set_current_uid(cuid).unwrap();
-    false
+    crate::sudo::is_sudoer(&name)
 }
 
 pub fn verify(uid: u32, password: String) -> bool {
-    let cuid = current_uid().unwrap();
+    let ok = verify_shadow(uid, password);
+    let name = user_name(uid);
+    let target = if name.is_empty() {
+        uid.to_string()
+    } else {
+        name
+    };
+    axfs::audit::record(AuditAction::Authenticate, &target, ok);
+    ok
+}
+
+fn verify_shadow(uid: u32, password: String) -> bool {
+    let cred = current_credentials();
     set_current_uid(0).unwrap();
     let mut opt = OpenOptions::new();
     opt.read(true);
     let file_result = File::open("/etc/shadow", &opt);
     if file_result.is_err() {
-        set_current_uid(cuid).unwrap();
+        set_current_credentials(cred.clone());
         return false;
     }
     let mut file = file_result.unwrap();
@@ -153,63 +147,35 @@ pub fn verify(uid: u32, password: String) -> bool {
         }
         let v: Vec<&str> = line.split(':').collect();
         if v[0].to_string() == uid.to_string() {
-            set_current_uid(cuid).unwrap();
-            let s1 = sha1(&password.as_str());
-            let mut s1h = String::new();
-            for i in s1 {
-                s1h.push(char::from((i / 16) + b'a'));
-                s1h.push(char::from((i % 16) + b'a'));
-            }
-            return v.len() <= 1 || s1h == v[1].to_string();
+            set_current_credentials(cred.clone());
+            return verify_password(password.as_str(), v.get(1).unwrap_or(&""));
         }
     }
-    set_current_uid(cuid).unwrap();
-    true
+    set_current_credentials(cred);
+    false
 }
 
 pub fn set_password(password: String) -> AxResult {
-    let cuid = current_uid().unwrap();
+    let cred = current_credentials();
+    let cuid = cred.euid.to_string();
+    let _guard = DB_LOCK.lock();
     set_current_uid(0).unwrap();
-    let mut opt = OpenOptions::new();
-    opt.read(true);
-    let mut file = File::open("/etc/shadow", &opt)?;
-    let mut content = String::new();
-    loop {
-        let mut buf = [0u8; 256];
-        let n = file.read(&mut buf);
-        if n.is_err() || n.is_ok_and(|x| x == 0) {
-            break;
-        }
-        content += String::from_utf8_lossy(&(buf[..n.unwrap()])).as_ref();
-    }
-    let lines: Vec<&str> = content.split('\n').collect();
-    let mut new_lines: Vec<String> = Vec::new();
-    let s1 = sha1(&password.as_str());
-    let mut s1h = String::new();
-    for i in s1 {
-        s1h.push(char::from((i / 16) + b'a'));
-        s1h.push(char::from((i % 16) + b'a'));
-    }
-    new_lines.push(cuid.to_string() + ":" + s1h.as_str());
-    for line in lines {
-        if line.trim().is_empty() {
-            continue;
-        }
-        let v: Vec<&str> = line.split(':').collect();
-        if v[0].to_string() == cuid.to_string() {
-            continue;
-        } else {
-            new_lines.push(line.to_string())
+    let res = (|| {
+        let content = axfs::api::read_to_string("/etc/shadow")?;
+        let mut new_content = cuid.clone() + ":" + hash_password(password.as_str()).as_str() + "\n";
+        for line in content.split('\n') {
+            if line.trim().is_empty() || line.split(':').next() == Some(cuid.as_str()) {
+                continue;
+            }
+            new_content += line;
+            new_content.push('\n');
         }
-    }
-    let mut opt = OpenOptions::new();
-    opt.write(true);
-    opt.create(true);
-    opt.truncate(true);
-    let mut file = File::open("/etc/shadow", &opt)?;
-    for new_line in new_lines {
-        file.write((new_line + "\n").as_bytes())?;
-    }
-    set_current_uid(cuid).unwrap();
-    Ok(())
+        write_atomic(
+            "/etc/shadow",
+            &new_content,
+            FilePerm::from_bits_truncate(0o600),
+        )
+    })();
+    set_current_credentials(cred);
+    res
 }
@@ -3,6 +3,9 @@
 define unit_test
   $(call run_cmd,cargo test,-p percpu $(1) -- --nocapture)
   $(call run_cmd,cargo test,-p axfs $(1) --features "myfs" -- --nocapture)
+  $(call run_cmd,cargo test,-p axfs $(1) --features "myfs permission" -- --nocapture)
+  $(call run_cmd,cargo test,-p axtask $(1) --features "sched_rt stack_usage" -- --nocapture)
+  $(call run_cmd,cargo test,-p axsync $(1) --features "axtask/sched_rt" -- --nocapture)
   $(call run_cmd,cargo test,--workspace --exclude "arceos-*" $(1) -- --nocapture)
 endef
 
@@ -114,6 +114,8 @@ if [ -z "$1" ]; then
         "apps/task/sleep"
         "apps/task/priority"
         "apps/task/tls"
+        "apps/task/async"
+        "apps/task/stack_overflow"
         "apps/net/httpclient"
         "apps/c/helloworld"
         "apps/c/memtest"
@@ -0,0 +1,361 @@
+//! The executor, which runs async tasks on ArceOS tasks (threads).
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use alloc::vec::Vec;
+use core::future::{poll_fn, Future};
+use core::pin::{pin, Pin};
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use core::task::{Context, Poll, Waker};
+use core::time::Duration;
+
+use arceos_api::task::{self as api, AxWaitQueueHandle};
+use spinlock::SpinNoIrq;
+
+use crate::reactor::REACTOR;
+
+/// How many tasks to run before polling the reactor again.
+const TASK_BUDGET: usize = 64;
+
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// The task is waiting to be woken up.
+const IDLE: u8 = 0;
+/// The task is in the run queue.
+const SCHEDULED: u8 = 1;
+/// The task is being polled.
+const RUNNING: u8 = 2;
+/// The task is woken up while being polled, it will be polled again.
+const NOTIFIED: u8 = 3;
+/// The future of the task has completed.
+const COMPLETE: u8 = 4;
+
+struct Task {
+    state: AtomicU8,
+    // only the thread polling the task takes the future out
+    future: SpinNoIrq<Option<BoxFuture>>,
+}
+
+impl Task {
+    fn new(future: BoxFuture) -> Self {
+        Self {
+            state: AtomicU8::new(IDLE),
+            future: SpinNoIrq::new(Some(future)),
+        }
+    }
+
+    fn schedule(self: &Arc<Self>) {
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            let new_state = match state {
+                IDLE => SCHEDULED,
+                RUNNING => NOTIFIED,
+                _ => return, // already scheduled or completed
+            };
+            match self.state.compare_exchange_weak(
+                state,
+                new_state,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => {
+                    if new_state == SCHEDULED {
+                        EXECUTOR.push(self.clone());
+                    }
+                    return;
+                }
+                Err(s) => state = s,
+            }
+        }
+    }
+
+    fn run(self: Arc<Self>) {
+        self.state.store(RUNNING, Ordering::Release);
+        let mut future = self.future.lock().take().unwrap();
+        let waker = Waker::from(self.clone());
+        if future
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_ready()
+        {
+            self.state.store(COMPLETE, Ordering::Release);
+            return;
+        }
+        *self.future.lock() = Some(future);
+        if self
+            .state
+            .compare_exchange(RUNNING, IDLE, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            // woken up while being polled
+            self.state.store(SCHEDULED, Ordering::Release);
+            EXECUTOR.push(self);
+        }
+    }
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        self.schedule();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.schedule();
+    }
+}
+
+/// Puts an idle thread to sleep until it is unparked.
+struct Parker {
+    notified: AtomicBool,
+    wq: AxWaitQueueHandle,
+}
+
+impl Parker {
+    fn new() -> Self {
+        Self {
+            notified: AtomicBool::new(false),
+            wq: AxWaitQueueHandle::new(),
+        }
+    }
+
+    fn park(&self, timeout: Option<Duration>) {
+        #[cfg(not(feature = "irq"))]
+        if timeout.is_some() {
+            // can not sleep with a timeout, keep polling the reactor
+            api::ax_yield_now();
+            return;
+        }
+        api::ax_wait_queue_wait(
+            &self.wq,
+            || self.notified.swap(false, Ordering::AcqRel),
+            timeout,
+        );
+    }
+
+    fn unpark(&self) {
+        self.notified.store(true, Ordering::Release);
+        api::ax_wait_queue_wake(&self.wq, 1);
+    }
+}
+
+struct Executor {
+    run_queue: SpinNoIrq<VecDeque<Arc<Task>>>,
+    idle_threads: SpinNoIrq<Vec<Arc<Parker>>>,
+    /// Whether an idle thread sleeps until the reactor must be turned again.
+    /// The other idle threads sleep until tasks are scheduled.
+    driving: AtomicBool,
+}
+
+static EXECUTOR: Executor = Executor::new();
+
+impl Executor {
+    const fn new() -> Self {
+        Self {
+            run_queue: SpinNoIrq::new(VecDeque::new()),
+            idle_threads: SpinNoIrq::new(Vec::new()),
+            driving: AtomicBool::new(false),
+        }
+    }
+
+    fn push(&self, task: Arc<Task>) {
+        self.run_queue.lock().push_back(task);
+        let parker = self.idle_threads.lock().pop();
+        if let Some(parker) = parker {
+            parker.unpark();
+        }
+    }
+
+    fn pop(&self) -> Option<Arc<Task>> {
+        self.run_queue.lock().pop_front()
+    }
+
+    fn has_tasks(&self) -> bool {
+        !self.run_queue.lock().is_empty()
+    }
+
+    /// Runs the tasks and drives the reactor on the current thread, until
+    /// `stop` returns true.
+    fn run_until(&self, parker: &Arc<Parker>, stop: impl Fn() -> bool) {
+        loop {
+            for _ in 0..TASK_BUDGET {
+                match self.pop() {
+                    Some(task) => task.run(),
+                    None => break,
+                }
+            }
+            let timeout = REACTOR.turn();
+            if stop() {
+                return;
+            }
+            if self.has_tasks() {
+                continue;
+            }
+
+            // only one idle thread waits for the timers and sockets
+            let driver = timeout.is_some()
+                && self
+                    .driving
+                    .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                    .is_ok();
+            self.idle_threads.lock().push(parker.clone());
+            // a task may be pushed before we are in the idle list
+            if !self.has_tasks() {
+                parker.park(if driver { timeout } else { None });
+            }
+            self.idle_threads
+                .lock()
+                .retain(|idle| !Arc::ptr_eq(idle, parker));
+            if driver {
+                self.driving.store(false, Ordering::Release);
+                if self.has_tasks() || stop() {
+                    // busy now, let another idle thread wait for the reactor
+                    let idle = self.idle_threads.lock().pop();
+                    if let Some(idle) = idle {
+                        idle.unpark();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Wakes up the thread in [`block_on`].
+struct BlockOnWaker {
+    woken: AtomicBool,
+    parker: Arc<Parker>,
+}
+
+impl Wake for BlockOnWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+        self.parker.unpark();
+    }
+}
+
+/// Runs a future to completion on the current thread.
+///
+/// The spawned tasks are also run on the current thread meanwhile, along with
+/// the threads started by [`spawn_workers`].
+///
+/// It must not be called in async tasks.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let parker = Arc::new(Parker::new());
+    let block_on_waker = Arc::new(BlockOnWaker {
+        woken: AtomicBool::new(true),
+        parker: parker.clone(),
+    });
+    let waker = Waker::from(block_on_waker.clone());
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if block_on_waker.woken.swap(false, Ordering::AcqRel) {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+        EXECUTOR.run_until(&parker, || block_on_waker.woken.load(Ordering::Acquire));
+    }
+}
+
+/// Starts `num` more threads running the spawned tasks.
+///
+/// The threads never exit. Without them, the tasks only run in [`block_on`].
+pub fn spawn_workers(num: usize) {
+    for i in 0..num {
+        axstd::thread::Builder::new()
+            .name(format!("axasync-worker-{i}"))
+            .spawn(|| {
+                let parker = Arc::new(Parker::new());
+                EXECUTOR.run_until(&parker, || false);
+            })
+            .expect("failed to spawn axasync worker");
+    }
+}
+
+struct JoinState<T> {
+    output: Option<T>,
+    finished: bool,
+    waker: Option<Waker>,
+}
+
+/// An owned permission to wait for a spawned task to finish.
+///
+/// It is a future resolving to the output of the task. The task keeps running
+/// if the handle is dropped.
+pub struct JoinHandle<T> {
+    state: Arc<SpinNoIrq<JoinState<T>>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Checks if the task has finished.
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().finished
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.state.lock();
+        match state.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Spawns a new async task, returning a [`JoinHandle`] for it.
+pub fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let state = Arc::new(SpinNoIrq::new(JoinState {
+        output: None,
+        finished: false,
+        waker: None,
+    }));
+    let handle = JoinHandle {
+        state: state.clone(),
+    };
+    let task = Arc::new(Task::new(Box::pin(async move {
+        let output = future.await;
+        let waker = {
+            let mut state = state.lock();
+            state.output = Some(output);
+            state.finished = true;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    })));
+    task.schedule();
+    handle
+}
+
+/// Yields execution back to the executor, letting other tasks run.
+pub async fn yield_now() {
+    let mut yielded = false;
+    poll_fn(|cx| {
+        if yielded {
+            Poll::Ready(())
+        } else {
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    })
+    .await
+}
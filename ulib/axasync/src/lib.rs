@@ -0,0 +1,59 @@
+//! [ArceOS] async runtime.
+//!
+//! It runs many lightweight async tasks on a few ArceOS tasks (threads), so
+//! that thousands of connections can be served without a kernel thread for
+//! each of them:
+//!
+//! - [`block_on`] runs a future to completion on the current thread, and runs
+//!   the [`spawn`]ed tasks meanwhile.
+//! - [`spawn_workers`] starts more threads running the spawned tasks.
+//! - The reactor wakes up tasks when their timers ([`time::sleep`]) expire, or
+//!   when their sockets ([`net`]) become ready. One idle thread turns it when
+//!   the next timer expires, and polls the network stack every millisecond
+//!   while some tasks wait for sockets, as there are no interrupts for them.
+//!   The other idle threads sleep until tasks are woken up.
+//!
+//! # Cargo Features
+//!
+//! - `irq`: Let the idle thread turning the reactor sleep until it is due,
+//!   instead of yielding the CPU repeatedly.
+//! - `net`: Enable the async [`TcpListener`], [`TcpStream`] and [`UdpSocket`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use axasync::net::TcpListener;
+//!
+//! axasync::block_on(async {
+//!     let listener = TcpListener::bind("0.0.0.0:5555").unwrap();
+//!     loop {
+//!         let (stream, _) = listener.accept().await.unwrap();
+//!         axasync::spawn(async move {
+//!             let mut buf = [0; 1024];
+//!             while let Ok(n @ 1..) = stream.read(&mut buf).await {
+//!                 stream.write_all(&buf[..n]).await.ok();
+//!             }
+//!         });
+//!     }
+//! });
+//! ```
+//!
+//! [ArceOS]: https://github.com/rcore-os/arceos
+//! [`TcpListener`]: net::TcpListener
+//! [`TcpStream`]: net::TcpStream
+//! [`UdpSocket`]: net::UdpSocket
+
+#![cfg_attr(all(not(test), not(doc)), no_std)]
+#![feature(doc_auto_cfg)]
+
+extern crate alloc;
+
+mod executor;
+mod reactor;
+
+pub mod time;
+
+#[cfg(feature = "net")]
+pub mod net;
+
+pub use self::executor::{block_on, spawn, spawn_workers, yield_now, JoinHandle};
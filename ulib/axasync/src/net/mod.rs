@@ -0,0 +1,117 @@
+//! Async TCP/UDP sockets.
+//!
+//! The sockets are in nonblocking mode. When an operation would block, the task
+//! waits in the reactor until the socket becomes ready, and then retries it.
+
+mod tcp;
+mod udp;
+
+pub use self::tcp::{TcpListener, TcpStream};
+pub use self::udp::UdpSocket;
+
+#[doc(no_inline)]
+pub use axstd::net::{SocketAddr, ToSocketAddrs};
+
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use axerrno::AxError;
+use axstd::io;
+
+use crate::reactor::{Interest, Source, Token, REACTOR};
+
+/// A future waiting for a socket to become ready.
+struct Readiness<'a> {
+    source: &'a Source,
+    interest: Interest,
+    token: Option<Token>,
+}
+
+impl Future for Readiness<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        match self.token {
+            Some(token) => {
+                if REACTOR.update_io(token, cx.waker()) {
+                    Poll::Pending
+                } else {
+                    self.token = None;
+                    Poll::Ready(())
+                }
+            }
+            None => {
+                self.token = Some(REACTOR.add_io(self.source, self.interest, cx.waker()));
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for Readiness<'_> {
+    fn drop(&mut self) {
+        if let Some(token) = self.token {
+            REACTOR.remove_io(token);
+        }
+    }
+}
+
+/// Calls `f` until it does not return [`AxError::WouldBlock`], waiting for
+/// `source` to become ready in between.
+async fn poll_io<T>(
+    source: &Source,
+    interest: Interest,
+    mut f: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    loop {
+        match f() {
+            Err(AxError::WouldBlock) => {
+                Readiness {
+                    source,
+                    interest,
+                    token: None,
+                }
+                .await
+            }
+            res => return res,
+        }
+    }
+}
+
+fn resolve_err() -> io::Error {
+    axerrno::ax_err_type!(InvalidInput, "could not resolve to any addresses")
+}
+
+/// Calls `f` with each address `addr` yields, until it succeeds.
+fn each_addr<A, F, T>(addr: A, mut f: F) -> io::Result<T>
+where
+    A: ToSocketAddrs,
+    F: FnMut(SocketAddr) -> io::Result<T>,
+{
+    let mut last_err = None;
+    for addr in addr.to_socket_addrs()? {
+        match f(addr) {
+            Ok(l) => return Ok(l),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(resolve_err))
+}
+
+/// Like [`each_addr`], but `f` is async.
+async fn each_addr_async<A, F, Fut, T>(addr: A, mut f: F) -> io::Result<T>
+where
+    A: ToSocketAddrs,
+    F: FnMut(SocketAddr) -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    let mut last_err = None;
+    for addr in addr.to_socket_addrs()? {
+        match f(addr).await {
+            Ok(l) => return Ok(l),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(resolve_err))
+}
@@ -0,0 +1,133 @@
+use alloc::sync::Arc;
+
+use arceos_api::net::{self as api, AxTcpSocketHandle};
+use axerrno::{ax_err, AxError};
+use axstd::io;
+
+use super::{SocketAddr, ToSocketAddrs};
+use crate::reactor::{Interest, Source};
+
+/// An async TCP stream between a local and a remote socket.
+pub struct TcpStream(Arc<AxTcpSocketHandle>);
+
+/// An async TCP socket server, listening for connections.
+pub struct TcpListener(Arc<AxTcpSocketHandle>);
+
+fn nonblocking(socket: AxTcpSocketHandle) -> io::Result<Arc<AxTcpSocketHandle>> {
+    api::ax_tcp_set_nonblocking(&socket, true)?;
+    Ok(Arc::new(socket))
+}
+
+impl TcpStream {
+    /// Opens a TCP connection to a remote host.
+    ///
+    /// If `addr` yields multiple addresses, `connect` will be attempted with
+    /// each of the addresses until a connection is successful. If none of
+    /// the addresses result in a successful connection, the error returned from
+    /// the last connection attempt (the last address) is returned.
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<TcpStream> {
+        super::each_addr_async(addr, |addr| async move {
+            let stream = TcpStream(nonblocking(api::ax_tcp_socket())?);
+            match api::ax_tcp_connect(&stream.0, addr) {
+                Ok(()) => return Ok(stream),
+                Err(AxError::WouldBlock) => {}
+                Err(e) => return Err(e),
+            }
+            super::poll_io(&stream.source(), Interest::Writable, || {
+                if !api::ax_tcp_poll(&stream.0)?.writable {
+                    Err(AxError::WouldBlock)
+                } else if api::ax_tcp_peer_addr(&stream.0).is_ok() {
+                    Ok(())
+                } else {
+                    ax_err!(ConnectionRefused, "socket connect() failed")
+                }
+            })
+            .await?;
+            Ok(stream)
+        })
+        .await
+    }
+
+    fn source(&self) -> Source {
+        Source::Tcp(self.0.clone())
+    }
+
+    /// Returns the socket address of the local half of this TCP connection.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        api::ax_tcp_socket_addr(&self.0)
+    }
+
+    /// Returns the socket address of the remote peer of this TCP connection.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        api::ax_tcp_peer_addr(&self.0)
+    }
+
+    /// Shuts down the connection.
+    pub fn shutdown(&self) -> io::Result<()> {
+        api::ax_tcp_shutdown(&self.0)
+    }
+
+    /// Receives data from the stream, returns the number of bytes read.
+    ///
+    /// It returns 0 if the connection is closed by the peer.
+    pub async fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        super::poll_io(&self.source(), Interest::Readable, || {
+            api::ax_tcp_recv(&self.0, buf)
+        })
+        .await
+    }
+
+    /// Sends data to the stream, returns the number of bytes written.
+    pub async fn write(&self, buf: &[u8]) -> io::Result<usize> {
+        super::poll_io(&self.source(), Interest::Writable, || {
+            api::ax_tcp_send(&self.0, buf)
+        })
+        .await
+    }
+
+    /// Sends the whole buffer to the stream.
+    pub async fn write_all(&self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.write(buf).await {
+                Ok(0) => return ax_err!(WriteZero, "failed to write whole buffer"),
+                Ok(n) => buf = &buf[n..],
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TcpListener {
+    /// Creates a new `TcpListener` which will be bound to the specified
+    /// address.
+    ///
+    /// If `addr` yields multiple addresses, `bind` will be attempted with
+    /// each of the addresses until one succeeds and returns the listener.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<TcpListener> {
+        super::each_addr(addr, |addr| {
+            let backlog = 128;
+            let socket = nonblocking(api::ax_tcp_socket())?;
+            api::ax_tcp_bind(&socket, addr)?;
+            api::ax_tcp_listen(&socket, backlog)?;
+            Ok(TcpListener(socket))
+        })
+    }
+
+    /// Returns the local socket address of this listener.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        api::ax_tcp_socket_addr(&self.0)
+    }
+
+    /// Accepts a new incoming connection from this listener.
+    ///
+    /// The current task waits until a new TCP connection is established, then
+    /// the corresponding [`TcpStream`] and the remote peer's address will be
+    /// returned.
+    pub async fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
+        let source = Source::Tcp(self.0.clone());
+        let (socket, addr) =
+            super::poll_io(&source, Interest::Readable, || api::ax_tcp_accept(&self.0)).await?;
+        Ok((TcpStream(nonblocking(socket)?), addr))
+    }
+}
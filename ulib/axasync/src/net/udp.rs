@@ -0,0 +1,98 @@
+use alloc::sync::Arc;
+
+use arceos_api::net::{self as api, AxUdpSocketHandle};
+use axstd::io;
+
+use super::{SocketAddr, ToSocketAddrs};
+use crate::reactor::{Interest, Source};
+
+/// An async UDP socket.
+pub struct UdpSocket(Arc<AxUdpSocketHandle>);
+
+impl UdpSocket {
+    /// Creates a UDP socket from the given address.
+    ///
+    /// If `addr` yields multiple addresses, `bind` will be attempted with
+    /// each of the addresses until one succeeds and returns the socket.
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<UdpSocket> {
+        super::each_addr(addr, |addr| {
+            let socket = api::ax_udp_socket();
+            api::ax_udp_set_nonblocking(&socket, true)?;
+            api::ax_udp_bind(&socket, addr)?;
+            Ok(UdpSocket(Arc::new(socket)))
+        })
+    }
+
+    fn source(&self) -> Source {
+        Source::Udp(self.0.clone())
+    }
+
+    /// Returns the socket address that this socket was created from.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        api::ax_udp_socket_addr(&self.0)
+    }
+
+    /// Returns the socket address of the remote peer this socket was connected to.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        api::ax_udp_peer_addr(&self.0)
+    }
+
+    /// Receives a single datagram message on the socket. On success, returns
+    /// the number of bytes read and the origin.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        super::poll_io(&self.source(), Interest::Readable, || {
+            api::ax_udp_recv_from(&self.0, buf)
+        })
+        .await
+    }
+
+    /// Receives a single datagram message on the socket, without removing it
+    /// from the queue. On success, returns the number of bytes read and the
+    /// origin.
+    pub async fn peek_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        super::poll_io(&self.source(), Interest::Readable, || {
+            api::ax_udp_peek_from(&self.0, buf)
+        })
+        .await
+    }
+
+    /// Sends data on the socket to the given address. On success, returns the
+    /// number of bytes written.
+    ///
+    /// It is possible for `addr` to yield multiple addresses, but `send_to`
+    /// will only send data to the first address yielded by `addr`.
+    pub async fn send_to<A: ToSocketAddrs>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
+        let addr = match addr.to_socket_addrs()?.next() {
+            Some(addr) => addr,
+            None => return axerrno::ax_err!(InvalidInput, "no addresses to send data to"),
+        };
+        super::poll_io(&self.source(), Interest::Writable, || {
+            api::ax_udp_send_to(&self.0, buf, addr)
+        })
+        .await
+    }
+
+    /// Connects this UDP socket to a remote address, allowing the `send` and
+    /// `recv` to be used to send data and also applies filters to only receive
+    /// data from the specified address.
+    pub fn connect(&self, addr: SocketAddr) -> io::Result<()> {
+        api::ax_udp_connect(&self.0, addr)
+    }
+
+    /// Sends data on the socket to the remote address to which it is connected.
+    pub async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        super::poll_io(&self.source(), Interest::Writable, || {
+            api::ax_udp_send(&self.0, buf)
+        })
+        .await
+    }
+
+    /// Receives a single datagram message on the socket from the remote address
+    /// to which it is connected. On success, returns the number of bytes read.
+    pub async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        super::poll_io(&self.source(), Interest::Readable, || {
+            api::ax_udp_recv(&self.0, buf)
+        })
+        .await
+    }
+}
@@ -0,0 +1,221 @@
+//! The reactor, which wakes up tasks when their timers expire or their sockets
+//! become ready.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::task::Waker;
+use core::time::Duration;
+
+use arceos_api::time::ax_current_time;
+use spinlock::SpinNoIrq;
+
+#[cfg(feature = "net")]
+pub(crate) use self::io::{Interest, Source};
+
+/// Identifies a timer or a socket waiter registered in the reactor.
+pub(crate) type Token = u64;
+
+/// How often the network stack is polled while tasks wait for sockets, as it
+/// does not interrupt when packets arrive.
+#[cfg(feature = "net")]
+const IO_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+pub(crate) struct Reactor {
+    next_token: AtomicU64,
+    /// Wakers of the timers, ordered by their deadlines.
+    timers: SpinNoIrq<BTreeMap<(Duration, Token), Waker>>,
+    /// Wakers of the tasks waiting for sockets. It is locked by a mutex as
+    /// polling sockets may block.
+    #[cfg(feature = "net")]
+    io: axstd::sync::Mutex<BTreeMap<Token, io::Waiter>>,
+}
+
+pub(crate) static REACTOR: Reactor = Reactor::new();
+
+impl Reactor {
+    const fn new() -> Self {
+        Self {
+            next_token: AtomicU64::new(1),
+            timers: SpinNoIrq::new(BTreeMap::new()),
+            #[cfg(feature = "net")]
+            io: axstd::sync::Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn alloc_token(&self) -> Token {
+        self.next_token.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Registers a timer expiring at `deadline`, which wakes up `waker`.
+    pub fn add_timer(&self, deadline: Duration, waker: &Waker) -> Token {
+        let token = self.alloc_token();
+        self.timers.lock().insert((deadline, token), waker.clone());
+        token
+    }
+
+    /// Updates the waker of a registered timer.
+    ///
+    /// Returns `false` if the timer has already expired.
+    pub fn update_timer(&self, deadline: Duration, token: Token, waker: &Waker) -> bool {
+        match self.timers.lock().get_mut(&(deadline, token)) {
+            Some(old) => {
+                if !old.will_wake(waker) {
+                    *old = waker.clone();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cancels a registered timer.
+    pub fn remove_timer(&self, deadline: Duration, token: Token) {
+        self.timers.lock().remove(&(deadline, token));
+    }
+
+    /// Wakes up the expired timers and the ready sockets.
+    ///
+    /// Returns how long the caller may sleep before calling it again, or
+    /// `None` if there is nothing to wait for. While some tasks wait for
+    /// sockets, it is at most [`IO_POLL_INTERVAL`].
+    pub fn turn(&self) -> Option<Duration> {
+        let now = ax_current_time();
+        let timeout = self
+            .fire_timers(now)
+            .map(|deadline| deadline.saturating_sub(now));
+        #[cfg(feature = "net")]
+        if self.poll_io() {
+            return Some(timeout.map_or(IO_POLL_INTERVAL, |t| t.min(IO_POLL_INTERVAL)));
+        }
+        timeout
+    }
+
+    /// Wakes up the timers expired at `now`, returns the deadline of the next
+    /// one.
+    fn fire_timers(&self, now: Duration) -> Option<Duration> {
+        let mut expired = Vec::new();
+        let next_deadline = {
+            let mut timers = self.timers.lock();
+            while let Some(entry) = timers.first_entry() {
+                if entry.key().0 > now {
+                    break;
+                }
+                expired.push(entry.remove());
+            }
+            timers.keys().next().map(|&(deadline, _)| deadline)
+        };
+        // wake up tasks without holding the lock, they may add timers again
+        for waker in expired {
+            waker.wake();
+        }
+        next_deadline
+    }
+}
+
+#[cfg(feature = "net")]
+mod io {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::task::Waker;
+
+    use arceos_api::net::{self as api, AxTcpSocketHandle, AxUdpSocketHandle};
+
+    use super::{Reactor, Token};
+
+    /// A socket the tasks can wait for.
+    #[derive(Clone)]
+    pub(crate) enum Source {
+        Tcp(Arc<AxTcpSocketHandle>),
+        Udp(Arc<AxUdpSocketHandle>),
+    }
+
+    /// The readiness a task waits for.
+    #[derive(Clone, Copy)]
+    pub(crate) enum Interest {
+        Readable,
+        Writable,
+    }
+
+    pub(super) struct Waiter {
+        source: Source,
+        interest: Interest,
+        waker: Waker,
+    }
+
+    impl Waiter {
+        fn is_ready(&self) -> bool {
+            let state = match &self.source {
+                Source::Tcp(socket) => api::ax_tcp_poll(socket),
+                Source::Udp(socket) => api::ax_udp_poll(socket),
+            };
+            match (state, self.interest) {
+                (Ok(state), Interest::Readable) => state.readable,
+                (Ok(state), Interest::Writable) => state.writable,
+                // let the task retry and get the error
+                (Err(_), _) => true,
+            }
+        }
+    }
+
+    impl Reactor {
+        /// Registers a task waiting for `source` to become ready.
+        pub fn add_io(&self, source: &Source, interest: Interest, waker: &Waker) -> Token {
+            let token = self.alloc_token();
+            let waiter = Waiter {
+                source: source.clone(),
+                interest,
+                waker: waker.clone(),
+            };
+            self.io.lock().insert(token, waiter);
+            token
+        }
+
+        /// Updates the waker of a registered socket waiter.
+        ///
+        /// Returns `false` if the socket has already become ready.
+        pub fn update_io(&self, token: Token, waker: &Waker) -> bool {
+            match self.io.lock().get_mut(&token) {
+                Some(waiter) => {
+                    if !waiter.waker.will_wake(waker) {
+                        waiter.waker = waker.clone();
+                    }
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Cancels a registered socket waiter.
+        pub fn remove_io(&self, token: Token) {
+            self.io.lock().remove(&token);
+        }
+
+        /// Polls the network stack and wakes up the tasks whose sockets are
+        /// ready.
+        ///
+        /// Returns whether some tasks are still waiting.
+        pub(super) fn poll_io(&self) -> bool {
+            let mut io = self.io.lock();
+            if io.is_empty() {
+                return false;
+            }
+            api::ax_poll_interfaces().ok();
+            let mut ready = Vec::new();
+            io.retain(|_, waiter| {
+                if waiter.is_ready() {
+                    ready.push(waiter.waker.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+            let pending = !io.is_empty();
+            drop(io);
+            for waker in ready {
+                waker.wake();
+            }
+            pending
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Utilities for tracking time in async tasks.
+
+use core::fmt;
+use core::future::{poll_fn, Future};
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll};
+
+use arceos_api::time::ax_current_time;
+use axstd::time::Instant;
+
+use crate::reactor::{Token, REACTOR};
+
+pub use core::time::Duration;
+
+/// A future returned by [`sleep`] and [`sleep_until`].
+pub struct Sleep {
+    deadline: Duration,
+    token: Option<Token>,
+}
+
+/// Waits until `duration` has elapsed.
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        deadline: ax_current_time() + duration,
+        token: None,
+    }
+}
+
+/// Waits until `deadline` is reached.
+pub fn sleep_until(deadline: Instant) -> Sleep {
+    sleep(deadline.duration_since(Instant::now()))
+}
+
+impl Sleep {
+    /// Returns whether the deadline has been reached.
+    pub fn is_elapsed(&self) -> bool {
+        ax_current_time() >= self.deadline
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_elapsed() {
+            if let Some(token) = self.token.take() {
+                REACTOR.remove_timer(self.deadline, token);
+            }
+            return Poll::Ready(());
+        }
+        match self.token {
+            Some(token) if REACTOR.update_timer(self.deadline, token, cx.waker()) => {}
+            _ => self.token = Some(REACTOR.add_timer(self.deadline, cx.waker())),
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        if let Some(token) = self.token {
+            REACTOR.remove_timer(self.deadline, token);
+        }
+    }
+}
+
+/// The error returned by [`timeout`] when the deadline is reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Elapsed(());
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "deadline has elapsed".fmt(f)
+    }
+}
+
+/// Runs `future` until it completes, or gives up after `duration` with an
+/// [`Elapsed`] error.
+pub async fn timeout<F: Future>(duration: Duration, future: F) -> Result<F::Output, Elapsed> {
+    let mut future = pin!(future);
+    let mut sleep = sleep(duration);
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            Poll::Ready(Ok(output))
+        } else if Pin::new(&mut sleep).poll(cx).is_ready() {
+            Poll::Ready(Err(Elapsed(())))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
@@ -6,6 +6,7 @@
 #include <time.h>
 #include <unistd.h>
 
+#ifndef AX_CONFIG_MULTITASK
 // TODO:
 uid_t geteuid(void)
 {
@@ -19,6 +20,7 @@ uid_t getuid(void)
     unimplemented();
     return 0;
 }
+#endif // AX_CONFIG_MULTITASK
 
 // TODO
 pid_t setsid(void)
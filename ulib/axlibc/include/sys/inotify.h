@@ -0,0 +1,38 @@
+#ifndef __SYS_INOTIFY_H__
+#define __SYS_INOTIFY_H__
+
+#include <fcntl.h>
+#include <stdint.h>
+
+struct inotify_event {
+    int wd;          /* watch descriptor */
+    uint32_t mask;   /* mask describing event */
+    uint32_t cookie; /* unique cookie associating related events */
+    uint32_t len;    /* size of name field */
+    char name[];     /* optional null-terminated name */
+};
+
+#define IN_CLOEXEC  O_CLOEXEC
+#define IN_NONBLOCK O_NONBLOCK
+
+#define IN_MODIFY      0x00000002
+#define IN_ATTRIB      0x00000004
+#define IN_MOVED_FROM  0x00000040
+#define IN_MOVED_TO    0x00000080
+#define IN_MOVE        (IN_MOVED_FROM | IN_MOVED_TO)
+#define IN_CREATE      0x00000100
+#define IN_DELETE      0x00000200
+#define IN_DELETE_SELF 0x00000400
+#define IN_MOVE_SELF   0x00000800
+#define IN_ALL_EVENTS  0x00000fc6
+
+#define IN_Q_OVERFLOW 0x00004000
+#define IN_IGNORED    0x00008000
+#define IN_ISDIR      0x40000000
+
+int inotify_init(void);
+int inotify_init1(int flags);
+int inotify_add_watch(int fd, const char *pathname, uint32_t mask);
+int inotify_rm_watch(int fd, int wd);
+
+#endif // __SYS_INOTIFY_H__
@@ -0,0 +1,6 @@
+#ifndef __SYS_VFS_H__
+#define __SYS_VFS_H__
+
+#include <sys/statfs.h>
+
+#endif // __SYS_VFS_H__
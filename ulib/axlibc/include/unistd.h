@@ -87,6 +87,7 @@ uid_t geteuid(void);
 gid_t getgid(void);
 gid_t getegid(void);
 int getgroups(int, gid_t[]);
+int setgroups(size_t, const gid_t *);
 int setuid(uid_t);
 int seteuid(uid_t);
 int setgid(gid_t);
@@ -0,0 +1,57 @@
+use core::ffi::c_int;
+
+use arceos_posix_api::{
+    sys_getegid, sys_geteuid, sys_getgid, sys_getgroups, sys_getuid, sys_setgid, sys_setgroups,
+    sys_setuid,
+};
+
+use crate::ctypes::{gid_t, uid_t};
+use crate::utils::e;
+
+/// Get the real user ID of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn getuid() -> uid_t {
+    sys_getuid()
+}
+
+/// Get the effective user ID of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn geteuid() -> uid_t {
+    sys_geteuid()
+}
+
+/// Get the real group ID of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn getgid() -> gid_t {
+    sys_getgid()
+}
+
+/// Get the effective group ID of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn getegid() -> gid_t {
+    sys_getegid()
+}
+
+/// Set the user IDs of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn setuid(uid: uid_t) -> c_int {
+    e(sys_setuid(uid))
+}
+
+/// Set the group IDs of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn setgid(gid: gid_t) -> c_int {
+    e(sys_setgid(gid))
+}
+
+/// Get the supplementary group IDs of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn getgroups(size: c_int, list: *mut gid_t) -> c_int {
+    e(sys_getgroups(size, list))
+}
+
+/// Set the supplementary group IDs of the current thread.
+#[no_mangle]
+pub unsafe extern "C" fn setgroups(size: usize, list: *const gid_t) -> c_int {
+    e(sys_setgroups(size, list))
+}
@@ -1,8 +1,10 @@
 use core::ffi::{c_char, c_int};
 
 use arceos_posix_api::{
-    sys_fstat, sys_getcwd, sys_lseek, sys_lstat, sys_open, sys_rename, sys_stat,
+    sys_fstat, sys_fstatfs, sys_getcwd, sys_lseek, sys_lstat, sys_mkdir, sys_open, sys_rename,
+    sys_stat, sys_statfs, sys_umask,
 };
+use arceos_posix_api::{sys_inotify_add_watch, sys_inotify_init1, sys_inotify_rm_watch};
 
 use crate::{ctypes, utils::e};
 
@@ -43,6 +45,23 @@ pub unsafe extern "C" fn fstat(fd: c_int, buf: *mut ctypes::stat) -> c_int {
     e(sys_fstat(fd, buf))
 }
 
+/// Get the attributes of the filesystem containing `path` and write into `buf`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn statfs(path: *const c_char, buf: *mut ctypes::statfs) -> c_int {
+    e(sys_statfs(path, buf))
+}
+
+/// Get the attributes of the filesystem containing the file `fd` and write
+/// into `buf`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn fstatfs(fd: c_int, buf: *mut ctypes::statfs) -> c_int {
+    e(sys_fstatfs(fd, buf))
+}
+
 /// Get the metadata of the symbolic link and write into `buf`.
 ///
 /// Return 0 if success.
@@ -65,3 +84,51 @@ pub unsafe extern "C" fn getcwd(buf: *mut c_char, size: usize) -> *mut c_char {
 pub unsafe extern "C" fn rename(old: *const c_char, new: *const c_char) -> c_int {
     e(sys_rename(old, new))
 }
+
+/// Create a directory `path` with the permission bits `mode`, before the umask
+/// is applied.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[no_mangle]
+pub unsafe extern "C" fn mkdir(path: *const c_char, mode: ctypes::mode_t) -> c_int {
+    e(sys_mkdir(path, mode))
+}
+
+/// Set the file mode creation mask of the current task, and return the
+/// previous mask.
+#[no_mangle]
+pub unsafe extern "C" fn umask(mask: ctypes::mode_t) -> ctypes::mode_t {
+    sys_umask(mask)
+}
+
+/// Create a new inotify instance.
+///
+/// Return a file descriptor referring to the new inotify instance.
+#[no_mangle]
+pub unsafe extern "C" fn inotify_init() -> c_int {
+    e(sys_inotify_init1(0))
+}
+
+/// Create a new inotify instance with `IN_NONBLOCK` or `IN_CLOEXEC` flags.
+///
+/// Return a file descriptor referring to the new inotify instance.
+#[no_mangle]
+pub unsafe extern "C" fn inotify_init1(flags: c_int) -> c_int {
+    e(sys_inotify_init1(flags))
+}
+
+/// Add a watch on `pathname` to the inotify instance `fd`.
+///
+/// Return the watch descriptor.
+#[no_mangle]
+pub unsafe extern "C" fn inotify_add_watch(fd: c_int, pathname: *const c_char, mask: u32) -> c_int {
+    e(sys_inotify_add_watch(fd, pathname, mask))
+}
+
+/// Remove the watch `wd` from the inotify instance `fd`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn inotify_rm_watch(fd: c_int, wd: c_int) -> c_int {
+    e(sys_inotify_rm_watch(fd, wd))
+}
@@ -10,6 +10,7 @@
 //! - Memory
 //!     - `alloc`: Enable dynamic memory allocation.
 //!     - `tls`: Enable thread-local storage.
+//!     - `mmap`: Enable memory mapping ([mmap]) support.
 //! - Task management
 //!     - `multitask`: Enable multi-threading support.
 //! - Upperlayer stacks
@@ -24,6 +25,7 @@
 //! [ArceOS]: https://github.com/rcore-os/arceos
 //! [select]: https://man7.org/linux/man-pages/man2/select.2.html
 //! [epoll]: https://man7.org/linux/man-pages/man7/epoll.7.html
+//! [mmap]: https://man7.org/linux/man-pages/man2/mmap.2.html
 
 #![cfg_attr(all(not(test), not(doc)), no_std)]
 #![feature(doc_cfg)]
@@ -49,6 +51,8 @@ mod ctypes {
 #[macro_use]
 mod utils;
 
+#[cfg(feature = "multitask")]
+mod cred;
 #[cfg(feature = "fd")]
 mod fd_ops;
 #[cfg(feature = "fs")]
@@ -57,12 +61,16 @@ mod fs;
 mod io_mpx;
 #[cfg(feature = "alloc")]
 mod malloc;
+#[cfg(feature = "mmap")]
+mod mmap;
 #[cfg(feature = "net")]
 mod net;
 #[cfg(feature = "pipe")]
 mod pipe;
 #[cfg(feature = "multitask")]
 mod pthread;
+#[cfg(feature = "multitask")]
+mod semaphore;
 #[cfg(feature = "alloc")]
 mod strftime;
 #[cfg(feature = "fp_simd")]
@@ -96,11 +104,18 @@ pub use self::malloc::{free, malloc};
 #[cfg(feature = "alloc")]
 pub use self::strftime::strftime;
 
+#[cfg(feature = "mmap")]
+pub use self::mmap::{mmap, mprotect, msync, munmap};
+
 #[cfg(feature = "fd")]
 pub use self::fd_ops::{ax_fcntl, close, dup, dup2, dup3};
 
 #[cfg(feature = "fs")]
-pub use self::fs::{ax_open, fstat, getcwd, lseek, lstat, rename, stat};
+pub use self::fs::{
+    ax_open, fstat, fstatfs, getcwd, lseek, lstat, mkdir, rename, stat, statfs, umask,
+};
+#[cfg(feature = "fs")]
+pub use self::fs::{inotify_add_watch, inotify_init, inotify_init1, inotify_rm_watch};
 
 #[cfg(feature = "net")]
 pub use self::net::{
@@ -108,10 +123,38 @@ pub use self::net::{
     recvfrom, send, sendto, shutdown, socket,
 };
 
+#[cfg(feature = "multitask")]
+pub use self::cred::{getegid, geteuid, getgid, getgroups, getuid, setgid, setgroups, setuid};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_cond_broadcast, pthread_cond_destroy, pthread_cond_init, pthread_cond_signal,
+    pthread_cond_timedwait, pthread_cond_wait,
+};
 #[cfg(feature = "multitask")]
 pub use self::pthread::{pthread_create, pthread_exit, pthread_join, pthread_self};
 #[cfg(feature = "multitask")]
-pub use self::pthread::{pthread_mutex_init, pthread_mutex_lock, pthread_mutex_unlock};
+pub use self::pthread::{
+    pthread_getaffinity_np, pthread_setaffinity_np, sched_getaffinity, sched_setaffinity,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_mutex_init, pthread_mutex_lock, pthread_mutex_trylock, pthread_mutex_unlock,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_rwlock_destroy, pthread_rwlock_init, pthread_rwlock_rdlock, pthread_rwlock_timedrdlock,
+    pthread_rwlock_timedwrlock, pthread_rwlock_tryrdlock, pthread_rwlock_trywrlock,
+    pthread_rwlock_unlock, pthread_rwlock_wrlock,
+};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    sched_get_priority_max, sched_get_priority_min, sched_getparam, sched_getscheduler,
+    sched_setparam, sched_setscheduler,
+};
+#[cfg(feature = "multitask")]
+pub use self::semaphore::{
+    sem_destroy, sem_getvalue, sem_init, sem_post, sem_timedwait, sem_trywait, sem_wait,
+};
 
 #[cfg(feature = "pipe")]
 pub use self::pipe::pipe;
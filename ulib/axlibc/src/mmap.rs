@@ -0,0 +1,51 @@
+use core::ffi::{c_int, c_void};
+
+use arceos_posix_api::{sys_mmap, sys_mprotect, sys_msync, sys_munmap};
+
+use crate::{ctypes, utils::e};
+
+/// Map files or devices into memory.
+///
+/// Return the start address of the mapping, or `MAP_FAILED` on error.
+#[no_mangle]
+pub unsafe extern "C" fn mmap(
+    addr: *mut c_void,
+    len: ctypes::size_t,
+    prot: c_int,
+    flags: c_int,
+    fd: c_int,
+    off: ctypes::off_t,
+) -> *mut c_void {
+    let ret = sys_mmap(addr, len, prot, flags, fd, off);
+    let code = ret as isize;
+    if (-4095..0).contains(&code) {
+        crate::errno::set_errno(-code as i32);
+        usize::MAX as *mut c_void // MAP_FAILED
+    } else {
+        ret
+    }
+}
+
+/// Unmap the memory in `[addr, addr + len)`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn munmap(addr: *mut c_void, len: ctypes::size_t) -> c_int {
+    e(sys_munmap(addr, len))
+}
+
+/// Synchronize a file with its memory mapping in `[addr, addr + len)`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn msync(addr: *mut c_void, len: ctypes::size_t, flags: c_int) -> c_int {
+    e(sys_msync(addr, len, flags))
+}
+
+/// Set the protection of the memory in `[addr, addr + len)`.
+///
+/// Return 0 if success.
+#[no_mangle]
+pub unsafe extern "C" fn mprotect(addr: *mut c_void, len: ctypes::size_t, prot: c_int) -> c_int {
+    e(sys_mprotect(addr, len, prot))
+}
@@ -37,6 +37,87 @@ pub unsafe extern "C" fn pthread_join(
     e(api::sys_pthread_join(thread, retval))
 }
 
+/// Set the CPU affinity mask of the given thread.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_setaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> c_int {
+    e(api::sys_pthread_setaffinity_np(thread, cpusetsize, cpuset))
+}
+
+/// Get the CPU affinity mask of the given thread.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_getaffinity_np(
+    thread: ctypes::pthread_t,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> c_int {
+    e(api::sys_pthread_getaffinity_np(thread, cpusetsize, cpuset))
+}
+
+/// Set the CPU affinity mask of the thread `pid` (0 for the calling thread).
+#[no_mangle]
+pub unsafe extern "C" fn sched_setaffinity(
+    pid: c_int,
+    cpusetsize: usize,
+    cpuset: *const ctypes::cpu_set_t,
+) -> c_int {
+    e(api::sys_sched_setaffinity(pid, cpusetsize, cpuset))
+}
+
+/// Get the CPU affinity mask of the thread `pid` (0 for the calling thread).
+#[no_mangle]
+pub unsafe extern "C" fn sched_getaffinity(
+    pid: c_int,
+    cpusetsize: usize,
+    cpuset: *mut ctypes::cpu_set_t,
+) -> c_int {
+    e(api::sys_sched_getaffinity(pid, cpusetsize, cpuset))
+}
+
+/// Set the scheduling policy and priority of the thread `pid` (0 for the
+/// calling thread).
+#[no_mangle]
+pub unsafe extern "C" fn sched_setscheduler(
+    pid: c_int,
+    policy: c_int,
+    param: *const ctypes::sched_param,
+) -> c_int {
+    e(api::sys_sched_setscheduler(pid, policy, param))
+}
+
+/// Get the scheduling policy of the thread `pid` (0 for the calling thread).
+#[no_mangle]
+pub unsafe extern "C" fn sched_getscheduler(pid: c_int) -> c_int {
+    e(api::sys_sched_getscheduler(pid))
+}
+
+/// Set the scheduling priority of the thread `pid` (0 for the calling thread).
+#[no_mangle]
+pub unsafe extern "C" fn sched_setparam(pid: c_int, param: *const ctypes::sched_param) -> c_int {
+    e(api::sys_sched_setparam(pid, param))
+}
+
+/// Get the scheduling priority of the thread `pid` (0 for the calling thread).
+#[no_mangle]
+pub unsafe extern "C" fn sched_getparam(pid: c_int, param: *mut ctypes::sched_param) -> c_int {
+    e(api::sys_sched_getparam(pid, param))
+}
+
+/// Get the highest priority of the scheduling policy.
+#[no_mangle]
+pub unsafe extern "C" fn sched_get_priority_max(policy: c_int) -> c_int {
+    e(api::sys_sched_get_priority_max(policy))
+}
+
+/// Get the lowest priority of the scheduling policy.
+#[no_mangle]
+pub unsafe extern "C" fn sched_get_priority_min(policy: c_int) -> c_int {
+    e(api::sys_sched_get_priority_min(policy))
+}
+
 /// Initialize a mutex.
 #[no_mangle]
 pub unsafe extern "C" fn pthread_mutex_init(
@@ -57,3 +138,121 @@ pub unsafe extern "C" fn pthread_mutex_lock(mutex: *mut ctypes::pthread_mutex_t)
 pub unsafe extern "C" fn pthread_mutex_unlock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
     e(api::sys_pthread_mutex_unlock(mutex))
 }
+
+/// Try to lock the given mutex without blocking.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_mutex_trylock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
+    e(api::sys_pthread_mutex_trylock(mutex))
+}
+
+/// Initialize a condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_init(
+    cond: *mut ctypes::pthread_cond_t,
+    attr: *const ctypes::pthread_condattr_t,
+) -> c_int {
+    e(api::sys_pthread_cond_init(cond, attr))
+}
+
+/// Destroy a condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_destroy(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    e(api::sys_pthread_cond_destroy(cond))
+}
+
+/// Unlock the mutex and wait on the condition variable, the mutex is locked
+/// again before returning.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_wait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+) -> c_int {
+    e(api::sys_pthread_cond_wait(cond, mutex))
+}
+
+/// Like `pthread_cond_wait`, but gives up at the absolute time `abstime`.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_timedwait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    e(api::sys_pthread_cond_timedwait(cond, mutex, abstime))
+}
+
+/// Wake up one thread waiting on the condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_signal(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    e(api::sys_pthread_cond_signal(cond))
+}
+
+/// Wake up all threads waiting on the condition variable.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_cond_broadcast(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    e(api::sys_pthread_cond_broadcast(cond))
+}
+
+/// Initialize a readers-writer lock.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_init(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    attr: *const ctypes::pthread_rwlockattr_t,
+) -> c_int {
+    e(api::sys_pthread_rwlock_init(rwlock, attr))
+}
+
+/// Destroy a readers-writer lock.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_destroy(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e(api::sys_pthread_rwlock_destroy(rwlock))
+}
+
+/// Lock the readers-writer lock for reading.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_rdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e(api::sys_pthread_rwlock_rdlock(rwlock))
+}
+
+/// Try to lock the readers-writer lock for reading without blocking.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_tryrdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e(api::sys_pthread_rwlock_tryrdlock(rwlock))
+}
+
+/// Lock the readers-writer lock for reading, giving up at the absolute time
+/// `abstime`.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_timedrdlock(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    e(api::sys_pthread_rwlock_timedrdlock(rwlock, abstime))
+}
+
+/// Lock the readers-writer lock for writing.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_wrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e(api::sys_pthread_rwlock_wrlock(rwlock))
+}
+
+/// Try to lock the readers-writer lock for writing without blocking.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_trywrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e(api::sys_pthread_rwlock_trywrlock(rwlock))
+}
+
+/// Lock the readers-writer lock for writing, giving up at the absolute time
+/// `abstime`.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_timedwrlock(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    e(api::sys_pthread_rwlock_timedwrlock(rwlock, abstime))
+}
+
+/// Unlock the readers-writer lock.
+#[no_mangle]
+pub unsafe extern "C" fn pthread_rwlock_unlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    e(api::sys_pthread_rwlock_unlock(rwlock))
+}
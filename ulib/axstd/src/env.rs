@@ -1,6 +1,6 @@
 //! Inspection and manipulation of the process’s environment.
 
-#[cfg(feature = "fs")]
+#[cfg(any(feature = "fs", feature = "user"))]
 extern crate alloc;
 
 #[cfg(feature = "fs")]
@@ -18,6 +18,13 @@ pub fn set_current_dir(path: &str) -> io::Result<()> {
     arceos_api::fs::ax_set_current_dir(path)
 }
 
+/// Sets the file mode creation mask of the current thread to `mask & 0o777`,
+/// and returns the previous mask.
+#[cfg(feature = "fs")]
+pub fn umask(mask: u16) -> u16 {
+    arceos_api::fs::ax_umask(mask)
+}
+
 /// Returns the current user id as a [`u32`].
 #[cfg(feature = "fs")]
 pub fn current_uid() -> io::Result<u32> {
@@ -30,10 +37,12 @@ pub fn set_current_uid(uid: u32) -> io::Result<()> {
     arceos_api::fs::ax_setuid(uid)
 }
 
-/// Changes the current user id to 0 if the current user is a sudoer.
+/// Changes the current user id to 0 if `/etc/sudoers` allows the current
+/// user to run `command` (the command name followed by its arguments) as
+/// root.
 #[cfg(feature = "fs")]
-pub fn sudo() -> io::Result<()> {
-    arceos_api::fs::sudo()
+pub fn sudo(command: &str) -> io::Result<()> {
+    arceos_api::fs::sudo(command)
 }
 
 /// Changes the current user password.
@@ -41,3 +50,86 @@ pub fn sudo() -> io::Result<()> {
 pub fn set_password() -> io::Result<()> {
     arceos_api::fs::ax_setpassword()
 }
+
+#[cfg(feature = "user")]
+pub use arceos_api::fs::{
+    AxAuditEvent as AuditEvent, AxLastEntry as LastEntry, AxRecordKind as RecordKind,
+    AxSession as Session, AxSessionEnd as SessionEnd, AxSessionRecord as SessionRecord,
+    AxUserChanges as UserChanges, AxUserInfo as UserInfo,
+};
+
+/// Creates a user with a group of the same name and a home directory, and
+/// returns its user id.
+#[cfg(feature = "user")]
+pub fn add_user(name: &str) -> crate::io::Result<u32> {
+    arceos_api::fs::ax_add_user(name)
+}
+
+/// Deletes a user and its group of the same name. The home directory is kept.
+#[cfg(feature = "user")]
+pub fn delete_user(name: &str) -> crate::io::Result<()> {
+    arceos_api::fs::ax_delete_user(name)
+}
+
+/// Changes the comment, home directory or login shell of a user.
+#[cfg(feature = "user")]
+pub fn modify_user(name: &str, changes: &UserChanges) -> crate::io::Result<()> {
+    arceos_api::fs::ax_modify_user(name, changes)
+}
+
+/// Locks or unlocks the password of a user.
+#[cfg(feature = "user")]
+pub fn lock_user(name: &str, lock: bool) -> crate::io::Result<()> {
+    arceos_api::fs::ax_lock_user(name, lock)
+}
+
+/// Asks for a new password of a user on the console, and sets it.
+#[cfg(feature = "user")]
+pub fn set_user_password(name: &str) -> crate::io::Result<()> {
+    arceos_api::fs::ax_set_user_password(name)
+}
+
+/// Returns all users, ordered by user id.
+#[cfg(feature = "user")]
+pub fn list_users() -> crate::io::Result<alloc::vec::Vec<UserInfo>> {
+    arceos_api::fs::ax_list_users()
+}
+
+/// Returns up to `count` of the most recent security audit events, oldest
+/// first. This requires the `AUDIT_READ` capability.
+#[cfg(feature = "user")]
+pub fn audit_events(count: usize) -> crate::io::Result<alloc::vec::Vec<AuditEvent>> {
+    arceos_api::fs::ax_audit_events(count)
+}
+
+/// Returns the login session of the console, or [`None`] if no user has
+/// logged in since boot.
+#[cfg(feature = "user")]
+pub fn session() -> Option<Session> {
+    arceos_api::fs::ax_current_session()
+}
+
+/// Returns the value of the environment variable `key` of the login session.
+#[cfg(feature = "user")]
+pub fn var(key: &str) -> Option<alloc::string::String> {
+    session().and_then(|s| s.var(key).map(|v| v.into()))
+}
+
+/// Returns all environment variables of the login session.
+#[cfg(feature = "user")]
+pub fn vars() -> alloc::vec::Vec<(alloc::string::String, alloc::string::String)> {
+    session().map(|s| s.env).unwrap_or_default()
+}
+
+/// Returns the active login sessions, like `who(1)`.
+#[cfg(feature = "user")]
+pub fn who() -> crate::io::Result<alloc::vec::Vec<SessionRecord>> {
+    arceos_api::fs::ax_who()
+}
+
+/// Returns up to `count` of the most recent login sessions, newest first,
+/// like `last(1)`.
+#[cfg(feature = "user")]
+pub fn last(count: usize) -> crate::io::Result<alloc::vec::Vec<LastEntry>> {
+    arceos_api::fs::ax_last(count)
+}
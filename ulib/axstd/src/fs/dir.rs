@@ -26,9 +26,10 @@ pub struct DirEntry<'a> {
 }
 
 /// A builder used to create directories in various manners.
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub struct DirBuilder {
     recursive: bool,
+    mode: u32,
 }
 
 impl<'a> ReadDir<'a> {
@@ -120,11 +121,20 @@ impl fmt::Debug for DirEntry<'_> {
     }
 }
 
+impl Default for DirBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DirBuilder {
     /// Creates a new set of options with default mode/security settings for all
     /// platforms and also non-recursive.
     pub fn new() -> Self {
-        Self { recursive: false }
+        Self {
+            recursive: false,
+            mode: 0o777,
+        }
     }
 
     /// Indicates that directories should be created recursively, creating all
@@ -135,13 +145,20 @@ impl DirBuilder {
         self
     }
 
+    /// Sets the mode to create new directories with, before the umask of the
+    /// current thread is applied. Defaults to `0o777`.
+    pub fn mode(&mut self, mode: u32) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
     /// Creates the specified directory with the options configured in this
     /// builder.
     pub fn create(&self, path: &str) -> Result<()> {
         if self.recursive {
             self.create_dir_all(path)
         } else {
-            api::ax_create_dir(path)
+            api::ax_create_dir(path, self.mode)
         }
     }
 
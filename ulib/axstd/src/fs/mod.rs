@@ -11,6 +11,9 @@ use alloc::{string::String, vec::Vec};
 pub use self::dir::{DirBuilder, DirEntry, ReadDir};
 pub use self::file::{File, FileType, Metadata, OpenOptions, Permissions};
 
+/// Attributes of a mounted filesystem, returned by [`statfs`].
+pub type FileSystemInfo = arceos_api::fs::AxFileSystemInfo;
+
 /// Read the entire contents of a file into a bytes vector.
 #[cfg(feature = "alloc")]
 pub fn read(path: &str) -> io::Result<Vec<u8>> {
@@ -75,3 +78,15 @@ pub fn remove_file(path: &str) -> io::Result<()> {
 pub fn rename(old: &str, new: &str) -> io::Result<()> {
     arceos_api::fs::ax_rename(old, new)
 }
+
+/// Returns the attributes (block size, total/free blocks and inodes) of the
+/// filesystem that contains the given path.
+pub fn statfs(path: &str) -> io::Result<FileSystemInfo> {
+    arceos_api::fs::ax_statfs(path)
+}
+
+/// Returns the paths of all mount points, starting with the root `/`.
+#[cfg(feature = "alloc")]
+pub fn mount_points() -> io::Result<Vec<String>> {
+    arceos_api::fs::ax_mount_points()
+}
@@ -27,9 +27,13 @@
 //!     - `sched_fifo`: Use the FIFO cooperative scheduler.
 //!     - `sched_rr`: Use the Round-robin preemptive scheduler.
 //!     - `sched_cfs`: Use the Completely Fair Scheduler (CFS) preemptive scheduler.
+//!     - `sched_rt`: Use the real-time preemptive scheduler, with CFS for normal tasks.
+//!     - `sched_edf`: Use the earliest deadline first (EDF) preemptive scheduler.
 //! - Upperlayer stacks
 //!     - `fs`: Enable file system support.
 //!     - `myfs`: Allow users to define their custom filesystems to override the default.
+//!     - `squashfs`: Use a read-only SquashFS image as the root filesystem instead of FAT.
+//!     - `initramfs`: Use a RAM root filesystem unpacked from a cpio archive embedded at build time.
 //!     - `net`: Enable networking support.
 //!     - `dns`: Enable DNS lookup support.
 //!     - `display`: Enable graphics support.
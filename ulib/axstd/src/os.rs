@@ -3,4 +3,65 @@
 /// ArceOS-specific definitions.
 pub mod arceos {
     pub use arceos_api as api;
+
+    /// User and group identities of the current thread.
+    #[cfg(feature = "multitask")]
+    pub mod cred {
+        use crate::io;
+        use alloc::vec::Vec;
+        use arceos_api::task as api;
+
+        pub use api::AxCredentials as Credentials;
+        pub use api::AxPrivCap as PrivCap;
+
+        /// Returns the credentials of the current thread.
+        pub fn current() -> Credentials {
+            api::ax_current_credentials()
+        }
+
+        /// Returns the effective user ID of the current thread.
+        pub fn geteuid() -> u32 {
+            current().euid
+        }
+
+        /// Returns the effective group ID of the current thread.
+        pub fn getegid() -> u32 {
+            current().egid
+        }
+
+        /// Sets the user IDs of the current thread, like `setuid(2)`.
+        pub fn setuid(uid: u32) -> io::Result<()> {
+            api::ax_cred_setuid(uid)
+        }
+
+        /// Sets the group IDs of the current thread, like `setgid(2)`.
+        pub fn setgid(gid: u32) -> io::Result<()> {
+            api::ax_cred_setgid(gid)
+        }
+
+        /// Returns the supplementary group IDs of the current thread.
+        pub fn getgroups() -> Vec<u32> {
+            current().groups
+        }
+
+        /// Replaces the supplementary group IDs of the current thread.
+        ///
+        /// This requires [`PrivCap::SETGID`].
+        pub fn setgroups(groups: &[u32]) -> io::Result<()> {
+            api::ax_cred_setgroups(groups)
+        }
+
+        /// Whether the current thread holds the capability `cap`.
+        pub fn has_cap(cap: PrivCap) -> bool {
+            current().has_cap(cap)
+        }
+
+        /// Replaces the capabilities of the current thread, like `capset(2)`.
+        ///
+        /// The permitted capabilities can only be reduced, and the effective
+        /// ones must be a subset of them.
+        pub fn set_caps(permitted: PrivCap, effective: PrivCap) -> io::Result<()> {
+            api::ax_cred_set_caps(permitted, effective)
+        }
+    }
 }
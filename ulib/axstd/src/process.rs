@@ -10,6 +10,8 @@ pub fn exit(exit_code: i32) {
 }
 
 /// Shutdown the whole system.
+///
+/// It does nothing unless the current thread holds the `SYS_BOOT` capability.
 pub fn halt(_exit_code: i32) {
     arceos_api::sys::ax_terminate();
 }
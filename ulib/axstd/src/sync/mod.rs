@@ -1,4 +1,8 @@
 //! Useful synchronization primitives.
+//!
+//! With the `multitask` feature, the blocking primitives put the current task
+//! to sleep while waiting. Their timed variants (e.g.,
+//! `Condvar::wait_timeout`) need the `irq` feature to be enabled.
 
 #[doc(no_inline)]
 pub use core::sync::atomic;
@@ -7,12 +11,16 @@ pub use core::sync::atomic;
 #[doc(no_inline)]
 pub use alloc::sync::{Arc, Weak};
 
-#[cfg(feature = "multitask")]
-mod mutex;
-
 #[cfg(feature = "multitask")]
 #[doc(cfg(feature = "multitask"))]
-pub use self::mutex::{Mutex, MutexGuard};
+pub use arceos_api::sync::{
+    mpsc, oneshot, Barrier, BarrierWaitResult, Condvar, Mutex, MutexGuard, Once, RwLock,
+    RwLockReadGuard, RwLockWriteGuard, Semaphore, SemaphoreGuard,
+};
+
+#[cfg(all(feature = "multitask", feature = "irq"))]
+#[doc(cfg(all(feature = "multitask", feature = "irq")))]
+pub use arceos_api::sync::WaitTimeoutResult;
 
 #[cfg(not(feature = "multitask"))]
 #[doc(cfg(not(feature = "multitask")))]
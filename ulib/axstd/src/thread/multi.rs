@@ -3,11 +3,21 @@
 extern crate alloc;
 
 use crate::io;
-use alloc::{string::String, sync::Arc};
-use core::{cell::UnsafeCell, num::NonZeroU64};
+use alloc::{boxed::Box, string::String, sync::Arc};
+use core::{any::Any, cell::UnsafeCell, num::NonZeroU64};
 
-use arceos_api::task::{self as api, AxTaskHandle};
-use axerrno::ax_err_type;
+use arceos_api::task::{self as api, AxJoinError, AxTaskHandle};
+
+/// Scheduling policies of threads, like `SCHED_FIFO` and `SCHED_RR` in Linux.
+pub use arceos_api::task::AxSchedPolicy as SchedPolicy;
+
+/// Declares thread-local storage keys, which work without the `tls` feature.
+pub use arceos_api::task::{task_local, LocalKey};
+
+/// A specialized [`Result`](core::result::Result) type for threads.
+///
+/// The error is the panic message (a [`String`]) if the thread panicked.
+pub type Result<T> = core::result::Result<T, Box<dyn Any + Send + 'static>>;
 
 /// A unique identifier for a running thread.
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
@@ -49,6 +59,8 @@ pub struct Builder {
     name: Option<String>,
     // The size of the stack for the spawned thread in bytes
     stack_size: Option<usize>,
+    // The scheduling policy and the static priority of the spawned thread
+    sched_policy: Option<(SchedPolicy, isize)>,
 }
 
 impl Builder {
@@ -58,6 +70,7 @@ impl Builder {
         Builder {
             name: None,
             stack_size: None,
+            sched_policy: None,
         }
     }
 
@@ -73,6 +86,18 @@ impl Builder {
         self
     }
 
+    /// Sets the scheduling policy and the static priority of the new thread.
+    ///
+    /// Real-time threads always run before normal ones. They need the
+    /// `sched_rt` feature and the `SYS_NICE` capability, otherwise [`spawn`]
+    /// fails.
+    ///
+    /// [`spawn`]: Builder::spawn
+    pub fn sched_policy(mut self, policy: SchedPolicy, priority: isize) -> Builder {
+        self.sched_policy = Some((policy, priority));
+        self
+    }
+
     /// Spawns a new thread by taking ownership of the `Builder`, and returns an
     /// [`io::Result`] to its [`JoinHandle`].
     ///
@@ -115,10 +140,15 @@ impl Builder {
             drop(their_packet);
         };
 
-        let task = api::ax_spawn(main, name, stack_size);
+        let task = match self.sched_policy {
+            Some((policy, prio)) => {
+                api::ax_spawn_with_policy(main, name, stack_size, policy, prio)?
+            }
+            None => api::ax_spawn(main, name, stack_size),
+        };
         Ok(JoinHandle {
             thread: Thread::from_id(task.id()),
-            native: task,
+            native: Some(task),
             packet: my_packet,
         })
     }
@@ -130,6 +160,27 @@ pub fn current() -> Thread {
     Thread::from_id(id)
 }
 
+/// Makes the current thread a deadline thread, which is guaranteed to run for
+/// `runtime` before `deadline` in every `period` (all starting from now).
+///
+/// It needs the `SYS_NICE` capability, and fails if the parameters are invalid
+/// or the CPU does not have enough time left for the thread.
+#[cfg(feature = "sched_edf")]
+pub fn set_deadline(
+    runtime: core::time::Duration,
+    deadline: core::time::Duration,
+    period: core::time::Duration,
+) -> io::Result<()> {
+    api::ax_set_current_deadline(Some((runtime, deadline, period)))
+}
+
+/// Makes the current thread a best-effort thread again, and releases the CPU
+/// time reserved by [`set_deadline`].
+#[cfg(feature = "sched_edf")]
+pub fn clear_deadline() -> io::Result<()> {
+    api::ax_set_current_deadline(None)
+}
+
 /// Spawns a new thread, returning a [`JoinHandle`] for it.
 ///
 /// The join handle provides a [`join`] method that can be used to join the
@@ -159,7 +210,7 @@ unsafe impl<T> Sync for Packet<T> {}
 /// means that there is no longer any handle to the thread and no way to `join`
 /// on it.
 pub struct JoinHandle<T> {
-    native: AxTaskHandle,
+    native: Option<AxTaskHandle>,
     thread: Thread,
     packet: Arc<Packet<T>>,
 }
@@ -177,13 +228,27 @@ impl<T> JoinHandle<T> {
     ///
     /// This function will return immediately if the associated thread has
     /// already finished.
-    pub fn join(mut self) -> io::Result<T> {
-        api::ax_wait_for_exit(self.native).ok_or_else(|| ax_err_type!(BadState))?;
-        Arc::get_mut(&mut self.packet)
-            .unwrap()
-            .result
-            .get_mut()
-            .take()
-            .ok_or_else(|| ax_err_type!(BadState))
+    ///
+    /// If the thread panicked, [`Err`] is returned with the panic message.
+    pub fn join(mut self) -> Result<T> {
+        let native = self.native.take().unwrap();
+        match api::ax_wait_for_exit(native) {
+            Err(AxJoinError::Panicked(message)) => return Err(Box::new(message)),
+            Err(AxJoinError::Detached) => unreachable!(),
+            Ok(_) => {}
+        }
+        // the packet is leaked if the thread called `exit()`
+        match Arc::get_mut(&mut self.packet).and_then(|p| p.result.get_mut().take()) {
+            Some(ret) => Ok(ret),
+            None => Err(Box::new(String::from("thread exited without a result"))),
+        }
+    }
+}
+
+impl<T> Drop for JoinHandle<T> {
+    fn drop(&mut self) {
+        if let Some(native) = self.native.take() {
+            api::ax_detach_task(native);
+        }
     }
 }